@@ -45,19 +45,34 @@ where
             }
         };
 
-    let req = match state.verify(&token) {
-        Ok(user) => {
-            let mut req = Request::from_parts(parts, body);
-            req.extensions_mut().insert(user);
-            req
-        }
+    // 无效和已注销的令牌都返回 401，客户端统一重新登录或刷新令牌
+    let claims = match state.verify(&token) {
+        Ok(claims) => claims,
         Err(e) => {
             let msg = format!("failed to verify token, error: {:?}", e);
             tracing::error!("{}", msg);
-            return ApiResponse::<()>::error(StatusCode::FORBIDDEN, msg).into_response();
+            return ApiResponse::<()>::error(StatusCode::UNAUTHORIZED, msg).into_response();
         }
     };
 
+    match state.is_revoked(&claims.jti).await {
+        Ok(false) => {}
+        Ok(true) => {
+            let msg = "token has been revoked";
+            return ApiResponse::<()>::error(StatusCode::UNAUTHORIZED, msg).into_response();
+        }
+        Err(e) => {
+            tracing::error!("failed to check token revocation, error: {:?}", e);
+            let msg = "failed to verify token";
+            return ApiResponse::<()>::error(StatusCode::INTERNAL_SERVER_ERROR, msg)
+                .into_response();
+        }
+    }
+
+    let mut req = Request::from_parts(parts, body);
+    req.extensions_mut().insert(claims.user.clone());
+    req.extensions_mut().insert(claims);
+
     next.run(req).await
 }
 
@@ -66,14 +81,15 @@ mod tests {
     use super::*;
 
     use crate::models::user::User;
-    use crate::utils::jwt::{DecodingKey, EncodingKey};
+    use crate::utils::jwt::{DecodingKey, EncodingKey, TokenClaims};
 
     use axum::body::Body;
     use axum::middleware::from_fn_with_state;
     use axum::routing::get;
     use axum::{Router, body};
     use jwt_simple::reexports::serde_json;
-    use std::sync::Arc;
+    use std::collections::HashSet;
+    use std::sync::{Arc, Mutex};
     use tower::ServiceExt;
 
     #[derive(Clone)]
@@ -82,14 +98,19 @@ mod tests {
     struct AppStateInner {
         sign_key: EncodingKey,
         verify_key: DecodingKey,
+        revoked: Mutex<HashSet<String>>,
     }
 
     impl TokenVerify for AppState {
         type Error = ();
 
-        fn verify(&self, token: &str) -> Result<User, Self::Error> {
+        fn verify(&self, token: &str) -> Result<TokenClaims, Self::Error> {
             self.0.verify_key.verify(token).map_err(|_| ())
         }
+
+        async fn is_revoked(&self, jti: &str) -> Result<bool, Self::Error> {
+            Ok(self.0.revoked.lock().unwrap().contains(jti))
+        }
     }
 
     #[tokio::test]
@@ -103,6 +124,7 @@ mod tests {
         let state = AppState(Arc::new(AppStateInner {
             sign_key: enc_key,
             verify_key: dec_key,
+            revoked: Mutex::default(),
         }));
 
         let app = Router::new()
@@ -118,7 +140,7 @@ mod tests {
             workspace_id: 1,
             created_at: chrono::Utc::now(),
         };
-        let token = state.0.sign_key.sign(user)?;
+        let token = state.0.sign_key.sign(user, "test")?;
 
         let req = Request::builder()
             .uri("/")
@@ -152,7 +174,20 @@ mod tests {
 
         let body = body::to_bytes(resp.into_body(), usize::MAX).await?;
         let api_resp: ApiResponse<()> = serde_json::from_slice(&body)?;
-        assert_eq!(api_resp.code, 403);
+        assert_eq!(api_resp.code, 401);
+
+        // 注销后的令牌被拒绝
+        let claims = state.verify(&token).expect("token is valid");
+        state.0.revoked.lock().unwrap().insert(claims.jti);
+        let req = Request::builder()
+            .uri("/")
+            .header("Authorization", format!("Bearer {}", token))
+            .body(Body::empty())?;
+        let resp = app.clone().oneshot(req).await?;
+
+        let body = body::to_bytes(resp.into_body(), usize::MAX).await?;
+        let api_resp: ApiResponse<()> = serde_json::from_slice(&body)?;
+        assert_eq!(api_resp.code, 401);
 
         Ok(())
     }
@@ -6,7 +6,7 @@ use tower_http::{
     trace::{DefaultMakeSpan, DefaultOnRequest, DefaultOnResponse, TraceLayer},
 };
 
-use crate::models::user::User;
+use crate::utils::jwt::TokenClaims;
 
 pub mod auth;
 pub mod request_id;
@@ -18,7 +18,16 @@ const HEADER_SERVER_TIME: &str = "x-server-time";
 pub trait TokenVerify {
     type Error: std::fmt::Debug;
 
-    fn verify(&self, token: &str) -> Result<User, Self::Error>;
+    fn verify(&self, token: &str) -> Result<TokenClaims, Self::Error>;
+
+    /// 令牌是否已被注销，默认不检查
+    fn is_revoked(
+        &self,
+        jti: &str,
+    ) -> impl std::future::Future<Output = Result<bool, Self::Error>> + Send {
+        let _ = jti;
+        async { Ok(false) }
+    }
 }
 
 pub fn set_layer(app: Router) -> Router {
@@ -0,0 +1,25 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use utoipa::ToSchema;
+
+/// 登录失败的原因
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "login_failure", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum LoginFailure {
+    /// 用户不存在或密码错误，两者不做区分
+    InvalidCredentials,
+    /// 失败次数过多，因此进入锁定期
+    LockedOut,
+}
+
+/// 一次失败的登录尝试
+#[derive(Clone, Debug, Deserialize, FromRow, Serialize, ToSchema)]
+pub struct LoginAudit {
+    pub id: i64,
+    pub email: String,
+    pub ip: Option<String>,
+    pub reason: LoginFailure,
+    pub created_at: DateTime<Utc>,
+}
@@ -1,8 +1,10 @@
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
+pub mod audit;
 pub mod chat;
 pub mod message;
+pub mod token;
 pub mod user;
 pub mod workspace;
 
@@ -0,0 +1,16 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use utoipa::ToSchema;
+
+/// 刷新令牌的记录，令牌本身只返回给客户端，数据库中保存的是哈希
+#[derive(Clone, Debug, Deserialize, FromRow, Serialize, ToSchema)]
+pub struct RefreshToken {
+    pub id: i64,
+    pub user_id: i64,
+    /// 同一次登录轮换出的令牌属于同一个族
+    pub family: String,
+    pub expires_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
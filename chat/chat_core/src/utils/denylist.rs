@@ -0,0 +1,182 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, PoisonError, RwLock,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use sqlx::{PgPool, postgres::PgListener};
+use tokio::sync::broadcast;
+
+use crate::error::AppError;
+
+// revoked_tokens 插入时触发的通知，见 migrations 中的 token_revoked 触发器
+const CHANNEL: &str = "token_revoked";
+// 监听连接出错后重连的间隔
+const RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 已注销访问令牌的 jti 黑名单，保存在 Postgres 中供多个服务共享，令牌过期后记录即可删除。
+///
+/// 每个进程在内存中缓存全部未过期的记录，通过 LISTEN/NOTIFY 同步；
+/// 监听连接断开、缓存可能不完整时退回到直接查询数据库。
+#[derive(Clone, Debug)]
+pub struct TokenDenylist {
+    pool: PgPool,
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    revoked: RwLock<HashMap<String, DateTime<Utc>>>,
+    synced: AtomicBool,
+    events: broadcast::Sender<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Revoked {
+    jti: String,
+    expires_at: DateTime<Utc>,
+}
+
+impl TokenDenylist {
+    /// 需要在 tokio 运行时中调用，会启动一个后台任务监听注销通知
+    pub fn new(pool: PgPool) -> Self {
+        let denylist = Self {
+            pool,
+            inner: Arc::new(Inner {
+                revoked: RwLock::default(),
+                synced: AtomicBool::new(false),
+                events: broadcast::channel(64).0,
+            }),
+        };
+        tokio::spawn(denylist.clone().run());
+        denylist
+    }
+
+    pub async fn revoke(&self, jti: &str, expires_at: DateTime<Utc>) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            insert into revoked_tokens (jti, expires_at)
+            values ($1, $2)
+            on conflict (jti) do nothing
+            "#,
+        )
+        .bind(jti)
+        .bind(expires_at)
+        .execute(&self.pool)
+        .await?;
+        // 本进程不等通知，立即生效
+        self.insert([(jti.to_string(), expires_at)]);
+
+        // 顺便清理已经过期的记录
+        sqlx::query("delete from revoked_tokens where expires_at < now()")
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn contains(&self, jti: &str) -> Result<bool, AppError> {
+        if self.inner.synced.load(Ordering::Acquire) {
+            return Ok(self.cached(jti));
+        }
+
+        let (revoked,): (bool,) =
+            sqlx::query_as("select exists(select 1 from revoked_tokens where jti = $1)")
+                .bind(jti)
+                .fetch_one(&self.pool)
+                .await?;
+
+        Ok(revoked)
+    }
+
+    /// 令牌被注销时返回，用于关闭 SSE 等长连接
+    pub async fn wait_revoked(&self, jti: &str) {
+        let mut events = self.inner.events.subscribe();
+        if self.cached(jti) {
+            return;
+        }
+        loop {
+            match events.recv().await {
+                Ok(revoked) if revoked == jti => return,
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Lagged(_)) if self.cached(jti) => return,
+                Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => std::future::pending().await,
+            }
+        }
+    }
+
+    fn cached(&self, jti: &str) -> bool {
+        self.inner
+            .revoked
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains_key(jti)
+    }
+
+    // 新增的 jti 会通知 wait_revoked，同时清理已过期的记录
+    fn insert(&self, items: impl IntoIterator<Item = (String, DateTime<Utc>)>) {
+        let now = Utc::now();
+        let mut revoked = self
+            .inner
+            .revoked
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        revoked.retain(|_, expires_at| *expires_at > now);
+        for (jti, expires_at) in items {
+            if revoked.insert(jti.clone(), expires_at).is_none() {
+                let _ = self.inner.events.send(jti);
+            }
+        }
+    }
+
+    async fn run(self) {
+        loop {
+            if let Err(e) = self.listen().await {
+                tracing::error!("token denylist listener failed: {}", e);
+            }
+            self.inner.synced.store(false, Ordering::Release);
+            if self.pool.is_closed() {
+                break;
+            }
+            tokio::time::sleep(RETRY_INTERVAL).await;
+        }
+    }
+
+    async fn listen(&self) -> Result<(), sqlx::Error> {
+        let mut listener = PgListener::connect_with(&self.pool).await?;
+        listener.listen(CHANNEL).await?;
+        self.reload().await?;
+
+        loop {
+            match listener.try_recv().await? {
+                Some(notification) => match serde_json::from_str::<Revoked>(notification.payload())
+                {
+                    Ok(Revoked { jti, expires_at }) => self.insert([(jti, expires_at)]),
+                    Err(e) => tracing::warn!("invalid {} payload: {}", CHANNEL, e),
+                },
+                // 连接已经自动重连，断开期间的通知可能丢失，重新加载
+                None => {
+                    self.inner.synced.store(false, Ordering::Release);
+                    self.reload().await?;
+                }
+            }
+        }
+    }
+
+    // 开始监听之后再加载，避免漏掉两者之间的注销
+    async fn reload(&self) -> Result<(), sqlx::Error> {
+        let rows: Vec<(String, DateTime<Utc>)> =
+            sqlx::query_as("select jti, expires_at from revoked_tokens where expires_at > now()")
+                .fetch_all(&self.pool)
+                .await?;
+        self.insert(rows);
+        self.inner.synced.store(true, Ordering::Release);
+        Ok(())
+    }
+}
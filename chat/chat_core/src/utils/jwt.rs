@@ -1,8 +1,14 @@
+use chrono::{DateTime, Utc};
 use jwt_simple::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 use crate::models::user::User;
 
-const JWT_DURATION: u64 = 60 * 60 * 24 * 7;
+/// 访问令牌的有效期，过期后用刷新令牌换取新的访问令牌
+pub const ACCESS_TOKEN_DURATION: u64 = 60 * 15;
+/// 校验过期时间时允许的各服务之间的时钟误差，单位为秒
+pub const TIME_TOLERANCE: u64 = 60;
 const JWT_ISSUER: &str = "chat_server";
 const JWT_AUDIENCE: &str = "chat_web";
 
@@ -12,15 +18,39 @@ pub struct EncodingKey(Ed25519KeyPair);
 #[derive(Clone)]
 pub struct DecodingKey(Ed25519PublicKey);
 
+/// 验证通过的访问令牌
+#[derive(Clone, Debug)]
+pub struct TokenClaims {
+    pub user: User,
+    /// 令牌 ID，注销时加入黑名单
+    pub jti: String,
+    /// 登录会话 ID，即签发时的刷新令牌族，注销时整个会话失效
+    pub sid: String,
+    /// 令牌不再被接受的时刻，即 `exp` 加上时钟误差，黑名单中的记录至少保留到此时
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AccessClaims {
+    #[serde(flatten)]
+    user: User,
+    sid: String,
+}
+
 impl EncodingKey {
     pub fn load(key: &str) -> Result<Self, jwt_simple::Error> {
         Ok(Self(Ed25519KeyPair::from_pem(key)?))
     }
 
-    pub fn sign(&self, user: User) -> Result<String, jwt_simple::Error> {
-        let claims = Claims::with_custom_claims(user, Duration::from_secs(JWT_DURATION))
+    pub fn sign(&self, user: User, sid: &str) -> Result<String, jwt_simple::Error> {
+        let custom = AccessClaims {
+            user,
+            sid: sid.to_string(),
+        };
+        let claims = Claims::with_custom_claims(custom, Duration::from_secs(ACCESS_TOKEN_DURATION))
             .with_issuer(JWT_ISSUER)
-            .with_audience(JWT_AUDIENCE);
+            .with_audience(JWT_AUDIENCE)
+            .with_jwt_id(Uuid::now_v7());
 
         self.0.sign(claims)
     }
@@ -31,16 +61,31 @@ impl DecodingKey {
         Ok(Self(Ed25519PublicKey::from_pem(key)?))
     }
 
-    pub fn verify(&self, token: &str) -> Result<User, jwt_simple::Error> {
+    pub fn verify(&self, token: &str) -> Result<TokenClaims, jwt_simple::Error> {
         let options = VerificationOptions {
             allowed_issuers: Some(HashSet::from([JWT_ISSUER.to_string()])),
             allowed_audiences: Some(HashSet::from([JWT_AUDIENCE.to_string()])),
+            // 默认的误差为 15 分钟，与令牌的有效期相当
+            time_tolerance: Some(Duration::from_secs(TIME_TOLERANCE)),
             ..Default::default()
         };
 
-        let claims = self.0.verify_token::<User>(token, Some(options))?;
+        let claims = self.0.verify_token::<AccessClaims>(token, Some(options))?;
+        // 没有 jti 的令牌无法注销，不予接受
+        let jti = claims
+            .jwt_id
+            .ok_or_else(|| jwt_simple::Error::msg("missing jwt id"))?;
+        let expires_at = claims
+            .expires_at
+            .and_then(|t| DateTime::from_timestamp((t.as_secs() + TIME_TOLERANCE) as i64, 0))
+            .ok_or_else(|| jwt_simple::Error::msg("missing expiration"))?;
 
-        Ok(claims.custom)
+        Ok(TokenClaims {
+            user: claims.custom.user,
+            jti,
+            sid: claims.custom.sid,
+            expires_at,
+        })
     }
 }
 
@@ -5,6 +5,7 @@ use axum::{
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
+pub mod denylist;
 pub mod jwt;
 
 #[derive(Debug, Deserialize, Serialize, ToSchema)]
@@ -1,4 +1,4 @@
-use std::{fs::File, path::PathBuf};
+use std::{fs::File, path::PathBuf, time::Duration};
 
 use anyhow::Result;
 use serde::Deserialize;
@@ -21,6 +21,43 @@ pub struct ServerConfig {
 pub struct AuthConfig {
     pub sign_key: String,
     pub verify_key: String,
+    #[serde(default)]
+    pub login: LoginConfig,
+}
+
+/// 登录限流：窗口内失败次数达到上限后锁定
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct LoginConfig {
+    /// 同一邮箱允许的失败次数
+    pub max_email_failures: usize,
+    /// 同一 IP 允许的失败次数，一个 IP 可能尝试多个邮箱，因此上限更高
+    pub max_ip_failures: usize,
+    /// 统计失败次数的窗口，单位为秒
+    pub window_secs: u64,
+    /// 锁定时长，单位为秒
+    pub lockout_secs: u64,
+}
+
+impl LoginConfig {
+    pub fn window(&self) -> Duration {
+        Duration::from_secs(self.window_secs)
+    }
+
+    pub fn lockout(&self) -> Duration {
+        Duration::from_secs(self.lockout_secs)
+    }
+}
+
+impl Default for LoginConfig {
+    fn default() -> Self {
+        Self {
+            max_email_failures: 5,
+            max_ip_failures: 20,
+            window_secs: 15 * 60,
+            lockout_secs: 15 * 60,
+        }
+    }
 }
 
 impl AppConfig {
@@ -1,12 +1,31 @@
-use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use std::net::IpAddr;
+
+use axum::{
+    Extension, Json,
+    extract::State,
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+};
 
 use crate::responses::AuthOutput;
 use crate::{
     AppState,
-    repos::user::UserRepo,
-    requests::user::{SignInReq, SignUpReq},
+    limiter::ClientIp,
+    repos::{audit::LoginAuditRepo, token::RefreshTokenRepo, user::UserRepo},
+    requests::user::{LogoutReq, RefreshReq, SignInReq, SignUpReq},
+};
+use chat_core::{
+    error::AppError,
+    models::{
+        audit::{LoginAudit, LoginFailure},
+        token::RefreshToken,
+        user::User,
+    },
+    utils::{
+        ApiResponse,
+        jwt::{ACCESS_TOKEN_DURATION, TokenClaims},
+    },
 };
-use chat_core::{error::AppError, models::user::User, utils::ApiResponse};
 
 #[utoipa::path(
     post,
@@ -24,9 +43,9 @@ pub async fn sign_up(
     Json(req): Json<SignUpReq>,
 ) -> Result<impl IntoResponse, AppError> {
     let user = User::create(&req, &state.pg_pool).await?;
-    let token = state.sign_key.sign(user)?;
+    let refresh = RefreshToken::issue(user.id, &state.pg_pool).await?;
 
-    Ok(ApiResponse::success(AuthOutput { token }))
+    Ok(ApiResponse::success(auth_output(&state, user, refresh)?))
 }
 
 #[utoipa::path(
@@ -36,6 +55,7 @@ pub async fn sign_up(
     responses(
         (status = 200, description = "User signed in successfully", body = AuthOutput),
         (status = 403, description = "User not found or password incorrect"),
+        (status = 429, description = "Too many failed attempts, see the Retry-After header"),
         (status = 500, description = "sqlx error"),
     ),
     tag = "auth",
@@ -43,27 +63,138 @@ pub async fn sign_up(
 )]
 pub async fn sign_in(
     State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
     Json(req): Json<SignInReq>,
-) -> Result<impl IntoResponse, AppError> {
-    let user = User::find_by_email(&req.email, &state.pg_pool).await?;
-    let body = match user {
+) -> Result<Response, AppError> {
+    let attempt = match state.login_limiter.try_acquire(&req.email, ip) {
+        Ok(attempt) => attempt,
+        Err(retry_after) => {
+            // 锁定期内的请求只记日志，审计记录只在进入锁定时写入一次，避免被刷满
+            tracing::warn!(target: "audit", email = req.email, ?ip, "sign in rejected, locked out");
+            return Ok(too_many_attempts(retry_after));
+        }
+    };
+
+    match User::verify(&req, &state.pg_pool).await? {
         Some(user) => {
-            let token = state.sign_key.sign(user)?;
-            ApiResponse::success(AuthOutput { token })
+            attempt.success();
+            let refresh = RefreshToken::issue(user.id, &state.pg_pool).await?;
+            let output = auth_output(&state, user, refresh)?;
+            Ok(ApiResponse::success(output).into_response())
         }
-        None => ApiResponse::error(
-            StatusCode::FORBIDDEN,
-            format!("user not found: {}", req.email),
-        ),
+        None => {
+            let locked = attempt.failure();
+            audit(&state, &req.email, ip, LoginFailure::InvalidCredentials).await;
+            if locked.is_some() {
+                audit(&state, &req.email, ip, LoginFailure::LockedOut).await;
+            }
+            // 不区分用户不存在和密码错误，避免泄露邮箱是否已注册
+            Ok(
+                ApiResponse::<()>::error(StatusCode::FORBIDDEN, "invalid email or password")
+                    .into_response(),
+            )
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/refresh",
+    request_body = RefreshReq,
+    responses(
+        (status = 200, description = "Tokens refreshed successfully", body = AuthOutput),
+        (status = 403, description = "Refresh token invalid, expired or already used"),
+        (status = 500, description = "sqlx error"),
+    ),
+    tag = "auth",
+    description = "Exchange a refresh token for a new access token and refresh token",
+)]
+pub async fn refresh(
+    State(state): State<AppState>,
+    Json(req): Json<RefreshReq>,
+) -> Result<impl IntoResponse, AppError> {
+    let forbidden = || ApiResponse::error(StatusCode::FORBIDDEN, "invalid refresh token");
+    let Some(refresh) = RefreshToken::rotate(&req.refresh_token, &state.pg_pool).await? else {
+        return Ok(forbidden());
+    };
+    let Some(user) = User::find_by_id(refresh.0.user_id, &state.pg_pool).await? else {
+        return Ok(forbidden());
     };
 
-    Ok(body)
+    Ok(ApiResponse::success(auth_output(&state, user, refresh)?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/logout",
+    security(
+        ("token" = []),
+    ),
+    request_body = LogoutReq,
+    responses(
+        (status = 200, description = "User logged out successfully"),
+        (status = 500, description = "sqlx error"),
+    ),
+    tag = "auth",
+    description = "Revoke the current access token and the refresh token family of its session; the body is optional",
+)]
+pub async fn logout(
+    State(state): State<AppState>,
+    Extension(claims): Extension<TokenClaims>,
+    req: Option<Json<LogoutReq>>,
+) -> Result<impl IntoResponse, AppError> {
+    state
+        .denylist
+        .revoke(&claims.jti, claims.expires_at)
+        .await?;
+    RefreshToken::revoke_family(&claims.sid, claims.user.id, &state.pg_pool).await?;
+    if let Some(refresh_token) = req.and_then(|Json(req)| req.refresh_token) {
+        RefreshToken::revoke(&refresh_token, claims.user.id, &state.pg_pool).await?;
+    }
+
+    Ok(Json(ApiResponse::success(())))
+}
+
+// 访问令牌的 sid 为刷新令牌族，注销时据此找到整个会话
+fn auth_output(
+    state: &AppState,
+    user: User,
+    (record, refresh_token): (RefreshToken, String),
+) -> Result<AuthOutput, AppError> {
+    Ok(AuthOutput {
+        token: state.sign_key.sign(user, &record.family)?,
+        expires_in: ACCESS_TOKEN_DURATION,
+        refresh_token,
+    })
+}
+
+async fn audit(state: &AppState, email: &str, ip: Option<IpAddr>, reason: LoginFailure) {
+    tracing::warn!(target: "audit", email, ?ip, ?reason, "sign in failed");
+    // 审计日志写入失败不影响登录结果
+    if let Err(e) = LoginAudit::record(email, ip, reason, &state.pg_pool).await {
+        tracing::error!("failed to write login audit: {}", e);
+    }
+}
+
+fn too_many_attempts(retry_after: std::time::Duration) -> Response {
+    // 向上取整，避免客户端在锁定结束前重试
+    let secs = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+    let body = ApiResponse::<()>::error(
+        StatusCode::TOO_MANY_REQUESTS,
+        format!(
+            "too many failed sign-in attempts, retry in {} seconds",
+            secs
+        ),
+    );
+    ([(header::RETRY_AFTER, secs.to_string())], body).into_response()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::requests::user::SignUpReq;
+    use chat_core::{middlewares::TokenVerify as _, utils::denylist::TokenDenylist};
+    use http_body_util::BodyExt as _;
 
     #[tokio::test]
     async fn test_sign_up() -> anyhow::Result<()> {
@@ -83,4 +214,193 @@ mod tests {
 
         Ok(())
     }
+
+    async fn sign_in_as(
+        state: &AppState,
+        ip: [u8; 4],
+        email: &str,
+        password: &str,
+    ) -> anyhow::Result<(Option<String>, ApiResponse<serde_json::Value>)> {
+        let req = SignInReq {
+            email: email.to_string(),
+            password: password.to_string(),
+        };
+        let res = sign_in(
+            State(state.clone()),
+            ClientIp(Some(IpAddr::from(ip))),
+            Json(req),
+        )
+        .await?;
+        let retry_after = res
+            .headers()
+            .get(header::RETRY_AFTER)
+            .map(|v| v.to_str().unwrap_or_default().to_string());
+        let body = res.into_body().collect().await?.to_bytes();
+        Ok((retry_after, serde_json::from_slice(&body)?))
+    }
+
+    #[tokio::test]
+    async fn test_sign_in() -> anyhow::Result<()> {
+        let (_tdb, state) = AppState::test_new().await?;
+        let ip = [10, 0, 0, 1];
+
+        let (_, res) = sign_in_as(&state, ip, "tchen@acme.org", "123456").await?;
+        assert!(res.success);
+        assert!(res.data.is_some());
+
+        // 密码错误和用户不存在返回相同的结果
+        let (_, wrong) = sign_in_as(&state, ip, "tchen@acme.org", "654321").await?;
+        let (_, unknown) = sign_in_as(&state, ip, "nobody@acme.org", "123456").await?;
+        assert_eq!(wrong.code, 403);
+        assert_eq!((wrong.code, wrong.error), (unknown.code, unknown.error));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sign_in_lockout() -> anyhow::Result<()> {
+        let (_tdb, state) = AppState::test_new().await?;
+        let ip = [10, 0, 0, 2];
+        let max = state.config.auth.login.max_email_failures;
+
+        for _ in 0..max {
+            let (_, res) = sign_in_as(&state, ip, "alice@acme.org", "wrong").await?;
+            assert_eq!(res.code, 403);
+        }
+        // 锁定后即使密码正确也会被拒绝
+        let (retry_after, res) = sign_in_as(&state, ip, "alice@acme.org", "123456").await?;
+        assert_eq!(res.code, 429);
+        assert!(retry_after.is_some());
+        let (_, res) = sign_in_as(&state, [10, 0, 0, 3], "alice@acme.org", "123456").await?;
+        assert_eq!(res.code, 429);
+        // 其他用户不受影响
+        let (_, res) = sign_in_as(&state, ip, "bob@acme.org", "123456").await?;
+        assert!(res.success);
+
+        // 被拒绝的请求不再写审计记录，只有进入锁定的那次记一条 LockedOut
+        let audits = LoginAudit::find_by_email("alice@acme.org", &state.pg_pool).await?;
+        assert_eq!(audits.len(), max + 1);
+        assert!(
+            audits[..max]
+                .iter()
+                .all(|a| a.reason == LoginFailure::InvalidCredentials)
+        );
+        assert_eq!(audits[max].reason, LoginFailure::LockedOut);
+        assert_eq!(audits[0].ip.as_deref(), Some("10.0.0.2"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_refresh_and_logout() -> anyhow::Result<()> {
+        let (_tdb, state) = AppState::test_new().await?;
+        let (_, res) = sign_in_as(&state, [10, 0, 0, 4], "tchen@acme.org", "123456").await?;
+        let data = res.data.expect("signed in");
+        let old_refresh = data["refresh_token"].as_str().unwrap_or_default();
+        assert_eq!(data["expires_in"], ACCESS_TOKEN_DURATION);
+
+        let res = refresh(
+            State(state.clone()),
+            Json(RefreshReq {
+                refresh_token: old_refresh.to_string(),
+            }),
+        )
+        .await?
+        .into_response();
+        let res: ApiResponse<serde_json::Value> =
+            serde_json::from_slice(&res.into_body().collect().await?.to_bytes())?;
+        let data = res.data.expect("refreshed");
+        let token = data["token"].as_str().unwrap_or_default();
+        let refresh_token = data["refresh_token"].as_str().unwrap_or_default();
+        assert_ne!(refresh_token, old_refresh);
+
+        let claims = state.verify(token)?;
+        assert_eq!(claims.user.email, "tchen@acme.org");
+        assert!(!state.is_revoked(&claims.jti).await?);
+
+        let req = LogoutReq {
+            refresh_token: Some(refresh_token.to_string()),
+        };
+        logout(
+            State(state.clone()),
+            Extension(claims.clone()),
+            Some(Json(req)),
+        )
+        .await?;
+        assert!(state.is_revoked(&claims.jti).await?);
+        assert!(
+            RefreshToken::rotate(refresh_token, &state.pg_pool)
+                .await?
+                .is_none()
+        );
+
+        // 不带请求体也会注销访问令牌所属会话的刷新令牌
+        let (_, res) = sign_in_as(&state, [10, 0, 0, 4], "tchen@acme.org", "123456").await?;
+        let data = res.data.expect("signed in");
+        let claims = state.verify(data["token"].as_str().unwrap_or_default())?;
+        logout(State(state.clone()), Extension(claims.clone()), None).await?;
+        assert!(state.is_revoked(&claims.jti).await?);
+        let refresh_token = data["refresh_token"].as_str().unwrap_or_default();
+        assert!(
+            RefreshToken::rotate(refresh_token, &state.pg_pool)
+                .await?
+                .is_none()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_logout_notifies_other_services() -> anyhow::Result<()> {
+        let (_tdb, state) = AppState::test_new().await?;
+        // 模拟 notify_server 等其他服务中的黑名单缓存
+        let other = TokenDenylist::new(state.pg_pool.clone());
+
+        let (_, res) = sign_in_as(&state, [10, 0, 0, 5], "tchen@acme.org", "123456").await?;
+        let data = res.data.expect("signed in");
+        let claims = state.verify(data["token"].as_str().unwrap_or_default())?;
+        assert!(!other.contains(&claims.jti).await?);
+
+        let wait = tokio::spawn({
+            let (other, jti) = (other.clone(), claims.jti.clone());
+            async move { other.wait_revoked(&jti).await }
+        });
+        logout(State(state.clone()), Extension(claims.clone()), None).await?;
+        tokio::time::timeout(std::time::Duration::from_secs(5), wait).await??;
+        assert!(other.contains(&claims.jti).await?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_revoked_token_rejected_after_exp() -> anyhow::Result<()> {
+        use jwt_simple::prelude::*;
+
+        let (_tdb, state) = AppState::test_new().await?;
+        let user = User::find_by_email("tchen@acme.org", &state.pg_pool)
+            .await?
+            .expect("user exists");
+
+        // 已经过期但仍在时钟误差之内的令牌
+        let mut custom = serde_json::to_value(&user)?;
+        custom["sid"] = "test".into();
+        let mut claims = Claims::with_custom_claims(custom, Duration::from_secs(0))
+            .with_issuer("chat_server")
+            .with_audience("chat_web")
+            .with_jwt_id("expired");
+        claims.expires_at = Some(Clock::now_since_epoch() - Duration::from_secs(5));
+        let token = Ed25519KeyPair::from_pem(&state.config.auth.sign_key)?.sign(claims)?;
+
+        let claims = state.verify(&token)?;
+        assert!(claims.expires_at.timestamp() as u64 > Clock::now_since_epoch().as_secs());
+        logout(State(state.clone()), Extension(claims), None).await?;
+
+        // 其他服务从数据库加载黑名单时仍然包含这个令牌
+        let other = TokenDenylist::new(state.pg_pool.clone());
+        assert!(state.verify(&token).is_ok());
+        assert!(state.is_revoked("expired").await?);
+        assert!(other.contains("expired").await?);
+
+        Ok(())
+    }
 }
@@ -1,4 +1,5 @@
 use axum::{Router, middleware::from_fn_with_state, routing};
+use conc::metrics::{Registry, http::HttpMetrics};
 use sqlx::PgPool;
 use std::{ops::Deref, sync::Arc};
 use tokio::fs;
@@ -6,24 +7,28 @@ use tokio::fs;
 use crate::{
     config::AppConfig,
     handlers::{
-        auth::{sign_in, sign_up},
+        auth::{logout, refresh, sign_in, sign_up},
         chat::{chat_create, chat_delete, chat_detail, chat_list, chat_update},
         file::{file_download, file_upload},
         message::{message_list, message_send},
         user::user_list,
     },
+    limiter::LoginLimiter,
     openapi::OpenApiRouter,
 };
 
 use chat_core::{
     error::AppError,
     middlewares::{TokenVerify, auth, set_layer},
-    models::user::User,
-    utils::jwt::{DecodingKey, EncodingKey},
+    utils::{
+        denylist::TokenDenylist,
+        jwt::{DecodingKey, EncodingKey, TokenClaims},
+    },
 };
 
 pub mod config;
 pub mod handlers;
+pub mod limiter;
 pub mod middlewares;
 pub mod openapi;
 pub mod repos;
@@ -41,6 +46,8 @@ pub struct AppStateInner {
     pg_pool: PgPool,
     sign_key: EncodingKey,
     verify_key: DecodingKey,
+    login_limiter: LoginLimiter,
+    denylist: TokenDenylist,
 }
 
 impl Deref for AppState {
@@ -63,6 +70,8 @@ impl AppState {
 
         let sign_key = EncodingKey::load(&config.auth.sign_key)?;
         let verify_key = DecodingKey::load(&config.auth.verify_key)?;
+        let login_limiter = LoginLimiter::new(config.auth.login.clone());
+        let denylist = TokenDenylist::new(pg_pool.clone());
 
         Ok(Self {
             inner: Arc::new(AppStateInner {
@@ -70,6 +79,8 @@ impl AppState {
                 pg_pool,
                 sign_key,
                 verify_key,
+                login_limiter,
+                denylist,
             }),
         })
     }
@@ -78,9 +89,13 @@ impl AppState {
 impl TokenVerify for AppState {
     type Error = AppError;
 
-    fn verify(&self, token: &str) -> Result<User, Self::Error> {
+    fn verify(&self, token: &str) -> Result<TokenClaims, Self::Error> {
         Ok(self.verify_key.verify(token)?)
     }
+
+    async fn is_revoked(&self, jti: &str) -> Result<bool, Self::Error> {
+        self.denylist.contains(jti).await
+    }
 }
 
 pub async fn get_router(state: AppState) -> Result<Router, AppError> {
@@ -96,6 +111,7 @@ pub async fn get_router(state: AppState) -> Result<Router, AppError> {
         .route("/chat/delete", routing::post(chat_delete))
         .route("/chat/{:chat_id}", routing::get(chat_detail))
         .route("/upload", routing::post(file_upload))
+        .route("/logout", routing::post(logout))
         .nest("/chat", message)
         .route(
             "/chat/files/{:workspace_id}/{*path}",
@@ -106,13 +122,18 @@ pub async fn get_router(state: AppState) -> Result<Router, AppError> {
             auth::verify_token::<AppState>,
         ))
         .route("/sign-up", routing::post(sign_up))
-        .route("/sign-in", routing::post(sign_in));
+        .route("/sign-in", routing::post(sign_in))
+        .route("/refresh", routing::post(refresh));
 
+    let metrics =
+        HttpMetrics::new(Registry::new()).map_err(|e| AppError::InternalError(e.to_string()))?;
     let app = Router::new()
         .openapi()
         .route("/", routing::get(|| async { "Hello, World!" }))
         .nest("/api", api)
-        .with_state(state);
+        .with_state(state)
+        .merge(metrics.router())
+        .layer(metrics.layer());
 
     Ok(set_layer(app))
 }
@@ -138,6 +159,8 @@ pub mod test_util {
             let post = config.server.pg_url.rfind('/').expect("invalid pg_url");
             let server_url = &config.server.pg_url[..post];
             let (tdb, pool) = Self::get_test_pool(server_url).await;
+            let login_limiter = LoginLimiter::new(config.auth.login.clone());
+            let denylist = TokenDenylist::new(pool.clone());
 
             let state = Self {
                 inner: Arc::new(AppStateInner {
@@ -145,6 +168,8 @@ pub mod test_util {
                     pg_pool: pool,
                     sign_key,
                     verify_key,
+                    login_limiter,
+                    denylist,
                 }),
             };
             Ok((tdb, state))
@@ -0,0 +1,404 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    convert::Infallible,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use axum::{
+    extract::{ConnectInfo, FromRequestParts},
+    http::request::Parts,
+};
+use conc::metrics::{Clock, SystemClock};
+
+use crate::config::LoginConfig;
+
+// 记录数超过此值时清理已过期的记录
+const MAX_ENTRIES: usize = 10_000;
+// 名额被进行中的尝试占满时，建议客户端等待的时间
+const PENDING_RETRY_AFTER: Duration = Duration::from_secs(1);
+
+/// 按邮箱和 IP 分别统计登录失败次数，窗口内达到上限后锁定一段时间
+#[derive(Debug, Clone)]
+pub struct LoginLimiter {
+    config: LoginConfig,
+    clock: Arc<dyn Clock>,
+    state: Arc<Mutex<State>>,
+}
+
+/// 一次进行中的登录尝试，占用邮箱和 IP 的一个名额，结束时调用 `success` 或 `failure`；
+/// 直接 drop（如查询数据库出错）时只释放名额，不计为失败
+#[derive(Debug)]
+pub struct LoginAttempt {
+    limiter: LoginLimiter,
+    email: String,
+    ip: Option<IpAddr>,
+    done: bool,
+}
+
+/// 连接的对端 IP；服务没有通过 `into_make_service_with_connect_info` 启动时为 `None`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIp(pub Option<IpAddr>);
+
+#[derive(Debug, Default)]
+struct State {
+    emails: HashMap<String, Entry>,
+    ips: HashMap<IpAddr, Entry>,
+}
+
+#[derive(Debug, Default)]
+struct Entry {
+    // 窗口内每次失败的时间
+    failures: VecDeque<Duration>,
+    locked_until: Option<Duration>,
+    // 已通过检查、还在校验密码的尝试数
+    pending: usize,
+}
+
+impl LoginLimiter {
+    pub fn new(config: LoginConfig) -> Self {
+        Self::with_clock(config, SystemClock::new())
+    }
+
+    pub fn with_clock(config: LoginConfig, clock: impl Clock) -> Self {
+        Self {
+            config,
+            clock: Arc::new(clock),
+            state: Arc::default(),
+        }
+    }
+
+    /// 邮箱或 IP 处于锁定期时返回剩余的锁定时间
+    pub fn check(&self, email: &str, ip: Option<IpAddr>) -> Option<Duration> {
+        let now = self.clock.now();
+        let state = self.lock();
+        let email = state
+            .emails
+            .get(&normalize(email))
+            .and_then(|e| e.retry_after(now));
+        let ip = ip
+            .and_then(|ip| state.ips.get(&ip))
+            .and_then(|e| e.retry_after(now));
+        email.max(ip)
+    }
+
+    /// 检查并占用名额在同一次加锁中完成：窗口内的失败数加上进行中的尝试数达到上限时拒绝，
+    /// 避免并发的请求在校验密码期间绕过锁定。被拒绝时返回建议的等待时间
+    pub fn try_acquire(&self, email: &str, ip: Option<IpAddr>) -> Result<LoginAttempt, Duration> {
+        let now = self.clock.now();
+        let window = self.config.window();
+        let email = normalize(email);
+
+        let mut state = self.lock();
+        // 任意邮箱都会在这里建立记录，因此清理不能只放在 record_failure 中
+        state.prune(now, window);
+        let email_entry = state.emails.get(&email);
+        let ip_entry = ip.and_then(|ip| state.ips.get(&ip));
+        if let Some(retry_after) = email_entry
+            .and_then(|e| e.retry_after(now))
+            .max(ip_entry.and_then(|e| e.retry_after(now)))
+        {
+            return Err(retry_after);
+        }
+        let full = |entry: Option<&Entry>, max: usize| {
+            max > 0 && entry.is_some_and(|e| e.attempts(now, window) >= max)
+        };
+        if full(email_entry, self.config.max_email_failures)
+            || full(ip_entry, self.config.max_ip_failures)
+        {
+            return Err(PENDING_RETRY_AFTER);
+        }
+
+        state.emails.entry(email.clone()).or_default().pending += 1;
+        if let Some(ip) = ip {
+            state.ips.entry(ip).or_default().pending += 1;
+        }
+        Ok(LoginAttempt {
+            limiter: self.clone(),
+            email,
+            ip,
+            done: false,
+        })
+    }
+
+    /// 记录一次失败，因此进入锁定时返回锁定时长
+    pub fn record_failure(&self, email: &str, ip: Option<IpAddr>) -> Option<Duration> {
+        let now = self.clock.now();
+        let LoginConfig {
+            max_email_failures,
+            max_ip_failures,
+            ..
+        } = self.config;
+        let (window, lockout) = (self.config.window(), self.config.lockout());
+
+        let mut state = self.lock();
+        state.prune(now, window);
+
+        let mut locked = state.emails.entry(normalize(email)).or_default().fail(
+            now,
+            max_email_failures,
+            window,
+            lockout,
+        );
+        if let Some(ip) = ip {
+            locked |= state
+                .ips
+                .entry(ip)
+                .or_default()
+                .fail(now, max_ip_failures, window, lockout);
+        }
+        locked.then_some(lockout)
+    }
+
+    /// 登录成功后清除该邮箱的失败记录；IP 的记录保留，避免攻击者用自己的账号重置计数
+    pub fn record_success(&self, email: &str) {
+        self.lock().emails.remove(&normalize(email));
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// 释放名额，没有失败记录的条目随之删除
+    fn release(&self, email: &str, ip: Option<IpAddr>) {
+        let (now, window) = (self.clock.now(), self.config.window());
+        let mut state = self.lock();
+        if let Some(entry) = state.emails.get_mut(email) {
+            entry.pending = entry.pending.saturating_sub(1);
+            if entry.is_stale(now, window) {
+                state.emails.remove(email);
+            }
+        }
+        if let Some(ip) = ip
+            && let Some(entry) = state.ips.get_mut(&ip)
+        {
+            entry.pending = entry.pending.saturating_sub(1);
+            if entry.is_stale(now, window) {
+                state.ips.remove(&ip);
+            }
+        }
+    }
+}
+
+impl State {
+    fn prune(&mut self, now: Duration, window: Duration) {
+        if self.emails.len() + self.ips.len() > MAX_ENTRIES {
+            self.emails.retain(|_, e| !e.is_stale(now, window));
+            self.ips.retain(|_, e| !e.is_stale(now, window));
+        }
+    }
+}
+
+impl LoginAttempt {
+    pub fn success(mut self) {
+        self.finish();
+        self.limiter.record_success(&self.email);
+    }
+
+    /// 计为一次失败，因此进入锁定时返回锁定时长
+    pub fn failure(mut self) -> Option<Duration> {
+        self.finish();
+        self.limiter.record_failure(&self.email, self.ip)
+    }
+
+    fn finish(&mut self) {
+        if !std::mem::replace(&mut self.done, true) {
+            self.limiter.release(&self.email, self.ip);
+        }
+    }
+}
+
+impl Drop for LoginAttempt {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+impl Entry {
+    fn attempts(&self, now: Duration, window: Duration) -> usize {
+        let failures = self
+            .failures
+            .iter()
+            .filter(|t| now.saturating_sub(**t) < window)
+            .count();
+        failures + self.pending
+    }
+
+    fn retry_after(&self, now: Duration) -> Option<Duration> {
+        self.locked_until
+            .filter(|until| *until > now)
+            .map(|until| until - now)
+    }
+
+    /// 上限为 0 时不限制
+    fn fail(&mut self, now: Duration, max: usize, window: Duration, lockout: Duration) -> bool {
+        while self
+            .failures
+            .front()
+            .is_some_and(|t| now.saturating_sub(*t) >= window)
+        {
+            self.failures.pop_front();
+        }
+        self.failures.push_back(now);
+        if max > 0 && self.failures.len() >= max {
+            self.failures.clear();
+            self.locked_until = Some(now + lockout);
+            return true;
+        }
+        false
+    }
+
+    fn is_stale(&self, now: Duration, window: Duration) -> bool {
+        self.pending == 0
+            && self.retry_after(now).is_none()
+            && self
+                .failures
+                .back()
+                .is_none_or(|t| now.saturating_sub(*t) >= window)
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for ClientIp {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let ip = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip());
+        Ok(Self(ip))
+    }
+}
+
+fn normalize(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use conc::metrics::ManualClock;
+
+    use super::*;
+
+    fn limiter() -> (LoginLimiter, ManualClock) {
+        let config = LoginConfig {
+            max_email_failures: 3,
+            max_ip_failures: 5,
+            window_secs: 60,
+            lockout_secs: 300,
+        };
+        let clock = ManualClock::new();
+        (LoginLimiter::with_clock(config, clock.clone()), clock)
+    }
+
+    #[test]
+    fn test_email_lockout() {
+        let (limiter, clock) = limiter();
+        let ip = Some(IpAddr::from([10, 0, 0, 1]));
+        assert_eq!(limiter.record_failure("a@acme.org", ip), None);
+        assert_eq!(limiter.record_failure("A@acme.org ", ip), None);
+        assert_eq!(limiter.check("a@acme.org", None), None);
+        assert_eq!(
+            limiter.record_failure("a@acme.org", ip),
+            Some(Duration::from_secs(300))
+        );
+
+        // 换一个 IP 也不能继续尝试这个邮箱
+        let other = Some(IpAddr::from([10, 0, 0, 2]));
+        assert_eq!(
+            limiter.check("a@acme.org", other),
+            Some(Duration::from_secs(300))
+        );
+        assert_eq!(limiter.check("b@acme.org", other), None);
+
+        clock.advance(Duration::from_secs(299));
+        assert_eq!(
+            limiter.check("a@acme.org", None),
+            Some(Duration::from_secs(1))
+        );
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(limiter.check("a@acme.org", None), None);
+    }
+
+    #[test]
+    fn test_failures_expire_with_window() {
+        let (limiter, clock) = limiter();
+        limiter.record_failure("a@acme.org", None);
+        limiter.record_failure("a@acme.org", None);
+        clock.advance(Duration::from_secs(60));
+        // 前两次已经滑出窗口
+        assert_eq!(limiter.record_failure("a@acme.org", None), None);
+        assert_eq!(limiter.record_failure("a@acme.org", None), None);
+        assert!(limiter.record_failure("a@acme.org", None).is_some());
+    }
+
+    #[test]
+    fn test_pending_attempts_count_against_limit() {
+        let (limiter, _) = limiter();
+        let ip = Some(IpAddr::from([10, 0, 0, 1]));
+
+        // 并发的请求在校验密码前就占满名额，不能绕过锁定
+        let attempts = (0..3)
+            .map(|_| limiter.try_acquire("a@acme.org", ip))
+            .collect::<Result<Vec<_>, _>>()
+            .expect("under the limit");
+        assert_eq!(
+            limiter.try_acquire("A@acme.org", None).err(),
+            Some(PENDING_RETRY_AFTER)
+        );
+        assert!(limiter.try_acquire("b@acme.org", ip).is_ok());
+
+        // 出错时 drop 只释放名额，不计为失败
+        let mut attempts = attempts.into_iter();
+        drop(attempts.next());
+        let retry = limiter
+            .try_acquire("a@acme.org", ip)
+            .expect("slot released");
+        for attempt in attempts {
+            assert_eq!(attempt.failure(), None);
+        }
+        assert_eq!(retry.failure(), Some(Duration::from_secs(300)));
+        assert_eq!(
+            limiter.try_acquire("a@acme.org", None).err(),
+            Some(Duration::from_secs(300))
+        );
+    }
+
+    #[test]
+    fn test_stale_entries_evicted() {
+        let (limiter, clock) = limiter();
+        let ip = Some(IpAddr::from([10, 0, 0, 1]));
+
+        // 只占用过名额、没有失败的记录在释放时删除
+        for i in 0..10 {
+            drop(limiter.try_acquire(&format!("user{}@acme.org", i), ip));
+        }
+        assert_eq!(limiter.lock().emails.len(), 0);
+        assert_eq!(limiter.lock().ips.len(), 0);
+
+        // 过期的失败记录在 try_acquire 时也会被清理
+        for i in 0..=MAX_ENTRIES {
+            limiter.record_failure(&format!("user{}@acme.org", i), None);
+        }
+        clock.advance(Duration::from_secs(60));
+        let attempt = limiter.try_acquire("a@acme.org", None);
+        assert!(attempt.is_ok());
+        assert_eq!(limiter.lock().emails.len(), 1);
+    }
+
+    #[test]
+    fn test_ip_lockout_and_success() {
+        let (limiter, _) = limiter();
+        let ip = Some(IpAddr::from([10, 0, 0, 1]));
+        for i in 0..4 {
+            limiter.record_failure(&format!("user{}@acme.org", i), ip);
+        }
+        limiter.record_success("user0@acme.org");
+        assert_eq!(limiter.check("user0@acme.org", ip), None);
+        // 成功登录不会重置 IP 的计数
+        assert!(limiter.record_failure("user9@acme.org", ip).is_some());
+        assert!(limiter.check("tchen@acme.org", ip).is_some());
+        assert_eq!(limiter.check("tchen@acme.org", None), None);
+    }
+}
@@ -1,4 +1,4 @@
-use std::sync::OnceLock;
+use std::{net::SocketAddr, sync::OnceLock};
 
 use anyhow::Result;
 use chat_server::{AppState, config::AppConfig, get_router};
@@ -62,6 +62,11 @@ async fn run() -> Result<()> {
     let listener = TcpListener::bind(&addr).await?;
     tracing::info!("Chat server is running on: {}", addr);
 
-    axum::serve(listener, app.into_make_service()).await?;
+    // 登录限流需要客户端地址
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
     Ok(())
 }
@@ -18,7 +18,7 @@ use crate::{
     requests::{
         chat::{CreateChatReq, DeleteChatReq, UpdateChatReq},
         message::{MessageListRequest, MessageSendRequest},
-        user::{SignInReq, SignUpReq},
+        user::{LogoutReq, RefreshReq, SignInReq, SignUpReq},
     },
 };
 
@@ -27,6 +27,8 @@ use crate::{
     paths(
         crate::handlers::auth::sign_up,
         crate::handlers::auth::sign_in,
+        crate::handlers::auth::refresh,
+        crate::handlers::auth::logout,
         crate::handlers::user::user_list,
         crate::handlers::chat::chat_create,
         crate::handlers::chat::chat_update,
@@ -41,6 +43,8 @@ use crate::{
         schemas(
             SignUpReq,
             SignInReq,
+            RefreshReq,
+            LogoutReq,
             User,
             ChatUser,
             Workspace,
@@ -0,0 +1,62 @@
+use std::net::IpAddr;
+
+use chat_core::{
+    error::AppError,
+    models::audit::{LoginAudit, LoginFailure},
+};
+use sqlx::PgPool;
+
+pub trait LoginAuditRepo: Sized {
+    fn record(
+        email: &str,
+        ip: Option<IpAddr>,
+        reason: LoginFailure,
+        db: &PgPool,
+    ) -> impl std::future::Future<Output = Result<(), AppError>> + Send;
+
+    fn find_by_email(
+        email: &str,
+        db: &PgPool,
+    ) -> impl std::future::Future<Output = Result<Vec<Self>, AppError>> + Send;
+}
+
+impl LoginAuditRepo for LoginAudit {
+    async fn record(
+        email: &str,
+        ip: Option<IpAddr>,
+        reason: LoginFailure,
+        db: &PgPool,
+    ) -> Result<(), AppError> {
+        // 邮箱来自用户输入，超出字段长度的部分截断
+        let email: String = email.chars().take(255).collect();
+        sqlx::query(
+            r#"
+            insert into login_audit (email, ip, reason)
+            values ($1, $2, $3)
+            "#,
+        )
+        .bind(email)
+        .bind(ip.map(|ip| ip.to_string()))
+        .bind(reason)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn find_by_email(email: &str, db: &PgPool) -> Result<Vec<Self>, AppError> {
+        let audits = sqlx::query_as(
+            r#"
+            select id, email, ip, reason, created_at
+            from login_audit
+            where email = $1
+            order by id
+            "#,
+        )
+        .bind(email)
+        .fetch_all(db)
+        .await?;
+
+        Ok(audits)
+    }
+}
@@ -1,5 +1,7 @@
+pub mod audit;
 pub mod chat;
 pub mod file;
 pub mod message;
+pub mod token;
 pub mod user;
 pub mod workspace;
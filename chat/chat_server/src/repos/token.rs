@@ -0,0 +1,197 @@
+use chat_core::{error::AppError, models::token::RefreshToken};
+use rand::RngCore as _;
+use sha2::{Digest, Sha256};
+use sqlx::{PgExecutor, PgPool};
+
+/// 刷新令牌的有效期，单位为天
+pub const REFRESH_TOKEN_DAYS: i32 = 30;
+
+pub trait RefreshTokenRepo: Sized {
+    /// 登录时签发，开始一个新的令牌族；返回记录和明文令牌
+    fn issue(
+        user_id: i64,
+        db: &PgPool,
+    ) -> impl std::future::Future<Output = Result<(Self, String), AppError>> + Send;
+
+    /// 用旧令牌换取新令牌，旧令牌随即失效。
+    /// 已失效的令牌再次出现说明可能已经泄露，此时注销整个令牌族
+    fn rotate(
+        token: &str,
+        db: &PgPool,
+    ) -> impl std::future::Future<Output = Result<Option<(Self, String)>, AppError>> + Send;
+
+    /// 注销令牌所在的整个令牌族，只处理属于 `user_id` 的令牌
+    fn revoke(
+        token: &str,
+        user_id: i64,
+        db: &PgPool,
+    ) -> impl std::future::Future<Output = Result<(), AppError>> + Send;
+
+    /// 注销访问令牌 `sid` 对应的令牌族
+    fn revoke_family(
+        family: &str,
+        user_id: i64,
+        db: &PgPool,
+    ) -> impl std::future::Future<Output = Result<(), AppError>> + Send;
+}
+
+impl RefreshTokenRepo for RefreshToken {
+    async fn issue(user_id: i64, db: &PgPool) -> Result<(Self, String), AppError> {
+        insert(user_id, &random_hex(16), db).await
+    }
+
+    async fn rotate(token: &str, db: &PgPool) -> Result<Option<(Self, String)>, AppError> {
+        let mut tx = db.begin().await?;
+        let used: Option<RefreshToken> = sqlx::query_as(
+            r#"
+            update refresh_tokens
+            set revoked_at = now()
+            where token_hash = $1 and revoked_at is null and expires_at > now()
+            returning id, user_id, family, expires_at, revoked_at, created_at
+            "#,
+        )
+        .bind(hash_token(token))
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if let Some(used) = used {
+            let rotated = insert(used.user_id, &used.family, &mut *tx).await?;
+            tx.commit().await?;
+            return Ok(Some(rotated));
+        }
+
+        let result = sqlx::query(
+            r#"
+            update refresh_tokens
+            set revoked_at = now()
+            where revoked_at is null and family in (
+              select family from refresh_tokens
+              where token_hash = $1 and revoked_at is not null
+            )
+            "#,
+        )
+        .bind(hash_token(token))
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        if result.rows_affected() > 0 {
+            tracing::warn!(target: "audit", "refresh token reused, token family revoked");
+        }
+
+        Ok(None)
+    }
+
+    async fn revoke(token: &str, user_id: i64, db: &PgPool) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            update refresh_tokens
+            set revoked_at = now()
+            where revoked_at is null and user_id = $2 and family in (
+              select family from refresh_tokens where token_hash = $1
+            )
+            "#,
+        )
+        .bind(hash_token(token))
+        .bind(user_id)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn revoke_family(family: &str, user_id: i64, db: &PgPool) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            update refresh_tokens
+            set revoked_at = now()
+            where revoked_at is null and user_id = $2 and family = $1
+            "#,
+        )
+        .bind(family)
+        .bind(user_id)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+}
+
+async fn insert(
+    user_id: i64,
+    family: &str,
+    db: impl PgExecutor<'_>,
+) -> Result<(RefreshToken, String), AppError> {
+    let token = random_hex(32);
+    let record = sqlx::query_as(
+        r#"
+        insert into refresh_tokens (user_id, family, token_hash, expires_at)
+        values ($1, $2, $3, now() + make_interval(days => $4))
+        returning id, user_id, family, expires_at, revoked_at, created_at
+        "#,
+    )
+    .bind(user_id)
+    .bind(family)
+    .bind(hash_token(&token))
+    .bind(REFRESH_TOKEN_DAYS)
+    .fetch_one(db)
+    .await?;
+
+    Ok((record, token))
+}
+
+fn random_hex(len: usize) -> String {
+    let mut bytes = vec![0; len];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+// 令牌本身是高熵随机数，不需要加盐或慢哈希
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AppState;
+
+    #[tokio::test]
+    async fn test_rotate_refresh_token() -> anyhow::Result<()> {
+        let (_tdb, state) = AppState::test_new().await?;
+        let db = &state.pg_pool;
+
+        let (first, token) = RefreshToken::issue(1, db).await?;
+        assert_eq!(token.len(), 64);
+        let (second, rotated) = RefreshToken::rotate(&token, db)
+            .await?
+            .expect("token is valid");
+        assert_eq!((second.user_id, &second.family), (1, &first.family));
+        assert_ne!(rotated, token);
+
+        // 旧令牌被重复使用，整个令牌族失效
+        assert!(RefreshToken::rotate(&token, db).await?.is_none());
+        assert!(RefreshToken::rotate(&rotated, db).await?.is_none());
+        assert!(RefreshToken::rotate("unknown", db).await?.is_none());
+
+        // 注销只影响当前用户的令牌族
+        let (_, token) = RefreshToken::issue(1, db).await?;
+        let (_, other) = RefreshToken::issue(2, db).await?;
+        RefreshToken::revoke(&token, 2, db).await?;
+        let (_, token) = RefreshToken::rotate(&token, db)
+            .await?
+            .expect("token is valid");
+        RefreshToken::revoke(&token, 1, db).await?;
+        assert!(RefreshToken::rotate(&token, db).await?.is_none());
+        let (second, other) = RefreshToken::rotate(&other, db)
+            .await?
+            .expect("token is valid");
+        RefreshToken::revoke_family(&second.family, 1, db).await?;
+        let (_, other) = RefreshToken::rotate(&other, db)
+            .await?
+            .expect("token is valid");
+        RefreshToken::revoke_family(&second.family, 2, db).await?;
+        assert!(RefreshToken::rotate(&other, db).await?.is_none());
+
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use std::mem;
+use std::{mem, sync::OnceLock};
 
 use chat_core::{
     error::AppError,
@@ -33,6 +33,11 @@ pub trait UserRepo: Sized {
         db: &PgPool,
     ) -> impl std::future::Future<Output = Result<Option<Self>, AppError>> + Send;
 
+    fn find_by_id(
+        id: i64,
+        db: &PgPool,
+    ) -> impl std::future::Future<Output = Result<Option<Self>, AppError>> + Send;
+
     fn verify(
         input: &SignInReq,
         db: &PgPool,
@@ -64,6 +69,8 @@ impl UserRepo for User {
             }
         };
 
+        let password = input.password.clone();
+        let password_hash = blocking(move || hash_password(&password)).await?;
         let user = sqlx::query_as(
             r#"
             insert into users
@@ -74,7 +81,7 @@ impl UserRepo for User {
         )
         .bind(&input.username)
         .bind(&input.email)
-        .bind(hash_password(&input.password)?)
+        .bind(password_hash)
         .bind(workspace.id)
         .fetch_one(db)
         .await?;
@@ -97,6 +104,21 @@ impl UserRepo for User {
         Ok(user)
     }
 
+    async fn find_by_id(id: i64, db: &PgPool) -> Result<Option<Self>, AppError> {
+        let user = sqlx::query_as(
+            r#"
+            select id, username, email, workspace_id, created_at
+            from users
+            where id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(db)
+        .await?;
+
+        Ok(user)
+    }
+
     async fn verify(input: &SignInReq, db: &PgPool) -> Result<Option<Self>, AppError> {
         let user: Option<User> = sqlx::query_as(
             r#"
@@ -109,10 +131,17 @@ impl UserRepo for User {
         .fetch_optional(db)
         .await?;
 
-        if let Some(mut user) = user {
-            let password_hash = mem::take(&mut user.password);
-            if verify_password(&input.password, &password_hash)? {
-                return Ok(Some(user));
+        let password = input.password.clone();
+        match user {
+            Some(mut user) => {
+                let password_hash = mem::take(&mut user.password);
+                if blocking(move || verify_password(&password, &password_hash)).await? {
+                    return Ok(Some(user));
+                }
+            }
+            None => {
+                // 用户不存在时同样计算一次哈希，避免通过响应时间判断邮箱是否已注册
+                blocking(move || verify_password(&password, dummy_hash()?)).await?;
             }
         }
 
@@ -148,6 +177,17 @@ impl UserRepo for User {
     }
 }
 
+// argon2 每次要几十毫秒，放到阻塞线程池中执行，避免占住 tokio 的工作线程
+async fn blocking<T, F>(f: F) -> Result<T, AppError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, AppError> + Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| AppError::InternalError(e.to_string()))?
+}
+
 fn hash_password(password: &str) -> Result<String, AppError> {
     let salt = SaltString::generate(&mut rand::thread_rng());
     let argon2 = Argon2::default();
@@ -156,6 +196,15 @@ fn hash_password(password: &str) -> Result<String, AppError> {
     Ok(hashed_password.to_string())
 }
 
+fn dummy_hash() -> Result<&'static str, AppError> {
+    static HASH: OnceLock<String> = OnceLock::new();
+    if let Some(hash) = HASH.get() {
+        return Ok(hash);
+    }
+    let hash = hash_password("dummy password")?;
+    Ok(HASH.get_or_init(|| hash))
+}
+
 fn verify_password(password: &str, password_hash: &str) -> Result<bool, AppError> {
     let argon2 = Argon2::default();
     let parsed_hash = PasswordHash::new(password_hash)?;
@@ -203,6 +252,17 @@ mod tests {
         let user = User::verify(&input, &db).await?.expect("user not found");
         assert_eq!(user.email, input.email);
 
+        let wrong = SignInReq {
+            email: input.email.clone(),
+            password: "wrong".to_string(),
+        };
+        assert!(User::verify(&wrong, &db).await?.is_none());
+        let unknown = SignInReq {
+            email: "nobody@example.com".to_string(),
+            password: input.password.clone(),
+        };
+        assert!(User::verify(&unknown, &db).await?.is_none());
+
         Ok(())
     }
 }
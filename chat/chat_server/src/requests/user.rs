@@ -14,3 +14,15 @@ pub struct SignInReq {
     pub email: String,
     pub password: String,
 }
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RefreshReq {
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct LogoutReq {
+    /// 当前会话的令牌族总会被注销，这里可以额外指定另一个刷新令牌
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+}
@@ -3,5 +3,10 @@ use utoipa::ToSchema;
 
 #[derive(Debug, Serialize, ToSchema)]
 pub struct AuthOutput {
+    /// 访问令牌
     pub token: String,
+    /// 访问令牌的有效期，单位为秒
+    pub expires_in: u64,
+    /// 访问令牌过期后用于换取新的令牌，每个只能使用一次
+    pub refresh_token: String,
 }
@@ -0,0 +1,20 @@
+-- Add migration script here
+-- failed sign-in attempts
+drop type if exists login_failure cascade;
+create type login_failure as enum (
+  'invalid_credentials',
+  'locked_out'
+);
+
+drop table if exists login_audit;
+create table login_audit (
+  id bigserial primary key,
+  -- email as submitted, the user may not exist
+  email varchar(255) not null default '',
+  ip varchar(64),
+  reason login_failure not null,
+  created_at timestamptz default current_timestamp
+);
+
+create index login_audit_email_idx on login_audit (email, created_at desc);
+create index login_audit_ip_idx on login_audit (ip, created_at desc);
@@ -0,0 +1,26 @@
+-- Add migration script here
+-- rotating refresh tokens, only the sha256 of each token is stored
+drop table if exists refresh_tokens;
+create table refresh_tokens (
+  id bigserial primary key,
+  user_id bigint not null,
+  -- tokens rotated from the same sign-in share a family
+  family varchar(64) not null,
+  token_hash char(64) not null unique,
+  expires_at timestamptz not null,
+  -- set when the token is rotated or the session logs out
+  revoked_at timestamptz,
+  created_at timestamptz default current_timestamp
+);
+
+create index refresh_tokens_family_idx on refresh_tokens (family);
+
+-- jti of logged-out access tokens, kept until the token expires
+drop table if exists revoked_tokens;
+create table revoked_tokens (
+  jti varchar(64) primary key,
+  expires_at timestamptz not null,
+  created_at timestamptz default current_timestamp
+);
+
+create index revoked_tokens_expires_at_idx on revoked_tokens (expires_at);
@@ -0,0 +1,17 @@
+-- Add migration script here
+-- notify every service when an access token is revoked, so they can update their in-memory denylist
+CREATE OR REPLACE FUNCTION token_revoked()
+  RETURNS TRIGGER
+  AS $$
+BEGIN
+  PERFORM
+    pg_notify('token_revoked', json_build_object('jti', NEW.jti, 'expires_at', NEW.expires_at)::text);
+  RETURN NEW;
+END;
+$$
+LANGUAGE plpgsql;
+
+CREATE TRIGGER token_revoked_trigger
+  AFTER INSERT ON revoked_tokens
+  FOR EACH ROW
+  EXECUTE FUNCTION token_revoked();
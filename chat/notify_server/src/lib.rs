@@ -3,10 +3,14 @@ use axum::{Router, middleware::from_fn_with_state, response::Html, routing};
 use chat_core::{
     error::AppError,
     middlewares::{TokenVerify, auth},
-    models::user::User,
-    utils::jwt::DecodingKey,
+    utils::{
+        denylist::TokenDenylist,
+        jwt::{DecodingKey, TokenClaims},
+    },
 };
+use conc::metrics::{Registry, http::HttpMetrics};
 use dashmap::DashMap;
+use sqlx::PgPool;
 use std::{ops::Deref, sync::Arc};
 use tokio::sync::broadcast;
 
@@ -23,17 +27,21 @@ pub struct AppStateInner {
     config: AppConfig,
     users: Arc<DashMap<i64, broadcast::Sender<Arc<notify::AppEvent>>>>,
     verify_key: DecodingKey,
+    // 与 chat_server 共享，拒绝已注销的令牌
+    denylist: TokenDenylist,
 }
 
 impl AppState {
     pub async fn try_new(config: AppConfig) -> Result<Self> {
         let users = Arc::new(DashMap::new());
         let verify_key = DecodingKey::load(&config.auth.verify_key)?;
+        let denylist = TokenDenylist::new(PgPool::connect(&config.server.pg_url).await?);
 
         Ok(Self(Arc::new(AppStateInner {
             config,
             users,
             verify_key,
+            denylist,
         })))
     }
 }
@@ -41,9 +49,13 @@ impl AppState {
 impl TokenVerify for AppState {
     type Error = AppError;
 
-    fn verify(&self, token: &str) -> Result<User, Self::Error> {
+    fn verify(&self, token: &str) -> Result<TokenClaims, Self::Error> {
         Ok(self.verify_key.verify(token)?)
     }
+
+    async fn is_revoked(&self, jti: &str) -> Result<bool, Self::Error> {
+        self.denylist.contains(jti).await
+    }
 }
 
 impl Deref for AppState {
@@ -57,6 +69,7 @@ impl Deref for AppState {
 pub async fn get_router(state: AppState) -> Result<Router> {
     notify::setup_pg_listener(state.clone()).await?;
 
+    let metrics = HttpMetrics::new(Registry::new())?;
     let router = Router::new()
         .route("/events", routing::get(sse_handler))
         .layer(from_fn_with_state(
@@ -64,7 +77,9 @@ pub async fn get_router(state: AppState) -> Result<Router> {
             auth::verify_token::<AppState>,
         ))
         .route("/", routing::get(index_handler))
-        .with_state(state);
+        .with_state(state)
+        .merge(metrics.router())
+        .layer(metrics.layer());
 
     Ok(router)
 }
@@ -5,7 +5,7 @@ use axum::{
     extract::State,
     response::{Sse, sse::Event},
 };
-use chat_core::models::user::User;
+use chat_core::utils::jwt::TokenClaims;
 use futures_util::stream::Stream;
 use tokio::sync::broadcast;
 use tokio_stream::{StreamExt, wrappers::BroadcastStream};
@@ -14,11 +14,12 @@ use crate::{AppState, notify::AppEvent};
 
 const CHANNEL_CAPACITY: usize = 256;
 
+/// 令牌只在建立连接时验证；之后令牌被注销时关闭连接，客户端需要用新令牌重连
 pub async fn sse_handler(
     State(state): State<AppState>,
-    Extension(user): Extension<User>,
+    Extension(claims): Extension<TokenClaims>,
 ) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
-    let user_id = user.id;
+    let user_id = claims.user.id;
     let users = &state.users;
 
     let rx = if let Some(tx) = users.get(&user_id) {
@@ -47,6 +48,10 @@ pub async fn sse_handler(
         }
     });
 
+    let denylist = state.denylist.clone();
+    let revoked = async move { denylist.wait_revoked(&claims.jti).await };
+    let stream = futures_util::StreamExt::take_until(stream, revoked);
+
     Sse::new(stream).keep_alive(
         axum::response::sse::KeepAlive::new()
             .interval(Duration::from_secs(1))
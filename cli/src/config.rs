@@ -0,0 +1,174 @@
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use clap::Command;
+use toml::{Table, Value};
+
+/// 配置文件中表的层级与子命令对应，键为参数名，例如：
+///
+/// ```toml
+/// [text]
+/// format = "ed25519"
+///
+/// [text.sign]
+/// key = "~/.config/rcli/ed25519_signing.key"
+///
+/// [http.serve]
+/// port = 9000
+/// ```
+///
+/// 父表中的值对所有子命令生效，命令行参数优先于配置
+pub fn load(path: &Path) -> anyhow::Result<Table> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read config {}", path.display()))?;
+    toml::from_str(&content).with_context(|| format!("invalid config {}", path.display()))
+}
+
+/// `--config <PATH>`，其次是 `RCLI_CONFIG`，最后是 `~/.config/rcli.toml`；
+/// 只有显式指定的文件不存在时才报错
+pub fn load_from_args(args: &[OsString]) -> anyhow::Result<Table> {
+    let explicit = args
+        .iter()
+        .enumerate()
+        .find_map(|(i, arg)| {
+            let arg = arg.to_str()?;
+            match arg.strip_prefix("--config") {
+                Some("") => args.get(i + 1).map(PathBuf::from),
+                Some(value) => value.strip_prefix('=').map(PathBuf::from),
+                None => None,
+            }
+        })
+        .or_else(|| std::env::var_os("RCLI_CONFIG").map(PathBuf::from));
+
+    match explicit {
+        Some(path) => load(&path),
+        None => match default_path() {
+            Some(path) if path.is_file() => load(&path),
+            _ => Ok(Table::new()),
+        },
+    }
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    std::env::home_dir().map(|home| home.join(".config").join("rcli.toml"))
+}
+
+/// 把配置中的值设置为对应参数的默认值
+pub fn apply_defaults(cmd: Command, config: &Table) -> Command {
+    apply(cmd, config, &BTreeMap::new())
+}
+
+fn apply(mut cmd: Command, table: &Table, inherited: &BTreeMap<String, Vec<String>>) -> Command {
+    let mut values = inherited.clone();
+    for (key, value) in table {
+        if !value.is_table() {
+            values.insert(key.replace('-', "_"), to_strings(value));
+        }
+    }
+
+    let ids = cmd
+        .get_arguments()
+        .map(|arg| arg.get_id().to_string())
+        .filter(|id| values.contains_key(id))
+        .collect::<Vec<_>>();
+    for id in ids {
+        let defaults = values[&id].clone();
+        cmd = cmd.mut_arg(id, |arg| arg.default_values(defaults).required(false));
+    }
+
+    let names = cmd
+        .get_subcommands()
+        .map(|sub| sub.get_name().to_string())
+        .collect::<Vec<_>>();
+    for name in names {
+        let empty = Table::new();
+        let sub_table = table.get(&name).and_then(Value::as_table).unwrap_or(&empty);
+        cmd = cmd.mut_subcommand(name, |sub| apply(sub, sub_table, &values));
+    }
+    cmd
+}
+
+fn to_strings(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(s) => vec![expand_home(s)],
+        Value::Array(items) => items.iter().flat_map(to_strings).collect(),
+        value => vec![value.to_string()],
+    }
+}
+
+fn expand_home(value: &str) -> String {
+    match (value.strip_prefix("~/"), std::env::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest).to_string_lossy().to_string(),
+        _ => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::{CommandFactory, FromArgMatches};
+
+    use super::*;
+    use crate::opts::{Args, SubCommand, http::HttpCmd, text::TextCmd};
+
+    fn parse(config: &str, args: &[&str]) -> anyhow::Result<Args> {
+        let config: Table = toml::from_str(config)?;
+        let cmd = apply_defaults(Args::command(), &config);
+        let matches =
+            cmd.try_get_matches_from(std::iter::once("cli").chain(args.iter().copied()))?;
+        Ok(Args::from_arg_matches(&matches)?)
+    }
+
+    #[test]
+    fn test_apply_defaults() -> anyhow::Result<()> {
+        let config = r#"
+            [http]
+            port = 9000
+
+            [text.sign]
+            key = "fixtures/base64_encode.txt"
+        "#;
+
+        let args = parse(config, &["http", "serve"])?;
+        let SubCommand::Http(HttpCmd::Serve(opts)) = args.cmd else {
+            panic!("expected http serve");
+        };
+        assert_eq!(opts.port, 9000);
+
+        // 命令行参数优先
+        let args = parse(config, &["http", "serve", "--port", "7000"])?;
+        let SubCommand::Http(HttpCmd::Serve(opts)) = args.cmd else {
+            panic!("expected http serve");
+        };
+        assert_eq!(opts.port, 7000);
+
+        // 原本必填的 --key 可以由配置提供
+        let args = parse(config, &["text", "sign"])?;
+        let SubCommand::Text(TextCmd::Sign { key, .. }) = args.cmd else {
+            panic!("expected text sign");
+        };
+        assert_eq!(key, "fixtures/base64_encode.txt");
+        assert!(parse("", &["text", "sign"]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_from_args() -> anyhow::Result<()> {
+        let path = std::env::temp_dir().join(format!("cli-config-{}.toml", std::process::id()));
+        std::fs::write(&path, "[genpass]\nlength = 32\n")?;
+
+        let args = ["cli", "--config", path.to_str().unwrap(), "genpass"].map(OsString::from);
+        let config = load_from_args(&args)?;
+        assert_eq!(config["genpass"]["length"].as_integer(), Some(32));
+
+        let arg = format!("--config={}", path.display());
+        assert!(load_from_args(&[OsString::from(arg)]).is_ok());
+        assert!(load_from_args(&["--config".into(), "missing.toml".into()]).is_err());
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+}
@@ -1,8 +1,42 @@
+pub mod config;
 pub mod opts;
 pub mod process;
 pub mod utils;
 
+use std::fmt::Display;
+
+use serde::Serialize;
+
+/// 全局的 `--quiet`/`--json` 输出模式
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum OutputMode {
+    #[default]
+    Text,
+    Json,
+    Quiet,
+}
+
 #[allow(async_fn_in_trait)]
 pub trait CmdExecutor {
-    async fn execute(&self) -> anyhow::Result<()>;
+    async fn execute(&self, output: OutputMode) -> anyhow::Result<()>;
+}
+
+impl OutputMode {
+    /// 打印命令的结果：text 模式打印 `text`，json 模式打印单行 JSON，quiet 模式不输出
+    pub fn emit(&self, text: impl Display, json: impl Serialize) -> anyhow::Result<()> {
+        match self {
+            OutputMode::Text => println!("{}", text),
+            OutputMode::Json => println!("{}", serde_json::to_string(&json)?),
+            OutputMode::Quiet => {}
+        }
+        Ok(())
+    }
+
+    /// 只在 json 模式下输出，用于 text 模式下原本没有输出的命令
+    pub fn emit_json(&self, json: impl Serialize) -> anyhow::Result<()> {
+        if *self == OutputMode::Json {
+            println!("{}", serde_json::to_string(&json)?);
+        }
+        Ok(())
+    }
 }
@@ -1,8 +1,11 @@
-use clap::Parser;
-use cli::{CmdExecutor, opts};
+use clap::{CommandFactory, FromArgMatches};
+use cli::{CmdExecutor, config, opts};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let opts = opts::Args::parse();
-    opts.cmd.execute().await
+    let args = std::env::args_os().collect::<Vec<_>>();
+    let table = config::load_from_args(&args)?;
+    let cmd = config::apply_defaults(opts::Args::command(), &table);
+    let opts = opts::Args::from_arg_matches(&cmd.get_matches_from(args)).map_err(|e| e.exit())?;
+    opts.cmd.execute(opts.output_mode()).await
 }
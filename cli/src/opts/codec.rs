@@ -0,0 +1,64 @@
+use clap::{Args, Parser, ValueEnum};
+use serde::Serialize;
+
+use crate::{CmdExecutor, OutputMode, opts, process::codec};
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Codec {
+    Base64,
+    Hex,
+    /// RFC 4648，带 `=` 填充
+    Base32,
+    /// 比特币字母表
+    Base58,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Base64Format {
+    Standard,
+    UrlSafe,
+    #[value(alias = "urlsafe")]
+    UrlSafeNoPad,
+    /// 标准字母表，每 76 个字符换行（RFC 2045）
+    Mime,
+}
+
+#[derive(Debug, Parser)]
+pub enum CodecCmd {
+    // cargo run --package cli -- codec encode --input cli/fixtures/base64_encode.txt --format urlsafe
+    // cargo run --package cli -- codec encode --input cli/assets/wordlist.txt --codec base58
+    #[command(name = "encode", about = "encode bytes to text")]
+    Encode(CodecOpts),
+
+    // cargo run --package cli -- codec decode --input cli/fixtures/base64_decode.txt --format urlsafe
+    // cargo run --package cli -- codec decode --input image.hex --codec hex --output image.png
+    #[command(name = "decode", about = "decode text to bytes")]
+    Decode(CodecOpts),
+}
+
+#[derive(Debug, Args)]
+pub struct CodecOpts {
+    #[arg(long, value_parser = opts::verify_file, default_value = "-")]
+    pub input: String,
+
+    /// `-` 表示 stdout，解码后的二进制数据原样写出
+    #[arg(long, default_value = "-")]
+    pub output: String,
+
+    #[arg(long, value_enum, default_value = "base64")]
+    pub codec: Codec,
+
+    /// 只对 base64 有效
+    #[arg(long, value_enum, default_value = "standard")]
+    pub format: Base64Format,
+}
+
+impl CmdExecutor for CodecCmd {
+    async fn execute(&self, output: OutputMode) -> anyhow::Result<()> {
+        match self {
+            CodecCmd::Encode(opts) => codec::process_encode(opts, output).await,
+            CodecCmd::Decode(opts) => codec::process_decode(opts, output).await,
+        }
+    }
+}
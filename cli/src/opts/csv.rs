@@ -1,21 +1,68 @@
-use clap::{Parser, ValueEnum};
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
+use serde_json::json;
 
-use crate::{CmdExecutor, opts};
+use crate::{CmdExecutor, OutputMode, opts};
 
-#[derive(Clone, Debug, ValueEnum)]
+#[derive(Clone, Copy, Debug, ValueEnum)]
 pub enum OutputFormat {
     Json,
+    Ndjson,
     Toml,
     Yaml,
+    Parquet,
+    Arrow,
+    Markdown,
 }
 
+/// `csv from` 支持的输入格式
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum InputFormat {
+    Json,
+    Ndjson,
+    Toml,
+    Yaml,
+}
+
+/// `--select name:alias`，alias 为空时保留原列名
+#[derive(Clone, Debug, PartialEq)]
+pub struct Selection {
+    pub column: String,
+    pub alias: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FilterOp {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Contains,
+}
+
+/// `--where column<op>value`，op 为 ==(=), !=, >, >=, <, <=, ~=（包含）
+#[derive(Clone, Debug, PartialEq)]
+pub struct Filter {
+    pub column: String,
+    pub op: FilterOp,
+    pub value: String,
+}
+
+// cargo run --package cli -- csv --input assets/juventus.csv --format yaml --output -
+// cargo run --package cli -- csv --input assets/juventus.csv --format markdown --select "Name,Kit Number:kit" --where "Kit Number>=30"
 #[derive(Debug, Parser)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 pub struct CsvCmd {
-    #[arg(long, value_parser = opts::verify_file)]
-    pub input: String,
+    #[command(subcommand)]
+    pub cmd: Option<CsvSubCmd>,
 
-    #[arg(long, default_value = "output.json")]
-    pub output: String,
+    #[arg(long, value_parser = opts::verify_file, required = true)]
+    pub input: Option<String>,
+
+    /// 输出文件，`-` 表示 stdout，默认为 output.<format>
+    #[arg(long)]
+    pub output: Option<String>,
 
     #[arg(long, value_parser = parse_format, default_value = "json")]
     pub format: OutputFormat,
@@ -23,21 +70,214 @@ pub struct CsvCmd {
     #[arg(long, default_value_t = ',')]
     pub delimiter: char,
 
-    #[arg(long, default_value_t = true)]
+    /// 第一行是否为表头，为 false 时列名为 column_1, column_2...
+    #[arg(long, default_value_t = true, action = ArgAction::Set)]
     pub header: bool,
+
+    /// 输出的列及其别名，如 `name,kit number:kit`
+    #[arg(long, value_parser = parse_selection, value_delimiter = ',')]
+    pub select: Vec<Selection>,
+
+    /// 过滤条件，可以重复使用，多个条件同时满足
+    #[arg(long = "where", value_parser = parse_filter)]
+    pub filters: Vec<Filter>,
+
+    /// 用于推断列类型的行数
+    #[arg(long, default_value_t = 1000)]
+    pub infer_rows: usize,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CsvSubCmd {
+    #[command(name = "from", about = "convert JSON, YAML or TOML back to CSV")]
+    From(CsvFromCmd),
+}
+
+// cargo run --package cli -- csv from --input output.json --format json --output -
+// cat output.yaml | cargo run --package cli -- csv from --input - --format yaml
+#[derive(Debug, Parser)]
+pub struct CsvFromCmd {
+    #[arg(long, value_parser = opts::verify_file, default_value = "-")]
+    pub input: String,
+
+    /// 输出文件，`-` 表示 stdout
+    #[arg(long, default_value = "output.csv")]
+    pub output: String,
+
+    #[arg(long, value_parser = parse_input_format, default_value = "json")]
+    pub format: InputFormat,
+
+    #[arg(long, default_value_t = ',')]
+    pub delimiter: char,
 }
 
 impl CmdExecutor for CsvCmd {
-    async fn execute(&self) -> anyhow::Result<()> {
-        crate::process::csv::process_csv(&self.input, &self.output, &self.format).await
+    async fn execute(&self, output: OutputMode) -> anyhow::Result<()> {
+        match &self.cmd {
+            Some(CsvSubCmd::From(cmd)) => {
+                crate::process::csv::process_from(cmd).await?;
+                output.emit_json(json!({ "input": cmd.input, "output": cmd.output }))
+            }
+            None => {
+                crate::process::csv::process_csv(self).await?;
+                output.emit_json(json!({ "input": self.input, "output": self.output_path() }))
+            }
+        }
+    }
+}
+
+impl CsvCmd {
+    /// 未指定 `--output` 时为 output.<format>
+    pub fn output_path(&self) -> String {
+        match &self.output {
+            Some(output) => output.clone(),
+            None => format!("output.{}", self.format.extension()),
+        }
+    }
+}
+
+impl OutputFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Json => "json",
+            OutputFormat::Ndjson => "ndjson",
+            OutputFormat::Toml => "toml",
+            OutputFormat::Yaml => "yaml",
+            OutputFormat::Parquet => "parquet",
+            OutputFormat::Arrow => "arrow",
+            OutputFormat::Markdown => "md",
+        }
     }
 }
 
 fn parse_format(format: &str) -> Result<OutputFormat, String> {
     match format {
         "json" => Ok(OutputFormat::Json),
+        "ndjson" | "jsonl" => Ok(OutputFormat::Ndjson),
         "yaml" => Ok(OutputFormat::Yaml),
         "toml" => Ok(OutputFormat::Toml),
+        "parquet" => Ok(OutputFormat::Parquet),
+        "arrow" => Ok(OutputFormat::Arrow),
+        "markdown" | "md" => Ok(OutputFormat::Markdown),
         _ => Err(format!("Unknown output format: {}", format)),
     }
 }
+
+pub fn parse_input_format(format: &str) -> Result<InputFormat, String> {
+    match format {
+        "json" => Ok(InputFormat::Json),
+        "ndjson" | "jsonl" => Ok(InputFormat::Ndjson),
+        "yaml" | "yml" => Ok(InputFormat::Yaml),
+        "toml" => Ok(InputFormat::Toml),
+        _ => Err(format!("Unknown input format: {}", format)),
+    }
+}
+
+fn parse_selection(selection: &str) -> Result<Selection, String> {
+    let (column, alias) = match selection.split_once(':') {
+        Some((column, alias)) => (column.trim(), Some(alias.trim().to_string())),
+        None => (selection.trim(), None),
+    };
+    if column.is_empty() {
+        return Err(format!("Invalid selection: {}", selection));
+    }
+
+    Ok(Selection {
+        column: column.to_string(),
+        alias: alias.filter(|a| !a.is_empty()),
+    })
+}
+
+fn parse_filter(filter: &str) -> Result<Filter, String> {
+    // 两个字符的运算符需要先匹配
+    const OPS: [(&str, FilterOp); 8] = [
+        ("==", FilterOp::Eq),
+        ("!=", FilterOp::Ne),
+        (">=", FilterOp::Ge),
+        ("<=", FilterOp::Le),
+        ("~=", FilterOp::Contains),
+        (">", FilterOp::Gt),
+        ("<", FilterOp::Lt),
+        ("=", FilterOp::Eq),
+    ];
+
+    OPS.iter()
+        .filter_map(|(token, op)| filter.find(token).map(|pos| (pos, token, *op)))
+        .min_by_key(|(pos, token, _)| (*pos, usize::MAX - token.len()))
+        .and_then(|(pos, token, op)| {
+            let column = filter[..pos].trim();
+            let value = filter[pos + token.len()..].trim();
+            (!column.is_empty()).then(|| Filter {
+                column: column.to_string(),
+                op,
+                value: value.to_string(),
+            })
+        })
+        .ok_or_else(|| format!("Invalid filter: {}", filter))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_csv_from() {
+        let cmd = CsvCmd::parse_from(["csv", "from", "--input", "-", "--format", "yml"]);
+        match cmd.cmd {
+            Some(CsvSubCmd::From(from)) => {
+                assert_eq!(from.input, "-");
+                assert_eq!(from.format, InputFormat::Yaml);
+                assert_eq!(from.output, "output.csv");
+            }
+            None => panic!("expected csv from"),
+        }
+
+        let cmd = CsvCmd::parse_from(["csv", "--input", "-"]);
+        assert!(cmd.cmd.is_none());
+        assert_eq!(cmd.input.as_deref(), Some("-"));
+
+        assert!(CsvCmd::try_parse_from(["csv"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_selection() {
+        assert_eq!(
+            parse_selection("Kit Number:kit"),
+            Ok(Selection {
+                column: "Kit Number".to_string(),
+                alias: Some("kit".to_string()),
+            })
+        );
+        assert_eq!(
+            parse_selection("name"),
+            Ok(Selection {
+                column: "name".to_string(),
+                alias: None,
+            })
+        );
+        assert!(parse_selection(":kit").is_err());
+    }
+
+    #[test]
+    fn test_parse_filter() {
+        assert_eq!(
+            parse_filter("age >= 30"),
+            Ok(Filter {
+                column: "age".to_string(),
+                op: FilterOp::Ge,
+                value: "30".to_string(),
+            })
+        );
+        assert_eq!(
+            parse_filter("name~=Bu"),
+            Ok(Filter {
+                column: "name".to_string(),
+                op: FilterOp::Contains,
+                value: "Bu".to_string(),
+            })
+        );
+        assert_eq!(parse_filter("a<b").map(|f| f.op), Ok(FilterOp::Lt));
+        assert!(parse_filter("age").is_err());
+        assert!(parse_filter(">30").is_err());
+    }
+}
@@ -0,0 +1,130 @@
+use std::path::Path;
+
+use clap::{Args, Parser};
+
+pub use super::csv::InputFormat as DataFormat;
+use crate::{
+    CmdExecutor, OutputMode,
+    opts::{self, csv::parse_input_format},
+    process::data,
+};
+
+#[derive(Debug, Parser)]
+pub enum DataCmd {
+    // cargo run --package cli -- data query --input users.yaml '.users[] | select(.age > 30) | .name'
+    // cargo run --package cli -- data query --input Cargo.toml --in-place '.package.version = "0.2.0"'
+    #[command(
+        name = "query",
+        about = "query or transform JSON, YAML, TOML or NDJSON with a jq-like filter"
+    )]
+    Query(DataQueryOpts),
+
+    // cargo run --package cli -- data convert --input config.yaml --to toml
+    #[command(
+        name = "convert",
+        about = "convert between JSON, YAML, TOML and NDJSON"
+    )]
+    Convert(DataIoOpts),
+}
+
+#[derive(Debug, Args)]
+pub struct DataQueryOpts {
+    /// jq 风格的过滤器
+    #[arg(default_value = ".")]
+    pub filter: String,
+
+    #[command(flatten)]
+    pub io: DataIoOpts,
+
+    /// 把结果按原格式写回输入文件，过滤器必须只产生一个结果（NDJSON 除外）。
+    /// TOML 只改写有变化的键，保留注释和格式；YAML 含有注释时拒绝改写
+    #[arg(long, short = 'i', conflicts_with_all = ["output", "to"])]
+    pub in_place: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct DataIoOpts {
+    #[arg(long, value_parser = opts::verify_file, default_value = "-")]
+    pub input: String,
+
+    /// `-` 表示 stdout
+    #[arg(long, default_value = "-")]
+    pub output: String,
+
+    /// 输入格式，默认根据扩展名判断，stdin 默认为 json
+    #[arg(long, value_parser = parse_input_format)]
+    pub from: Option<DataFormat>,
+
+    /// 输出格式，默认根据输出文件的扩展名判断，否则与输入相同
+    #[arg(long, value_parser = parse_input_format)]
+    pub to: Option<DataFormat>,
+
+    /// 字符串结果不加引号直接输出
+    #[arg(long, short = 'r')]
+    pub raw_output: bool,
+
+    /// JSON 输出不换行缩进
+    #[arg(long, short = 'c')]
+    pub compact: bool,
+}
+
+impl DataIoOpts {
+    pub fn input_format(&self) -> DataFormat {
+        self.from
+            .or_else(|| infer_format(&self.input))
+            .unwrap_or(DataFormat::Json)
+    }
+
+    pub fn output_format(&self) -> DataFormat {
+        self.to
+            .or_else(|| infer_format(&self.output))
+            .unwrap_or_else(|| self.input_format())
+    }
+}
+
+impl CmdExecutor for DataCmd {
+    async fn execute(&self, output: OutputMode) -> anyhow::Result<()> {
+        match self {
+            DataCmd::Query(opts) => {
+                data::process_query(&opts.filter, &opts.io, opts.in_place, output).await
+            }
+            DataCmd::Convert(opts) => data::process_query(".", opts, false, output).await,
+        }
+    }
+}
+
+fn infer_format(path: &str) -> Option<DataFormat> {
+    let ext = Path::new(path).extension()?.to_str()?.to_ascii_lowercase();
+    parse_input_format(&ext).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_data_query() {
+        let cmd = DataCmd::parse_from(["data", "query", "--input", "-", ".a"]);
+        let DataCmd::Query(opts) = cmd else {
+            panic!("expected data query");
+        };
+        assert_eq!(opts.filter, ".a");
+        assert_eq!(opts.io.input_format(), DataFormat::Json);
+
+        let cmd = DataCmd::parse_from(["data", "convert", "--input", "-", "--output", "x.yml"]);
+        let DataCmd::Convert(opts) = cmd else {
+            panic!("expected data convert");
+        };
+        assert_eq!(opts.output_format(), DataFormat::Yaml);
+
+        assert!(DataCmd::try_parse_from(["data", "query", "-i", "--to", "toml"]).is_err());
+    }
+
+    #[test]
+    fn test_infer_format() {
+        assert_eq!(infer_format("a/b.JSONL"), Some(DataFormat::Ndjson));
+        assert_eq!(infer_format("Cargo.toml"), Some(DataFormat::Toml));
+        assert_eq!(infer_format("-"), None);
+        assert_eq!(infer_format("data.csv"), None);
+    }
+}
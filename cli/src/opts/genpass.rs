@@ -1,37 +1,153 @@
 use clap::Parser;
+use serde_json::json;
 
-use crate::CmdExecutor;
+use crate::{
+    CmdExecutor, OutputMode,
+    process::genpass::{self, Charset},
+};
 
+// cargo run --package cli -- genpass --length 20 --no-symbol --count 5
+// cargo run --package cli -- genpass --charset 0123456789abcdef --length 32
+// cargo run --package cli -- genpass --passphrase --words 5 --separator .
 #[derive(Debug, Parser)]
 pub struct GenPassCmd {
-    #[arg(long, default_value = "16")]
+    #[arg(long, default_value_t = 16)]
     pub length: usize,
 
-    #[arg(long, default_value_t = true)]
-    pub include_upper: bool,
+    /// 生成的数量
+    #[arg(long, default_value_t = 1)]
+    pub count: usize,
 
-    #[arg(long, default_value_t = true)]
-    pub include_lower: bool,
+    /// 包含大写字母（默认）
+    #[arg(long, alias = "include-upper", overrides_with = "no_upper")]
+    pub upper: bool,
 
-    #[arg(long, default_value_t = true)]
-    pub include_digit: bool,
+    /// 不包含大写字母
+    #[arg(long, overrides_with = "upper")]
+    pub no_upper: bool,
 
-    #[arg(long, default_value_t = true)]
-    pub include_symbol: bool,
+    /// 包含小写字母（默认）
+    #[arg(long, alias = "include-lower", overrides_with = "no_lower")]
+    pub lower: bool,
+
+    /// 不包含小写字母
+    #[arg(long, overrides_with = "lower")]
+    pub no_lower: bool,
+
+    /// 包含数字（默认）
+    #[arg(long, alias = "include-digit", overrides_with = "no_digit")]
+    pub digit: bool,
+
+    /// 不包含数字
+    #[arg(long, overrides_with = "digit")]
+    pub no_digit: bool,
+
+    /// 包含符号（默认）
+    #[arg(long, alias = "include-symbol", overrides_with = "no_symbol")]
+    pub symbol: bool,
+
+    /// 不包含符号
+    #[arg(long, overrides_with = "symbol")]
+    pub no_symbol: bool,
+
+    /// 自定义字符集，设置后忽略 --[no-]upper/lower/digit/symbol
+    #[arg(long, conflicts_with = "passphrase")]
+    pub charset: Option<String>,
+
+    /// 从内置单词表中随机选取单词组成口令
+    #[arg(long)]
+    pub passphrase: bool,
+
+    /// 口令中的单词数
+    #[arg(long, default_value_t = 6)]
+    pub words: usize,
+
+    /// 口令中单词的分隔符
+    #[arg(long, default_value = "-")]
+    pub separator: String,
+}
+
+impl GenPassCmd {
+    // --no-xxx 和 --xxx 互相覆盖，以最后出现的为准
+    fn charset(&self) -> Charset {
+        match &self.charset {
+            Some(chars) => Charset::custom(chars),
+            None => Charset::new(
+                !self.no_upper,
+                !self.no_lower,
+                !self.no_digit,
+                !self.no_symbol,
+            ),
+        }
+    }
 }
 
 impl CmdExecutor for GenPassCmd {
-    async fn execute(&self) -> anyhow::Result<()> {
-        let password = crate::process::genpass::process_genpass(
-            self.length,
-            self.include_upper,
-            self.include_lower,
-            self.include_digit,
-            self.include_symbol,
-        )
-        .await?;
-
-        println!("password: {}", password);
-        Ok(())
+    async fn execute(&self, output: OutputMode) -> anyhow::Result<()> {
+        let charset = self.charset();
+        let mut passwords = Vec::with_capacity(self.count);
+        for _ in 0..self.count {
+            let (password, strength) = if self.passphrase {
+                (
+                    genpass::generate_passphrase(self.words, &self.separator)?,
+                    genpass::passphrase_strength(self.words),
+                )
+            } else {
+                (
+                    genpass::generate_password(self.length, &charset)?,
+                    genpass::password_strength(self.length, &charset),
+                )
+            };
+
+            if output == OutputMode::Text {
+                println!("password: {}  strength: {}", password, strength);
+            }
+            passwords.push(json!({
+                "password": password,
+                "strength": {
+                    "label": strength.label(),
+                    "score": strength.score,
+                    "entropy": strength.entropy,
+                },
+            }));
+        }
+        output.emit_json(passwords)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> GenPassCmd {
+        GenPassCmd::parse_from(std::iter::once("genpass").chain(args.iter().copied()))
+    }
+
+    #[test]
+    fn test_negatable_flags() {
+        assert_eq!(parse(&[]).charset(), Charset::new(true, true, true, true));
+        assert_eq!(
+            parse(&["--no-symbol", "--no-upper"]).charset(),
+            Charset::new(false, true, true, false)
+        );
+        // 后出现的参数覆盖前面的
+        assert_eq!(
+            parse(&["--no-digit", "--digit", "--symbol", "--no-symbol"]).charset(),
+            Charset::new(true, true, true, false)
+        );
+        assert_eq!(
+            parse(&["--include-upper"]).charset(),
+            Charset::new(true, true, true, true)
+        );
+    }
+
+    #[test]
+    fn test_custom_charset_and_passphrase() {
+        let cmd = parse(&["--charset", "abc", "--no-lower"]);
+        assert_eq!(cmd.charset(), Charset::custom("abc"));
+
+        assert!(
+            GenPassCmd::try_parse_from(["genpass", "--charset", "abc", "--passphrase"]).is_err()
+        );
     }
 }
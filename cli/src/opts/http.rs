@@ -1,29 +1,315 @@
-use std::path::PathBuf;
+use std::{net::IpAddr, path::PathBuf};
 
-use clap::Parser;
+use clap::{Args, Parser};
+use reqwest::Method;
 
-use crate::{CmdExecutor, opts::verify_path};
+use crate::{
+    CmdExecutor, OutputMode,
+    opts::{verify_file, verify_path},
+    process::http::{client, runner},
+};
 
-// cargo run --package cli -- http serve --dir assets
 #[derive(Debug, Parser)]
 pub enum HttpCmd {
+    // cargo run --package cli -- http serve --dir assets
+    // cargo run --package cli -- http serve --dir assets --bind 127.0.0.1 --cors --tls-cert crm/fixtures/cert.pem --tls-key crm/fixtures/key.pem
+    // cargo run --package cli -- http serve --dir /tmp/share --allow-upload --allow-delete --max-upload-size 1G --auth alice:secret
     #[command(name = "serve", about = "serve a directory over HTTP")]
-    Serve {
-        #[arg(long, value_parser = verify_path, default_value = ".")]
-        dir: PathBuf,
+    Serve(HttpServeOpts),
 
-        #[arg(long, default_value = "8080")]
-        port: u16,
-    },
+    // cargo run --package cli -- http get :9090/api/chat/list --token <TOKEN>
+    #[command(name = "get", about = "send a GET request")]
+    Get(HttpRequestOpts),
+
+    // cargo run --package cli -- http post :9090/api/sign-in email=Noah@gmail.com password=123456 --session /tmp/chat.json
+    #[command(name = "post", about = "send a POST request")]
+    Post(HttpRequestOpts),
+
+    // cargo run --package cli -- http put :8080/hello.txt x-request-id:1 content=hello
+    #[command(name = "put", about = "send a PUT request")]
+    Put(HttpRequestOpts),
+
+    // cargo run --package cli -- http delete :8080/hello.txt
+    #[command(name = "delete", about = "send a DELETE request")]
+    Delete(HttpRequestOpts),
+
+    // cargo run --package cli -- http run test.http --name signin --name "test get chat list"
+    #[command(name = "run", about = "run requests in a .http file")]
+    Run(HttpRunOpts),
+}
+
+#[derive(Debug, Args)]
+pub struct HttpRequestOpts {
+    /// 请求地址，`:9090/api` 表示 http://localhost:9090/api
+    pub url: String,
+
+    /// `key=value` 字符串字段，`key:=json` JSON 字段，`key==value` 查询参数，`Name:value` 请求头
+    #[arg(value_parser = parse_request_item)]
+    pub items: Vec<RequestItem>,
+
+    /// 以 `Authorization: Bearer <TOKEN>` 发送，使用 --session 时会被保存
+    #[arg(long)]
+    pub token: Option<String>,
+
+    /// 字段以 application/x-www-form-urlencoded 发送，默认为 JSON
+    #[arg(long)]
+    pub form: bool,
+
+    /// 把响应体保存为文件，而不是打印
+    #[arg(short, long)]
+    pub download: bool,
+
+    /// 下载保存的路径，默认从 Content-Disposition 或 URL 推断
+    #[arg(short, long, requires = "download")]
+    pub output: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub client: HttpClientOpts,
+}
+
+#[derive(Debug, Args)]
+pub struct HttpRunOpts {
+    #[arg(value_parser = verify_file)]
+    pub file: String,
+
+    /// 只执行指定 `@name` 或标题的请求，它们依赖的命名请求会先执行
+    #[arg(long)]
+    pub name: Vec<String>,
+
+    /// 覆盖文件中的变量，如 `--var host=localhost:9090`
+    #[arg(long = "var", value_parser = parse_variable)]
+    pub vars: Vec<(String, String)>,
+
+    #[command(flatten)]
+    pub client: HttpClientOpts,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct HttpClientOpts {
+    /// 会话文件，在多次调用之间保存 cookie 和 token
+    #[arg(long)]
+    pub session: Option<PathBuf>,
+
+    /// 同时打印请求
+    #[arg(short, long)]
+    pub verbose: bool,
+
+    /// 不输出颜色，输出不是终端时自动关闭
+    #[arg(long)]
+    pub no_color: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RequestItem {
+    Header(String, String),
+    Query(String, String),
+    Field(String, String),
+    JsonField(String, serde_json::Value),
+}
+
+#[derive(Debug, Args)]
+pub struct HttpServeOpts {
+    #[arg(long, value_parser = verify_path, default_value = ".")]
+    pub dir: PathBuf,
+
+    /// 监听的地址
+    #[arg(long, default_value = "0.0.0.0")]
+    pub bind: IpAddr,
+
+    #[arg(long, default_value = "8080")]
+    pub port: u16,
+
+    /// 允许任意来源的跨域请求
+    #[arg(long)]
+    pub cors: bool,
+
+    /// PEM 格式的证书链，和 --tls-key 一起使用时启用 HTTPS
+    #[arg(long, requires = "tls_key")]
+    pub tls_cert: Option<PathBuf>,
+
+    /// PEM 格式的私钥
+    #[arg(long, requires = "tls_cert")]
+    pub tls_key: Option<PathBuf>,
+
+    /// 允许通过 PUT 或 multipart POST 上传文件
+    #[arg(long)]
+    pub allow_upload: bool,
+
+    /// 单个请求体的最大大小，如 512K, 100M, 2G
+    #[arg(long, value_parser = parse_size, default_value = "100M")]
+    pub max_upload_size: u64,
+
+    /// 允许通过 DELETE 删除文件和空目录
+    #[arg(long)]
+    pub allow_delete: bool,
+
+    /// `user:password` 启用 basic auth，不含 `:` 时作为 bearer token
+    #[arg(long, value_parser = parse_auth)]
+    pub auth: Option<HttpAuth>,
+
+    /// 在 /metrics 暴露 Prometheus 格式的请求数和延迟指标
+    #[arg(long)]
+    pub metrics: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HttpAuth {
+    Basic { username: String, password: String },
+    Bearer(String),
 }
 
 impl CmdExecutor for HttpCmd {
-    async fn execute(&self) -> anyhow::Result<()> {
+    async fn execute(&self, output: OutputMode) -> anyhow::Result<()> {
         match self {
-            HttpCmd::Serve { dir, port } => {
-                crate::process::http::process_http_serve(dir, *port).await?;
+            HttpCmd::Serve(opts) => crate::process::http::process_http_serve(opts, output).await,
+            HttpCmd::Get(opts) => client::process_http_request(Method::GET, opts, output).await,
+            HttpCmd::Post(opts) => client::process_http_request(Method::POST, opts, output).await,
+            HttpCmd::Put(opts) => client::process_http_request(Method::PUT, opts, output).await,
+            HttpCmd::Delete(opts) => {
+                client::process_http_request(Method::DELETE, opts, output).await
             }
+            HttpCmd::Run(opts) => runner::process_http_run(opts, output).await,
+        }
+    }
+}
+
+fn parse_size(size: &str) -> Result<u64, String> {
+    let size = size.trim();
+    let (value, unit) = match size.find(|c: char| !c.is_ascii_digit()) {
+        Some(pos) => size.split_at(pos),
+        None => (size, ""),
+    };
+    let value = value
+        .parse::<u64>()
+        .map_err(|_| format!("Invalid size: {}", size))?;
+    let unit = match unit.to_ascii_uppercase().trim_end_matches('B') {
+        "" => 1,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        _ => return Err(format!("Invalid size unit: {}", unit)),
+    };
+
+    value
+        .checked_mul(unit)
+        .ok_or_else(|| format!("Size too large: {}", size))
+}
+
+fn parse_auth(auth: &str) -> Result<HttpAuth, String> {
+    match auth.split_once(':') {
+        Some(("", _)) => Err("Username must not be empty".to_string()),
+        Some((username, password)) => Ok(HttpAuth::Basic {
+            username: username.to_string(),
+            password: password.to_string(),
+        }),
+        None if auth.is_empty() => Err("Token must not be empty".to_string()),
+        None => Ok(HttpAuth::Bearer(auth.to_string())),
+    }
+}
+
+// 与 httpie 相同，取最先出现的分隔符，同一位置优先匹配较长的
+fn parse_request_item(item: &str) -> Result<RequestItem, String> {
+    const SEPARATORS: [&str; 4] = ["==", ":=", "=", ":"];
+    let (pos, sep) = item
+        .char_indices()
+        .find_map(|(pos, _)| {
+            SEPARATORS
+                .iter()
+                .find(|sep| item[pos..].starts_with(**sep))
+                .map(|sep| (pos, *sep))
+        })
+        .ok_or_else(|| format!("Invalid request item: {}", item))?;
+
+    let key = item[..pos].to_string();
+    let value = item[pos + sep.len()..].to_string();
+    if key.is_empty() {
+        return Err(format!("Invalid request item: {}", item));
+    }
+
+    match sep {
+        "==" => Ok(RequestItem::Query(key, value)),
+        ":=" => serde_json::from_str(&value)
+            .map(|value| RequestItem::JsonField(key, value))
+            .map_err(|e| format!("Invalid JSON in {}: {}", item, e)),
+        "=" => Ok(RequestItem::Field(key, value)),
+        _ => Ok(RequestItem::Header(key, value.trim().to_string())),
+    }
+}
+
+fn parse_variable(var: &str) -> Result<(String, String), String> {
+    match var.split_once('=') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_string(), value.to_string()))
         }
-        Ok(())
+        _ => Err(format!("Invalid variable: {}, expected name=value", var)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1024"), Ok(1024));
+        assert_eq!(parse_size("512K"), Ok(512 * 1024));
+        assert_eq!(parse_size("100m"), Ok(100 * 1024 * 1024));
+        assert_eq!(parse_size("2GB"), Ok(2 * 1024 * 1024 * 1024));
+        assert!(parse_size("M").is_err());
+        assert!(parse_size("3T").is_err());
+    }
+
+    #[test]
+    fn test_parse_auth() {
+        assert_eq!(
+            parse_auth("alice:s3:cret"),
+            Ok(HttpAuth::Basic {
+                username: "alice".to_string(),
+                password: "s3:cret".to_string(),
+            })
+        );
+        assert_eq!(
+            parse_auth("token123"),
+            Ok(HttpAuth::Bearer("token123".to_string()))
+        );
+        assert!(parse_auth(":secret").is_err());
+        assert!(parse_auth("").is_err());
+    }
+
+    #[test]
+    fn test_parse_request_item() {
+        assert_eq!(
+            parse_request_item("name=Noah"),
+            Ok(RequestItem::Field("name".to_string(), "Noah".to_string()))
+        );
+        assert_eq!(
+            parse_request_item("user_ids:=[1, 2]"),
+            Ok(RequestItem::JsonField(
+                "user_ids".to_string(),
+                serde_json::json!([1, 2])
+            ))
+        );
+        assert_eq!(
+            parse_request_item("limit==2"),
+            Ok(RequestItem::Query("limit".to_string(), "2".to_string()))
+        );
+        assert_eq!(
+            parse_request_item("x-request-id: abc"),
+            Ok(RequestItem::Header(
+                "x-request-id".to_string(),
+                "abc".to_string()
+            ))
+        );
+        // 值中可以包含其他分隔符
+        assert_eq!(
+            parse_request_item("url=http://a?b==c"),
+            Ok(RequestItem::Field(
+                "url".to_string(),
+                "http://a?b==c".to_string()
+            ))
+        );
+        assert!(parse_request_item("no-separator").is_err());
+        assert!(parse_request_item("=value").is_err());
+        assert!(parse_request_item("ids:=[1,").is_err());
     }
 }
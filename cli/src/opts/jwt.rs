@@ -0,0 +1,160 @@
+use clap::{Args, Parser, ValueEnum};
+use serde_json::json;
+
+use crate::{CmdExecutor, OutputMode, opts, process::jwt};
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum JwtAlgorithm {
+    /// Ed25519，与 chat_server 使用的算法相同
+    #[value(name = "eddsa", alias = "ed25519")]
+    EdDSA,
+    #[value(name = "hs256")]
+    HS256,
+    #[value(name = "es256")]
+    ES256,
+}
+
+#[derive(Debug, Parser)]
+pub enum JwtCmd {
+    // cargo run --package cli -- jwt sign --key chat/chat_core/fixtures/enc.pem --iss chat_server --aud chat_web --claims '{"id":1,"workspace_id":1}'
+    #[command(name = "sign", about = "sign a JWT")]
+    Sign(JwtSignOpts),
+
+    // cargo run --package cli -- jwt verify --key chat/chat_core/fixtures/dec.pem --token <TOKEN>
+    #[command(name = "verify", about = "verify a JWT and print its claims")]
+    Verify(JwtVerifyOpts),
+
+    // cargo run --package cli -- jwt decode --token <TOKEN>
+    #[command(name = "decode", about = "decode a JWT without verification")]
+    Decode {
+        /// JWT，`-` 表示从 stdin 读取
+        #[arg(long, default_value = "-")]
+        token: String,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct JwtSignOpts {
+    /// EdDSA/ES256 为 PEM 私钥，HS256 为原始密钥
+    #[arg(long, value_parser = opts::verify_file)]
+    pub key: String,
+
+    #[arg(long, value_enum, default_value = "eddsa")]
+    pub alg: JwtAlgorithm,
+
+    #[arg(long)]
+    pub sub: Option<String>,
+
+    /// 可以重复使用
+    #[arg(long)]
+    pub aud: Vec<String>,
+
+    #[arg(long)]
+    pub iss: Option<String>,
+
+    /// 有效期，如 30s, 15m, 12h, 14d, 2w
+    #[arg(long, value_parser = parse_duration, default_value = "14d")]
+    pub exp: u64,
+
+    /// 自定义 claims，JSON 对象
+    #[arg(long, value_parser = parse_claims)]
+    pub claims: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+#[derive(Debug, Args)]
+pub struct JwtVerifyOpts {
+    /// EdDSA/ES256 为 PEM 公钥，HS256 为原始密钥
+    #[arg(long, value_parser = opts::verify_file)]
+    pub key: String,
+
+    #[arg(long, value_enum, default_value = "eddsa")]
+    pub alg: JwtAlgorithm,
+
+    /// JWT，`-` 表示从 stdin 读取
+    #[arg(long, default_value = "-")]
+    pub token: String,
+
+    /// 允许的 audience，为空时不检查
+    #[arg(long)]
+    pub aud: Vec<String>,
+
+    /// 允许的 issuer，为空时不检查
+    #[arg(long)]
+    pub iss: Vec<String>,
+}
+
+impl CmdExecutor for JwtCmd {
+    async fn execute(&self, output: OutputMode) -> anyhow::Result<()> {
+        match self {
+            JwtCmd::Sign(opts) => {
+                let token = jwt::process_sign(opts).await?;
+                output.emit(&token, json!({ "token": token }))
+            }
+            JwtCmd::Verify(opts) => {
+                let claims = jwt::process_verify(opts).await?;
+                output.emit(
+                    serde_json::to_string_pretty(&claims)?,
+                    json!({ "valid": true, "claims": claims }),
+                )
+            }
+            JwtCmd::Decode { token } => {
+                let decoded = jwt::process_decode(token).await?;
+                output.emit(serde_json::to_string_pretty(&decoded)?, decoded)
+            }
+        }
+    }
+}
+
+fn parse_duration(duration: &str) -> Result<u64, String> {
+    let duration = duration.trim();
+    let (value, unit) = match duration.find(|c: char| !c.is_ascii_digit()) {
+        Some(pos) => duration.split_at(pos),
+        None => (duration, "s"),
+    };
+    let value = value
+        .parse::<u64>()
+        .map_err(|_| format!("Invalid duration: {}", duration))?;
+    let unit = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 60 * 60 * 24,
+        "w" => 60 * 60 * 24 * 7,
+        _ => return Err(format!("Invalid duration unit: {}", unit)),
+    };
+
+    value
+        .checked_mul(unit)
+        .ok_or_else(|| format!("Duration too large: {}", duration))
+}
+
+fn parse_claims(claims: &str) -> Result<serde_json::Map<String, serde_json::Value>, String> {
+    match serde_json::from_str(claims) {
+        Ok(serde_json::Value::Object(map)) => Ok(map),
+        Ok(_) => Err("Claims must be a JSON object".to_string()),
+        Err(e) => Err(format!("Invalid claims: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("14d"), Ok(14 * 24 * 3600));
+        assert_eq!(parse_duration("90"), Ok(90));
+        assert_eq!(parse_duration("15m"), Ok(900));
+        assert_eq!(parse_duration("2w"), Ok(2 * 7 * 24 * 3600));
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("3y").is_err());
+    }
+
+    #[test]
+    fn test_parse_claims() {
+        let claims = parse_claims(r#"{"id": 1, "username": "Noah"}"#).unwrap();
+        assert_eq!(claims["id"], 1);
+        assert!(parse_claims("[1, 2]").is_err());
+        assert!(parse_claims("{").is_err());
+    }
+}
@@ -1,48 +1,110 @@
-pub mod base64;
+pub mod codec;
 pub mod csv;
+pub mod data;
 pub mod genpass;
 pub mod http;
+pub mod jwt;
+pub mod otp;
 pub mod text;
 
 use std::path::PathBuf;
 
-use clap::Parser;
+use clap::{CommandFactory, Parser};
+use clap_complete::Shell;
 
-use crate::CmdExecutor;
+use crate::{CmdExecutor, OutputMode};
 
 #[derive(Debug, Parser)]
 #[command(author, about, version, long_about = None)]
 pub struct Args {
+    /// 只输出错误信息
+    #[arg(long, short, global = true)]
+    pub quiet: bool,
+
+    /// 以 JSON 输出结果
+    #[arg(long, global = true, conflicts_with = "quiet")]
+    pub json: bool,
+
+    /// 默认是 `$RCLI_CONFIG` 或 `~/.config/rcli.toml`
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
     #[command(subcommand)]
     pub cmd: SubCommand,
 }
 
+impl Args {
+    pub fn output_mode(&self) -> OutputMode {
+        if self.json {
+            OutputMode::Json
+        } else if self.quiet {
+            OutputMode::Quiet
+        } else {
+            OutputMode::Text
+        }
+    }
+}
+
 #[derive(Debug, Parser)]
 pub enum SubCommand {
     #[command(name = "csv", about = "convert CSV file to JSON or...")]
     Csv(csv::CsvCmd),
 
+    #[command(
+        subcommand,
+        name = "data",
+        about = "query, transform or convert JSON, YAML, TOML and NDJSON"
+    )]
+    Data(data::DataCmd),
+
     #[command(name = "genpass", about = "generate random password")]
     GenPass(genpass::GenPassCmd),
 
-    #[command(subcommand, name = "base64", about = "base64 encode or decode")]
-    Base64(base64::Base64Cmd),
+    #[command(
+        subcommand,
+        name = "codec",
+        visible_alias = "base64",
+        about = "encode or decode base64, hex, base32 or base58"
+    )]
+    Codec(codec::CodecCmd),
 
     #[command(subcommand, name = "text", about = "text sign or hash")]
     Text(text::TextCmd),
 
-    #[command(subcommand, name = "http", about = "file server")]
+    #[command(subcommand, name = "http", about = "file server and HTTP client")]
     Http(http::HttpCmd),
+
+    #[command(subcommand, name = "jwt", about = "sign, verify or decode JWT")]
+    Jwt(jwt::JwtCmd),
+
+    #[command(subcommand, name = "otp", about = "generate or verify TOTP/HOTP codes")]
+    Otp(otp::OtpCmd),
+
+    // cargo run --package cli -- completions zsh > ~/.zfunc/_cli
+    #[command(name = "completions", about = "generate shell completions")]
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
 }
 
 impl CmdExecutor for SubCommand {
-    async fn execute(&self) -> anyhow::Result<()> {
+    async fn execute(&self, output: OutputMode) -> anyhow::Result<()> {
         match self {
-            SubCommand::Csv(cmd) => cmd.execute().await,
-            SubCommand::GenPass(cmd) => cmd.execute().await,
-            SubCommand::Base64(cmd) => cmd.execute().await,
-            SubCommand::Text(cmd) => cmd.execute().await,
-            SubCommand::Http(cmd) => cmd.execute().await,
+            SubCommand::Csv(cmd) => cmd.execute(output).await,
+            SubCommand::Data(cmd) => cmd.execute(output).await,
+            SubCommand::GenPass(cmd) => cmd.execute(output).await,
+            SubCommand::Codec(cmd) => cmd.execute(output).await,
+            SubCommand::Text(cmd) => cmd.execute(output).await,
+            SubCommand::Http(cmd) => cmd.execute(output).await,
+            SubCommand::Jwt(cmd) => cmd.execute(output).await,
+            SubCommand::Otp(cmd) => cmd.execute(output).await,
+            SubCommand::Completions { shell } => {
+                let mut cmd = Args::command();
+                let name = cmd.get_name().to_string();
+                clap_complete::generate(*shell, &mut cmd, name, &mut std::io::stdout());
+                Ok(())
+            }
         }
     }
 }
@@ -77,4 +139,24 @@ mod tests {
             Err(format!("Input File not found: {}", "test.csv"))
         );
     }
+
+    #[test]
+    fn test_output_mode() {
+        let args = Args::parse_from(["cli", "genpass", "--json"]);
+        assert_eq!(args.output_mode(), OutputMode::Json);
+        let args = Args::parse_from(["cli", "-q", "genpass"]);
+        assert_eq!(args.output_mode(), OutputMode::Quiet);
+        assert!(Args::try_parse_from(["cli", "--json", "--quiet", "genpass"]).is_err());
+    }
+
+    #[test]
+    fn test_completions() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::PowerShell] {
+            let mut buf = Vec::new();
+            clap_complete::generate(shell, &mut Args::command(), "cli", &mut buf);
+            let script = String::from_utf8(buf).unwrap();
+            assert!(script.contains("genpass"));
+            assert!(script.contains("serve"));
+        }
+    }
 }
@@ -0,0 +1,185 @@
+use clap::{Args, Parser, ValueEnum};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{
+    CmdExecutor, OutputMode,
+    opts::codec::{Base64Format, Codec},
+    process::{codec, otp},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum OtpAlgorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+/// base32 编码的共享密钥，忽略大小写、空白和填充
+#[derive(Debug, Clone, PartialEq)]
+pub struct OtpSecret(pub Vec<u8>);
+
+#[derive(Debug, Parser)]
+pub enum OtpCmd {
+    // cargo run --package cli -- otp generate --secret JBSWY3DPEHPK3PXP
+    // cargo run --package cli -- otp generate --secret JBSWY3DPEHPK3PXP --counter 7
+    #[command(name = "generate", about = "generate a TOTP or HOTP code")]
+    Generate(OtpOpts),
+
+    // cargo run --package cli -- otp verify --secret JBSWY3DPEHPK3PXP 123456
+    #[command(name = "verify", about = "verify a TOTP or HOTP code")]
+    Verify {
+        code: String,
+
+        #[command(flatten)]
+        opts: OtpOpts,
+
+        /// 允许前后偏差的步数，HOTP 只向后查找
+        #[arg(long, default_value_t = 1)]
+        window: u64,
+    },
+
+    // cargo run --package cli -- otp uri --secret JBSWY3DPEHPK3PXP --issuer rcli --account alice@example.com --qr
+    #[command(name = "uri", about = "print an otpauth:// URI for authenticator apps")]
+    Uri {
+        #[command(flatten)]
+        opts: OtpOpts,
+
+        #[arg(long)]
+        account: String,
+
+        #[arg(long)]
+        issuer: Option<String>,
+
+        /// 同时在终端中显示二维码
+        #[arg(long)]
+        qr: bool,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct OtpOpts {
+    #[arg(long, value_parser = parse_secret)]
+    pub secret: OtpSecret,
+
+    #[arg(long, value_enum, default_value = "sha1")]
+    pub algorithm: OtpAlgorithm,
+
+    #[arg(long, default_value_t = 6, value_parser = clap::value_parser!(u32).range(6..=10))]
+    pub digits: u32,
+
+    /// TOTP 的时间步长（秒）
+    #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(u64).range(1..))]
+    pub period: u64,
+
+    /// 指定时使用 HOTP
+    #[arg(long)]
+    pub counter: Option<u64>,
+
+    /// 计算 TOTP 使用的 Unix 时间戳，默认为当前时间
+    #[arg(long, conflicts_with = "counter")]
+    pub time: Option<u64>,
+}
+
+impl OtpOpts {
+    pub fn time(&self) -> u64 {
+        self.time
+            .unwrap_or_else(|| chrono::Utc::now().timestamp().max(0) as u64)
+    }
+}
+
+impl CmdExecutor for OtpCmd {
+    async fn execute(&self, output: OutputMode) -> anyhow::Result<()> {
+        match self {
+            OtpCmd::Generate(opts) => match opts.counter {
+                Some(counter) => {
+                    let code = otp::hotp(&opts.secret.0, counter, opts.digits, opts.algorithm);
+                    output.emit(&code, json!({ "code": code, "counter": counter }))
+                }
+                None => {
+                    let time = opts.time();
+                    let code = otp::totp(
+                        &opts.secret.0,
+                        time,
+                        opts.period,
+                        opts.digits,
+                        opts.algorithm,
+                    );
+                    let remaining = opts.period - time % opts.period;
+                    output.emit(&code, json!({ "code": code, "remaining": remaining }))
+                }
+            },
+            OtpCmd::Verify { code, opts, window } => {
+                let matched = match opts.counter {
+                    Some(counter) => otp::verify_hotp(opts, code, counter, *window)
+                        .map(|c| json!({ "valid": true, "counter": c })),
+                    None => otp::verify_totp(opts, code, opts.time(), *window)
+                        .map(|offset| json!({ "valid": true, "offset": offset })),
+                };
+                match matched {
+                    Some(result) => output.emit("code is valid", result),
+                    None => {
+                        output.emit("code is invalid", json!({ "valid": false }))?;
+                        anyhow::bail!("code verification failed");
+                    }
+                }
+            }
+            OtpCmd::Uri {
+                opts,
+                account,
+                issuer,
+                qr,
+            } => {
+                let uri = otp::otpauth_uri(opts, account, issuer.as_deref());
+                let text = if *qr {
+                    format!("{}\n{}", otp::render_qr(&uri)?, uri)
+                } else {
+                    uri.clone()
+                };
+                output.emit(text, json!({ "uri": uri }))
+            }
+        }
+    }
+}
+
+fn parse_secret(secret: &str) -> Result<OtpSecret, String> {
+    let secret = codec::decode(secret, Codec::Base32, Base64Format::Standard)
+        .map_err(|e| format!("Invalid base32 secret: {}", e))?;
+    if secret.is_empty() {
+        return Err("Secret must not be empty".to_string());
+    }
+    Ok(OtpSecret(secret))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_secret() {
+        assert_eq!(
+            parse_secret("jbsw y3dp ehpk 3pxp"),
+            Ok(OtpSecret(b"Hello!\xde\xad\xbe\xef".to_vec()))
+        );
+        assert!(parse_secret("JBSWY3DP1").is_err());
+        assert!(parse_secret("").is_err());
+    }
+
+    #[test]
+    fn test_parse_otp_opts() {
+        let cmd = OtpCmd::parse_from(["otp", "verify", "--secret", "JBSWY3DP", "123456"]);
+        let OtpCmd::Verify { code, opts, window } = cmd else {
+            panic!("expected otp verify");
+        };
+        assert_eq!(code, "123456");
+        assert_eq!((opts.digits, opts.period, window), (6, 30, 1));
+
+        let args = ["otp", "generate", "--secret", "JBSWY3DP"];
+        assert!(OtpCmd::try_parse_from([&args[..], &["--digits", "5"]].concat()).is_err());
+        assert!(
+            OtpCmd::try_parse_from([&args[..], &["--counter", "1", "--time", "1"]].concat())
+                .is_err()
+        );
+    }
+}
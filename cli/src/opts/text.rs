@@ -1,21 +1,25 @@
 use std::path::PathBuf;
 
-use clap::Parser;
+use clap::{ArgGroup, Args, Parser, ValueEnum};
+use serde::Serialize;
+use serde_json::json;
 
 use crate::{
-    CmdExecutor,
+    CmdExecutor, OutputMode,
     opts::{self, verify_path},
-    process::text,
+    process::text::{self, cipher::KeySource},
+    utils,
 };
 
 #[derive(Debug, Parser)]
 pub enum TextCmd {
     // argo run --package cli -- text generate --format blake3
     // cargo run --package cli -- text generate --format ed25519
+    // cargo run --package cli -- text generate --format chacha20poly1305
     #[command(name = "generate", about = "generate a key")]
     Generate {
-        #[arg(long, value_parser = parse_format, default_value = "blake3")]
-        format: TextSignFormat,
+        #[arg(long, value_parser = parse_key_format, default_value = "blake3")]
+        format: TextKeyFormat,
 
         #[arg(long, value_parser = verify_path)]
         output: PathBuf,
@@ -33,11 +37,21 @@ pub enum TextCmd {
 
         #[arg(long, value_parser = opts::verify_file, default_value = "-")]
         input: String,
+
+        /// 把签名写入分离的签名文件（如 data.sig），而不是打印出来
+        #[arg(long)]
+        sig_file: Option<String>,
+
+        /// 使用 Ed25519ph 流式签名大文件，签名与标准 Ed25519 不兼容
+        #[arg(long)]
+        prehash: bool,
     },
 
     // argo run --package cli -- text verify --key ./cli/fixtures/blake3.key --format blake3 --sig xxx
     // cargo run --package cli -- text verify --format ed25519 --key ./cli/fixtures/ed25519_verifying.key --sig xxx
+    // cargo run --package cli -- text verify --format ed25519 --key dec.pem --input data.bin --sig-file data.sig
     #[command(name = "verify", about = "verify a message with a public key")]
+    #[command(group(ArgGroup::new("signature").required(true).args(["sig", "sig_file"])))]
     Verify {
         #[arg(long, value_parser = parse_format, default_value = "blake3")]
         format: TextSignFormat,
@@ -49,43 +63,254 @@ pub enum TextCmd {
         input: String,
 
         #[arg(long)]
-        sig: String,
+        sig: Option<String>,
+
+        /// 从分离的签名文件读取签名
+        #[arg(long, value_parser = opts::verify_file)]
+        sig_file: Option<String>,
+
+        /// 验证 `text sign --prehash` 生成的 Ed25519ph 签名
+        #[arg(long)]
+        prehash: bool,
+    },
+
+    // cargo run --package cli -- text hash --input Cargo.toml --algo sha256
+    // cargo run --package cli -- text hash --input cli/src --manifest src.manifest
+    #[command(
+        name = "hash",
+        about = "hash a file or directory with Blake3 or SHA-256"
+    )]
+    Hash {
+        #[arg(long, value_enum, default_value = "blake3")]
+        algo: HashAlgorithm,
+
+        /// 文件、目录或 `-`（stdin）
+        #[arg(long, value_parser = opts::verify_file, default_value = "-")]
+        input: String,
+
+        /// 输入为目录时，把每个文件的哈希写入清单文件，`-` 表示 stdout
+        #[arg(long)]
+        manifest: Option<String>,
+    },
+
+    // cargo run --package cli -- text encrypt --key ./cli/fixtures/chacha20poly1305.key --input Cargo.toml --output Cargo.toml.enc
+    // RCLI_PASSPHRASE=secret cargo run --package cli -- text encrypt --cipher aes256gcm --passphrase-env RCLI_PASSPHRASE --input Cargo.toml --output Cargo.toml.enc
+    #[command(
+        name = "encrypt",
+        about = "encrypt data with ChaCha20-Poly1305 or AES-GCM"
+    )]
+    Encrypt {
+        #[arg(long, value_parser = parse_cipher, default_value = "chacha20poly1305")]
+        cipher: TextCipher,
+
+        #[command(flatten)]
+        key: CipherKey,
+
+        #[arg(long, value_parser = opts::verify_file, default_value = "-")]
+        input: String,
+
+        /// 输出文件，`-` 表示 stdout
+        #[arg(long, default_value = "-")]
+        output: String,
+
+        /// 每次加密的明文块大小（字节）
+        #[arg(long, default_value_t = 64 * 1024)]
+        chunk_size: u32,
+    },
+
+    // cargo run --package cli -- text decrypt --key ./cli/fixtures/chacha20poly1305.key --input Cargo.toml.enc
+    #[command(name = "decrypt", about = "decrypt data produced by text encrypt")]
+    Decrypt {
+        #[command(flatten)]
+        key: CipherKey,
+
+        #[arg(long, value_parser = opts::verify_file, default_value = "-")]
+        input: String,
+
+        /// 输出文件，`-` 表示 stdout
+        #[arg(long, default_value = "-")]
+        output: String,
     },
 }
 
+/// 密钥文件和口令二选一；口令不从命令行参数读取，避免出现在 `ps` 和 shell 历史中
+#[derive(Debug, Args)]
+#[group(required = true, multiple = false)]
+pub struct CipherKey {
+    /// `text generate` 生成的 32 字节密钥
+    #[arg(long, value_parser = opts::verify_file)]
+    key: Option<String>,
+
+    /// 从文件读取口令（忽略末尾的换行），使用 Argon2id 派生密钥
+    #[arg(long, value_parser = opts::verify_file)]
+    passphrase_file: Option<String>,
+
+    /// 从环境变量读取口令，参数为变量名
+    #[arg(long, value_name = "VAR")]
+    passphrase_env: Option<String>,
+}
+
 #[derive(Debug, Clone)]
 pub enum TextSignFormat {
     Blake3,
     Ed25519,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    Blake3,
+    Sha256,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextCipher {
+    ChaCha20Poly1305,
+    Aes256Gcm,
+}
+
+#[derive(Debug, Clone)]
+pub enum TextKeyFormat {
+    Sign(TextSignFormat),
+    Cipher(TextCipher),
+}
+
+impl CipherKey {
+    fn source(&self) -> anyhow::Result<KeySource> {
+        let passphrase = match (&self.key, &self.passphrase_file, &self.passphrase_env) {
+            (Some(key), _, _) => return Ok(KeySource::File(key.clone())),
+            (None, Some(path), _) => {
+                let content = std::fs::read_to_string(path)?;
+                let line = content.strip_suffix('\n').unwrap_or(&content);
+                line.strip_suffix('\r').unwrap_or(line).to_string()
+            }
+            (None, None, Some(var)) => std::env::var(var)
+                .map_err(|e| anyhow::anyhow!("failed to read passphrase from ${}: {}", var, e))?,
+            (None, None, None) => {
+                unreachable!("clap requires --key, --passphrase-file or --passphrase-env")
+            }
+        };
+        if passphrase.is_empty() {
+            anyhow::bail!("passphrase is empty");
+        }
+        Ok(KeySource::Passphrase(passphrase))
+    }
+}
+
 impl CmdExecutor for TextCmd {
-    async fn execute(&self) -> anyhow::Result<()> {
+    async fn execute(&self, mode: OutputMode) -> anyhow::Result<()> {
         match self {
             TextCmd::Generate { format, output } => {
-                let key = text::process_generate(format).await?;
-                match format {
-                    TextSignFormat::Blake3 => {
-                        std::fs::write(output.join("blake3.key"), &key[0])?;
+                let files = match format {
+                    TextKeyFormat::Sign(format) => {
+                        let key = text::process_generate(format).await?;
+                        let names = match format {
+                            TextSignFormat::Blake3 => vec!["blake3.key"],
+                            TextSignFormat::Ed25519 => {
+                                vec!["ed25519_signing.key", "ed25519_verifying.key"]
+                            }
+                        };
+                        names
+                            .into_iter()
+                            .zip(key)
+                            .map(|(name, key)| (output.join(name), key))
+                            .collect::<Vec<_>>()
                     }
-                    TextSignFormat::Ed25519 => {
-                        std::fs::write(output.join("ed25519_signing.key"), &key[0])?;
-                        std::fs::write(output.join("ed25519_verifying.key"), &key[1])?;
+                    TextKeyFormat::Cipher(cipher) => {
+                        let key = text::cipher::process_generate_key();
+                        vec![(output.join(format!("{}.key", cipher.name())), key)]
                     }
+                };
+                for (path, key) in &files {
+                    utils::write_atomic(path, key, Some(0o600))?;
                 }
+                let files = files.iter().map(|(path, _)| path).collect::<Vec<_>>();
+                mode.emit_json(json!({ "files": files }))?;
             }
-            TextCmd::Sign { input, key, format } => {
-                let signed = text::process_sign(input, key, format).await?;
-                println!("signature: {:?}", signed);
+            TextCmd::Sign {
+                input,
+                key,
+                format,
+                sig_file,
+                prehash,
+            } => {
+                let signed = text::process_sign(input, key, format, *prehash).await?;
+                match sig_file {
+                    Some(path) => {
+                        text::write_sig_file(path, &signed)?;
+                        mode.emit(
+                            format!("signature written to {}", path),
+                            json!({ "sig_file": path }),
+                        )?;
+                    }
+                    None => mode.emit(&signed, json!({ "signature": signed }))?,
+                }
             }
             TextCmd::Verify {
                 input,
                 key,
                 sig,
+                sig_file,
                 format,
+                prehash,
+            } => {
+                let sig = match (sig, sig_file) {
+                    (Some(sig), _) => sig.clone(),
+                    (None, Some(path)) => text::read_sig_file(path)?,
+                    (None, None) => unreachable!("clap requires --sig or --sig-file"),
+                };
+                let is_valid = text::process_verify(input, key, &sig, format, *prehash).await?;
+                let text = if is_valid { "valid" } else { "invalid" };
+                mode.emit(
+                    format!("signature is {}", text),
+                    json!({ "valid": is_valid }),
+                )?;
+                // 验证失败时以非 0 状态码退出，方便脚本判断
+                if !is_valid {
+                    anyhow::bail!("signature verification failed");
+                }
+            }
+            TextCmd::Hash {
+                algo,
+                input,
+                manifest,
+            } => {
+                let output = text::hash::process_hash(input, *algo).await?;
+                if let Some(manifest) = manifest {
+                    let writer = crate::utils::get_writer(manifest).await?;
+                    text::hash::write_manifest(&output, writer)?;
+                }
+                let entries = output
+                    .entries
+                    .iter()
+                    .map(
+                        |entry| json!({ "path": entry.path, "digest": hex::encode(&entry.digest) }),
+                    )
+                    .collect::<Vec<_>>();
+                mode.emit(
+                    format!("{}  {}", hex::encode(&output.root), input),
+                    json!({
+                        "algo": algo,
+                        "input": input,
+                        "root": hex::encode(&output.root),
+                        "entries": entries,
+                    }),
+                )?;
+            }
+            TextCmd::Encrypt {
+                cipher,
+                key,
+                input,
+                output,
+                chunk_size,
             } => {
-                let is_valid = text::process_verify(input, key, sig, format).await?;
-                println!("signature is_valid: {:?}", is_valid);
+                text::cipher::process_encrypt(input, output, &key.source()?, *cipher, *chunk_size)
+                    .await?;
+                mode.emit_json(json!({ "input": input, "output": output }))?;
+            }
+            TextCmd::Decrypt { key, input, output } => {
+                text::cipher::process_decrypt(input, output, &key.source()?).await?;
+                mode.emit_json(json!({ "input": input, "output": output }))?;
             }
         }
 
@@ -93,6 +318,15 @@ impl CmdExecutor for TextCmd {
     }
 }
 
+impl TextCipher {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TextCipher::ChaCha20Poly1305 => "chacha20poly1305",
+            TextCipher::Aes256Gcm => "aes256gcm",
+        }
+    }
+}
+
 fn parse_format(format: &str) -> anyhow::Result<TextSignFormat> {
     match format.to_lowercase().as_str() {
         "blake3" => Ok(TextSignFormat::Blake3),
@@ -100,3 +334,49 @@ fn parse_format(format: &str) -> anyhow::Result<TextSignFormat> {
         _ => anyhow::bail!("Unsupported format: {}", format),
     }
 }
+
+fn parse_cipher(cipher: &str) -> anyhow::Result<TextCipher> {
+    match cipher.to_lowercase().as_str() {
+        "chacha20poly1305" | "chacha20" => Ok(TextCipher::ChaCha20Poly1305),
+        "aes256gcm" | "aes-gcm" | "aes" => Ok(TextCipher::Aes256Gcm),
+        _ => anyhow::bail!("Unsupported cipher: {}", cipher),
+    }
+}
+
+fn parse_key_format(format: &str) -> anyhow::Result<TextKeyFormat> {
+    parse_format(format)
+        .map(TextKeyFormat::Sign)
+        .or_else(|_| parse_cipher(format).map(TextKeyFormat::Cipher))
+        .map_err(|_| anyhow::anyhow!("Unsupported format: {}", format))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(args: &[&str]) -> anyhow::Result<KeySource> {
+        let args = ["text", "decrypt"].iter().chain(args);
+        match TextCmd::try_parse_from(args)? {
+            TextCmd::Decrypt { key, .. } => key.source(),
+            cmd => unreachable!("{:?}", cmd),
+        }
+    }
+
+    #[test]
+    fn test_passphrase_sources() -> anyhow::Result<()> {
+        let path = std::env::temp_dir().join(format!("cli-passphrase-{}", std::process::id()));
+        std::fs::write(&path, "correct horse\r\n")?;
+        let path = path.to_string_lossy().to_string();
+        let source = key(&["--passphrase-file", &path])?;
+        assert!(matches!(source, KeySource::Passphrase(p) if p == "correct horse"));
+
+        std::fs::write(&path, "\n")?;
+        assert!(key(&["--passphrase-file", &path]).is_err());
+        std::fs::remove_file(&path)?;
+
+        // 口令不能直接写在命令行上
+        assert!(key(&["--passphrase", "secret"]).is_err());
+        assert!(key(&["--passphrase-env", "RCLI_TEST_MISSING_PASSPHRASE"]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,335 @@
+use std::io::{Read, Write};
+
+use base64::{
+    alphabet,
+    engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+    prelude::*,
+};
+
+use serde_json::json;
+
+use crate::{
+    OutputMode,
+    opts::codec::{Base64Format, Codec, CodecOpts},
+    utils,
+};
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const MIME_LINE_LENGTH: usize = 76;
+
+// 解码时不关心是否有填充
+const DECODE_CONFIG: GeneralPurposeConfig =
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent);
+const STANDARD_DECODER: GeneralPurpose = GeneralPurpose::new(&alphabet::STANDARD, DECODE_CONFIG);
+const URL_SAFE_DECODER: GeneralPurpose = GeneralPurpose::new(&alphabet::URL_SAFE, DECODE_CONFIG);
+
+/// `--json` 且输出到 stdout 时，结果放在 JSON 的 `encoded` 字段中
+pub async fn process_encode(opts: &CodecOpts, mode: OutputMode) -> anyhow::Result<()> {
+    let mut reader = utils::get_reader(&opts.input).await?;
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+
+    let encoded = encode(&data, opts.codec, opts.format);
+    if mode == OutputMode::Json && opts.output == "-" {
+        return mode.emit_json(json!({ "codec": opts.codec, "encoded": encoded }));
+    }
+
+    let mut writer = utils::get_writer(&opts.output).await?;
+    writeln!(writer, "{}", encoded)?;
+    writer.flush()?;
+    mode.emit_json(json!({ "codec": opts.codec, "output": opts.output }))
+}
+
+/// `--json` 且输出到 stdout 时，解码结果必须是 UTF-8 文本
+pub async fn process_decode(opts: &CodecOpts, mode: OutputMode) -> anyhow::Result<()> {
+    let mut reader = utils::get_reader(&opts.input).await?;
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+
+    let decoded = decode(&text, opts.codec, opts.format)?;
+    if mode == OutputMode::Json && opts.output == "-" {
+        let decoded = String::from_utf8(decoded)
+            .map_err(|_| anyhow::anyhow!("decoded data is not UTF-8, use --output"))?;
+        return mode.emit_json(json!({ "codec": opts.codec, "decoded": decoded }));
+    }
+
+    let mut writer = utils::get_writer(&opts.output).await?;
+    writer.write_all(&decoded)?;
+    writer.flush()?;
+    mode.emit_json(json!({ "codec": opts.codec, "output": opts.output }))
+}
+
+pub fn encode(data: &[u8], codec: Codec, format: Base64Format) -> String {
+    match codec {
+        Codec::Base64 => match format {
+            Base64Format::Standard => BASE64_STANDARD.encode(data),
+            Base64Format::UrlSafe => BASE64_URL_SAFE.encode(data),
+            Base64Format::UrlSafeNoPad => BASE64_URL_SAFE_NO_PAD.encode(data),
+            Base64Format::Mime => wrap_lines(&BASE64_STANDARD.encode(data), MIME_LINE_LENGTH),
+        },
+        Codec::Hex => hex::encode(data),
+        Codec::Base32 => base32_encode(data),
+        Codec::Base58 => base58_encode(data),
+    }
+}
+
+/// 忽略输入中的空白字符，因此可以直接解码换行后的 MIME 文本
+pub fn decode(text: &str, codec: Codec, format: Base64Format) -> anyhow::Result<Vec<u8>> {
+    let text = text
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>();
+
+    let decoded = match codec {
+        Codec::Base64 => match format {
+            Base64Format::Standard | Base64Format::Mime => STANDARD_DECODER.decode(text)?,
+            Base64Format::UrlSafe | Base64Format::UrlSafeNoPad => URL_SAFE_DECODER.decode(text)?,
+        },
+        Codec::Hex => {
+            let text = text
+                .strip_prefix("0x")
+                .or_else(|| text.strip_prefix("0X"))
+                .unwrap_or(&text);
+            hex::decode(text)?
+        }
+        Codec::Base32 => base32_decode(&text)?,
+        Codec::Base58 => base58_decode(&text)?,
+    };
+    Ok(decoded)
+}
+
+fn wrap_lines(text: &str, width: usize) -> String {
+    text.as_bytes()
+        .chunks(width)
+        .map(|line| std::str::from_utf8(line).expect("base64 is ascii"))
+        .collect::<Vec<_>>()
+        .join("\r\n")
+}
+
+fn base32_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(5) * 8);
+    for chunk in data.chunks(5) {
+        let mut buf = [0u8; 5];
+        buf[..chunk.len()].copy_from_slice(chunk);
+        let bits = buf.iter().fold(0u64, |acc, b| (acc << 8) | *b as u64);
+
+        // 每 5 个字节输出 8 个字符，不足时用 = 填充
+        let chars = (chunk.len() * 8).div_ceil(5);
+        for i in 0..8 {
+            if i < chars {
+                let index = (bits >> (35 - i * 5)) & 0x1f;
+                out.push(BASE32_ALPHABET[index as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn base32_decode(text: &str) -> anyhow::Result<Vec<u8>> {
+    let text = text.trim_end_matches('=');
+    let mut out = Vec::with_capacity(text.len() * 5 / 8);
+    let mut bits = 0u64;
+    let mut count = 0;
+    for c in text.chars() {
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|&a| a as char == c.to_ascii_uppercase())
+            .ok_or_else(|| anyhow::anyhow!("Invalid base32 character: {:?}", c))?;
+        bits = (bits << 5) | value as u64;
+        count += 5;
+        if count >= 8 {
+            count -= 8;
+            out.push((bits >> count) as u8);
+        }
+    }
+
+    // 剩余的位必须是 0，否则不是合法的编码
+    if count >= 5 || bits & ((1 << count) - 1) != 0 {
+        anyhow::bail!("Invalid base32 length or trailing bits");
+    }
+    Ok(out)
+}
+
+fn base58_encode(data: &[u8]) -> String {
+    let zeros = data.iter().take_while(|&&b| b == 0).count();
+
+    // 按 58 进制的小端数字累加
+    let mut digits: Vec<u8> = Vec::with_capacity(data.len() * 138 / 100 + 1);
+    for &byte in &data[zeros..] {
+        let mut carry = byte as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    // 每个前导 0 字节对应一个 '1'
+    std::iter::repeat_n('1', zeros)
+        .chain(
+            digits
+                .iter()
+                .rev()
+                .map(|&d| BASE58_ALPHABET[d as usize] as char),
+        )
+        .collect()
+}
+
+fn base58_decode(text: &str) -> anyhow::Result<Vec<u8>> {
+    let zeros = text.chars().take_while(|&c| c == '1').count();
+
+    let mut bytes: Vec<u8> = Vec::with_capacity(text.len());
+    for c in text.chars().skip(zeros) {
+        let mut carry = BASE58_ALPHABET
+            .iter()
+            .position(|&a| a as char == c)
+            .ok_or_else(|| anyhow::anyhow!("Invalid base58 character: {:?}", c))?
+            as u32;
+        for byte in bytes.iter_mut() {
+            carry += *byte as u32 * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+
+    let mut out = vec![0u8; zeros];
+    out.extend(bytes.iter().rev());
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BINARY: &[u8] = &[0, 0, 0xff, 0xfe, 0x10, 0x80, 0x7f];
+
+    #[test]
+    fn test_base64_formats() -> anyhow::Result<()> {
+        let data = b"\xfb\xff\xfe?";
+        assert_eq!(
+            encode(data, Codec::Base64, Base64Format::Standard),
+            "+//+Pw=="
+        );
+        assert_eq!(
+            encode(data, Codec::Base64, Base64Format::UrlSafe),
+            "-__-Pw=="
+        );
+        assert_eq!(
+            encode(data, Codec::Base64, Base64Format::UrlSafeNoPad),
+            "-__-Pw"
+        );
+
+        // 有无填充都可以解码
+        assert_eq!(
+            decode("-__-Pw", Codec::Base64, Base64Format::UrlSafe)?,
+            data
+        );
+        assert_eq!(
+            decode("-__-Pw==\n", Codec::Base64, Base64Format::UrlSafeNoPad)?,
+            data
+        );
+        assert!(decode("+//+Pw==", Codec::Base64, Base64Format::UrlSafe).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_base64_mime() -> anyhow::Result<()> {
+        let data = vec![7u8; 100];
+        let encoded = encode(&data, Codec::Base64, Base64Format::Mime);
+        let lines = encoded.split("\r\n").collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].len(), MIME_LINE_LENGTH);
+        assert_eq!(decode(&encoded, Codec::Base64, Base64Format::Mime)?, data);
+        Ok(())
+    }
+
+    #[test]
+    fn test_base32() -> anyhow::Result<()> {
+        // RFC 4648 测试向量
+        let vectors = [
+            ("", ""),
+            ("f", "MY======"),
+            ("fo", "MZXQ===="),
+            ("foo", "MZXW6==="),
+            ("foob", "MZXW6YQ="),
+            ("fooba", "MZXW6YTB"),
+            ("foobar", "MZXW6YTBOI======"),
+        ];
+        for (plain, encoded) in vectors {
+            assert_eq!(base32_encode(plain.as_bytes()), encoded);
+            assert_eq!(base32_decode(encoded)?, plain.as_bytes());
+        }
+        assert_eq!(base32_decode("mzxw6ytboi")?, b"foobar");
+        assert!(base32_decode("MZXW6YTB1").is_err());
+        assert!(base32_decode("MZ7").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_base58() -> anyhow::Result<()> {
+        assert_eq!(base58_encode(b"Hello World!"), "2NEpo7TZRRrLZSi2U");
+        assert_eq!(base58_encode(&[0, 0, 0x28, 0x7f, 0xb4, 0xcd]), "11233QC4");
+        assert_eq!(base58_decode("11233QC4")?, [0, 0, 0x28, 0x7f, 0xb4, 0xcd]);
+        assert_eq!(base58_encode(&[]), "");
+        assert!(base58_decode("0OIl").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_binary_roundtrip() -> anyhow::Result<()> {
+        for codec in [Codec::Base64, Codec::Hex, Codec::Base32, Codec::Base58] {
+            let encoded = encode(BINARY, codec, Base64Format::Standard);
+            assert_eq!(decode(&encoded, codec, Base64Format::Standard)?, BINARY);
+        }
+        assert_eq!(
+            decode("0xFF10", Codec::Hex, Base64Format::Standard)?,
+            [0xff, 0x10]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_decode_writes_raw_bytes() -> anyhow::Result<()> {
+        let dir = std::env::temp_dir();
+        let input = dir.join(format!("cli-codec-{}.txt", std::process::id()));
+        let output = dir.join(format!("cli-codec-{}.bin", std::process::id()));
+        let (input, output) = (input.to_string_lossy(), output.to_string_lossy());
+
+        let opts = CodecOpts {
+            input: input.to_string(),
+            output: output.to_string(),
+            codec: Codec::Base64,
+            format: Base64Format::Standard,
+        };
+
+        std::fs::write(input.as_ref(), BINARY)?;
+        process_encode(&opts, OutputMode::Quiet).await?;
+        assert_eq!(std::fs::read_to_string(output.as_ref())?, "AAD//hCAfw==\n");
+
+        std::fs::copy(output.as_ref(), input.as_ref())?;
+        process_decode(&opts, OutputMode::Quiet).await?;
+        assert_eq!(std::fs::read(output.as_ref())?, BINARY);
+
+        let text = std::fs::read_to_string("fixtures/base64_decode.txt")?;
+        let decoded = decode(&text, Codec::Base64, Base64Format::UrlSafeNoPad)?;
+        assert_eq!(
+            String::from_utf8(decoded)?,
+            std::fs::read_to_string("fixtures/base64_encode.txt")?.trim()
+        );
+
+        std::fs::remove_file(input.as_ref())?;
+        std::fs::remove_file(output.as_ref())?;
+        Ok(())
+    }
+}
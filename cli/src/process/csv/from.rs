@@ -0,0 +1,172 @@
+use std::{collections::HashMap, io::Read};
+
+use csv::WriterBuilder;
+use serde_json::{Map, Value};
+
+use crate::{opts::csv::CsvFromCmd, process::data, utils};
+
+pub async fn process_from(opts: &CsvFromCmd) -> anyhow::Result<()> {
+    let delimiter = u8::try_from(opts.delimiter)
+        .ok()
+        .filter(u8::is_ascii)
+        .ok_or_else(|| anyhow::anyhow!("delimiter must be an ASCII character"))?;
+
+    let mut reader = utils::get_reader(&opts.input).await?;
+    let mut content = String::new();
+    reader.read_to_string(&mut content)?;
+
+    let rows = records(data::parse(&content, opts.format)?)?
+        .iter()
+        .map(|record| {
+            let mut row = Vec::new();
+            flatten("", record, &mut row);
+            row
+        })
+        .collect::<Vec<_>>();
+
+    // 表头为所有记录的列的并集，按第一次出现的顺序排列
+    let mut headers = Vec::new();
+    let mut index = HashMap::new();
+    for (column, _) in rows.iter().flatten() {
+        if !index.contains_key(column) {
+            index.insert(column.clone(), headers.len());
+            headers.push(column.clone());
+        }
+    }
+
+    let mut writer = WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(utils::get_writer(&opts.output).await?);
+    writer.write_record(&headers)?;
+    for row in rows {
+        let mut record = vec![String::new(); headers.len()];
+        for (column, value) in row {
+            record[index[&column]] = value;
+        }
+        writer.write_record(&record)?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// 取出需要转换的记录：
+/// 顶层为数组时每个元素为一行；顶层对象只有一个数组字段时（如 `[[rows]]`）取该数组；其他对象作为单独一行
+fn records(value: Value) -> anyhow::Result<Vec<Map<String, Value>>> {
+    let items = match value {
+        Value::Array(items) => items,
+        Value::Object(map) if map.len() == 1 && map.values().all(Value::is_array) => {
+            match map.into_iter().next() {
+                Some((_, Value::Array(items))) => items,
+                _ => unreachable!(),
+            }
+        }
+        Value::Object(map) => return Ok(vec![map]),
+        v => anyhow::bail!("expected an object or an array of objects, got {}", v),
+    };
+
+    items
+        .into_iter()
+        .enumerate()
+        .map(|(n, item)| match item {
+            Value::Object(map) => Ok(map),
+            v => anyhow::bail!("record {} is not an object: {}", n + 1, v),
+        })
+        .collect()
+}
+
+/// 嵌套对象展开为 `a.b.c` 形式的列，数组保留为 JSON 字符串
+fn flatten(prefix: &str, map: &Map<String, Value>, row: &mut Vec<(String, String)>) {
+    for (key, value) in map {
+        let column = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+
+        match value {
+            Value::Object(nested) => flatten(&column, nested, row),
+            Value::Null => row.push((column, String::new())),
+            Value::String(s) => row.push((column, s.clone())),
+            v => row.push((column, v.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::opts::csv::InputFormat;
+
+    fn temp_path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("cli-csv-from-{}-{}", std::process::id(), name))
+            .to_string_lossy()
+            .to_string()
+    }
+
+    async fn convert(content: &str, format: InputFormat) -> anyhow::Result<String> {
+        let name = format!("{:?}", format).to_lowercase();
+        let input = temp_path(&format!("{}.in", name));
+        let output = temp_path(&format!("{}.csv", name));
+        std::fs::write(&input, content)?;
+
+        let opts = CsvFromCmd {
+            input: input.clone(),
+            output: output.clone(),
+            format,
+            delimiter: ',',
+        };
+        process_from(&opts).await?;
+
+        let csv = std::fs::read_to_string(&output)?;
+        std::fs::remove_file(input)?;
+        std::fs::remove_file(output)?;
+        Ok(csv)
+    }
+
+    #[tokio::test]
+    async fn test_process_from_json() -> anyhow::Result<()> {
+        let json = r#"[
+            {"name": "alice", "age": 30, "address": {"city": "Turin", "zip": "10100"}},
+            {"name": "bob, jr", "tags": ["a", "b"], "address": {"city": null}}
+        ]"#;
+
+        let csv = convert(json, InputFormat::Json).await?;
+        assert_eq!(
+            csv,
+            "name,age,address.city,address.zip,tags\n\
+             alice,30,Turin,10100,\n\
+             \"bob, jr\",,,,\"[\"\"a\"\",\"\"b\"\"]\"\n"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_process_from_ndjson_and_yaml() -> anyhow::Result<()> {
+        let ndjson = "{\"a\":1}\n\n{\"b\":true}\n";
+        let csv = convert(ndjson, InputFormat::Ndjson).await?;
+        assert_eq!(csv, "a,b\n1,\n,true\n");
+
+        let yaml = "- a: 1\n  b:\n    c: x\n- a: 2\n";
+        let csv = convert(yaml, InputFormat::Yaml).await?;
+        assert_eq!(csv, "a,b.c\n1,x\n2,\n");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_process_from_toml() -> anyhow::Result<()> {
+        let toml = "[[players]]\nname = \"alice\"\njoined = 2024-01-31\n\n\
+                    [[players]]\nname = \"bob\"\n";
+        let csv = convert(toml, InputFormat::Toml).await?;
+        assert_eq!(csv, "joined,name\n2024-01-31,alice\n,bob\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_records_rejects_scalars() {
+        assert!(records(serde_json::json!([1, 2])).is_err());
+        assert!(records(serde_json::json!("x")).is_err());
+        assert_eq!(records(serde_json::json!({"a": 1})).unwrap().len(), 1);
+    }
+}
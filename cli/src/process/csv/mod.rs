@@ -0,0 +1,359 @@
+mod from;
+mod value;
+mod writer;
+
+use std::{cmp::Ordering, path::Path};
+
+use anyhow::Context as _;
+use csv::{ReaderBuilder, StringRecord};
+
+use crate::{
+    opts::csv::{CsvCmd, Filter, FilterOp},
+    utils,
+};
+pub use from::process_from;
+use value::{ColumnType, Value};
+use writer::new_writer;
+
+// 过滤条件对应的列下标
+struct ColumnFilter<'a> {
+    index: usize,
+    filter: &'a Filter,
+}
+
+pub async fn process_csv(opts: &CsvCmd) -> anyhow::Result<()> {
+    let delimiter = u8::try_from(opts.delimiter)
+        .ok()
+        .filter(u8::is_ascii)
+        .ok_or_else(|| anyhow::anyhow!("delimiter must be an ASCII character"))?;
+
+    let input = opts
+        .input
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("--input is required"))?;
+    let reader = utils::get_reader(input).await?;
+    let mut reader = ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(opts.header)
+        .from_reader(reader);
+
+    // 没有表头时 headers() 返回第一行数据，只用来确定列数
+    let headers = reader.headers()?.clone();
+    let columns = if opts.header {
+        headers.iter().map(|h| h.to_string()).collect::<Vec<_>>()
+    } else {
+        (1..=headers.len())
+            .map(|i| format!("column_{}", i))
+            .collect::<Vec<_>>()
+    };
+
+    let projection = project(&columns, opts)?;
+    let filters = opts
+        .filters
+        .iter()
+        .map(|filter| {
+            let index = column_index(&columns, &filter.column)?;
+            Ok(ColumnFilter { index, filter })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    // 先读取一部分数据推断列类型，剩余的数据逐行转换
+    let mut records = reader.records();
+    let sample = records
+        .by_ref()
+        .take(opts.infer_rows.max(1))
+        .collect::<Result<Vec<_>, _>>()?;
+    let types = infer_types(columns.len(), &sample);
+
+    let output = opts.output_path();
+    let table = match input {
+        "-" => "rows".to_string(),
+        input => Path::new(input)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| "rows".to_string()),
+    };
+    let mut writer = new_writer(
+        opts.format,
+        utils::get_writer(&output).await?,
+        projection.iter().map(|(_, name)| name.clone()).collect(),
+        projection.iter().map(|(i, _)| types[*i]).collect(),
+        table,
+    )?;
+
+    let rows = sample.into_iter().map(Ok).chain(records);
+    let mut warned = vec![false; columns.len()];
+    for (n, record) in rows.enumerate() {
+        let record = record?;
+        let values = parse_record(&record, &columns, &types, n + 1, &mut warned);
+
+        if !filters.iter().all(|f| matches(f.filter, &values[f.index])) {
+            continue;
+        }
+
+        let row = projection
+            .iter()
+            .map(|(i, _)| values[*i].clone())
+            .collect::<Vec<_>>();
+        writer
+            .write_row(&row)
+            .with_context(|| format!("failed to convert row {}", n + 1))?;
+    }
+
+    writer.finish()
+}
+
+/// 根据 --select 计算输出的列：(源列下标, 输出列名)
+fn project(columns: &[String], opts: &CsvCmd) -> anyhow::Result<Vec<(usize, String)>> {
+    if opts.select.is_empty() {
+        return Ok(columns.iter().cloned().enumerate().collect());
+    }
+
+    opts.select
+        .iter()
+        .map(|s| {
+            let index = column_index(columns, &s.column)?;
+            let name = s.alias.clone().unwrap_or_else(|| s.column.clone());
+            Ok((index, name))
+        })
+        .collect()
+}
+
+fn column_index(columns: &[String], name: &str) -> anyhow::Result<usize> {
+    columns
+        .iter()
+        .position(|c| c == name)
+        .ok_or_else(|| anyhow::anyhow!("Unknown column: {}", name))
+}
+
+fn infer_types(len: usize, sample: &[StringRecord]) -> Vec<ColumnType> {
+    let mut types = vec![ColumnType::Null; len];
+    for record in sample {
+        for (ty, raw) in types.iter_mut().zip(record.iter()) {
+            *ty = ty.merge(ColumnType::infer(raw));
+        }
+    }
+    types
+}
+
+/// 类型只从前 --infer-rows 行推断，之后与推断类型不符的值按合并后的类型解析，每列只警告一次；
+/// Parquet 等列式格式的 schema 已经确定，写入时会报错
+fn parse_record(
+    record: &StringRecord,
+    columns: &[String],
+    types: &[ColumnType],
+    row: usize,
+    warned: &mut [bool],
+) -> Vec<Value> {
+    record
+        .iter()
+        .zip(types)
+        .enumerate()
+        .map(|(i, (raw, ty))| {
+            ty.parse(raw).unwrap_or_else(|_| {
+                if !std::mem::replace(&mut warned[i], true) {
+                    tracing::warn!(
+                        "row {}: column {:?} was inferred as {:?} but got {:?}, try a larger --infer-rows",
+                        row,
+                        columns[i],
+                        ty,
+                        raw
+                    );
+                }
+                ty.parse_lossy(raw)
+            })
+        })
+        .collect()
+}
+
+fn matches(filter: &Filter, value: &Value) -> bool {
+    if filter.op == FilterOp::Contains {
+        return value.to_string().contains(&filter.value);
+    }
+
+    match value.compare(&filter.value) {
+        Some(ord) => match filter.op {
+            FilterOp::Eq => ord == Ordering::Equal,
+            FilterOp::Ne => ord != Ordering::Equal,
+            FilterOp::Gt => ord == Ordering::Greater,
+            FilterOp::Ge => ord != Ordering::Less,
+            FilterOp::Lt => ord == Ordering::Less,
+            FilterOp::Le => ord != Ordering::Greater,
+            FilterOp::Contains => unreachable!(),
+        },
+        // 空值或类型不匹配时只满足 !=
+        None => filter.op == FilterOp::Ne,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser as _;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    use super::*;
+
+    const CSV: &str = "name;age;score;active;joined\n\
+                       alice;30;9.5;true;2024-01-31\n\
+                       bob;;7;false;2023-12-01\n\
+                       carol;42;8.25;true;\n";
+
+    fn temp_path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("cli-csv-{}-{}", std::process::id(), name))
+            .to_string_lossy()
+            .to_string()
+    }
+
+    fn parse_cmd(args: &[&str]) -> CsvCmd {
+        CsvCmd::parse_from(std::iter::once("csv").chain(args.iter().copied()))
+    }
+
+    #[tokio::test]
+    async fn test_process_csv_ndjson() -> anyhow::Result<()> {
+        let input = temp_path("ndjson.csv");
+        let output = temp_path("ndjson.out");
+        std::fs::write(&input, CSV)?;
+
+        let cmd = parse_cmd(&[
+            "--input",
+            &input,
+            "--output",
+            &output,
+            "--format",
+            "ndjson",
+            "--delimiter",
+            ";",
+            "--select",
+            "name:user,age,joined",
+            "--where",
+            "active==true",
+        ]);
+        process_csv(&cmd).await?;
+
+        let content = std::fs::read_to_string(&output)?;
+        assert_eq!(
+            content,
+            "{\"user\":\"alice\",\"age\":30,\"joined\":\"2024-01-31\"}\n\
+             {\"user\":\"carol\",\"age\":42,\"joined\":null}\n"
+        );
+
+        std::fs::remove_file(input)?;
+        std::fs::remove_file(output)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_process_csv_without_header_markdown() -> anyhow::Result<()> {
+        let input = temp_path("markdown.csv");
+        let output = temp_path("markdown.out");
+        std::fs::write(&input, "1,a|b\n2,c\n")?;
+
+        let cmd = parse_cmd(&[
+            "--input",
+            &input,
+            "--output",
+            &output,
+            "--format",
+            "md",
+            "--header",
+            "false",
+            "--where",
+            "column_1>1",
+        ]);
+        process_csv(&cmd).await?;
+
+        let content = std::fs::read_to_string(&output)?;
+        assert_eq!(
+            content,
+            "| column_1 | column_2 |\n| --- | --- |\n| 2 | c |\n"
+        );
+
+        std::fs::remove_file(input)?;
+        std::fs::remove_file(output)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_process_csv_parquet() -> anyhow::Result<()> {
+        let input = temp_path("parquet.csv");
+        let output = temp_path("parquet.out");
+        std::fs::write(&input, CSV)?;
+
+        let cmd = parse_cmd(&[
+            "--input",
+            &input,
+            "--output",
+            &output,
+            "--format",
+            "parquet",
+            "--delimiter",
+            ";",
+        ]);
+        process_csv(&cmd).await?;
+
+        let file = std::fs::File::open(&output)?;
+        let builder = ParquetRecordBatchReaderBuilder::try_new(file)?;
+        let schema = builder.schema().clone();
+        let rows = builder
+            .build()?
+            .map(|batch| batch.map(|b| b.num_rows()))
+            .sum::<Result<usize, _>>()?;
+
+        assert_eq!(rows, 3);
+        let types = schema
+            .fields()
+            .iter()
+            .map(|f| f.data_type().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(types, ["Utf8", "Int64", "Float64", "Boolean", "Date32"]);
+
+        std::fs::remove_file(input)?;
+        std::fs::remove_file(output)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_process_csv_value_after_sample() -> anyhow::Result<()> {
+        let input = temp_path("widen.csv");
+        let output = temp_path("widen.out");
+        std::fs::write(&input, "id,score\n1,2\n2,2.5\n3,abc\n")?;
+
+        let args = ["--input", &input, "--output", &output, "--infer-rows", "1"];
+        process_csv(&parse_cmd(&[&args[..], &["--format", "ndjson"]].concat())).await?;
+        let content = std::fs::read_to_string(&output)?;
+        assert_eq!(
+            content,
+            "{\"id\":1,\"score\":2}\n\
+             {\"id\":2,\"score\":2.5}\n\
+             {\"id\":3,\"score\":\"abc\"}\n"
+        );
+
+        // 列式格式的类型已经确定，不匹配的值不能丢弃，报告所在的行和列
+        let err = process_csv(&parse_cmd(&[&args[..], &["--format", "parquet"]].concat()))
+            .await
+            .unwrap_err();
+        let msg = format!("{:#}", err);
+        assert!(msg.contains("row 2"), "{}", msg);
+        assert!(
+            msg.contains("column \"score\" was inferred as Int64"),
+            "{}",
+            msg
+        );
+
+        // 整数可以写入推断为浮点数的列
+        std::fs::write(&input, "id,score\n1,2.5\n2,3\n")?;
+        process_csv(&parse_cmd(&[&args[..], &["--format", "parquet"]].concat())).await?;
+        let file = std::fs::File::open(&output)?;
+        let batch = ParquetRecordBatchReaderBuilder::try_new(file)?
+            .build()?
+            .next()
+            .expect("one batch")?;
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.column(1).null_count(), 0);
+
+        std::fs::remove_file(input)?;
+        std::fs::remove_file(output)?;
+        Ok(())
+    }
+}
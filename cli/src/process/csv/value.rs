@@ -0,0 +1,178 @@
+use std::cmp::Ordering;
+
+use chrono::NaiveDate;
+use serde::{Serialize, Serializer};
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// 从 CSV 字符串推断出的列类型
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColumnType {
+    Null,
+    Bool,
+    Int,
+    Float,
+    Date,
+    String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Date(NaiveDate),
+    String(String),
+}
+
+impl ColumnType {
+    /// 推断单个值的类型，空字符串为 Null
+    pub fn infer(raw: &str) -> Self {
+        if raw.is_empty() {
+            ColumnType::Null
+        } else if parse_bool(raw).is_some() {
+            ColumnType::Bool
+        } else if raw.parse::<i64>().is_ok() {
+            ColumnType::Int
+        } else if raw.parse::<f64>().is_ok() {
+            ColumnType::Float
+        } else if NaiveDate::parse_from_str(raw, DATE_FORMAT).is_ok() {
+            ColumnType::Date
+        } else {
+            ColumnType::String
+        }
+    }
+
+    /// 合并同一列中两个值的类型：Null 不影响结果，Int 和 Float 合并为 Float，其他不同类型退化为 String
+    pub fn merge(self, other: Self) -> Self {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (ColumnType::Null, t) | (t, ColumnType::Null) => t,
+            (ColumnType::Int, ColumnType::Float) | (ColumnType::Float, ColumnType::Int) => {
+                ColumnType::Float
+            }
+            _ => ColumnType::String,
+        }
+    }
+
+    /// 按列类型解析值，空字符串总是 Null
+    pub fn parse(self, raw: &str) -> anyhow::Result<Value> {
+        if raw.is_empty() {
+            return Ok(Value::Null);
+        }
+
+        let value = match self {
+            ColumnType::Null | ColumnType::String => Value::String(raw.to_string()),
+            ColumnType::Bool => Value::Bool(
+                parse_bool(raw).ok_or_else(|| anyhow::anyhow!("invalid bool: {:?}", raw))?,
+            ),
+            ColumnType::Int => Value::Int(raw.parse()?),
+            ColumnType::Float => Value::Float(raw.parse()?),
+            ColumnType::Date => Value::Date(NaiveDate::parse_from_str(raw, DATE_FORMAT)?),
+        };
+
+        Ok(value)
+    }
+
+    /// 值与列类型不符时按两者合并后的类型解析：整数列中的小数为 Float，其他为 String
+    pub fn parse_lossy(self, raw: &str) -> Value {
+        self.merge(ColumnType::infer(raw))
+            .parse(raw)
+            .unwrap_or_else(|_| Value::String(raw.to_string()))
+    }
+}
+
+impl Value {
+    /// 和过滤条件中的字面量比较，类型不匹配时返回 None
+    pub fn compare(&self, literal: &str) -> Option<Ordering> {
+        match self {
+            Value::Null => None,
+            Value::Bool(b) => parse_bool(literal).map(|l| b.cmp(&l)),
+            Value::Int(i) => (*i as f64).partial_cmp(&literal.parse::<f64>().ok()?),
+            Value::Float(f) => f.partial_cmp(&literal.parse::<f64>().ok()?),
+            Value::Date(d) => NaiveDate::parse_from_str(literal, DATE_FORMAT)
+                .ok()
+                .map(|l| d.cmp(&l)),
+            Value::String(s) => Some(s.as_str().cmp(literal)),
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Null => Ok(()),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Int(i) => write!(f, "{}", i),
+            Value::Float(v) => write!(f, "{}", v),
+            Value::Date(d) => write!(f, "{}", d.format(DATE_FORMAT)),
+            Value::String(s) => f.write_str(s),
+        }
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Null => serializer.serialize_none(),
+            Value::Bool(b) => serializer.serialize_bool(*b),
+            Value::Int(i) => serializer.serialize_i64(*i),
+            Value::Float(v) => serializer.serialize_f64(*v),
+            Value::Date(_) => serializer.collect_str(self),
+            Value::String(s) => serializer.serialize_str(s),
+        }
+    }
+}
+
+fn parse_bool(raw: &str) -> Option<bool> {
+    match raw.to_ascii_lowercase().as_str() {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_infer_and_merge() {
+        let infer = |values: &[&str]| {
+            values
+                .iter()
+                .map(|v| ColumnType::infer(v))
+                .fold(ColumnType::Null, ColumnType::merge)
+        };
+
+        assert_eq!(infer(&["1", "", "42"]), ColumnType::Int);
+        assert_eq!(infer(&["1", "2.5"]), ColumnType::Float);
+        assert_eq!(infer(&["true", "False"]), ColumnType::Bool);
+        assert_eq!(infer(&["2024-01-31", ""]), ColumnType::Date);
+        assert_eq!(infer(&["1", "abc"]), ColumnType::String);
+        assert_eq!(infer(&["", ""]), ColumnType::Null);
+    }
+
+    #[test]
+    fn test_parse_and_compare() -> anyhow::Result<()> {
+        assert_eq!(ColumnType::Float.parse("3")?, Value::Float(3.0));
+        assert_eq!(ColumnType::Int.parse("")?, Value::Null);
+        assert!(ColumnType::Int.parse("x").is_err());
+
+        assert_eq!(Value::Int(10).compare("9"), Some(Ordering::Greater));
+        assert_eq!(Value::Int(10).compare("10.0"), Some(Ordering::Equal));
+        assert_eq!(
+            ColumnType::Date.parse("2024-01-31")?.compare("2024-02-01"),
+            Some(Ordering::Less)
+        );
+        assert_eq!(Value::Int(10).compare("abc"), None);
+        assert_eq!(Value::Null.compare(""), None);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,410 @@
+use std::{io::Write, sync::Arc};
+
+use arrow::{
+    array::{ArrayRef, BooleanBuilder, Date32Builder, Float64Builder, Int64Builder, StringBuilder},
+    datatypes::{DataType, Field, Schema, SchemaRef},
+    ipc::writer::FileWriter,
+    record_batch::RecordBatch,
+};
+use parquet::arrow::ArrowWriter;
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+use crate::{
+    opts::csv::OutputFormat,
+    process::csv::value::{ColumnType, Value},
+};
+
+// Arrow / Parquet 每个 RecordBatch 的行数
+const BATCH_SIZE: usize = 8192;
+
+/// 逐行写出转换结果，不需要把整个文件读入内存
+pub trait RowWriter {
+    fn write_row(&mut self, row: &[Value]) -> anyhow::Result<()>;
+
+    fn finish(self: Box<Self>) -> anyhow::Result<()>;
+}
+
+/// 按列名顺序序列化为 map
+struct Row<'a> {
+    columns: &'a [String],
+    values: &'a [Value],
+    skip_null: bool,
+}
+
+impl Serialize for Row<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        for (column, value) in self.columns.iter().zip(self.values) {
+            if self.skip_null && value.is_null() {
+                continue;
+            }
+            map.serialize_entry(column, value)?;
+        }
+        map.end()
+    }
+}
+
+pub fn new_writer(
+    format: OutputFormat,
+    writer: Box<dyn Write + Send>,
+    columns: Vec<String>,
+    types: Vec<ColumnType>,
+    table: String,
+) -> anyhow::Result<Box<dyn RowWriter>> {
+    let writer: Box<dyn RowWriter> = match format {
+        OutputFormat::Json => Box::new(JsonWriter {
+            writer,
+            columns,
+            rows: 0,
+        }),
+        OutputFormat::Ndjson => Box::new(NdjsonWriter { writer, columns }),
+        OutputFormat::Yaml => Box::new(YamlWriter { writer, columns }),
+        // TOML 的顶层必须是 table，每一行写成一个 [[table]]
+        OutputFormat::Toml => Box::new(TomlWriter {
+            writer,
+            columns,
+            table,
+        }),
+        OutputFormat::Markdown => Box::new(MarkdownWriter::new(writer, columns)?),
+        OutputFormat::Arrow => {
+            let batches = BatchBuilder::new(columns, types);
+            let writer = FileWriter::try_new(writer, &batches.schema)?;
+            Box::new(ArrowBatchWriter {
+                batches,
+                sink: ArrowSink::Ipc(writer),
+            })
+        }
+        OutputFormat::Parquet => {
+            let batches = BatchBuilder::new(columns, types);
+            let writer = ArrowWriter::try_new(writer, batches.schema.clone(), None)?;
+            Box::new(ArrowBatchWriter {
+                batches,
+                sink: ArrowSink::Parquet(writer),
+            })
+        }
+    };
+
+    Ok(writer)
+}
+
+struct JsonWriter {
+    writer: Box<dyn Write + Send>,
+    columns: Vec<String>,
+    rows: usize,
+}
+
+impl RowWriter for JsonWriter {
+    fn write_row(&mut self, values: &[Value]) -> anyhow::Result<()> {
+        let sep = if self.rows == 0 { "[\n  " } else { ",\n  " };
+        self.writer.write_all(sep.as_bytes())?;
+        let row = Row {
+            columns: &self.columns,
+            values,
+            skip_null: false,
+        };
+        serde_json::to_writer(&mut self.writer, &row)?;
+        self.rows += 1;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> anyhow::Result<()> {
+        let end = if self.rows == 0 { "[]\n" } else { "\n]\n" };
+        self.writer.write_all(end.as_bytes())?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+struct NdjsonWriter {
+    writer: Box<dyn Write + Send>,
+    columns: Vec<String>,
+}
+
+impl RowWriter for NdjsonWriter {
+    fn write_row(&mut self, values: &[Value]) -> anyhow::Result<()> {
+        let row = Row {
+            columns: &self.columns,
+            values,
+            skip_null: false,
+        };
+        serde_json::to_writer(&mut self.writer, &row)?;
+        self.writer.write_all(b"\n")?;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> anyhow::Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+struct YamlWriter {
+    writer: Box<dyn Write + Send>,
+    columns: Vec<String>,
+}
+
+impl RowWriter for YamlWriter {
+    fn write_row(&mut self, values: &[Value]) -> anyhow::Result<()> {
+        let row = Row {
+            columns: &self.columns,
+            values,
+            skip_null: false,
+        };
+        // 单元素序列的输出就是 "- key: value"，可以直接拼接
+        let yaml = serde_yaml::to_string(&[row])?;
+        self.writer.write_all(yaml.as_bytes())?;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> anyhow::Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+struct TomlWriter {
+    writer: Box<dyn Write + Send>,
+    columns: Vec<String>,
+    table: String,
+}
+
+impl RowWriter for TomlWriter {
+    fn write_row(&mut self, values: &[Value]) -> anyhow::Result<()> {
+        // TOML 没有 null，跳过空值
+        let row = Row {
+            columns: &self.columns,
+            values,
+            skip_null: true,
+        };
+        writeln!(self.writer, "[[{}]]", table_header(&self.table))?;
+        writeln!(self.writer, "{}", toml::to_string(&row)?)?;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> anyhow::Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+// 非法的 bare key 需要加引号
+fn table_header(name: &str) -> String {
+    let bare = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if bare {
+        name.to_string()
+    } else {
+        toml::Value::String(name.to_string()).to_string()
+    }
+}
+
+struct MarkdownWriter {
+    writer: Box<dyn Write + Send>,
+}
+
+impl MarkdownWriter {
+    fn new(mut writer: Box<dyn Write + Send>, columns: Vec<String>) -> anyhow::Result<Self> {
+        let header = columns
+            .iter()
+            .map(|c| escape_markdown(c))
+            .collect::<Vec<_>>();
+        writeln!(writer, "| {} |", header.join(" | "))?;
+        writeln!(writer, "|{}", " --- |".repeat(columns.len()))?;
+        Ok(Self { writer })
+    }
+}
+
+impl RowWriter for MarkdownWriter {
+    fn write_row(&mut self, values: &[Value]) -> anyhow::Result<()> {
+        let cells = values
+            .iter()
+            .map(|v| escape_markdown(&v.to_string()))
+            .collect::<Vec<_>>();
+        writeln!(self.writer, "| {} |", cells.join(" | "))?;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> anyhow::Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+fn escape_markdown(s: &str) -> String {
+    s.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+enum ColumnBuilder {
+    Bool(BooleanBuilder),
+    Int(Int64Builder),
+    Float(Float64Builder),
+    Date(Date32Builder),
+    String(StringBuilder),
+}
+
+impl ColumnBuilder {
+    fn new(ty: ColumnType) -> Self {
+        match ty {
+            ColumnType::Bool => ColumnBuilder::Bool(BooleanBuilder::new()),
+            ColumnType::Int => ColumnBuilder::Int(Int64Builder::new()),
+            ColumnType::Float => ColumnBuilder::Float(Float64Builder::new()),
+            ColumnType::Date => ColumnBuilder::Date(Date32Builder::new()),
+            ColumnType::Null | ColumnType::String => ColumnBuilder::String(StringBuilder::new()),
+        }
+    }
+
+    fn data_type(ty: ColumnType) -> DataType {
+        match ty {
+            ColumnType::Bool => DataType::Boolean,
+            ColumnType::Int => DataType::Int64,
+            ColumnType::Float => DataType::Float64,
+            ColumnType::Date => DataType::Date32,
+            ColumnType::Null | ColumnType::String => DataType::Utf8,
+        }
+    }
+
+    /// 列类型已经写入 schema，推断样本之后出现的不同类型的值无法写入
+    fn accepts(&self, value: &Value) -> bool {
+        matches!(
+            (self, value),
+            (_, Value::Null)
+                | (ColumnBuilder::Bool(_), Value::Bool(_))
+                | (ColumnBuilder::Int(_), Value::Int(_))
+                | (ColumnBuilder::Float(_), Value::Float(_) | Value::Int(_))
+                | (ColumnBuilder::Date(_), Value::Date(_))
+                | (ColumnBuilder::String(_), _)
+        )
+    }
+
+    fn append(&mut self, value: &Value) -> anyhow::Result<()> {
+        match (self, value) {
+            (ColumnBuilder::Bool(b), Value::Null) => b.append_null(),
+            (ColumnBuilder::Int(b), Value::Null) => b.append_null(),
+            (ColumnBuilder::Float(b), Value::Null) => b.append_null(),
+            (ColumnBuilder::Date(b), Value::Null) => b.append_null(),
+            (ColumnBuilder::String(b), Value::Null) => b.append_null(),
+            (ColumnBuilder::Bool(b), Value::Bool(v)) => b.append_value(*v),
+            (ColumnBuilder::Int(b), Value::Int(v)) => b.append_value(*v),
+            (ColumnBuilder::Float(b), Value::Float(v)) => b.append_value(*v),
+            (ColumnBuilder::Date(b), Value::Date(v)) => {
+                // NaiveDate::default() 为 1970-01-01
+                let days = v.signed_duration_since(chrono::NaiveDate::default());
+                b.append_value(days.num_days() as i32)
+            }
+            (ColumnBuilder::Float(b), Value::Int(v)) => b.append_value(*v as f64),
+            (ColumnBuilder::String(b), v) => b.append_value(v.to_string()),
+            (_, v) => anyhow::bail!("unexpected value {:?} for column", v),
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        match self {
+            ColumnBuilder::Bool(b) => Arc::new(b.finish()),
+            ColumnBuilder::Int(b) => Arc::new(b.finish()),
+            ColumnBuilder::Float(b) => Arc::new(b.finish()),
+            ColumnBuilder::Date(b) => Arc::new(b.finish()),
+            ColumnBuilder::String(b) => Arc::new(b.finish()),
+        }
+    }
+}
+
+struct BatchBuilder {
+    schema: SchemaRef,
+    builders: Vec<ColumnBuilder>,
+    rows: usize,
+}
+
+impl BatchBuilder {
+    fn new(columns: Vec<String>, types: Vec<ColumnType>) -> Self {
+        let fields = columns
+            .iter()
+            .zip(&types)
+            .map(|(name, ty)| Field::new(name, ColumnBuilder::data_type(*ty), true))
+            .collect::<Vec<_>>();
+        let builders = types.into_iter().map(ColumnBuilder::new).collect();
+
+        Self {
+            schema: Arc::new(Schema::new(fields)),
+            builders,
+            rows: 0,
+        }
+    }
+
+    /// 先检查整行，避免部分列已经写入
+    fn append(&mut self, values: &[Value]) -> anyhow::Result<()> {
+        for ((builder, value), field) in self.builders.iter().zip(values).zip(self.schema.fields())
+        {
+            if !builder.accepts(value) {
+                anyhow::bail!(
+                    "column {:?} was inferred as {} but got {:?}, try a larger --infer-rows",
+                    field.name(),
+                    field.data_type(),
+                    value.to_string()
+                );
+            }
+        }
+        for (builder, value) in self.builders.iter_mut().zip(values) {
+            builder.append(value)?;
+        }
+        self.rows += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> anyhow::Result<RecordBatch> {
+        let columns = self.builders.iter_mut().map(|b| b.finish()).collect();
+        self.rows = 0;
+        Ok(RecordBatch::try_new(self.schema.clone(), columns)?)
+    }
+}
+
+enum ArrowSink {
+    Ipc(FileWriter<Box<dyn Write + Send>>),
+    Parquet(ArrowWriter<Box<dyn Write + Send>>),
+}
+
+impl ArrowSink {
+    fn write(&mut self, batch: &RecordBatch) -> anyhow::Result<()> {
+        match self {
+            ArrowSink::Ipc(w) => w.write(batch)?,
+            ArrowSink::Parquet(w) => w.write(batch)?,
+        }
+        Ok(())
+    }
+}
+
+struct ArrowBatchWriter {
+    batches: BatchBuilder,
+    sink: ArrowSink,
+}
+
+impl RowWriter for ArrowBatchWriter {
+    fn write_row(&mut self, values: &[Value]) -> anyhow::Result<()> {
+        self.batches.append(values)?;
+        if self.batches.rows >= BATCH_SIZE {
+            let batch = self.batches.finish()?;
+            self.sink.write(&batch)?;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> anyhow::Result<()> {
+        if self.batches.rows > 0 {
+            let batch = self.batches.finish()?;
+            self.sink.write(&batch)?;
+        }
+
+        match self.sink {
+            ArrowSink::Ipc(mut w) => {
+                w.finish()?;
+                w.into_inner()?.flush()?;
+            }
+            ArrowSink::Parquet(w) => {
+                w.into_inner()?.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
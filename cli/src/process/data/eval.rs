@@ -0,0 +1,954 @@
+use std::cmp::Ordering;
+
+use serde_json::{Map, Value};
+
+use super::filter::{BinOp, Expr};
+
+// 赋值时数组下标的上限，避免 `.a[100000000000] = 1` 分配巨大的数组；与 jq 一致
+const MAX_ARRAY_INDEX: usize = 536_870_911;
+
+/// 每个表达式可以产生 0 到多个结果
+pub fn eval(expr: &Expr, input: &Value) -> anyhow::Result<Vec<Value>> {
+    let values = match expr {
+        Expr::Identity => vec![input.clone()],
+        Expr::Recurse => {
+            let mut values = Vec::new();
+            recurse(input, &mut values);
+            values
+        }
+        Expr::Literal(value) => vec![value.clone()],
+        Expr::Index(target, index) => {
+            let mut values = Vec::new();
+            for value in eval(target, input)? {
+                for key in eval(index, input)? {
+                    values.push(index_value(&value, &key)?);
+                }
+            }
+            values
+        }
+        Expr::Slice(target, start, end) => {
+            let start = eval_bound(start.as_deref(), input)?;
+            let end = eval_bound(end.as_deref(), input)?;
+            eval(target, input)?
+                .iter()
+                .map(|value| slice(value, start, end))
+                .collect::<anyhow::Result<_>>()?
+        }
+        Expr::Iterate(target) => {
+            let mut values = Vec::new();
+            for value in eval(target, input)? {
+                match value {
+                    Value::Array(items) => values.extend(items),
+                    Value::Object(map) => values.extend(map.into_iter().map(|(_, v)| v)),
+                    v => anyhow::bail!("cannot iterate over {}", type_name(&v)),
+                }
+            }
+            values
+        }
+        Expr::Optional(target) => eval(target, input).unwrap_or_default(),
+        Expr::Pipe(lhs, rhs) => {
+            let mut values = Vec::new();
+            for value in eval(lhs, input)? {
+                values.extend(eval(rhs, &value)?);
+            }
+            values
+        }
+        Expr::Comma(lhs, rhs) => {
+            let mut values = eval(lhs, input)?;
+            values.extend(eval(rhs, input)?);
+            values
+        }
+        Expr::Neg(expr) => eval(expr, input)?
+            .iter()
+            .map(|v| match v.as_f64() {
+                Some(n) => number(-n),
+                None => anyhow::bail!("cannot negate {}", type_name(v)),
+            })
+            .collect::<anyhow::Result<_>>()?,
+        Expr::Binary(op, lhs, rhs) => {
+            let rhs = eval(rhs, input)?;
+            let mut values = Vec::new();
+            for l in eval(lhs, input)? {
+                for r in &rhs {
+                    values.push(binary(*op, &l, r)?);
+                }
+            }
+            values
+        }
+        Expr::And(lhs, rhs) | Expr::Or(lhs, rhs) => {
+            let is_and = matches!(expr, Expr::And(_, _));
+            let mut values = Vec::new();
+            for l in eval(lhs, input)? {
+                // and 左边为假、or 左边为真时不计算右边
+                if truthy(&l) != is_and {
+                    values.push(Value::Bool(!is_and));
+                    continue;
+                }
+                for r in eval(rhs, input)? {
+                    values.push(Value::Bool(truthy(&r)));
+                }
+            }
+            values
+        }
+        Expr::Alternative(lhs, rhs) => {
+            let values = eval(lhs, input)
+                .unwrap_or_default()
+                .into_iter()
+                .filter(truthy)
+                .collect::<Vec<_>>();
+            if values.is_empty() {
+                eval(rhs, input)?
+            } else {
+                values
+            }
+        }
+        Expr::Assign(path, value) => {
+            let paths = paths(path, input)?;
+            let mut values = Vec::new();
+            for value in eval(value, input)? {
+                let mut output = input.clone();
+                for path in &paths {
+                    set_path(&mut output, path, value.clone())?;
+                }
+                values.push(output);
+            }
+            values
+        }
+        Expr::Update(path, f) => {
+            let mut output = input.clone();
+            let mut deleted = Vec::new();
+            for path in paths(path, input)? {
+                // 更新函数没有结果时删除该路径
+                match eval(f, &get_path(&output, &path))?.into_iter().next() {
+                    Some(value) => set_path(&mut output, &path, value)?,
+                    None => deleted.push(path),
+                }
+            }
+            delete_paths(&mut output, deleted)?;
+            vec![output]
+        }
+        Expr::Array(None) => vec![Value::Array(Vec::new())],
+        Expr::Array(Some(expr)) => vec![Value::Array(eval(expr, input)?)],
+        Expr::Object(entries) => {
+            let mut objects = vec![Map::new()];
+            for (key, value) in entries {
+                let keys = eval(key, input)?;
+                let values = eval(value, input)?;
+                let mut next = Vec::new();
+                for object in &objects {
+                    for key in &keys {
+                        let Value::String(key) = key else {
+                            anyhow::bail!("object key must be a string, got {}", type_name(key));
+                        };
+                        for value in &values {
+                            let mut object = object.clone();
+                            object.insert(key.clone(), value.clone());
+                            next.push(object);
+                        }
+                    }
+                }
+                objects = next;
+            }
+            objects.into_iter().map(Value::Object).collect()
+        }
+        Expr::Call(name, args) => call(name, args, input)?,
+    };
+    Ok(values)
+}
+
+fn call(name: &str, args: &[Expr], input: &Value) -> anyhow::Result<Vec<Value>> {
+    let value = match (name, args) {
+        ("empty", []) => return Ok(Vec::new()),
+        ("recurse", []) => return eval(&Expr::Recurse, input),
+        ("select", [f]) => {
+            let values = eval(f, input)?;
+            return Ok(if values.iter().any(truthy) {
+                vec![input.clone()]
+            } else {
+                Vec::new()
+            });
+        }
+        ("has", [key]) => {
+            return eval(key, input)?
+                .iter()
+                .map(|key| match (input, key) {
+                    (Value::Object(map), Value::String(k)) => Ok(Value::Bool(map.contains_key(k))),
+                    (Value::Array(items), Value::Number(n)) => Ok(Value::Bool(
+                        n.as_f64()
+                            .is_some_and(|n| n >= 0.0 && (n as usize) < items.len()),
+                    )),
+                    _ => anyhow::bail!(
+                        "cannot check whether {} has a {} key",
+                        type_name(input),
+                        type_name(key)
+                    ),
+                })
+                .collect();
+        }
+        ("del", [path]) => {
+            let mut output = input.clone();
+            delete_paths(&mut output, paths(path, input)?)?;
+            output
+        }
+        ("map", [f]) => {
+            let mut values = Vec::new();
+            for item in elements(input)? {
+                values.extend(eval(f, &item)?);
+            }
+            Value::Array(values)
+        }
+        ("not", []) => Value::Bool(!truthy(input)),
+        ("length", []) => match input {
+            Value::Null => Value::from(0),
+            Value::Bool(_) => anyhow::bail!("boolean has no length"),
+            Value::Number(n) => number(n.as_f64().unwrap_or_default().abs())?,
+            Value::String(s) => Value::from(s.chars().count()),
+            Value::Array(items) => Value::from(items.len()),
+            Value::Object(map) => Value::from(map.len()),
+        },
+        ("keys", []) => match input {
+            Value::Object(map) => {
+                let mut keys = map.keys().cloned().collect::<Vec<_>>();
+                keys.sort();
+                Value::from(keys)
+            }
+            Value::Array(items) => Value::from((0..items.len()).collect::<Vec<_>>()),
+            v => anyhow::bail!("{} has no keys", type_name(v)),
+        },
+        ("type", []) => Value::from(type_name(input)),
+        ("add", []) => {
+            let mut items = elements(input)?.into_iter();
+            match items.next() {
+                Some(first) => {
+                    items.try_fold(first, |acc, item| binary(BinOp::Add, &acc, &item))?
+                }
+                None => Value::Null,
+            }
+        }
+        ("first", []) => index_value(input, &Value::from(0))?,
+        ("last", []) => index_value(input, &Value::from(-1))?,
+        ("reverse", []) => match input {
+            Value::String(s) => Value::String(s.chars().rev().collect()),
+            Value::Null => Value::Array(Vec::new()),
+            v => Value::Array(array(v)?.iter().rev().cloned().collect()),
+        },
+        ("sort", []) => {
+            let mut items = array(input)?.clone();
+            items.sort_by(compare);
+            Value::Array(items)
+        }
+        ("sort_by", [f]) => Value::Array(sort_by(input, f)?.into_iter().map(|(_, v)| v).collect()),
+        ("group_by", [f]) => {
+            let mut groups: Vec<(Value, Vec<Value>)> = Vec::new();
+            for (key, item) in sort_by(input, f)? {
+                match groups.last_mut() {
+                    Some((last, group)) if compare(last, &key) == Ordering::Equal => {
+                        group.push(item)
+                    }
+                    _ => groups.push((key, vec![item])),
+                }
+            }
+            Value::Array(groups.into_iter().map(|(_, g)| Value::Array(g)).collect())
+        }
+        ("unique", []) => {
+            let mut items = array(input)?.clone();
+            items.sort_by(compare);
+            items.dedup_by(|a, b| compare(a, b) == Ordering::Equal);
+            Value::Array(items)
+        }
+        ("min", []) | ("max", []) => {
+            let items = array(input)?.iter();
+            let found = if name == "min" {
+                items.min_by(|a, b| compare(a, b))
+            } else {
+                items.max_by(|a, b| compare(a, b))
+            };
+            found.cloned().unwrap_or(Value::Null)
+        }
+        ("any", []) => Value::Bool(array(input)?.iter().any(truthy)),
+        ("all", []) => Value::Bool(array(input)?.iter().all(truthy)),
+        ("flatten", []) => {
+            let mut items = Vec::new();
+            flatten(array(input)?, &mut items);
+            Value::Array(items)
+        }
+        ("to_entries", []) => Value::Array(
+            object(input)?
+                .iter()
+                .map(|(k, v)| serde_json::json!({ "key": k, "value": v }))
+                .collect(),
+        ),
+        ("from_entries", []) => Value::Object(from_entries(input)?),
+        ("with_entries", [f]) => {
+            let entries = call("to_entries", &[], input)?;
+            let mapped = call("map", std::slice::from_ref(f), &entries[0])?;
+            Value::Object(from_entries(&mapped[0])?)
+        }
+        ("tostring", []) => match input {
+            Value::String(_) => input.clone(),
+            v => Value::String(v.to_string()),
+        },
+        ("tonumber", []) => match input {
+            Value::Number(_) => input.clone(),
+            Value::String(s) => number(
+                s.trim()
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("cannot parse {:?} as a number", s))?,
+            )?,
+            v => anyhow::bail!("cannot convert {} to a number", type_name(v)),
+        },
+        ("tojson", []) => Value::String(input.to_string()),
+        ("fromjson", []) => serde_json::from_str(string(input)?)?,
+        ("ascii_downcase", []) => Value::String(string(input)?.to_ascii_lowercase()),
+        ("ascii_upcase", []) => Value::String(string(input)?.to_ascii_uppercase()),
+        ("join", [sep]) => {
+            return eval(sep, input)?
+                .iter()
+                .map(|sep| {
+                    let parts = array(input)?
+                        .iter()
+                        .map(|item| match item {
+                            Value::Null => Ok(String::new()),
+                            Value::String(s) => Ok(s.clone()),
+                            Value::Number(_) | Value::Bool(_) => Ok(item.to_string()),
+                            v => anyhow::bail!("cannot join {}", type_name(v)),
+                        })
+                        .collect::<anyhow::Result<Vec<_>>>()?;
+                    Ok(Value::String(parts.join(string(sep)?)))
+                })
+                .collect();
+        }
+        ("split", [sep]) | ("startswith", [sep]) | ("endswith", [sep]) => {
+            return eval(sep, input)?
+                .iter()
+                .map(|sep| {
+                    let (s, sep) = (string(input)?, string(sep)?);
+                    Ok(match name {
+                        "split" => Value::from(s.split(sep).collect::<Vec<_>>()),
+                        "startswith" => Value::Bool(s.starts_with(sep)),
+                        _ => Value::Bool(s.ends_with(sep)),
+                    })
+                })
+                .collect();
+        }
+        ("contains", [other]) => {
+            return eval(other, input)?
+                .iter()
+                .map(|other| Ok(Value::Bool(contains(input, other)?)))
+                .collect();
+        }
+        (name, args) => anyhow::bail!("unknown function {}/{}", name, args.len()),
+    };
+    Ok(vec![value])
+}
+
+/// 可以作为赋值或 `del` 目标的路径，路径元素是字符串键或数组下标
+fn paths(expr: &Expr, input: &Value) -> anyhow::Result<Vec<Vec<Value>>> {
+    let paths = match expr {
+        Expr::Identity => vec![Vec::new()],
+        Expr::Recurse => {
+            let mut paths = Vec::new();
+            recurse_paths(input, &mut Vec::new(), &mut paths);
+            paths
+        }
+        Expr::Index(target, index) => {
+            let mut paths = Vec::new();
+            for path in self::paths(target, input)? {
+                for key in eval(index, input)? {
+                    if !matches!(key, Value::String(_) | Value::Number(_)) {
+                        anyhow::bail!("invalid path component: {}", type_name(&key));
+                    }
+                    let mut path = path.clone();
+                    path.push(key);
+                    paths.push(path);
+                }
+            }
+            paths
+        }
+        Expr::Iterate(target) => {
+            let mut paths = Vec::new();
+            for path in self::paths(target, input)? {
+                let keys = match get_path(input, &path) {
+                    Value::Array(items) => (0..items.len()).map(Value::from).collect(),
+                    Value::Object(map) => map.keys().cloned().map(Value::String).collect(),
+                    Value::Null => Vec::new(),
+                    v => anyhow::bail!("cannot iterate over {}", type_name(&v)),
+                };
+                for key in keys {
+                    let mut path = path.clone();
+                    path.push(key);
+                    paths.push(path);
+                }
+            }
+            paths
+        }
+        Expr::Optional(target) => self::paths(target, input).unwrap_or_default(),
+        Expr::Pipe(lhs, rhs) => {
+            let mut paths = Vec::new();
+            for prefix in self::paths(lhs, input)? {
+                let value = get_path(input, &prefix);
+                for suffix in self::paths(rhs, &value)? {
+                    paths.push([prefix.clone(), suffix].concat());
+                }
+            }
+            paths
+        }
+        Expr::Comma(lhs, rhs) => {
+            let mut paths = self::paths(lhs, input)?;
+            paths.extend(self::paths(rhs, input)?);
+            paths
+        }
+        Expr::Call(name, args) => match (name.as_str(), args.as_slice()) {
+            ("select", [f]) if eval(f, input)?.iter().any(truthy) => vec![Vec::new()],
+            ("select", [_]) | ("empty", []) => Vec::new(),
+            ("recurse", []) => self::paths(&Expr::Recurse, input)?,
+            ("first", []) => vec![vec![Value::from(0)]],
+            ("last", []) => vec![vec![Value::from(-1)]],
+            _ => anyhow::bail!("{} is not a valid path expression", name),
+        },
+        _ => anyhow::bail!("invalid path expression"),
+    };
+    Ok(paths)
+}
+
+pub fn get_path(value: &Value, path: &[Value]) -> Value {
+    path.iter()
+        .try_fold(value.clone(), |value, key| index_value(&value, key).ok())
+        .unwrap_or(Value::Null)
+}
+
+/// 路径上不存在的对象或数组会被创建，数组下标超出范围时用 null 填充
+pub fn set_path(value: &mut Value, path: &[Value], new: Value) -> anyhow::Result<()> {
+    let Some((key, rest)) = path.split_first() else {
+        *value = new;
+        return Ok(());
+    };
+
+    if value.is_null() {
+        *value = match key {
+            Value::Number(_) => Value::Array(Vec::new()),
+            _ => Value::Object(Map::new()),
+        };
+    }
+    let child = match (value, key) {
+        (Value::Object(map), Value::String(k)) => map.entry(k.clone()).or_insert(Value::Null),
+        (Value::Array(items), Value::Number(n)) => {
+            let index = array_index(n.as_f64().unwrap_or_default(), items.len())
+                .filter(|index| *index <= MAX_ARRAY_INDEX)
+                .ok_or_else(|| anyhow::anyhow!("array index out of bounds: {}", n))?;
+            if index >= items.len() {
+                items.resize(index + 1, Value::Null);
+            }
+            &mut items[index]
+        }
+        (value, key) => anyhow::bail!(
+            "cannot index {} with {}",
+            type_name(value),
+            describe_key(key)
+        ),
+    };
+    set_path(child, rest, new)
+}
+
+/// 先删除下标大的元素，避免删除数组元素后其它路径的下标失效
+pub fn delete_paths(value: &mut Value, mut paths: Vec<Vec<Value>>) -> anyhow::Result<()> {
+    let len = |v: &Value| v.as_array().map(Vec::len).unwrap_or_default();
+    for path in &mut paths {
+        let mut parent = value.clone();
+        for key in path.iter_mut() {
+            // 负数下标转换为实际位置，排序才正确
+            if let Some(n) = key.as_f64().filter(|n| *n < 0.0)
+                && let Some(index) = array_index(n, len(&parent))
+            {
+                *key = Value::from(index);
+            }
+            parent = index_value(&parent, key).unwrap_or(Value::Null);
+        }
+    }
+    paths.sort_by(|a, b| compare(&Value::from(b.clone()), &Value::from(a.clone())));
+    paths.dedup();
+
+    for path in paths {
+        let Some((last, parent)) = path.split_last() else {
+            *value = Value::Null;
+            continue;
+        };
+        let Some(parent) = parent
+            .iter()
+            .try_fold(&mut *value, |v, key| match (v, key) {
+                (Value::Object(map), Value::String(k)) => map.get_mut(k),
+                (Value::Array(items), Value::Number(n)) => {
+                    items.get_mut(n.as_u64().unwrap_or(u64::MAX) as usize)
+                }
+                _ => None,
+            })
+        else {
+            continue;
+        };
+        match (parent, last) {
+            (Value::Object(map), Value::String(k)) => {
+                map.remove(k);
+            }
+            (Value::Array(items), Value::Number(n)) => {
+                if let Some(index) = n.as_u64().map(|n| n as usize).filter(|i| *i < items.len()) {
+                    items.remove(index);
+                }
+            }
+            (Value::Null, _) => {}
+            (parent, key) => anyhow::bail!(
+                "cannot delete {} from {}",
+                describe_key(key),
+                type_name(parent)
+            ),
+        }
+    }
+    Ok(())
+}
+
+fn index_value(value: &Value, key: &Value) -> anyhow::Result<Value> {
+    match (value, key) {
+        (Value::Object(map), Value::String(k)) => Ok(map.get(k).cloned().unwrap_or(Value::Null)),
+        (Value::Array(items), Value::Number(n)) => {
+            Ok(array_index(n.as_f64().unwrap_or_default(), items.len())
+                .and_then(|i| items.get(i))
+                .cloned()
+                .unwrap_or(Value::Null))
+        }
+        (Value::Null, Value::String(_) | Value::Number(_)) => Ok(Value::Null),
+        (value, key) => anyhow::bail!(
+            "cannot index {} with {}",
+            type_name(value),
+            describe_key(key)
+        ),
+    }
+}
+
+/// 负数下标从末尾开始计算，小数向下取整
+fn array_index(n: f64, len: usize) -> Option<usize> {
+    let n = n.floor() as i64;
+    let index = if n < 0 { len as i64 + n } else { n };
+    usize::try_from(index).ok()
+}
+
+fn eval_bound(expr: Option<&Expr>, input: &Value) -> anyhow::Result<Option<f64>> {
+    let Some(expr) = expr else {
+        return Ok(None);
+    };
+    match eval(expr, input)?.as_slice() {
+        [Value::Null] => Ok(None),
+        [Value::Number(n)] => Ok(n.as_f64()),
+        _ => anyhow::bail!("slice bounds must be numbers"),
+    }
+}
+
+fn slice(value: &Value, start: Option<f64>, end: Option<f64>) -> anyhow::Result<Value> {
+    let range = |len: usize| {
+        let clamp = |n: f64| array_index(n, len).unwrap_or(0).min(len);
+        let start = start.map(clamp).unwrap_or(0);
+        let end = end.map(clamp).unwrap_or(len);
+        start..end.max(start)
+    };
+    match value {
+        Value::Null => Ok(Value::Null),
+        Value::Array(items) => Ok(Value::Array(items[range(items.len())].to_vec())),
+        Value::String(s) => {
+            let chars = s.chars().collect::<Vec<_>>();
+            Ok(Value::String(chars[range(chars.len())].iter().collect()))
+        }
+        v => anyhow::bail!("cannot slice {}", type_name(v)),
+    }
+}
+
+fn binary(op: BinOp, l: &Value, r: &Value) -> anyhow::Result<Value> {
+    let ord = || compare(l, r);
+    let value = match op {
+        BinOp::Eq => Value::Bool(ord() == Ordering::Equal),
+        BinOp::Ne => Value::Bool(ord() != Ordering::Equal),
+        BinOp::Lt => Value::Bool(ord() == Ordering::Less),
+        BinOp::Le => Value::Bool(ord() != Ordering::Greater),
+        BinOp::Gt => Value::Bool(ord() == Ordering::Greater),
+        BinOp::Ge => Value::Bool(ord() != Ordering::Less),
+        _ => match (op, l, r) {
+            (BinOp::Add, Value::Null, v) | (BinOp::Add, v, Value::Null) => v.clone(),
+            (_, Value::Number(a), Value::Number(b)) => {
+                let (a, b) = (
+                    a.as_f64().unwrap_or_default(),
+                    b.as_f64().unwrap_or_default(),
+                );
+                match op {
+                    BinOp::Add => number(a + b)?,
+                    BinOp::Sub => number(a - b)?,
+                    BinOp::Mul => number(a * b)?,
+                    BinOp::Div if b == 0.0 => anyhow::bail!("division by zero"),
+                    BinOp::Div => number(a / b)?,
+                    BinOp::Rem if b as i64 == 0 => anyhow::bail!("division by zero"),
+                    _ => match (a as i64).checked_rem(b as i64) {
+                        Some(n) => number(n as f64)?,
+                        None => anyhow::bail!("{} % {} overflows", a, b),
+                    },
+                }
+            }
+            (BinOp::Add, Value::String(a), Value::String(b)) => {
+                Value::String(format!("{}{}", a, b))
+            }
+            (BinOp::Add, Value::Array(a), Value::Array(b)) => {
+                Value::Array([a.clone(), b.clone()].concat())
+            }
+            (BinOp::Add, Value::Object(a), Value::Object(b)) => {
+                let mut merged = a.clone();
+                merged.extend(b.clone());
+                Value::Object(merged)
+            }
+            (BinOp::Sub, Value::Array(a), Value::Array(b)) => Value::Array(
+                a.iter()
+                    .filter(|item| !b.iter().any(|x| compare(item, x) == Ordering::Equal))
+                    .cloned()
+                    .collect(),
+            ),
+            (BinOp::Mul, Value::Object(_), Value::Object(_)) => deep_merge(l, r),
+            (BinOp::Div, Value::String(a), Value::String(b)) => {
+                Value::from(a.split(b.as_str()).collect::<Vec<_>>())
+            }
+            _ => anyhow::bail!(
+                "cannot apply {:?} to {} and {}",
+                op,
+                type_name(l),
+                type_name(r)
+            ),
+        },
+    };
+    Ok(value)
+}
+
+fn deep_merge(l: &Value, r: &Value) -> Value {
+    match (l, r) {
+        (Value::Object(a), Value::Object(b)) => {
+            let mut merged = a.clone();
+            for (k, v) in b {
+                let value = match merged.get(k) {
+                    Some(old) => deep_merge(old, v),
+                    None => v.clone(),
+                };
+                merged.insert(k.clone(), value);
+            }
+            Value::Object(merged)
+        }
+        (_, r) => r.clone(),
+    }
+}
+
+/// jq 的排序规则：null < false < true < 数字 < 字符串 < 数组 < 对象
+pub fn compare(a: &Value, b: &Value) -> Ordering {
+    fn rank(v: &Value) -> u8 {
+        match v {
+            Value::Null => 0,
+            Value::Bool(false) => 1,
+            Value::Bool(true) => 2,
+            Value::Number(_) => 3,
+            Value::String(_) => 4,
+            Value::Array(_) => 5,
+            Value::Object(_) => 6,
+        }
+    }
+
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x
+            .as_f64()
+            .partial_cmp(&y.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Value::String(x), Value::String(y)) => x.cmp(y),
+        (Value::Array(x), Value::Array(y)) => x
+            .iter()
+            .zip(y)
+            .map(|(a, b)| compare(a, b))
+            .find(|o| o.is_ne())
+            .unwrap_or_else(|| x.len().cmp(&y.len())),
+        (Value::Object(x), Value::Object(y)) => {
+            let mut keys_x = x.keys().collect::<Vec<_>>();
+            let mut keys_y = y.keys().collect::<Vec<_>>();
+            keys_x.sort();
+            keys_y.sort();
+            keys_x.cmp(&keys_y).then_with(|| {
+                keys_x
+                    .iter()
+                    .map(|k| compare(&x[*k], &y[*k]))
+                    .find(|o| o.is_ne())
+                    .unwrap_or(Ordering::Equal)
+            })
+        }
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+fn contains(a: &Value, b: &Value) -> anyhow::Result<bool> {
+    Ok(match (a, b) {
+        (Value::String(a), Value::String(b)) => a.contains(b.as_str()),
+        (Value::Array(a), Value::Array(b)) => b
+            .iter()
+            .all(|b| a.iter().any(|a| contains(a, b).unwrap_or(false))),
+        (Value::Object(a), Value::Object(b)) => b
+            .iter()
+            .all(|(k, b)| a.get(k).is_some_and(|a| contains(a, b).unwrap_or(false))),
+        (a, b) if type_name(a) == type_name(b) => compare(a, b) == Ordering::Equal,
+        (a, b) => anyhow::bail!(
+            "{} and {} cannot have their containment checked",
+            type_name(a),
+            type_name(b)
+        ),
+    })
+}
+
+fn sort_by(input: &Value, f: &Expr) -> anyhow::Result<Vec<(Value, Value)>> {
+    let mut items = array(input)?
+        .iter()
+        .map(|item| Ok((Value::Array(eval(f, item)?), item.clone())))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    items.sort_by(|a, b| compare(&a.0, &b.0));
+    Ok(items)
+}
+
+fn from_entries(input: &Value) -> anyhow::Result<Map<String, Value>> {
+    array(input)?
+        .iter()
+        .map(|entry| {
+            let field = |names: &[&str]| {
+                names
+                    .iter()
+                    .find_map(|name| entry.get(*name).filter(|v| !v.is_null()))
+                    .cloned()
+            };
+            let key = match field(&["key", "k", "name"]) {
+                Some(Value::String(key)) => key,
+                Some(key @ (Value::Number(_) | Value::Bool(_))) => key.to_string(),
+                _ => anyhow::bail!("entry has no string key: {}", entry),
+            };
+            Ok((key, field(&["value", "v"]).unwrap_or(Value::Null)))
+        })
+        .collect()
+}
+
+fn recurse(value: &Value, out: &mut Vec<Value>) {
+    out.push(value.clone());
+    match value {
+        Value::Array(items) => items.iter().for_each(|v| recurse(v, out)),
+        Value::Object(map) => map.values().for_each(|v| recurse(v, out)),
+        _ => {}
+    }
+}
+
+fn recurse_paths(value: &Value, prefix: &mut Vec<Value>, out: &mut Vec<Vec<Value>>) {
+    out.push(prefix.clone());
+    let children: Vec<(Value, &Value)> = match value {
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (Value::from(i), v))
+            .collect(),
+        Value::Object(map) => map
+            .iter()
+            .map(|(k, v)| (Value::from(k.as_str()), v))
+            .collect(),
+        _ => Vec::new(),
+    };
+    for (key, child) in children {
+        prefix.push(key);
+        recurse_paths(child, prefix, out);
+        prefix.pop();
+    }
+}
+
+fn flatten(items: &[Value], out: &mut Vec<Value>) {
+    for item in items {
+        match item {
+            Value::Array(nested) => flatten(nested, out),
+            v => out.push(v.clone()),
+        }
+    }
+}
+
+fn elements(value: &Value) -> anyhow::Result<Vec<Value>> {
+    match value {
+        Value::Array(items) => Ok(items.clone()),
+        Value::Object(map) => Ok(map.values().cloned().collect()),
+        v => anyhow::bail!("cannot iterate over {}", type_name(v)),
+    }
+}
+
+fn array(value: &Value) -> anyhow::Result<&Vec<Value>> {
+    value
+        .as_array()
+        .ok_or_else(|| anyhow::anyhow!("expected an array, got {}", type_name(value)))
+}
+
+fn object(value: &Value) -> anyhow::Result<&Map<String, Value>> {
+    value
+        .as_object()
+        .ok_or_else(|| anyhow::anyhow!("expected an object, got {}", type_name(value)))
+}
+
+fn string(value: &Value) -> anyhow::Result<&str> {
+    value
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("expected a string, got {}", type_name(value)))
+}
+
+fn truthy(value: &Value) -> bool {
+    !matches!(value, Value::Null | Value::Bool(false))
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn describe_key(key: &Value) -> String {
+    match key {
+        Value::String(s) => format!("{:?}", s),
+        v => type_name(v).to_string(),
+    }
+}
+
+/// 整数结果保持为整数，避免输出 `3.0`
+pub fn number(n: f64) -> anyhow::Result<Value> {
+    if n.fract() == 0.0 && n.abs() < 9.0e15 {
+        return Ok(Value::from(n as i64));
+    }
+    serde_json::Number::from_f64(n)
+        .map(Value::Number)
+        .ok_or_else(|| anyhow::anyhow!("invalid number: {}", n))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::process::data::filter::Filter;
+
+    fn run(filter: &str, input: serde_json::Value) -> anyhow::Result<Vec<serde_json::Value>> {
+        Filter::parse(filter)?.run(&input)
+    }
+
+    #[test]
+    fn test_eval_paths() -> anyhow::Result<()> {
+        let input = json!({"users": [{"name": "a", "age": 20}, {"name": "b", "age": 40}]});
+        assert_eq!(
+            run(".users[].name", input.clone())?,
+            [json!("a"), json!("b")]
+        );
+        assert_eq!(run(".users[-1].age", input.clone())?, [json!(40)]);
+        assert_eq!(run(".users[1:].[0].name", input.clone())?, [json!("b")]);
+        assert_eq!(run(".missing.field", input.clone())?, [json!(null)]);
+        assert_eq!(run(r#""abcdef"[2:-1]"#, json!(null))?, [json!("cde")]);
+        assert!(run(".users.name", input.clone()).is_err());
+        assert_eq!(run(".users.name?", input)?, Vec::<serde_json::Value>::new());
+        Ok(())
+    }
+
+    #[test]
+    fn test_eval_select_and_construct() -> anyhow::Result<()> {
+        let input = json!({"users": [
+            {"name": "a", "age": 20, "tags": ["x"]},
+            {"name": "b", "age": 40, "tags": ["x", "y"]},
+            {"name": "c", "age": 35}
+        ]});
+        assert_eq!(
+            run(".users[] | select(.age > 30) | .name", input.clone())?,
+            [json!("b"), json!("c")]
+        );
+        assert_eq!(
+            run("[.users[] | {name, old: (.age >= 35)}]", input.clone())?,
+            [json!([
+                {"name": "a", "old": false},
+                {"name": "b", "old": true},
+                {"name": "c", "old": true}
+            ])]
+        );
+        assert_eq!(
+            run(".users | map(.tags // [] | length) | add", input.clone())?,
+            [json!(3)]
+        );
+        assert_eq!(
+            run(
+                ".users | sort_by(-.age) | map(.name) | join(\",\")",
+                input.clone()
+            )?,
+            [json!("b,c,a")]
+        );
+        assert_eq!(
+            run("{(.users[0].name): .users[0].age}", input.clone())?,
+            [json!({"a": 20})]
+        );
+        assert_eq!(
+            run(".users | group_by(.age > 30) | map(length)", input)?,
+            [json!([1, 2])]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_eval_operators() -> anyhow::Result<()> {
+        assert_eq!(
+            run("1 + 2 * 3, 7 % 3, 1 / 4", json!(null))?,
+            [json!(7), json!(1), json!(0.25)]
+        );
+        assert_eq!(run(r#""a,b" / ",""#, json!(null))?, [json!(["a", "b"])]);
+        assert_eq!(run("[1,2,3] - [2]", json!(null))?, [json!([1, 3])]);
+        assert_eq!(
+            run("{a: {b: 1}} * {a: {c: 2}}", json!(null))?,
+            [json!({"a": {"b": 1, "c": 2}})]
+        );
+        assert_eq!(
+            run(".a and (.b or false)", json!({"a": 1, "b": null}))?,
+            [json!(false)]
+        );
+        assert_eq!(
+            run("[null, 1, \"a\", [], {}, false] | sort", json!(null))?,
+            [json!([null, false, 1, "a", [], {}])]
+        );
+        assert_eq!(run("1 == 1.0", json!(null))?, [json!(true)]);
+        assert!(run("1 / 0", json!(null)).is_err());
+        assert!(run("5 % 0", json!(null)).is_err());
+        assert!(run(". % -1", json!(i64::MIN)).is_err());
+        assert!(run("{} + 1", json!(null)).is_err());
+        assert!(run("nope(1)", json!(null)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_eval_assign_and_delete() -> anyhow::Result<()> {
+        let input = json!({"server": {"port": 80}, "items": [1, 2, 3, 4]});
+        assert_eq!(
+            run(
+                ".server.port = 8080 | .server.tls.enabled = true",
+                input.clone()
+            )?,
+            [json!({"server": {"port": 8080, "tls": {"enabled": true}}, "items": [1, 2, 3, 4]})]
+        );
+        assert_eq!(
+            run(".items[] |= . * 10", input.clone())?[0]["items"],
+            json!([10, 20, 30, 40])
+        );
+        assert_eq!(
+            run("del(.items[] | select(. % 2 == 0)) | .items", input.clone())?,
+            [json!([1, 3])]
+        );
+        assert_eq!(
+            run("del(.items[0, -1], .server) ", input.clone())?,
+            [json!({"items": [2, 3]})]
+        );
+        assert_eq!(
+            run(".list[2] = 1 | .list", json!({}))?,
+            [json!([null, null, 1])]
+        );
+        assert!(run(".server.port[0] = 1", input.clone()).is_err());
+        assert!(run(".a[100000000000] = 1", json!({})).is_err());
+        assert!(run("(.items | length) = 1", input).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,559 @@
+use serde_json::Value;
+
+use super::eval;
+
+/// jq 风格的过滤器，支持的语法：
+///
+/// - 路径：`.`、`..`、`.foo`、`."foo bar"`、`.[0]`、`.[-1]`、`.[1:3]`、`.[]`、`.foo?`
+/// - 运算：`|`、`,`、`//`、`and`/`or`、比较、`+ - * / %`、`= |=`
+/// - 构造：`[...]`、`{name, age: .user.age, (.key): .value}`
+/// - 函数：`select(f)`、`map(f)`、`del(path)`、`keys`、`length`、`sort_by(f)` 等
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter(Expr);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Identity,
+    Recurse,
+    Literal(Value),
+    Index(Box<Expr>, Box<Expr>),
+    Slice(Box<Expr>, Option<Box<Expr>>, Option<Box<Expr>>),
+    Iterate(Box<Expr>),
+    Optional(Box<Expr>),
+    Pipe(Box<Expr>, Box<Expr>),
+    Comma(Box<Expr>, Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Alternative(Box<Expr>, Box<Expr>),
+    Assign(Box<Expr>, Box<Expr>),
+    Update(Box<Expr>, Box<Expr>),
+    Array(Option<Box<Expr>>),
+    Object(Vec<(Expr, Expr)>),
+    Call(String, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Punct(&'static str),
+    Ident(String),
+    Str(String),
+    Num(f64),
+}
+
+// 多字符的符号需要先匹配
+const PUNCTS: [&str; 28] = [
+    "|=", "//", "==", "!=", "<=", ">=", "..", ".", "|", ",", ":", ";", "?", "(", ")", "[", "]",
+    "{", "}", "+", "-", "*", "/", "%", "<", ">", "=", "$",
+];
+
+struct Parser {
+    tokens: Vec<Token>,
+    // 每个 token 前是否有空白，`.foo` 中的字段名必须紧跟在 `.` 之后
+    spaced: Vec<bool>,
+    pos: usize,
+}
+
+impl Filter {
+    pub fn parse(source: &str) -> anyhow::Result<Self> {
+        let (tokens, spaced) = tokenize(source)?;
+        let mut parser = Parser {
+            tokens,
+            spaced,
+            pos: 0,
+        };
+        let expr = parser.parse_pipe()?;
+        if let Some(token) = parser.peek() {
+            anyhow::bail!("unexpected {} in filter", describe(token));
+        }
+        Ok(Self(expr))
+    }
+
+    pub fn run(&self, input: &Value) -> anyhow::Result<Vec<Value>> {
+        eval::eval(&self.0, input)
+    }
+}
+
+fn tokenize(source: &str) -> anyhow::Result<(Vec<Token>, Vec<bool>)> {
+    let chars = source.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut spaced = Vec::new();
+    let mut i = 0;
+    let mut space = true;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            space = true;
+            i += 1;
+            continue;
+        }
+        if c == '#' {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            continue;
+        }
+
+        let token = if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            Token::Ident(chars[start..i].iter().collect())
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            if i < chars.len() && matches!(chars[i], 'e' | 'E') {
+                i += 1;
+                if i < chars.len() && matches!(chars[i], '+' | '-') {
+                    i += 1;
+                }
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+            let text = chars[start..i].iter().collect::<String>();
+            Token::Num(
+                text.parse()
+                    .map_err(|_| anyhow::anyhow!("invalid number: {}", text))?,
+            )
+        } else if c == '"' {
+            let (s, end) = read_string(&chars, i + 1)?;
+            i = end;
+            Token::Str(s)
+        } else {
+            let rest = chars[i..].iter().take(2).collect::<String>();
+            let punct = PUNCTS
+                .iter()
+                .find(|p| rest.starts_with(**p))
+                .ok_or_else(|| anyhow::anyhow!("unexpected character {:?} in filter", c))?;
+            if *punct == "$" {
+                anyhow::bail!("variables are not supported");
+            }
+            i += punct.len();
+            Token::Punct(punct)
+        };
+
+        tokens.push(token);
+        spaced.push(space);
+        space = false;
+    }
+    Ok((tokens, spaced))
+}
+
+/// 返回字符串内容和结束引号之后的位置
+fn read_string(chars: &[char], mut i: usize) -> anyhow::Result<(String, usize)> {
+    let mut s = String::new();
+    while i < chars.len() {
+        match chars[i] {
+            '"' => return Ok((s, i + 1)),
+            '\\' => {
+                let escaped = chars.get(i + 1).copied();
+                i += 2;
+                match escaped {
+                    Some('n') => s.push('\n'),
+                    Some('t') => s.push('\t'),
+                    Some('r') => s.push('\r'),
+                    Some('u') => {
+                        let hex = chars.get(i..i + 4).unwrap_or_default();
+                        let code = u32::from_str_radix(&hex.iter().collect::<String>(), 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| anyhow::anyhow!("invalid unicode escape"))?;
+                        s.push(code);
+                        i += 4;
+                    }
+                    Some(c @ ('"' | '\\' | '/')) => s.push(c),
+                    c => anyhow::bail!("invalid escape: \\{}", c.unwrap_or(' ')),
+                }
+            }
+            c => {
+                s.push(c);
+                i += 1;
+            }
+        }
+    }
+    anyhow::bail!("unterminated string in filter")
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Punct(p) => format!("'{}'", p),
+        Token::Ident(name) => format!("'{}'", name),
+        Token::Str(s) => format!("{:?}", s),
+        Token::Num(n) => n.to_string(),
+    }
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_punct(&self, punct: &str) -> bool {
+        matches!(self.peek(), Some(Token::Punct(p)) if *p == punct)
+    }
+
+    fn peek_ident(&self, name: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(n)) if n == name)
+    }
+
+    fn eat(&mut self, punct: &str) -> bool {
+        let found = self.peek_punct(punct);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, punct: &str) -> anyhow::Result<()> {
+        if self.eat(punct) {
+            return Ok(());
+        }
+        match self.peek() {
+            Some(token) => anyhow::bail!("expected '{}', found {}", punct, describe(token)),
+            None => anyhow::bail!("expected '{}' at end of filter", punct),
+        }
+    }
+
+    fn next(&mut self) -> anyhow::Result<Token> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("unexpected end of filter"))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn parse_pipe(&mut self) -> anyhow::Result<Expr> {
+        let mut lhs = self.parse_comma()?;
+        while self.eat("|") {
+            let rhs = self.parse_comma()?;
+            lhs = Expr::Pipe(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_comma(&mut self) -> anyhow::Result<Expr> {
+        let mut lhs = self.parse_alternative()?;
+        while self.eat(",") {
+            let rhs = self.parse_alternative()?;
+            lhs = Expr::Comma(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_alternative(&mut self) -> anyhow::Result<Expr> {
+        let lhs = self.parse_assign()?;
+        if self.eat("//") {
+            let rhs = self.parse_alternative()?;
+            return Ok(Expr::Alternative(Box::new(lhs), Box::new(rhs)));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_assign(&mut self) -> anyhow::Result<Expr> {
+        let lhs = self.parse_or()?;
+        if self.eat("=") {
+            let rhs = self.parse_or()?;
+            return Ok(Expr::Assign(Box::new(lhs), Box::new(rhs)));
+        }
+        if self.eat("|=") {
+            let rhs = self.parse_or()?;
+            return Ok(Expr::Update(Box::new(lhs), Box::new(rhs)));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_or(&mut self) -> anyhow::Result<Expr> {
+        let mut lhs = self.parse_and()?;
+        while self.peek_ident("or") {
+            self.pos += 1;
+            let rhs = self.parse_and()?;
+            lhs = Expr::Or(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_and(&mut self) -> anyhow::Result<Expr> {
+        let mut lhs = self.parse_comparison()?;
+        while self.peek_ident("and") {
+            self.pos += 1;
+            let rhs = self.parse_comparison()?;
+            lhs = Expr::And(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_comparison(&mut self) -> anyhow::Result<Expr> {
+        const OPS: [(&str, BinOp); 6] = [
+            ("==", BinOp::Eq),
+            ("!=", BinOp::Ne),
+            ("<", BinOp::Lt),
+            ("<=", BinOp::Le),
+            (">", BinOp::Gt),
+            (">=", BinOp::Ge),
+        ];
+        self.parse_binary(&OPS, Self::parse_additive, false)
+    }
+
+    fn parse_additive(&mut self) -> anyhow::Result<Expr> {
+        const OPS: [(&str, BinOp); 2] = [("+", BinOp::Add), ("-", BinOp::Sub)];
+        self.parse_binary(&OPS, Self::parse_multiplicative, true)
+    }
+
+    fn parse_multiplicative(&mut self) -> anyhow::Result<Expr> {
+        const OPS: [(&str, BinOp); 3] = [("*", BinOp::Mul), ("/", BinOp::Div), ("%", BinOp::Rem)];
+        self.parse_binary(&OPS, Self::parse_unary, true)
+    }
+
+    /// 比较运算不能连续使用，`a < b < c` 是错误的
+    fn parse_binary(
+        &mut self,
+        ops: &[(&str, BinOp)],
+        next: fn(&mut Self) -> anyhow::Result<Expr>,
+        repeat: bool,
+    ) -> anyhow::Result<Expr> {
+        let mut lhs = next(self)?;
+        while let Some((_, op)) = ops.iter().find(|(p, _)| self.peek_punct(p)) {
+            self.pos += 1;
+            let rhs = next(self)?;
+            lhs = Expr::Binary(*op, Box::new(lhs), Box::new(rhs));
+            if !repeat {
+                break;
+            }
+        }
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> anyhow::Result<Expr> {
+        if self.eat("-") {
+            return Ok(Expr::Neg(Box::new(self.parse_postfix()?)));
+        }
+        self.parse_postfix()
+    }
+
+    fn parse_postfix(&mut self) -> anyhow::Result<Expr> {
+        let mut expr = self.parse_term()?;
+        loop {
+            if self.peek_punct(".") {
+                self.pos += 1;
+                expr = match self.field_name() {
+                    Some(name) => Expr::Index(Box::new(expr), Box::new(name)),
+                    None if self.peek_punct("[") => {
+                        self.pos += 1;
+                        self.parse_bracket(expr)?
+                    }
+                    None => anyhow::bail!("expected field name after '.'"),
+                };
+            } else if self.eat("[") {
+                expr = self.parse_bracket(expr)?;
+            } else if self.eat("?") {
+                expr = Expr::Optional(Box::new(expr));
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    /// `.` 之后紧跟的字段名：`.foo` 或 `."foo bar"`
+    fn field_name(&mut self) -> Option<Expr> {
+        if self.spaced.get(self.pos).copied().unwrap_or(true) {
+            return None;
+        }
+        let name = match self.peek()? {
+            Token::Ident(name) | Token::Str(name) => name.clone(),
+            _ => return None,
+        };
+        self.pos += 1;
+        Some(Expr::Literal(Value::String(name)))
+    }
+
+    /// `[` 之后的部分：`[]`、`[index]`、`[start:end]`
+    fn parse_bracket(&mut self, target: Expr) -> anyhow::Result<Expr> {
+        let target = Box::new(target);
+        if self.eat("]") {
+            return Ok(Expr::Iterate(target));
+        }
+
+        let start = if self.peek_punct(":") {
+            None
+        } else {
+            Some(Box::new(self.parse_pipe()?))
+        };
+        let expr = if self.eat(":") {
+            let end = if self.peek_punct("]") {
+                None
+            } else {
+                Some(Box::new(self.parse_pipe()?))
+            };
+            Expr::Slice(target, start, end)
+        } else {
+            let index = start.ok_or_else(|| anyhow::anyhow!("expected index"))?;
+            Expr::Index(target, index)
+        };
+        self.expect("]")?;
+        Ok(expr)
+    }
+
+    fn parse_term(&mut self) -> anyhow::Result<Expr> {
+        match self.next()? {
+            Token::Punct(".") => match self.field_name() {
+                Some(name) => Ok(Expr::Index(Box::new(Expr::Identity), Box::new(name))),
+                None if self.peek_punct("[") && !self.spaced[self.pos] => {
+                    self.pos += 1;
+                    self.parse_bracket(Expr::Identity)
+                }
+                None => Ok(Expr::Identity),
+            },
+            Token::Punct("..") => Ok(Expr::Recurse),
+            Token::Num(n) => Ok(Expr::Literal(eval::number(n)?)),
+            Token::Str(s) => Ok(Expr::Literal(Value::String(s))),
+            Token::Punct("(") => {
+                let expr = self.parse_pipe()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            Token::Punct("[") => {
+                if self.eat("]") {
+                    return Ok(Expr::Array(None));
+                }
+                let expr = self.parse_pipe()?;
+                self.expect("]")?;
+                Ok(Expr::Array(Some(Box::new(expr))))
+            }
+            Token::Punct("{") => self.parse_object(),
+            Token::Ident(name) => match name.as_str() {
+                "true" => Ok(Expr::Literal(Value::Bool(true))),
+                "false" => Ok(Expr::Literal(Value::Bool(false))),
+                "null" => Ok(Expr::Literal(Value::Null)),
+                _ => {
+                    let mut args = Vec::new();
+                    if self.eat("(") {
+                        loop {
+                            args.push(self.parse_pipe()?);
+                            if !self.eat(";") {
+                                break;
+                            }
+                        }
+                        self.expect(")")?;
+                    }
+                    Ok(Expr::Call(name, args))
+                }
+            },
+            token => anyhow::bail!("unexpected {} in filter", describe(&token)),
+        }
+    }
+
+    fn parse_object(&mut self) -> anyhow::Result<Expr> {
+        let mut entries = Vec::new();
+        if self.eat("}") {
+            return Ok(Expr::Object(entries));
+        }
+
+        loop {
+            let key = match self.next()? {
+                Token::Ident(name) | Token::Str(name) => Expr::Literal(Value::String(name)),
+                Token::Punct("(") => {
+                    let key = self.parse_pipe()?;
+                    self.expect(")")?;
+                    key
+                }
+                token => anyhow::bail!("unexpected {} in object key", describe(&token)),
+            };
+
+            // `{name}` 是 `{name: .name}` 的简写
+            let value = if self.eat(":") {
+                self.parse_alternative()?
+            } else {
+                Expr::Index(Box::new(Expr::Identity), Box::new(key.clone()))
+            };
+            entries.push((key, value));
+
+            if self.eat("}") {
+                return Ok(Expr::Object(entries));
+            }
+            self.expect(",")?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn field(name: &str) -> Box<Expr> {
+        Box::new(Expr::Literal(json!(name)))
+    }
+
+    #[test]
+    fn test_parse_paths() -> anyhow::Result<()> {
+        let Filter(expr) = Filter::parse(".users[].name")?;
+        let users = Expr::Index(Box::new(Expr::Identity), field("users"));
+        let expected = Expr::Index(Box::new(Expr::Iterate(Box::new(users))), field("name"));
+        assert_eq!(expr, expected);
+
+        let Filter(expr) = Filter::parse(r#"."a b"[1:]?"#)?;
+        let target = Expr::Index(Box::new(Expr::Identity), field("a b"));
+        let slice = Expr::Slice(
+            Box::new(target),
+            Some(Box::new(Expr::Literal(json!(1)))),
+            None,
+        );
+        assert_eq!(expr, Expr::Optional(Box::new(slice)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_precedence() -> anyhow::Result<()> {
+        // `. and .b` 中的 and 是运算符而不是字段名
+        let Filter(expr) = Filter::parse(". and .b or .c")?;
+        assert!(matches!(expr, Expr::Or(lhs, _) if matches!(*lhs, Expr::And(_, _))));
+
+        let Filter(expr) = Filter::parse("1 + 2 * 3")?;
+        assert!(
+            matches!(expr, Expr::Binary(BinOp::Add, _, rhs) if matches!(*rhs, Expr::Binary(BinOp::Mul, _, _)))
+        );
+
+        let Filter(expr) = Filter::parse(".a = 1 | .b")?;
+        assert!(matches!(expr, Expr::Pipe(lhs, _) if matches!(*lhs, Expr::Assign(_, _))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for filter in [
+            ".users[",
+            "{a:}",
+            "1 < 2 < 3",
+            ".a |",
+            "$x",
+            "\"abc",
+            "select(.a",
+        ] {
+            assert!(Filter::parse(filter).is_err(), "{}", filter);
+        }
+    }
+}
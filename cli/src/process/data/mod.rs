@@ -0,0 +1,499 @@
+mod eval;
+pub mod filter;
+
+use std::{
+    io::{Read, Write},
+    path::Path,
+};
+
+use anyhow::Context as _;
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+use toml_edit::{ArrayOfTables, DocumentMut, InlineTable, Item, Table, TableLike};
+
+pub use filter::Filter;
+
+use crate::{
+    OutputMode,
+    opts::data::{DataFormat, DataIoOpts},
+    utils,
+};
+
+/// 对每个输入运行过滤器，NDJSON 的每一行、YAML 的每个文档都是单独的输入
+pub async fn process_query(
+    filter: &str,
+    opts: &DataIoOpts,
+    in_place: bool,
+    mode: OutputMode,
+) -> anyhow::Result<()> {
+    let filter = Filter::parse(filter)?;
+    let from = opts.input_format();
+
+    let mut reader = utils::get_reader(&opts.input).await?;
+    let mut content = String::new();
+    reader.read_to_string(&mut content)?;
+
+    let mut results = Vec::new();
+    for input in parse_documents(&content, from)? {
+        results.extend(filter.run(&input)?);
+    }
+
+    if in_place {
+        if opts.input == "-" {
+            anyhow::bail!("--in-place requires --input <FILE>");
+        }
+        if from != DataFormat::Ndjson && results.len() != 1 {
+            anyhow::bail!(
+                "--in-place requires the filter to produce exactly one value, got {}",
+                results.len()
+            );
+        }
+        let updated = match from {
+            DataFormat::Toml => update_toml(&content, &results[0])?,
+            DataFormat::Yaml if has_yaml_comment(&content) => anyhow::bail!(
+                "--in-place would drop the comments in {}, write to --output instead",
+                opts.input
+            ),
+            _ => format_values(&results, from, false, opts.compact)?,
+        };
+        // 沿用原文件的权限，0600 的配置文件替换后不会变成所有人可读
+        utils::write_atomic(Path::new(&opts.input), updated.as_bytes(), None)?;
+        return mode.emit_json(json!({ "input": opts.input, "output": opts.input }));
+    }
+
+    if mode == OutputMode::Json && opts.output == "-" {
+        return mode.emit_json(json!({ "results": results }));
+    }
+    let content = format_values(
+        &results,
+        opts.output_format(),
+        opts.raw_output,
+        opts.compact,
+    )?;
+    let mut writer = utils::get_writer(&opts.output).await?;
+    writer.write_all(content.as_bytes())?;
+    writer.flush()?;
+    mode.emit_json(json!({ "input": opts.input, "output": opts.output }))
+}
+
+/// 整个文件解析为一个值，NDJSON 解析为数组
+pub fn parse(content: &str, format: DataFormat) -> anyhow::Result<Value> {
+    let value = match format {
+        DataFormat::Json => serde_json::from_str(content)?,
+        DataFormat::Ndjson => Value::Array(parse_documents(content, format)?),
+        DataFormat::Yaml => serde_yaml::from_str(content)?,
+        DataFormat::Toml => from_toml(toml::from_str(content)?),
+    };
+
+    Ok(value)
+}
+
+fn parse_documents(content: &str, format: DataFormat) -> anyhow::Result<Vec<Value>> {
+    match format {
+        DataFormat::Ndjson => content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(n, line)| {
+                serde_json::from_str(line).with_context(|| format!("invalid line {}", n + 1))
+            })
+            .collect(),
+        DataFormat::Yaml => serde_yaml::Deserializer::from_str(content)
+            .map(|doc| Ok(Value::deserialize(doc)?))
+            .collect(),
+        format => Ok(vec![parse(content, format)?]),
+    }
+}
+
+// toml 的日期时间直接转为 serde_json 会变成内部结构，这里手动转换为字符串
+fn from_toml(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::from(i),
+        toml::Value::Float(f) => Value::from(f),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(d) => Value::String(d.to_string()),
+        toml::Value::Array(a) => Value::Array(a.into_iter().map(from_toml).collect()),
+        toml::Value::Table(t) => {
+            Value::Object(t.into_iter().map(|(k, v)| (k, from_toml(v))).collect())
+        }
+    }
+}
+
+/// 多个结果时 JSON 逐个输出，YAML 用 `---` 分隔，TOML 只能输出一个对象
+pub fn format_values(
+    values: &[Value],
+    format: DataFormat,
+    raw: bool,
+    compact: bool,
+) -> anyhow::Result<String> {
+    let mut out = String::new();
+    for (i, value) in values.iter().enumerate() {
+        if raw && let Value::String(s) = value {
+            out.push_str(s);
+            out.push('\n');
+            continue;
+        }
+
+        match format {
+            DataFormat::Json if !compact => out.push_str(&serde_json::to_string_pretty(value)?),
+            DataFormat::Json | DataFormat::Ndjson => out.push_str(&serde_json::to_string(value)?),
+            DataFormat::Yaml => {
+                if i > 0 {
+                    out.push_str("---\n");
+                }
+                out.push_str(serde_yaml::to_string(value)?.trim_end());
+            }
+            DataFormat::Toml => {
+                if values.len() > 1 {
+                    anyhow::bail!(
+                        "TOML output requires exactly one value, got {}",
+                        values.len()
+                    );
+                }
+                if !value.is_object() {
+                    anyhow::bail!("TOML output requires an object, got {}", value);
+                }
+                let toml = toml::to_string_pretty(value).context("cannot convert to TOML")?;
+                out.push_str(toml.trim_end());
+            }
+        }
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// 在原文档上只改动有变化的键，保留注释、键的顺序和格式
+fn update_toml(original: &str, value: &Value) -> anyhow::Result<String> {
+    let Value::Object(new) = value else {
+        anyhow::bail!("TOML output requires an object, got {}", value);
+    };
+    let Value::Object(old) = parse(original, DataFormat::Toml)? else {
+        unreachable!("TOML document is always a table");
+    };
+    let mut doc: DocumentMut = original.parse()?;
+    merge_table(doc.as_table_mut(), &old, new, false)?;
+    Ok(doc.to_string())
+}
+
+fn merge_table(
+    table: &mut dyn TableLike,
+    old: &Map<String, Value>,
+    new: &Map<String, Value>,
+    inline: bool,
+) -> anyhow::Result<()> {
+    let removed: Vec<String> = table
+        .iter()
+        .map(|(key, _)| key.to_string())
+        .filter(|key| !new.contains_key(key))
+        .collect();
+    for key in removed {
+        table.remove(&key);
+    }
+
+    for (key, value) in new {
+        let Some(item) = table.get_mut(key) else {
+            let item = if inline {
+                Item::Value(to_toml_value(value)?)
+            } else {
+                to_toml_item(value)?
+            };
+            table.insert(key, item);
+            continue;
+        };
+        match (old.get(key), value) {
+            (Some(prev), value) if prev == value => {}
+            (Some(Value::Object(prev)), Value::Object(value)) if item.is_table_like() => {
+                let inline = item.is_value();
+                let table = item.as_table_like_mut().expect("checked above");
+                merge_table(table, prev, value, inline)?;
+            }
+            (Some(Value::Array(prev)), Value::Array(values))
+                if item.is_array_of_tables()
+                    && prev.len() == values.len()
+                    && values.iter().all(Value::is_object) =>
+            {
+                let tables = item.as_array_of_tables_mut().expect("checked above");
+                for ((table, prev), value) in tables.iter_mut().zip(prev).zip(values) {
+                    if let (Value::Object(prev), Value::Object(value)) = (prev, value) {
+                        merge_table(table, prev, value, false)?;
+                    }
+                }
+            }
+            (_, value) => match item {
+                // 保留值前后的空白和行尾注释
+                Item::Value(old) => {
+                    let decor = old.decor().clone();
+                    *old = to_toml_value(value)?;
+                    *old.decor_mut() = decor;
+                }
+                item => *item = to_toml_item(value)?,
+            },
+        }
+    }
+    Ok(())
+}
+
+fn to_toml_item(value: &Value) -> anyhow::Result<Item> {
+    let item = match value {
+        Value::Object(map) => {
+            let mut table = Table::new();
+            for (key, value) in map {
+                table.insert(key, to_toml_item(value)?);
+            }
+            Item::Table(table)
+        }
+        Value::Array(items) if !items.is_empty() && items.iter().all(Value::is_object) => {
+            let mut tables = ArrayOfTables::new();
+            for item in items {
+                if let Item::Table(table) = to_toml_item(item)? {
+                    tables.push(table);
+                }
+            }
+            Item::ArrayOfTables(tables)
+        }
+        value => Item::Value(to_toml_value(value)?),
+    };
+    Ok(item)
+}
+
+fn to_toml_value(value: &Value) -> anyhow::Result<toml_edit::Value> {
+    let value = match value {
+        Value::Null => anyhow::bail!("TOML cannot represent null"),
+        Value::Bool(b) => (*b).into(),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => i.into(),
+            None => n
+                .as_f64()
+                .ok_or_else(|| anyhow::anyhow!("TOML cannot represent {}", n))?
+                .into(),
+        },
+        Value::String(s) => s.as_str().into(),
+        Value::Array(items) => {
+            let items = items
+                .iter()
+                .map(to_toml_value)
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            toml_edit::Value::Array(items.into_iter().collect())
+        }
+        Value::Object(map) => {
+            let mut table = InlineTable::new();
+            for (key, value) in map {
+                table.insert(key, to_toml_value(value)?);
+            }
+            toml_edit::Value::InlineTable(table)
+        }
+    };
+    Ok(value)
+}
+
+/// serde_yaml 不保留注释，粗略判断：不在引号内、前面是空白的 `#`
+fn has_yaml_comment(content: &str) -> bool {
+    content.lines().any(|line| {
+        let (mut quote, mut prev) = (None, ' ');
+        for c in line.chars() {
+            match quote {
+                Some(q) if c == q => quote = None,
+                Some(_) => {}
+                None if c == '#' && prev.is_whitespace() => return true,
+                None if matches!(c, '\'' | '"')
+                    && matches!(prev, ' ' | ':' | ',' | '[' | '{' | '-') =>
+                {
+                    quote = Some(c)
+                }
+                None => {}
+            }
+            prev = c;
+        }
+        false
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn io_opts(input: &str, output: &str, to: Option<DataFormat>) -> DataIoOpts {
+        DataIoOpts {
+            input: input.to_string(),
+            output: output.to_string(),
+            from: None,
+            to,
+            raw_output: false,
+            compact: false,
+        }
+    }
+
+    fn temp_path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("cli-data-{}-{}", std::process::id(), name))
+            .to_string_lossy()
+            .to_string()
+    }
+
+    #[test]
+    fn test_parse_documents() -> anyhow::Result<()> {
+        let docs = parse_documents("a: 1\n---\na: 2\n", DataFormat::Yaml)?;
+        assert_eq!(docs, [json!({"a": 1}), json!({"a": 2})]);
+
+        let docs = parse_documents("{\"a\":1}\n\n[2]\n", DataFormat::Ndjson)?;
+        assert_eq!(docs, [json!({"a": 1}), json!([2])]);
+
+        let value = parse("[t]\nday = 2024-01-31\n", DataFormat::Toml)?;
+        assert_eq!(value, json!({"t": {"day": "2024-01-31"}}));
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_values() -> anyhow::Result<()> {
+        let values = [json!({"a": [1, 2]}), json!("x")];
+        assert_eq!(
+            format_values(&values, DataFormat::Json, false, true)?,
+            "{\"a\":[1,2]}\n\"x\"\n"
+        );
+        assert_eq!(
+            format_values(&values, DataFormat::Yaml, true, false)?,
+            "a:\n- 1\n- 2\nx\n"
+        );
+        assert_eq!(
+            format_values(&values[..1], DataFormat::Toml, false, false)?,
+            "a = [\n    1,\n    2,\n]\n"
+        );
+        assert!(format_values(&values, DataFormat::Toml, false, false).is_err());
+        assert!(format_values(&[json!([1])], DataFormat::Toml, false, false).is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_process_query_and_in_place() -> anyhow::Result<()> {
+        let input = temp_path("users.yaml");
+        let output = temp_path("names.json");
+        std::fs::write(
+            &input,
+            "users:\n- name: alice\n  age: 31\n- name: bob\n  age: 25\n",
+        )?;
+
+        let filter = "[.users[] | select(.age > 30) | .name]";
+        process_query(
+            filter,
+            &io_opts(&input, &output, None),
+            false,
+            OutputMode::Quiet,
+        )
+        .await?;
+        assert_eq!(std::fs::read_to_string(&output)?, "[\n  \"alice\"\n]\n");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&input, std::fs::Permissions::from_mode(0o600))?;
+        }
+        let filter = ".users[0].age |= . + 1 | del(.users[1])";
+        process_query(filter, &io_opts(&input, "-", None), true, OutputMode::Quiet).await?;
+        assert_eq!(
+            std::fs::read_to_string(&input)?,
+            "users:\n- name: alice\n  age: 32\n"
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&input)?.permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // 结果不是一个值时不修改文件
+        let opts = io_opts(&input, "-", None);
+        assert!(
+            process_query(".users, .users", &opts, true, OutputMode::Quiet)
+                .await
+                .is_err()
+        );
+        assert!(
+            process_query("empty", &opts, true, OutputMode::Quiet)
+                .await
+                .is_err()
+        );
+        assert_eq!(
+            std::fs::read_to_string(&input)?,
+            "users:\n- name: alice\n  age: 32\n"
+        );
+
+        process_query(
+            ".",
+            &io_opts(&input, &output, Some(DataFormat::Toml)),
+            false,
+            OutputMode::Quiet,
+        )
+        .await?;
+        assert_eq!(
+            std::fs::read_to_string(&output)?,
+            "[[users]]\nname = \"alice\"\nage = 32\n"
+        );
+
+        std::fs::remove_file(input)?;
+        std::fs::remove_file(output)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_in_place_keeps_comments_and_order() -> anyhow::Result<()> {
+        let input = temp_path("Cargo.toml");
+        let manifest = r#"# 包信息
+[package]
+version = "0.1.0" # 发布前修改
+name = "cli"
+edition = "2024"
+
+[dependencies]
+# 按字母顺序排列
+tokio = { version = "1", features = ["full"] }
+anyhow = "1"
+
+[[bin]]
+name = "cli"
+"#;
+        std::fs::write(&input, manifest)?;
+        let opts = io_opts(&input, "-", None);
+        let filter = r#".package.version = "0.2.0" | .dependencies.tokio.version = "1.48" | .bin[0].test = false"#;
+        process_query(filter, &opts, true, OutputMode::Quiet).await?;
+        let expected = manifest
+            .replace(r#""0.1.0""#, r#""0.2.0""#)
+            .replace(r#"version = "1""#, r#"version = "1.48""#)
+            .replace(
+                "[[bin]]\nname = \"cli\"\n",
+                "[[bin]]\nname = \"cli\"\ntest = false\n",
+            );
+        assert_eq!(std::fs::read_to_string(&input)?, expected);
+
+        process_query("del(.dependencies.anyhow)", &opts, true, OutputMode::Quiet).await?;
+        let updated = std::fs::read_to_string(&input)?;
+        assert!(!updated.contains("anyhow"));
+        assert!(updated.starts_with("# 包信息\n[package]\nversion = \"0.2.0\" # 发布前修改\n"));
+        std::fs::remove_file(&input)?;
+
+        // JSON 保留键的顺序，YAML 有注释时拒绝改写
+        let input = temp_path("order.json");
+        std::fs::write(&input, "{\"b\": 1, \"a\": 2}")?;
+        let opts = io_opts(&input, "-", None);
+        process_query(".c = 3", &opts, true, OutputMode::Quiet).await?;
+        assert_eq!(
+            std::fs::read_to_string(&input)?,
+            "{\n  \"b\": 1,\n  \"a\": 2,\n  \"c\": 3\n}\n"
+        );
+        std::fs::remove_file(&input)?;
+
+        let input = temp_path("commented.yaml");
+        let yaml = "b: 1 # keep\na: 'x # not a comment'\n";
+        std::fs::write(&input, yaml)?;
+        let opts = io_opts(&input, "-", None);
+        assert!(
+            process_query(".a = 1", &opts, true, OutputMode::Quiet)
+                .await
+                .is_err()
+        );
+        assert_eq!(std::fs::read_to_string(&input)?, yaml);
+        assert!(!has_yaml_comment("a: 'x # y'\nb: \"it's # z\"\nc: it's\n"));
+        std::fs::remove_file(&input)?;
+        Ok(())
+    }
+}
@@ -1,10 +1,106 @@
-use rand::seq::{IteratorRandom, SliceRandom as _};
+use std::sync::LazyLock;
+
+use rand::seq::SliceRandom as _;
 
 const UPPER: &str = "ABCDEFGHJKLMNPQRSTUVWXYZ";
 const LOWER: &str = "abcdefghijkmnpqrstuvwxyz";
 const DIGIT: &str = "0123456789";
 const SYMBOL: &str = "!#$%&*+-?@^_|~";
 
+// 内置单词表，每行一个单词
+static WORDS: LazyLock<Vec<&'static str>> = LazyLock::new(|| {
+    include_str!("../../assets/wordlist.txt")
+        .lines()
+        .map(str::trim)
+        .filter(|w| !w.is_empty())
+        .collect()
+});
+
+/// 生成密码使用的字符集，每个分组至少出现一个字符
+#[derive(Debug, Clone, PartialEq)]
+pub struct Charset {
+    groups: Vec<Vec<char>>,
+}
+
+/// 密码强度，score 与 zxcvbn 一样为 0~4
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Strength {
+    pub entropy: f64,
+    pub score: u8,
+}
+
+impl Charset {
+    pub fn new(upper: bool, lower: bool, digit: bool, symbol: bool) -> Self {
+        let groups = [
+            (upper, UPPER),
+            (lower, LOWER),
+            (digit, DIGIT),
+            (symbol, SYMBOL),
+        ]
+        .into_iter()
+        .filter(|(include, _)| *include)
+        .map(|(_, chars)| chars.chars().collect())
+        .collect();
+        Self { groups }
+    }
+
+    /// 自定义字符集，重复的字符只保留一个
+    pub fn custom(chars: &str) -> Self {
+        let mut group = Vec::new();
+        for c in chars.chars() {
+            if !group.contains(&c) {
+                group.push(c);
+            }
+        }
+        Self {
+            groups: vec![group].into_iter().filter(|g| !g.is_empty()).collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.groups.iter().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Strength {
+    pub fn from_entropy(entropy: f64) -> Self {
+        let score = match entropy {
+            e if e < 28.0 => 0,
+            e if e < 36.0 => 1,
+            e if e < 60.0 => 2,
+            e if e < 128.0 => 3,
+            _ => 4,
+        };
+        Self { entropy, score }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self.score {
+            0 => "very weak",
+            1 => "weak",
+            2 => "fair",
+            3 => "strong",
+            _ => "very strong",
+        }
+    }
+}
+
+impl std::fmt::Display for Strength {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({}/4, {:.1} bits)",
+            self.label(),
+            self.score,
+            self.entropy
+        )
+    }
+}
+
 pub async fn process_genpass(
     length: usize,
     include_upper: bool,
@@ -12,32 +108,31 @@ pub async fn process_genpass(
     include_digit: bool,
     include_symbol: bool,
 ) -> anyhow::Result<String> {
-    let mut rng = rand::thread_rng();
-    let mut pool = String::new();
-    let mut password_chars = Vec::new();
+    let charset = Charset::new(include_upper, include_lower, include_digit, include_symbol);
+    generate_password(length, &charset)
+}
 
-    if include_upper {
-        pool.push_str(UPPER);
-        password_chars.push(UPPER.chars().choose(&mut rng).unwrap());
-    }
-    if include_lower {
-        pool.push_str(LOWER);
-        password_chars.push(LOWER.chars().choose(&mut rng).unwrap());
-    }
-    if include_digit {
-        pool.push_str(DIGIT);
-        password_chars.push(DIGIT.chars().choose(&mut rng).unwrap());
-    }
-    if include_symbol {
-        pool.push_str(SYMBOL);
-        password_chars.push(SYMBOL.chars().choose(&mut rng).unwrap());
-    }
-    if pool.is_empty() {
+pub fn generate_password(length: usize, charset: &Charset) -> anyhow::Result<String> {
+    if charset.is_empty() {
         return Err(anyhow::anyhow!("no characters to choose from"));
     }
+    if length < charset.groups.len() {
+        return Err(anyhow::anyhow!(
+            "length must be at least {} to include every character group",
+            charset.groups.len()
+        ));
+    }
+
+    let mut rng = rand::thread_rng();
+    let mut password_chars = charset
+        .groups
+        .iter()
+        .map(|group| *group.choose(&mut rng).unwrap())
+        .collect::<Vec<_>>();
 
+    let pool = charset.groups.concat();
     while password_chars.len() < length {
-        password_chars.push(pool.chars().choose(&mut rng).unwrap());
+        password_chars.push(*pool.choose(&mut rng).unwrap());
     }
 
     password_chars.shuffle(&mut rng);
@@ -45,3 +140,87 @@ pub async fn process_genpass(
 
     Ok(password)
 }
+
+pub fn generate_passphrase(words: usize, separator: &str) -> anyhow::Result<String> {
+    if words == 0 {
+        return Err(anyhow::anyhow!("passphrase needs at least one word"));
+    }
+
+    let mut rng = rand::thread_rng();
+    let passphrase = (0..words)
+        .map(|_| *WORDS.choose(&mut rng).unwrap())
+        .collect::<Vec<_>>()
+        .join(separator);
+
+    Ok(passphrase)
+}
+
+/// 随机密码的熵：length * log2(字符集大小)
+pub fn password_strength(length: usize, charset: &Charset) -> Strength {
+    Strength::from_entropy(length as f64 * (charset.len() as f64).log2())
+}
+
+/// 口令的熵：单词数 * log2(单词表大小)，分隔符是公开的，不增加熵
+pub fn passphrase_strength(words: usize) -> Strength {
+    Strength::from_entropy(words as f64 * (WORDS.len() as f64).log2())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_password() -> anyhow::Result<()> {
+        let charset = Charset::new(true, false, true, false);
+        let password = generate_password(32, &charset)?;
+        assert_eq!(password.chars().count(), 32);
+        assert!(
+            password
+                .chars()
+                .all(|c| UPPER.contains(c) || DIGIT.contains(c))
+        );
+        assert!(password.chars().any(|c| UPPER.contains(c)));
+        assert!(password.chars().any(|c| DIGIT.contains(c)));
+
+        assert!(generate_password(1, &charset).is_err());
+        assert!(generate_password(8, &Charset::new(false, false, false, false)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_custom_charset() -> anyhow::Result<()> {
+        let charset = Charset::custom("abcabc");
+        assert_eq!(charset.len(), 3);
+
+        let password = generate_password(20, &charset)?;
+        assert!(password.chars().all(|c| "abc".contains(c)));
+        assert!(Charset::custom("").is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_passphrase() -> anyhow::Result<()> {
+        let passphrase = generate_passphrase(5, ".")?;
+        let words = passphrase.split('.').collect::<Vec<_>>();
+        assert_eq!(words.len(), 5);
+        assert!(words.iter().all(|w| WORDS.contains(w)));
+        assert!(generate_passphrase(0, "-").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_strength() {
+        let full = Charset::new(true, true, true, true);
+        assert_eq!(full.len(), 72);
+        assert_eq!(password_strength(4, &full).score, 0);
+        assert_eq!(password_strength(16, &full).score, 3);
+        assert_eq!(password_strength(24, &full).score, 4);
+
+        let strength = Strength::from_entropy(42.0);
+        assert_eq!(strength.label(), "fair");
+        assert_eq!(strength.to_string(), "fair (2/4, 42.0 bits)");
+
+        // 单词表至少 1024 个单词，每个单词不少于 10 bits
+        assert!(passphrase_strength(1).entropy >= 10.0);
+    }
+}
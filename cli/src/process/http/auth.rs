@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    http::{HeaderMap, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use base64::prelude::*;
+
+use crate::{opts::http::HttpAuth, utils::constant_time_eq};
+
+pub async fn require_auth(State(auth): State<Arc<HttpAuth>>, req: Request, next: Next) -> Response {
+    if auth.verify(req.headers()) {
+        return next.run(req).await;
+    }
+
+    let challenge = match auth.as_ref() {
+        HttpAuth::Basic { .. } => "Basic realm=\"rcli\", charset=\"UTF-8\"",
+        HttpAuth::Bearer(_) => "Bearer realm=\"rcli\"",
+    };
+    (
+        StatusCode::UNAUTHORIZED,
+        [(header::WWW_AUTHENTICATE, challenge)],
+    )
+        .into_response()
+}
+
+impl HttpAuth {
+    fn verify(&self, headers: &HeaderMap) -> bool {
+        let Some(value) = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+        else {
+            return false;
+        };
+        let Some((scheme, credentials)) = value.trim().split_once(' ') else {
+            return false;
+        };
+        let credentials = credentials.trim();
+
+        match self {
+            HttpAuth::Basic { username, password } => {
+                if !scheme.eq_ignore_ascii_case("basic") {
+                    return false;
+                }
+                let expected = format!("{}:{}", username, password);
+                BASE64_STANDARD
+                    .decode(credentials)
+                    .is_ok_and(|decoded| constant_time_eq(&decoded, expected.as_bytes()))
+            }
+            HttpAuth::Bearer(token) => {
+                scheme.eq_ignore_ascii_case("bearer")
+                    && constant_time_eq(credentials.as_bytes(), token.as_bytes())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    fn headers(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_verify_basic() {
+        let auth = HttpAuth::Basic {
+            username: "alice".to_string(),
+            password: "secret".to_string(),
+        };
+        let encoded = BASE64_STANDARD.encode("alice:secret");
+        assert!(auth.verify(&headers(&format!("Basic {}", encoded))));
+        assert!(auth.verify(&headers(&format!("basic  {}", encoded))));
+
+        let wrong = BASE64_STANDARD.encode("alice:wrong");
+        assert!(!auth.verify(&headers(&format!("Basic {}", wrong))));
+        assert!(!auth.verify(&headers(&format!("Bearer {}", encoded))));
+        assert!(!auth.verify(&HeaderMap::new()));
+    }
+
+    #[test]
+    fn test_verify_bearer() {
+        let auth = HttpAuth::Bearer("token123".to_string());
+        assert!(auth.verify(&headers("Bearer token123")));
+        assert!(!auth.verify(&headers("Bearer token1234")));
+        assert!(!auth.verify(&headers("token123")));
+    }
+}
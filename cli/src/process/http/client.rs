@@ -0,0 +1,650 @@
+use std::{
+    io::{IsTerminal, Write},
+    path::{Path, PathBuf},
+};
+
+use futures::StreamExt;
+use reqwest::{
+    Method, StatusCode, Version,
+    header::{self, HeaderMap, HeaderName, HeaderValue},
+};
+use serde_json::{Map, Value, json};
+use tokio::{fs, io::AsyncWriteExt};
+
+use super::session::Session;
+use crate::{
+    OutputMode,
+    opts::http::{HttpClientOpts, HttpRequestOpts, RequestItem},
+};
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const BLUE: &str = "\x1b[34m";
+const MAGENTA: &str = "\x1b[35m";
+const CYAN: &str = "\x1b[36m";
+const MAX_REDIRECTS: usize = 10;
+
+pub struct HttpClient {
+    client: reqwest::Client,
+    session: Session,
+    session_path: Option<PathBuf>,
+    verbose: bool,
+    color: bool,
+    terminal: bool,
+    output: OutputMode,
+}
+
+/// 已读取完整响应体的响应，.http 文件中的后续请求可以引用它
+#[derive(Debug)]
+pub struct RecordedResponse {
+    pub status: StatusCode,
+    pub version: Version,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+}
+
+pub async fn process_http_request(
+    method: Method,
+    opts: &HttpRequestOpts,
+    output: OutputMode,
+) -> anyhow::Result<()> {
+    let mut client = HttpClient::new(&opts.client, output)?;
+    let req = build_request(&client, method, opts)?;
+    if let Some(token) = &opts.token {
+        client.set_token(req.url(), token);
+    }
+    let res = client.send(req).await?;
+
+    if opts.download && res.status().is_success() {
+        let (path, size) = download(res, opts.output.as_deref()).await?;
+        if output == OutputMode::Text {
+            eprintln!("saved {} bytes to {}", size, path.display());
+        }
+        return output.emit_json(json!({ "path": path, "size": size }));
+    }
+
+    let res = RecordedResponse::read(res).await?;
+    client.print_response(&res)?;
+    if opts.download {
+        anyhow::bail!("download failed: {}", res.status);
+    }
+    Ok(())
+}
+
+impl HttpClient {
+    pub fn new(opts: &HttpClientOpts, output: OutputMode) -> anyhow::Result<Self> {
+        let session = match &opts.session {
+            Some(path) => Session::load(path)?,
+            None => Session::default(),
+        };
+        let terminal = std::io::stdout().is_terminal();
+        // 重定向由 send 逐跳处理，每一跳都按实际的地址读写会话
+        let client = reqwest::Client::builder()
+            .user_agent(concat!("rcli/", env!("CARGO_PKG_VERSION")))
+            .redirect(reqwest::redirect::Policy::none())
+            .build()?;
+
+        Ok(Self {
+            client,
+            session,
+            session_path: opts.session.clone(),
+            verbose: opts.verbose,
+            color: terminal && !opts.no_color,
+            terminal,
+            output,
+        })
+    }
+
+    pub fn output(&self) -> OutputMode {
+        self.output
+    }
+
+    pub fn set_token(&mut self, url: &reqwest::Url, token: &str) {
+        self.session.set_token(url, token);
+    }
+
+    pub fn request(&self, method: Method, url: &str) -> reqwest::RequestBuilder {
+        self.client.request(method, normalize_url(url))
+    }
+
+    /// 发送前加上会话中的 cookie 和 token，收到响应后更新会话文件；
+    /// 跟随重定向时每一跳都重新应用和更新会话
+    pub async fn send(&mut self, mut req: reqwest::Request) -> anyhow::Result<reqwest::Response> {
+        for _ in 0..=MAX_REDIRECTS {
+            // 保留加上会话之前的请求，用于构造下一跳
+            let next = req.try_clone();
+            let url = req.url().clone();
+            self.session.apply(&url, req.headers_mut())?;
+            if self.verbose && self.output == OutputMode::Text {
+                let mut stdout = std::io::stdout().lock();
+                self.write_request(&req, &mut stdout)?;
+            }
+
+            let res = self.client.execute(req).await?;
+            self.session.update(res.url(), res.headers());
+            if let Some(path) = &self.session_path {
+                self.session.save(path)?;
+            }
+            match redirect(&res, next)? {
+                Some(next) => req = next,
+                None => return Ok(res),
+            }
+        }
+        anyhow::bail!("too many redirects")
+    }
+
+    pub fn print_title(&self, title: &str) -> anyhow::Result<()> {
+        if self.output != OutputMode::Text {
+            return Ok(());
+        }
+        let mut stdout = std::io::stdout().lock();
+        writeln!(stdout, "{}", self.paint(BOLD, &format!("### {}", title)))?;
+        Ok(())
+    }
+
+    /// `--json` 时每个响应输出一行 JSON
+    pub fn print_response(&self, res: &RecordedResponse) -> anyhow::Result<()> {
+        if self.output != OutputMode::Text {
+            return self.output.emit_json(res.to_json());
+        }
+        let mut stdout = std::io::stdout().lock();
+        self.write_response(res, &mut stdout)?;
+        stdout.flush()?;
+        Ok(())
+    }
+
+    fn write_request(&self, req: &reqwest::Request, w: &mut impl Write) -> anyhow::Result<()> {
+        writeln!(
+            w,
+            "{} {}",
+            self.paint(BOLD, req.method().as_str()),
+            self.paint(CYAN, req.url().as_str())
+        )?;
+        self.write_headers(req.headers(), w)?;
+        writeln!(w)?;
+
+        if let Some(body) = req.body().and_then(|body| body.as_bytes()) {
+            self.write_body(req.headers(), body, w)?;
+        }
+        writeln!(w)?;
+        Ok(())
+    }
+
+    fn write_response(&self, res: &RecordedResponse, w: &mut impl Write) -> anyhow::Result<()> {
+        let status_color = match res.status.as_u16() {
+            200..=299 => GREEN,
+            300..=399 => YELLOW,
+            _ => RED,
+        };
+        writeln!(
+            w,
+            "{} {}",
+            self.paint(BLUE, &format!("{:?}", res.version)),
+            self.paint(status_color, &res.status.to_string())
+        )?;
+        self.write_headers(&res.headers, w)?;
+        writeln!(w)?;
+        self.write_body(&res.headers, &res.body, w)?;
+        Ok(())
+    }
+
+    fn write_headers(&self, headers: &HeaderMap, w: &mut impl Write) -> anyhow::Result<()> {
+        for (name, value) in headers {
+            writeln!(
+                w,
+                "{}: {}",
+                self.paint(CYAN, name.as_str()),
+                String::from_utf8_lossy(value.as_bytes())
+            )?;
+        }
+        Ok(())
+    }
+
+    fn write_body(
+        &self,
+        headers: &HeaderMap,
+        body: &[u8],
+        w: &mut impl Write,
+    ) -> anyhow::Result<()> {
+        if body.is_empty() {
+            return Ok(());
+        }
+
+        let is_json = headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.contains("json"));
+        if is_json && let Ok(value) = serde_json::from_slice::<Value>(body) {
+            let mut out = String::new();
+            self.format_json(&value, 0, &mut out);
+            writeln!(w, "{}", out)?;
+            return Ok(());
+        }
+
+        match std::str::from_utf8(body) {
+            Ok(text) if text.ends_with('\n') => write!(w, "{}", text)?,
+            Ok(text) => writeln!(w, "{}", text)?,
+            // 输出被重定向时保留原始字节，方便 `> file`
+            Err(_) if !self.terminal => w.write_all(body)?,
+            Err(_) => writeln!(
+                w,
+                "+-----------------------------------------+\n\
+                 | NOTE: binary data not shown in terminal |\n\
+                 +-----------------------------------------+"
+            )?,
+        }
+        Ok(())
+    }
+
+    // 与 serde_json::to_string_pretty 的格式相同，只是加上颜色
+    fn format_json(&self, value: &Value, indent: usize, out: &mut String) {
+        let pad = |n: usize| "  ".repeat(n);
+        match value {
+            Value::Null | Value::Bool(_) => out.push_str(&self.paint(MAGENTA, &value.to_string())),
+            Value::Number(n) => out.push_str(&self.paint(CYAN, &n.to_string())),
+            Value::String(_) => out.push_str(&self.paint(GREEN, &value.to_string())),
+            Value::Array(items) if items.is_empty() => out.push_str("[]"),
+            Value::Object(map) if map.is_empty() => out.push_str("{}"),
+            Value::Array(items) => {
+                out.push_str("[\n");
+                for (i, item) in items.iter().enumerate() {
+                    out.push_str(&pad(indent + 1));
+                    self.format_json(item, indent + 1, out);
+                    out.push_str(if i + 1 < items.len() { ",\n" } else { "\n" });
+                }
+                out.push_str(&pad(indent));
+                out.push(']');
+            }
+            Value::Object(map) => {
+                out.push_str("{\n");
+                for (i, (key, item)) in map.iter().enumerate() {
+                    out.push_str(&pad(indent + 1));
+                    out.push_str(&self.paint(BLUE, &Value::String(key.clone()).to_string()));
+                    out.push_str(": ");
+                    self.format_json(item, indent + 1, out);
+                    out.push_str(if i + 1 < map.len() { ",\n" } else { "\n" });
+                }
+                out.push_str(&pad(indent));
+                out.push('}');
+            }
+        }
+    }
+
+    fn paint(&self, color: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", color, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+impl RecordedResponse {
+    pub async fn read(res: reqwest::Response) -> anyhow::Result<Self> {
+        Ok(Self {
+            status: res.status(),
+            version: res.version(),
+            headers: res.headers().clone(),
+            body: res.bytes().await?.to_vec(),
+        })
+    }
+
+    /// JSON 响应体原样嵌入，其它内容作为字符串
+    pub fn to_json(&self) -> Value {
+        let headers = self
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                let value = String::from_utf8_lossy(value.as_bytes()).to_string();
+                (name.to_string(), Value::String(value))
+            })
+            .collect::<Map<_, _>>();
+        let body = serde_json::from_slice::<Value>(&self.body)
+            .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&self.body).to_string()));
+        json!({
+            "status": self.status.as_u16(),
+            "headers": headers,
+            "body": body,
+        })
+    }
+}
+
+pub fn build_request(
+    client: &HttpClient,
+    method: Method,
+    opts: &HttpRequestOpts,
+) -> anyhow::Result<reqwest::Request> {
+    let mut headers = HeaderMap::new();
+    let mut query = Vec::new();
+    let mut fields = Map::new();
+    let mut form = Vec::new();
+
+    for item in &opts.items {
+        match item {
+            RequestItem::Header(name, value) => {
+                headers.append(
+                    HeaderName::from_bytes(name.as_bytes())?,
+                    HeaderValue::from_str(value)?,
+                );
+            }
+            RequestItem::Query(key, value) => query.push((key, value)),
+            RequestItem::Field(key, value) => {
+                fields.insert(key.clone(), Value::String(value.clone()));
+                form.push((key, value));
+            }
+            RequestItem::JsonField(key, value) => {
+                if opts.form {
+                    anyhow::bail!("`{}:=` JSON fields can't be sent with --form", key);
+                }
+                fields.insert(key.clone(), value.clone());
+            }
+        }
+    }
+
+    let mut builder = client.request(method, &opts.url).query(&query);
+    if !fields.is_empty() {
+        builder = if opts.form {
+            builder.form(&form)
+        } else {
+            if !headers.contains_key(header::ACCEPT) {
+                headers.insert(
+                    header::ACCEPT,
+                    HeaderValue::from_static("application/json, */*;q=0.5"),
+                );
+            }
+            builder.json(&fields)
+        };
+    }
+
+    Ok(builder.headers(headers).build()?)
+}
+
+/// 根据 3xx 响应构造下一跳请求；不是重定向或请求体无法复制时返回 `None`。
+/// 301/302/303 改为不带请求体的 GET，跳到其他 origin 时去掉认证相关的请求头
+fn redirect(
+    res: &reqwest::Response,
+    next: Option<reqwest::Request>,
+) -> anyhow::Result<Option<reqwest::Request>> {
+    let keep_method = match res.status() {
+        StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND | StatusCode::SEE_OTHER => false,
+        StatusCode::TEMPORARY_REDIRECT | StatusCode::PERMANENT_REDIRECT => true,
+        _ => return Ok(None),
+    };
+    let (Some(location), Some(mut next)) = (res.headers().get(header::LOCATION), next) else {
+        return Ok(None);
+    };
+
+    let url = res.url().join(location.to_str()?)?;
+    if !keep_method && next.method() != Method::HEAD {
+        *next.method_mut() = Method::GET;
+        *next.body_mut() = None;
+        for name in [header::CONTENT_TYPE, header::CONTENT_LENGTH] {
+            next.headers_mut().remove(name);
+        }
+    }
+    if url.origin() != next.url().origin() {
+        for name in [
+            header::AUTHORIZATION,
+            header::COOKIE,
+            header::PROXY_AUTHORIZATION,
+        ] {
+            next.headers_mut().remove(name);
+        }
+    }
+    *next.url_mut() = url;
+    Ok(Some(next))
+}
+
+/// `:9090/api` 表示 localhost，省略协议时使用 http
+fn normalize_url(url: &str) -> String {
+    if url.starts_with(':') {
+        format!("http://localhost{}", url)
+    } else if url.contains("://") {
+        url.to_string()
+    } else {
+        format!("http://{}", url)
+    }
+}
+
+async fn download(res: reqwest::Response, output: Option<&Path>) -> anyhow::Result<(PathBuf, u64)> {
+    let path = match output {
+        Some(path) => path.to_path_buf(),
+        None => unique_path(&download_file_name(&res)),
+    };
+
+    let mut file = fs::File::create(&path).await?;
+    let mut size = 0u64;
+    let mut stream = res.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk?;
+        size += chunk.len() as u64;
+        file.write_all(&chunk).await?;
+    }
+    file.flush().await?;
+    Ok((path, size))
+}
+
+fn download_file_name(res: &reqwest::Response) -> String {
+    let from_header = res
+        .headers()
+        .get(header::CONTENT_DISPOSITION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| {
+            v.split(';')
+                .filter_map(|part| part.trim().strip_prefix("filename="))
+                .next()
+                .map(|name| name.trim_matches('"').to_string())
+        });
+    let from_url = res
+        .url()
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .map(|name| {
+            percent_encoding::percent_decode_str(name)
+                .decode_utf8_lossy()
+                .to_string()
+        });
+
+    // 只取最后一段，不允许服务器指定其他目录
+    [from_header, from_url]
+        .into_iter()
+        .flatten()
+        .filter_map(|name| name.rsplit(['/', '\\']).next().map(str::to_string))
+        .find(|name| !name.is_empty() && name != "." && name != "..")
+        .unwrap_or_else(|| "index.html".to_string())
+}
+
+// 不覆盖已存在的文件，依次尝试 name-1.ext, name-2.ext...
+fn unique_path(name: &str) -> PathBuf {
+    let path = PathBuf::from(name);
+    if !path.exists() {
+        return path;
+    }
+
+    let (stem, ext) = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem, format!(".{}", ext)),
+        _ => (name, String::new()),
+    };
+    (1..)
+        .map(|i| PathBuf::from(format!("{}-{}{}", stem, i, ext)))
+        .find(|path| !path.exists())
+        .expect("infinite iterator")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client_opts() -> HttpClientOpts {
+        HttpClientOpts {
+            session: None,
+            verbose: false,
+            no_color: true,
+        }
+    }
+
+    fn request_opts(url: &str, items: Vec<RequestItem>) -> HttpRequestOpts {
+        HttpRequestOpts {
+            url: url.to_string(),
+            items,
+            token: None,
+            form: false,
+            download: false,
+            output: None,
+            client: client_opts(),
+        }
+    }
+
+    #[test]
+    fn test_normalize_url() {
+        assert_eq!(normalize_url(":9090/api"), "http://localhost:9090/api");
+        assert_eq!(normalize_url("example.com"), "http://example.com");
+        assert_eq!(normalize_url("https://example.com"), "https://example.com");
+    }
+
+    #[test]
+    fn test_build_request() -> anyhow::Result<()> {
+        let client = HttpClient::new(&client_opts(), OutputMode::Text)?;
+        let opts = request_opts(
+            ":9090/api/chat/create",
+            vec![
+                RequestItem::Header("x-request-id".to_string(), "abc".to_string()),
+                RequestItem::Query("limit".to_string(), "2".to_string()),
+                RequestItem::Field("name".to_string(), "IT".to_string()),
+                RequestItem::JsonField("user_ids".to_string(), serde_json::json!([1, 2])),
+            ],
+        );
+
+        let req = build_request(&client, Method::POST, &opts)?;
+        assert_eq!(
+            req.url().as_str(),
+            "http://localhost:9090/api/chat/create?limit=2"
+        );
+        assert_eq!(req.headers()["x-request-id"], "abc");
+        assert_eq!(req.headers()[header::CONTENT_TYPE], "application/json");
+        let body: Value = serde_json::from_slice(req.body().unwrap().as_bytes().unwrap())?;
+        assert_eq!(body, serde_json::json!({"name": "IT", "user_ids": [1, 2]}));
+
+        let mut opts = opts;
+        opts.form = true;
+        assert!(build_request(&client, Method::POST, &opts).is_err());
+        opts.items.pop();
+        let req = build_request(&client, Method::POST, &opts)?;
+        assert_eq!(req.body().unwrap().as_bytes().unwrap(), b"name=IT");
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_response() -> anyhow::Result<()> {
+        let mut client = HttpClient::new(&client_opts(), OutputMode::Text)?;
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+        let res = RecordedResponse {
+            status: StatusCode::OK,
+            version: Version::HTTP_11,
+            headers,
+            body: br#"{"data":{"ids":[1,2],"token":null},"empty":[]}"#.to_vec(),
+        };
+
+        let mut out = Vec::new();
+        client.write_response(&res, &mut out)?;
+        let expected = format!(
+            "HTTP/1.1 200 OK\ncontent-type: application/json\n\n{}\n",
+            serde_json::to_string_pretty(&serde_json::from_slice::<Value>(&res.body)?)?
+        );
+        assert_eq!(String::from_utf8(out)?, expected);
+
+        client.color = true;
+        let mut out = Vec::new();
+        client.write_response(&res, &mut out)?;
+        let out = String::from_utf8(out)?;
+        assert!(out.contains("\x1b[32m200 OK\x1b[0m"));
+        assert!(out.contains("\x1b[34m\"token\"\x1b[0m: \x1b[35mnull\x1b[0m"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_redirect_scopes_cookies_to_each_hop() -> anyhow::Result<()> {
+        use axum::{Router, http::HeaderMap, response::IntoResponse, routing::get};
+
+        fn cookie(headers: &HeaderMap) -> String {
+            headers
+                .get(header::COOKIE)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or_default()
+                .to_string()
+        }
+
+        // 两个 origin：127.0.0.1 跳转到 localhost
+        let target = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let target_port = target.local_addr()?.port();
+        let app = Router::new().route(
+            "/land",
+            get(|headers: HeaderMap| async move {
+                let auth = headers.contains_key(header::AUTHORIZATION);
+                (
+                    [(header::SET_COOKIE, "b=2; Path=/")],
+                    format!("cookie={} auth={}", cookie(&headers), auth),
+                )
+            }),
+        );
+        tokio::spawn(async move { axum::serve(target, app).await });
+
+        let origin = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let origin_port = origin.local_addr()?.port();
+        let location = format!("http://localhost:{}/land", target_port);
+        let app = Router::new().route(
+            "/start",
+            get(move || {
+                let location = location.clone();
+                async move {
+                    (
+                        StatusCode::FOUND,
+                        [
+                            (header::SET_COOKIE, "a=1; Path=/".to_string()),
+                            (header::LOCATION, location),
+                        ],
+                    )
+                        .into_response()
+                }
+            }),
+        );
+        tokio::spawn(async move { axum::serve(origin, app).await });
+
+        let mut client = HttpClient::new(&client_opts(), OutputMode::Quiet)?;
+        let start = format!("http://127.0.0.1:{}/start", origin_port);
+        let req = client.request(Method::GET, &start).build()?;
+        client.set_token(req.url(), "secret");
+        let res = client.send(req).await?;
+        assert_eq!(res.url().host_str(), Some("localhost"));
+        // 第一跳的 cookie 和 token 不会发送给另一个 origin
+        assert_eq!(res.text().await?, "cookie= auth=false");
+
+        let cookies = client
+            .session
+            .cookies
+            .iter()
+            .map(|c| (c.name.as_str(), c.domain.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(cookies, [("a", "127.0.0.1"), ("b", "localhost")]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_unique_path() -> anyhow::Result<()> {
+        let dir = std::env::temp_dir().join(format!("cli-download-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let name = dir.join("a.txt").to_string_lossy().to_string();
+        assert_eq!(unique_path(&name), dir.join("a.txt"));
+        std::fs::write(&name, "")?;
+        assert_eq!(unique_path(&name), dir.join("a-1.txt"));
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
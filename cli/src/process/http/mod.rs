@@ -0,0 +1,840 @@
+mod auth;
+pub mod client;
+pub mod runner;
+mod session;
+mod upload;
+
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Instant, UNIX_EPOCH},
+};
+
+use axum::{
+    Json, Router,
+    body::Body,
+    extract::{ConnectInfo, DefaultBodyLimit, Request, State},
+    http::{HeaderMap, HeaderValue, Method, StatusCode, header},
+    middleware::{self, Next},
+    response::{Html, IntoResponse, Redirect, Response},
+};
+use chrono::{DateTime, Utc};
+use conc::metrics::{Registry, http::HttpMetrics};
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo},
+    server::conn::auto,
+    service::TowerToHyperService,
+};
+use percent_encoding::{AsciiSet, CONTROLS, percent_decode_str, utf8_percent_encode};
+use serde::Serialize;
+use tokio::net::TcpListener;
+use tokio_rustls::{
+    TlsAcceptor,
+    rustls::{
+        ServerConfig,
+        crypto::ring,
+        pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject},
+    },
+};
+use tower::ServiceExt;
+use tower_http::{cors::CorsLayer, services::ServeFile};
+use tracing::{debug, info, warn};
+
+use crate::{
+    OutputMode,
+    opts::http::{HttpAuth, HttpServeOpts},
+};
+
+// 目录列表中的链接需要转义的字符，`:` 避免文件名被当作 `javascript:` 等协议
+const PATH_SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'&')
+    .add(b'\'')
+    .add(b':')
+    .add(b'\\')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+pub struct HttpServeState {
+    dir: PathBuf,
+    allow_upload: bool,
+    allow_delete: bool,
+    max_upload_size: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct DirEntry {
+    name: String,
+    is_dir: bool,
+    size: u64,
+    modified: Option<DateTime<Utc>>,
+}
+
+/// `--quiet` 时不输出访问日志，只保留警告
+pub async fn process_http_serve(opts: &HttpServeOpts, output: OutputMode) -> anyhow::Result<()> {
+    let level = match output {
+        OutputMode::Quiet => "warn",
+        _ => "info",
+    };
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| level.into()),
+        )
+        .try_init();
+
+    let router = get_router(opts)?;
+    let addr = SocketAddr::new(opts.bind, opts.port);
+    let listener = TcpListener::bind(addr).await?;
+
+    if opts.auth.is_some() && opts.tls_cert.is_none() {
+        warn!("--auth without TLS sends credentials in plain text");
+    }
+
+    match (&opts.tls_cert, &opts.tls_key) {
+        (Some(cert), Some(key)) => {
+            let config = load_tls_config(cert, key)?;
+            info!("serving {:?} on https://{}", opts.dir, addr);
+            serve_tls(listener, router, config).await
+        }
+        _ => {
+            info!("serving {:?} on http://{}", opts.dir, addr);
+            axum::serve(
+                listener,
+                router.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .await?;
+            Ok(())
+        }
+    }
+}
+
+pub fn get_router(opts: &HttpServeOpts) -> anyhow::Result<Router> {
+    let state = HttpServeState {
+        dir: opts.dir.canonicalize()?,
+        allow_upload: opts.allow_upload,
+        allow_delete: opts.allow_delete,
+        max_upload_size: opts.max_upload_size,
+    };
+
+    // multipart 受 DefaultBodyLimit 限制，PUT 在写入时自行计数
+    let limit = usize::try_from(opts.max_upload_size).unwrap_or(usize::MAX);
+    let mut router = Router::new()
+        .fallback(handle_request)
+        .with_state(Arc::new(state))
+        .layer(DefaultBodyLimit::max(limit));
+
+    // /metrics 同样需要认证，指标层放在认证之外以统计认证失败的请求
+    let metrics = opts
+        .metrics
+        .then(|| HttpMetrics::new(Registry::new()))
+        .transpose()?;
+    if let Some(metrics) = &metrics {
+        router = router.merge(metrics.router());
+    }
+    if let Some(auth) = &opts.auth {
+        let auth: Arc<HttpAuth> = Arc::new(auth.clone());
+        router = router.layer(middleware::from_fn_with_state(auth, auth::require_auth));
+    }
+    // CORS 在认证之外，预检请求不需要携带凭据
+    if opts.cors {
+        router = router.layer(CorsLayer::permissive());
+    }
+    if let Some(metrics) = metrics {
+        router = router.layer(metrics.layer());
+    }
+
+    Ok(router.layer(middleware::from_fn(access_log)))
+}
+
+async fn serve_tls(
+    listener: TcpListener,
+    router: Router,
+    config: ServerConfig,
+) -> anyhow::Result<()> {
+    let acceptor = TlsAcceptor::from(Arc::new(config));
+    loop {
+        let (stream, remote) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                warn!("failed to accept connection: {}", e);
+                continue;
+            }
+        };
+
+        let acceptor = acceptor.clone();
+        let router = router.clone();
+        tokio::spawn(async move {
+            let stream = match acceptor.accept(stream).await {
+                Ok(stream) => stream,
+                Err(e) => {
+                    warn!("TLS handshake with {} failed: {}", remote, e);
+                    return;
+                }
+            };
+
+            // 和 axum::serve 一样注入 ConnectInfo，访问日志中才有客户端地址
+            let service = router.map_request(move |mut req: Request<_>| {
+                req.extensions_mut().insert(ConnectInfo(remote));
+                req
+            });
+            let result = auto::Builder::new(TokioExecutor::new())
+                .serve_connection_with_upgrades(
+                    TokioIo::new(stream),
+                    TowerToHyperService::new(service),
+                )
+                .await;
+            if let Err(e) = result {
+                debug!("connection with {} failed: {}", remote, e);
+            }
+        });
+    }
+}
+
+fn load_tls_config(cert: &Path, key: &Path) -> anyhow::Result<ServerConfig> {
+    let certs = CertificateDer::pem_file_iter(cert)?.collect::<Result<Vec<_>, _>>()?;
+    let key = PrivateKeyDer::from_pem_file(key)?;
+
+    let mut config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)?;
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+    Ok(config)
+}
+
+async fn access_log(req: Request, next: Next) -> Response {
+    let start = Instant::now();
+    let remote = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|info| info.0.to_string())
+        .unwrap_or_else(|| "-".to_string());
+    let method = req.method().clone();
+    let uri = req.uri().clone();
+    let version = req.version();
+
+    let res = next.run(req).await;
+
+    let length = res
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("-");
+    info!(
+        target: "access",
+        "{} \"{} {} {:?}\" {} {} {:?}",
+        remote,
+        method,
+        uri,
+        version,
+        res.status().as_u16(),
+        length,
+        start.elapsed()
+    );
+    res
+}
+
+async fn handle_request(State(state): State<Arc<HttpServeState>>, req: Request) -> Response {
+    match *req.method() {
+        Method::GET | Method::HEAD => serve_path(state, req).await,
+        Method::PUT | Method::POST | Method::DELETE if upload::is_cross_site(&req) => {
+            StatusCode::FORBIDDEN.into_response()
+        }
+        Method::PUT if state.allow_upload => upload::put_file(state, req).await,
+        Method::POST if state.allow_upload => upload::post_multipart(state, req).await,
+        Method::DELETE if state.allow_delete => upload::delete_path(state, req).await,
+        _ => StatusCode::METHOD_NOT_ALLOWED.into_response(),
+    }
+}
+
+async fn serve_path(state: Arc<HttpServeState>, req: Request) -> Response {
+    let path = match resolve_path(&state.dir, req.uri().path()) {
+        Ok(path) => path,
+        Err(status) => return status.into_response(),
+    };
+
+    let result = if path.is_dir() {
+        serve_dir(&path, req, state.allow_upload).await
+    } else {
+        serve_file(&path, req).await
+    };
+
+    result.unwrap_or_else(|e| {
+        warn!("failed to serve {:?}: {}", path, e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })
+}
+
+/// 把请求路径映射到根目录下的文件，拒绝 `..` 以及通过符号链接跳出根目录的路径
+fn resolve_path(root: &Path, uri_path: &str) -> Result<PathBuf, StatusCode> {
+    resolve_segments(root, &path_segments(uri_path)?)
+}
+
+fn path_segments(uri_path: &str) -> Result<Vec<String>, StatusCode> {
+    let decoded = percent_decode_str(uri_path)
+        .decode_utf8()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let mut segments = Vec::new();
+    for segment in decoded.split('/') {
+        match segment {
+            "" | "." => continue,
+            ".." => return Err(StatusCode::FORBIDDEN),
+            s if s.contains(['\\', '\0']) => return Err(StatusCode::BAD_REQUEST),
+            s => segments.push(s.to_string()),
+        }
+    }
+    Ok(segments)
+}
+
+fn resolve_segments(root: &Path, segments: &[String]) -> Result<PathBuf, StatusCode> {
+    let path = segments
+        .iter()
+        .fold(root.to_path_buf(), |path, segment| path.join(segment));
+    let path = path.canonicalize().map_err(|_| StatusCode::NOT_FOUND)?;
+    if !path.starts_with(root) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Ok(path)
+}
+
+async fn serve_dir(path: &Path, req: Request, allow_upload: bool) -> anyhow::Result<Response> {
+    let uri_path = req.uri().path();
+    // 目录需要以 / 结尾，否则相对链接会指向上一级目录
+    if !uri_path.ends_with('/') {
+        let location = match req.uri().query() {
+            Some(query) => format!("{}/?{}", uri_path, query),
+            None => format!("{}/", uri_path),
+        };
+        return Ok(Redirect::permanent(&location).into_response());
+    }
+
+    let index = path.join("index.html");
+    if index.is_file() {
+        return serve_file(&index, req).await;
+    }
+
+    let entries = list_dir(path).await?;
+    let want_json = req.uri().query().is_some_and(|q| q.contains("format=json"))
+        || req
+            .headers()
+            .get(header::ACCEPT)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.contains("application/json"));
+
+    if want_json {
+        Ok(Json(entries).into_response())
+    } else {
+        let title = percent_decode_str(uri_path).decode_utf8_lossy();
+        Ok(Html(render_listing(&title, &entries, allow_upload)).into_response())
+    }
+}
+
+async fn list_dir(path: &Path) -> anyhow::Result<Vec<DirEntry>> {
+    let mut entries = Vec::new();
+    let mut dir = tokio::fs::read_dir(path).await?;
+    while let Some(entry) = dir.next_entry().await? {
+        let meta = entry.metadata().await?;
+        entries.push(DirEntry {
+            name: entry.file_name().to_string_lossy().to_string(),
+            is_dir: meta.is_dir(),
+            size: if meta.is_dir() { 0 } else { meta.len() },
+            modified: meta.modified().ok().map(DateTime::<Utc>::from),
+        });
+    }
+
+    // 目录在前，然后按名称排序
+    entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+    Ok(entries)
+}
+
+fn render_listing(title: &str, entries: &[DirEntry], allow_upload: bool) -> String {
+    let title = escape_html(title);
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Index of {0}</title></head>\n\
+         <body>\n<h1>Index of {0}</h1>\n<table>\n",
+        title
+    );
+    if title != "/" {
+        html.push_str("<tr><td><a href=\"../\">../</a></td><td></td><td></td></tr>\n");
+    }
+    for entry in entries {
+        let suffix = if entry.is_dir { "/" } else { "" };
+        let modified = entry
+            .modified
+            .map(|m| m.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default();
+        let size = if entry.is_dir {
+            "-".to_string()
+        } else {
+            entry.size.to_string()
+        };
+        // 以 `./` 开头，链接总是相对于当前目录
+        html.push_str(&format!(
+            "<tr><td><a href=\"./{}{}\">{}{}</a></td><td>{}</td><td>{}</td></tr>\n",
+            utf8_percent_encode(&entry.name, PATH_SEGMENT),
+            suffix,
+            escape_html(&entry.name),
+            suffix,
+            size,
+            modified
+        ));
+    }
+    html.push_str("</table>\n");
+    if allow_upload {
+        html.push_str(
+            "<form method=\"post\" enctype=\"multipart/form-data\">\n\
+             <input type=\"file\" name=\"file\" multiple>\n\
+             <button type=\"submit\">Upload</button>\n</form>\n",
+        );
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// ServeFile 负责 MIME、Range、Last-Modified，这里额外处理 ETag
+async fn serve_file(path: &Path, req: Request) -> anyhow::Result<Response> {
+    let meta = tokio::fs::metadata(path).await?;
+    let etag = etag(&meta);
+
+    if if_none_match(req.headers(), &etag) {
+        let mut res = StatusCode::NOT_MODIFIED.into_response();
+        res.headers_mut().insert(header::ETAG, etag);
+        return Ok(res);
+    }
+
+    let mut res = ServeFile::new(path)
+        .precompressed_gzip()
+        .precompressed_br()
+        .oneshot(req)
+        .await?
+        .map(Body::new);
+    if res.status().is_success() {
+        res.headers_mut().insert(header::ETAG, etag);
+    }
+    Ok(res)
+}
+
+// 弱 ETag：由文件大小和修改时间组成
+fn etag(meta: &std::fs::Metadata) -> HeaderValue {
+    let modified = meta
+        .modified()
+        .ok()
+        .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    HeaderValue::from_str(&format!("W/\"{:x}-{:x}\"", meta.len(), modified))
+        .expect("etag is valid header value")
+}
+
+fn if_none_match(headers: &HeaderMap, etag: &HeaderValue) -> bool {
+    let Some(value) = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+    else {
+        return false;
+    };
+
+    // If-None-Match 使用弱比较
+    let strip = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    let etag = strip(etag.to_str().unwrap_or_default());
+    value
+        .split(',')
+        .any(|tag| tag.trim() == "*" || strip(tag) == etag)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::to_bytes;
+
+    use super::*;
+
+    fn fixture_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("cli-http-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("sub dir")).unwrap();
+        std::fs::write(dir.join("hello.txt"), "hello world").unwrap();
+        std::fs::write(dir.join("data.bin"), [0u8, 159, 146, 150, 255]).unwrap();
+        std::fs::write(dir.join("sub dir/<b>.json"), "{}").unwrap();
+        dir
+    }
+
+    fn serve_opts(dir: &Path) -> HttpServeOpts {
+        HttpServeOpts {
+            dir: dir.to_path_buf(),
+            bind: [127, 0, 0, 1].into(),
+            port: 8080,
+            cors: false,
+            tls_cert: None,
+            tls_key: None,
+            allow_upload: false,
+            max_upload_size: 1024,
+            allow_delete: false,
+            auth: None,
+            metrics: false,
+        }
+    }
+
+    async fn request(
+        router: &Router,
+        method: Method,
+        uri: &str,
+        headers: &[(header::HeaderName, &str)],
+        body: impl Into<Body>,
+    ) -> Response {
+        let mut req = Request::builder().method(method).uri(uri);
+        for (name, value) in headers {
+            req = req.header(name, *value);
+        }
+        router
+            .clone()
+            .oneshot(req.body(body.into()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    async fn get(router: &Router, uri: &str, headers: &[(header::HeaderName, &str)]) -> Response {
+        request(router, Method::GET, uri, headers, Body::empty()).await
+    }
+
+    async fn body(res: Response) -> Vec<u8> {
+        to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap()
+            .to_vec()
+    }
+
+    #[tokio::test]
+    async fn test_serve_file_with_etag_and_range() -> anyhow::Result<()> {
+        let dir = fixture_dir("file");
+        let router = get_router(&serve_opts(&dir))?;
+
+        let res = get(&router, "/hello.txt", &[]).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[header::CONTENT_TYPE], "text/plain");
+        let etag = res.headers()[header::ETAG].to_str()?.to_string();
+        assert_eq!(body(res).await, b"hello world");
+
+        let res = get(&router, "/hello.txt", &[(header::IF_NONE_MATCH, &etag)]).await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+
+        let res = get(&router, "/hello.txt", &[(header::RANGE, "bytes=6-")]).await;
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(body(res).await, b"world");
+
+        // 二进制文件原样返回
+        let res = get(&router, "/data.bin", &[]).await;
+        assert_eq!(
+            res.headers()[header::CONTENT_TYPE],
+            "application/octet-stream"
+        );
+        assert_eq!(body(res).await, [0u8, 159, 146, 150, 255]);
+
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_path_traversal_is_blocked() -> anyhow::Result<()> {
+        let dir = fixture_dir("traversal");
+        let router = get_router(&serve_opts(&dir.join("sub dir")))?;
+
+        let res = get(&router, "/../hello.txt", &[]).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        let res = get(&router, "/%2e%2e/hello.txt", &[]).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        let res = get(&router, "/missing.txt", &[]).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(dir.join("hello.txt"), dir.join("sub dir/link.txt"))?;
+            let res = get(&router, "/link.txt", &[]).await;
+            assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        }
+
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_directory_listing() -> anyhow::Result<()> {
+        let dir = fixture_dir("listing");
+        let router = get_router(&serve_opts(&dir))?;
+
+        let res = get(&router, "/sub%20dir", &[]).await;
+        assert_eq!(res.status(), StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(res.headers()[header::LOCATION], "/sub%20dir/");
+
+        let res = get(&router, "/", &[(header::ACCEPT, "application/json")]).await;
+        let entries: serde_json::Value = serde_json::from_slice(&body(res).await)?;
+        let names = entries
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["name"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["sub dir", "data.bin", "hello.txt"]);
+        assert_eq!(entries[0]["is_dir"], true);
+
+        let res = get(&router, "/sub%20dir/", &[]).await;
+        let html = String::from_utf8(body(res).await)?;
+        assert!(html.contains("Index of /sub dir/"));
+        assert!(html.contains("<a href=\"./%3Cb%3E.json\">&lt;b&gt;.json</a>"));
+
+        let res = router
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(Method::DELETE)
+                    .uri("/hello.txt")
+                    .body(Body::empty())?,
+            )
+            .await?;
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_listing_escapes_scheme_like_names() -> anyhow::Result<()> {
+        let dir = fixture_dir("scheme");
+        std::fs::write(dir.join("javascript:alert(1)"), "")?;
+        std::fs::write(dir.join("a'&b"), "")?;
+        let router = get_router(&serve_opts(&dir))?;
+
+        let html = String::from_utf8(body(get(&router, "/", &[]).await).await)?;
+        assert!(
+            html.contains("<a href=\"./javascript%3Aalert(1)\">"),
+            "{}",
+            html
+        );
+        assert!(
+            html.contains("<a href=\"./a%27%26b\">a&#39;&amp;b</a>"),
+            "{}",
+            html
+        );
+        assert!(!html.contains("href=\"javascript"), "{}", html);
+
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_tls_config() -> anyhow::Result<()> {
+        let config = load_tls_config(
+            Path::new("../crm/fixtures/cert.pem"),
+            Path::new("../crm/fixtures/key.pem"),
+        )?;
+        assert_eq!(config.alpn_protocols[0], b"h2");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_upload_and_delete() -> anyhow::Result<()> {
+        let dir = fixture_dir("upload");
+        let router = get_router(&serve_opts(&dir))?;
+
+        // 默认只读
+        let res = request(&router, Method::PUT, "/new.txt", &[], "new").await;
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        let res = request(&router, Method::DELETE, "/hello.txt", &[], Body::empty()).await;
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+
+        let router = get_router(&HttpServeOpts {
+            allow_upload: true,
+            allow_delete: true,
+            ..serve_opts(&dir)
+        })?;
+
+        let res = request(&router, Method::PUT, "/sub%20dir/new.txt", &[], "new").await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(std::fs::read_to_string(dir.join("sub dir/new.txt"))?, "new");
+        let res = request(&router, Method::PUT, "/hello.txt", &[], "replaced").await;
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+        assert_eq!(std::fs::read_to_string(dir.join("hello.txt"))?, "replaced");
+
+        let res = request(&router, Method::PUT, "/big.txt", &[], vec![0u8; 2048]).await;
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(!dir.join("big.txt").exists());
+        let res = request(&router, Method::PUT, "/../escape.txt", &[], "x").await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        let res = request(&router, Method::PUT, "/missing/a.txt", &[], "x").await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        let boundary = "X-BOUNDARY";
+        let form = format!(
+            "--{0}\r\nContent-Disposition: form-data; name=\"note\"\r\n\r\nignored\r\n\
+             --{0}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"../a.txt\"\r\n\
+             Content-Type: text/plain\r\n\r\nfrom form\r\n--{0}--\r\n",
+            boundary
+        );
+        let content_type = format!("multipart/form-data; boundary={}", boundary);
+        let res = request(
+            &router,
+            Method::POST,
+            "/sub%20dir/",
+            &[(header::CONTENT_TYPE, &content_type)],
+            form,
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+        let uploaded: serde_json::Value = serde_json::from_slice(&body(res).await)?;
+        assert_eq!(uploaded[0]["name"], "a.txt");
+        assert_eq!(uploaded[0]["size"], 9);
+        assert_eq!(
+            std::fs::read_to_string(dir.join("sub dir/a.txt"))?,
+            "from form"
+        );
+
+        // 其他站点的页面不能借用浏览器缓存的凭据上传或删除
+        let cross_site = [
+            (header::ORIGIN, "https://evil.example"),
+            (header::HOST, "localhost:8080"),
+        ];
+        let res = request(&router, Method::POST, "/sub%20dir/", &cross_site, "x").await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        let fetch_site = header::HeaderName::from_static("sec-fetch-site");
+        let res = request(
+            &router,
+            Method::DELETE,
+            "/hello.txt",
+            &[(fetch_site.clone(), "cross-site")],
+            Body::empty(),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        let res = request(
+            &router,
+            Method::PUT,
+            "/x.txt",
+            &[(header::ORIGIN, "null")],
+            "x",
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        assert!(dir.join("hello.txt").exists());
+
+        // 目录列表页面中的表单是同源请求
+        let same_origin = [
+            (header::CONTENT_TYPE, content_type.as_str()),
+            (header::ORIGIN, "http://localhost:8080"),
+            (header::HOST, "localhost:8080"),
+            (header::ACCEPT, "text/html"),
+        ];
+        let form = format!(
+            "--{0}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"b.txt\"\r\n\r\nb\r\n--{0}--\r\n",
+            boundary
+        );
+        let res = request(&router, Method::POST, "/sub%20dir/", &same_origin, form).await;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+        assert!(dir.join("sub dir/b.txt").exists());
+
+        let form = format!(
+            "--{0}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"javascript:alert(1)\"\r\n\r\nx\r\n--{0}--\r\n",
+            boundary
+        );
+        let res = request(
+            &router,
+            Method::POST,
+            "/sub%20dir/",
+            &[(header::CONTENT_TYPE, &content_type)],
+            form,
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let res = request(&router, Method::DELETE, "/sub%20dir", &[], Body::empty()).await;
+        assert_eq!(res.status(), StatusCode::CONFLICT);
+        let res = request(&router, Method::DELETE, "/hello.txt", &[], Body::empty()).await;
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+        assert!(!dir.join("hello.txt").exists());
+        let res = request(&router, Method::DELETE, "/hello.txt", &[], Body::empty()).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        let res = request(&router, Method::DELETE, "/", &[], Body::empty()).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_auth() -> anyhow::Result<()> {
+        let dir = fixture_dir("auth");
+        let router = get_router(&HttpServeOpts {
+            auth: Some(HttpAuth::Bearer("token123".to_string())),
+            ..serve_opts(&dir)
+        })?;
+
+        let res = get(&router, "/hello.txt", &[]).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            res.headers()[header::WWW_AUTHENTICATE],
+            "Bearer realm=\"rcli\""
+        );
+
+        let res = get(
+            &router,
+            "/hello.txt",
+            &[(header::AUTHORIZATION, "Bearer token123")],
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_metrics() -> anyhow::Result<()> {
+        let dir = fixture_dir("metrics");
+        let router = get_router(&HttpServeOpts {
+            metrics: true,
+            ..serve_opts(&dir)
+        })?;
+
+        get(&router, "/hello.txt", &[]).await;
+        get(&router, "/missing.txt", &[]).await;
+
+        let res = get(&router, "/metrics", &[]).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let text = String::from_utf8(body(res).await)?;
+        assert!(
+            text.contains(
+                "http_requests_total{method=\"GET\",route=\"fallback\",status=\"200\"} 1\n"
+            )
+        );
+        assert!(
+            text.contains(
+                "http_requests_total{method=\"GET\",route=\"fallback\",status=\"404\"} 1\n"
+            )
+        );
+
+        // 未开启时 /metrics 只是普通的文件路径
+        let router = get_router(&serve_opts(&dir))?;
+        let res = get(&router, "/metrics", &[]).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,560 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use chrono::Utc;
+use reqwest::Method;
+use serde_json::Value;
+
+use super::client::{HttpClient, RecordedResponse};
+use crate::{OutputMode, opts::http::HttpRunOpts};
+
+const METHODS: [&str; 7] = ["GET", "POST", "PUT", "DELETE", "PATCH", "HEAD", "OPTIONS"];
+
+// 变量最多嵌套的层数，防止循环引用
+const MAX_DEPTH: usize = 16;
+
+/// VS Code REST Client 格式的 .http 文件
+#[derive(Debug, Default, PartialEq)]
+struct HttpFile {
+    variables: HashMap<String, String>,
+    requests: Vec<RequestBlock>,
+}
+
+#[derive(Debug, PartialEq)]
+struct RequestBlock {
+    title: String,
+    name: Option<String>,
+    method: String,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: Vec<String>,
+}
+
+struct Runner {
+    client: HttpClient,
+    variables: HashMap<String, String>,
+    responses: HashMap<String, RecordedResponse>,
+    base_dir: PathBuf,
+}
+
+pub async fn process_http_run(opts: &HttpRunOpts, output: OutputMode) -> anyhow::Result<()> {
+    let content = std::fs::read_to_string(&opts.file)?;
+    let file = HttpFile::parse(&content)?;
+
+    let mut variables = file.variables.clone();
+    variables.extend(opts.vars.iter().cloned());
+    let mut runner = Runner {
+        client: HttpClient::new(&opts.client, output)?,
+        variables,
+        responses: HashMap::new(),
+        base_dir: Path::new(&opts.file)
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default(),
+    };
+
+    runner.run(&file, &opts.name).await
+}
+
+impl HttpFile {
+    fn parse(content: &str) -> anyhow::Result<Self> {
+        let mut file = HttpFile::default();
+
+        let mut title = String::new();
+        let mut lines = Vec::new();
+        for line in content.lines() {
+            if let Some(next_title) = line.strip_prefix("###") {
+                file.parse_block(&title, &lines)?;
+                title = next_title.trim().to_string();
+                lines.clear();
+            } else {
+                lines.push(line);
+            }
+        }
+        file.parse_block(&title, &lines)?;
+
+        Ok(file)
+    }
+
+    fn parse_block(&mut self, title: &str, lines: &[&str]) -> anyhow::Result<()> {
+        let mut name = None;
+        let mut request: Option<RequestBlock> = None;
+        let mut in_body = false;
+
+        for line in lines {
+            let trimmed = line.trim();
+            match &mut request {
+                None => {
+                    if trimmed.is_empty() {
+                        continue;
+                    }
+                    if let Some(comment) = strip_comment(trimmed) {
+                        if let Some(n) = comment.trim().strip_prefix("@name") {
+                            name = Some(n.trim().to_string());
+                        }
+                        continue;
+                    }
+                    if let Some(var) = trimmed.strip_prefix('@') {
+                        let (key, value) = var
+                            .split_once('=')
+                            .with_context(|| format!("invalid variable: {}", line))?;
+                        self.variables
+                            .insert(key.trim().to_string(), value.trim().to_string());
+                        continue;
+                    }
+                    request = Some(parse_request_line(trimmed, title, name.take()));
+                }
+                Some(req) if !in_body => {
+                    if trimmed.is_empty() {
+                        in_body = true;
+                    } else if strip_comment(trimmed).is_some() {
+                        continue;
+                    } else if trimmed.starts_with(['?', '&']) {
+                        // 查询参数可以分多行书写
+                        req.url.push_str(trimmed);
+                    } else {
+                        let (key, value) = trimmed
+                            .split_once(':')
+                            .with_context(|| format!("invalid header: {}", line))?;
+                        req.headers
+                            .push((key.trim().to_string(), value.trim().to_string()));
+                    }
+                }
+                Some(req) => req.body.push(line.to_string()),
+            }
+        }
+
+        if let Some(mut req) = request {
+            while req.body.last().is_some_and(|line| line.trim().is_empty()) {
+                req.body.pop();
+            }
+            self.requests.push(req);
+        }
+        Ok(())
+    }
+
+    /// 按执行顺序返回请求下标，被引用的命名请求排在引用它的请求之前
+    fn plan(&self, names: &[String]) -> anyhow::Result<Vec<usize>> {
+        let selected = if names.is_empty() {
+            (0..self.requests.len()).collect()
+        } else {
+            names
+                .iter()
+                .map(|name| {
+                    self.requests
+                        .iter()
+                        .position(|req| req.name.as_ref() == Some(name) || &req.title == name)
+                        .with_context(|| format!("request not found: {}", name))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?
+        };
+
+        let mut order = Vec::new();
+        let mut visiting = HashSet::new();
+        for idx in selected {
+            self.visit(idx, &mut order, &mut visiting)?;
+        }
+        Ok(order)
+    }
+
+    fn visit(
+        &self,
+        idx: usize,
+        order: &mut Vec<usize>,
+        visiting: &mut HashSet<usize>,
+    ) -> anyhow::Result<()> {
+        if order.contains(&idx) {
+            return Ok(());
+        }
+        if !visiting.insert(idx) {
+            anyhow::bail!("circular dependency at: {}", self.requests[idx].title);
+        }
+
+        for dep in self.dependencies(&self.requests[idx]) {
+            let dep_idx = self
+                .requests
+                .iter()
+                .position(|req| req.name.as_deref() == Some(dep.as_str()))
+                .with_context(|| format!("request not found: {}", dep))?;
+            self.visit(dep_idx, order, visiting)?;
+        }
+
+        visiting.remove(&idx);
+        order.push(idx);
+        Ok(())
+    }
+
+    fn dependencies(&self, req: &RequestBlock) -> Vec<String> {
+        let texts = [req.url.as_str()]
+            .into_iter()
+            .chain(req.headers.iter().map(|(_, value)| value.as_str()))
+            .chain(req.body.iter().map(String::as_str));
+
+        let mut deps = Vec::new();
+        let mut seen = HashSet::new();
+        for text in texts {
+            for name in placeholders(text) {
+                self.collect_dependencies(name, &mut deps, &mut seen);
+            }
+        }
+        deps
+    }
+
+    fn collect_dependencies<'a>(
+        &'a self,
+        name: &'a str,
+        deps: &mut Vec<String>,
+        seen: &mut HashSet<&'a str>,
+    ) {
+        if !seen.insert(name) {
+            return;
+        }
+        if let Some((target, _)) = name.split_once(".response.") {
+            if !deps.iter().any(|dep| dep == target) {
+                deps.push(target.to_string());
+            }
+        } else if let Some(value) = self.variables.get(name) {
+            for name in placeholders(value) {
+                self.collect_dependencies(name, deps, seen);
+            }
+        }
+    }
+}
+
+impl Runner {
+    async fn run(&mut self, file: &HttpFile, names: &[String]) -> anyhow::Result<()> {
+        for idx in file.plan(names)? {
+            let block = &file.requests[idx];
+            self.client.print_title(&block.title)?;
+
+            let req = self
+                .build_request(block)
+                .with_context(|| format!("failed to build request: {}", block.title))?;
+            let res = self.client.send(req).await?;
+            let res = RecordedResponse::read(res).await?;
+            self.client.print_response(&res)?;
+            if self.client.output() == OutputMode::Text {
+                println!();
+            }
+
+            if let Some(name) = &block.name {
+                self.responses.insert(name.clone(), res);
+            }
+        }
+        Ok(())
+    }
+
+    fn build_request(&self, block: &RequestBlock) -> anyhow::Result<reqwest::Request> {
+        let method = Method::from_bytes(block.method.as_bytes())?;
+        let mut builder = self
+            .client
+            .request(method, &self.substitute(&block.url, 0)?);
+
+        let mut multipart = false;
+        for (key, value) in &block.headers {
+            let value = self.substitute(value, 0)?;
+            if key.eq_ignore_ascii_case("content-type") && value.starts_with("multipart/") {
+                multipart = true;
+            }
+            builder = builder.header(key.as_str(), value);
+        }
+
+        if !block.body.is_empty() {
+            builder = builder.body(self.build_body(&block.body, multipart)?);
+        }
+        Ok(builder.build()?)
+    }
+
+    /// `< path` 行替换为文件内容，路径相对于 .http 文件所在目录
+    fn build_body(&self, lines: &[String], multipart: bool) -> anyhow::Result<Vec<u8>> {
+        let newline = if multipart { "\r\n" } else { "\n" };
+        let mut body = Vec::new();
+        for (i, line) in lines.iter().enumerate() {
+            if i > 0 {
+                body.extend_from_slice(newline.as_bytes());
+            }
+            match line.trim().strip_prefix("< ") {
+                Some(path) => {
+                    let path = self.base_dir.join(self.substitute(path.trim(), 0)?);
+                    let content = std::fs::read(&path)
+                        .with_context(|| format!("failed to read {}", path.display()))?;
+                    body.extend_from_slice(&content);
+                }
+                None => body.extend_from_slice(self.substitute(line, 0)?.as_bytes()),
+            }
+        }
+        Ok(body)
+    }
+
+    fn substitute(&self, text: &str, depth: usize) -> anyhow::Result<String> {
+        if depth > MAX_DEPTH {
+            anyhow::bail!("variables nested too deeply: {}", text);
+        }
+
+        let mut out = String::new();
+        let mut rest = text;
+        while let Some(start) = rest.find("{{") {
+            let end = rest[start..]
+                .find("}}")
+                .map(|end| start + end)
+                .with_context(|| format!("unclosed variable: {}", text))?;
+            out.push_str(&rest[..start]);
+            out.push_str(&self.resolve(rest[start + 2..end].trim(), depth)?);
+            rest = &rest[end + 2..];
+        }
+        out.push_str(rest);
+        Ok(out)
+    }
+
+    fn resolve(&self, name: &str, depth: usize) -> anyhow::Result<String> {
+        if let Some(value) = self.variables.get(name) {
+            return self.substitute(value, depth + 1);
+        }
+        if let Some((target, path)) = name.split_once(".response.") {
+            let res = self
+                .responses
+                .get(target)
+                .with_context(|| format!("request {} has not been sent", target))?;
+            return response_value(res, path);
+        }
+
+        match name {
+            "$timestamp" => Ok(Utc::now().timestamp().to_string()),
+            _ => anyhow::bail!("undefined variable: {}", name),
+        }
+    }
+}
+
+/// 支持 `headers.<name>`、`body`、`body.a.b.0` 和 JSONPath 风格的 `body.$.a.b`
+fn response_value(res: &RecordedResponse, path: &str) -> anyhow::Result<String> {
+    if let Some(header) = path.strip_prefix("headers.") {
+        let value = res
+            .headers
+            .get(header)
+            .with_context(|| format!("header not found: {}", header))?;
+        return Ok(value.to_str()?.to_string());
+    }
+
+    let path = match path {
+        "body" | "body.*" | "body.$" => return Ok(String::from_utf8_lossy(&res.body).to_string()),
+        path => path
+            .strip_prefix("body.")
+            .with_context(|| format!("unsupported response reference: {}", path))?,
+    };
+    let path = path.strip_prefix("$.").unwrap_or(path);
+
+    let body: Value = serde_json::from_slice(&res.body).context("response body is not JSON")?;
+    let mut value = &body;
+    for segment in path.split('.') {
+        value = match value {
+            Value::Object(map) => map.get(segment),
+            Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => None,
+        }
+        .with_context(|| format!("{} not found in response body", path))?;
+    }
+
+    Ok(match value {
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    })
+}
+
+fn parse_request_line(line: &str, title: &str, name: Option<String>) -> RequestBlock {
+    let line = line
+        .rsplit_once(" HTTP/")
+        .map(|(line, _)| line)
+        .unwrap_or(line);
+    let (method, url) = match line.split_once(char::is_whitespace) {
+        Some((method, url)) if METHODS.contains(&method.to_ascii_uppercase().as_str()) => {
+            (method.to_ascii_uppercase(), url.trim().to_string())
+        }
+        _ => ("GET".to_string(), line.to_string()),
+    };
+
+    let title = match (title, &name) {
+        ("", Some(name)) => name.clone(),
+        ("", None) => format!("{} {}", method, url),
+        (title, _) => title.to_string(),
+    };
+    RequestBlock {
+        title,
+        name,
+        method,
+        url,
+        headers: Vec::new(),
+        body: Vec::new(),
+    }
+}
+
+fn strip_comment(line: &str) -> Option<&str> {
+    line.strip_prefix('#').or_else(|| line.strip_prefix("//"))
+}
+
+fn placeholders(text: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        names.push(rest[start + 2..start + end].trim());
+        rest = &rest[start + end + 2..];
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use axum::{
+        Json, Router,
+        http::{HeaderMap, StatusCode, header},
+        response::IntoResponse,
+        routing::{get, post},
+    };
+    use serde_json::json;
+
+    use super::*;
+    use crate::opts::http::HttpClientOpts;
+
+    #[test]
+    fn test_parse_test_http() -> anyhow::Result<()> {
+        let file = HttpFile::parse(&std::fs::read_to_string("../test.http")?)?;
+
+        assert_eq!(file.requests[0].title, "test index");
+        assert_eq!(file.requests[0].url, "http://localhost:9090");
+        assert_eq!(
+            file.requests[0].headers,
+            [("Cache-Control".to_string(), "no-cache".to_string())]
+        );
+
+        let signin = &file.requests[2];
+        assert_eq!(signin.name.as_deref(), Some("signin"));
+        assert_eq!(signin.method, "POST");
+        assert_eq!(signin.body.first().map(String::as_str), Some("{"));
+        assert_eq!(signin.body.last().map(String::as_str), Some("}"));
+
+        assert_eq!(
+            file.variables["token"],
+            "{{signin.response.body.data.token}}"
+        );
+        let list = file
+            .requests
+            .iter()
+            .find(|req| req.title == "test get user list")
+            .unwrap();
+        assert_eq!(file.dependencies(list), ["signin"]);
+
+        // 只执行一个请求时，先执行它依赖的 signin
+        let order = file.plan(&["test get chat list".to_string()])?;
+        assert_eq!(order.len(), 2);
+        assert_eq!(file.requests[order[0]].title, "test sign in");
+        assert!(file.plan(&["missing".to_string()]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_circular_dependency() -> anyhow::Result<()> {
+        let file = HttpFile::parse(
+            "# @name a\nGET /a?x={{b.response.body.id}}\n\n### b\n# @name b\nGET /b?x={{a.response.body.id}}\n",
+        )?;
+        assert!(file.plan(&[]).is_err());
+        Ok(())
+    }
+
+    async fn sign_in() -> impl IntoResponse {
+        (
+            [
+                (header::SET_COOKIE, "sid=42; Path=/; HttpOnly"),
+                (header::HeaderName::from_static("x-request-id"), "req-1"),
+            ],
+            Json(json!({"data": {"token": "secret", "ids": [7, 8]}})),
+        )
+    }
+
+    async fn whoami(headers: HeaderMap, body: String) -> impl IntoResponse {
+        let header = |name: header::HeaderName| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or_default()
+                .to_string()
+        };
+        if header(header::AUTHORIZATION) != "Bearer secret" {
+            return StatusCode::UNAUTHORIZED.into_response();
+        }
+        Json(json!({
+            "cookie": header(header::COOKIE),
+            "request_id": header(header::HeaderName::from_static("x-request-id")),
+            "body": body,
+        }))
+        .into_response()
+    }
+
+    #[tokio::test]
+    async fn test_run_with_named_responses() -> anyhow::Result<()> {
+        let app = Router::new()
+            .route("/sign-in", post(sign_in))
+            .route("/whoami", get(whoami).post(whoami));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let addr: SocketAddr = listener.local_addr()?;
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let content = r#"
+@host = 127.0.0.1:{{port}}
+@token = {{signin.response.body.$.data.token}}
+
+### whoami
+POST http://{{host}}/whoami HTTP/1.1
+Authorization: Bearer {{ token }}
+x-request-id: {{signin.response.headers.x-request-id}}
+
+second id: {{signin.response.body.data.ids.1}}
+
+### sign in
+# @name signin
+POST http://{{host}}/sign-in
+"#;
+        let file = HttpFile::parse(content)?;
+        let mut variables = file.variables.clone();
+        variables.insert("port".to_string(), addr.port().to_string());
+
+        let session = std::env::temp_dir().join(format!("cli-runner-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&session);
+        let mut runner = Runner {
+            client: HttpClient::new(
+                &HttpClientOpts {
+                    session: Some(session.clone()),
+                    verbose: false,
+                    no_color: true,
+                },
+                OutputMode::Quiet,
+            )?,
+            variables,
+            responses: HashMap::new(),
+            base_dir: PathBuf::new(),
+        };
+        runner.run(&file, &["whoami".to_string()]).await?;
+
+        assert!(runner.responses.contains_key("signin"));
+        let req = runner.build_request(&file.requests[0])?;
+        assert_eq!(req.headers()["x-request-id"], "req-1");
+
+        let res = runner.client.send(req).await?;
+        let body: Value = res.json().await?;
+        assert_eq!(body["cookie"], "sid=42");
+        assert_eq!(body["body"], "second id: 8");
+
+        // cookie 已保存到会话文件中
+        let saved: Value = serde_json::from_str(&std::fs::read_to_string(&session)?)?;
+        assert_eq!(saved["cookies"][0]["name"], "sid");
+        assert_eq!(saved["cookies"][0]["value"], "42");
+        std::fs::remove_file(session)?;
+        Ok(())
+    }
+}
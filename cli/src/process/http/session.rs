@@ -0,0 +1,338 @@
+use std::{collections::BTreeMap, path::Path};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use reqwest::{
+    Url,
+    header::{self, HeaderMap, HeaderValue},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::utils;
+
+/// 在多次请求之间保存的 cookie 和 token，只发送给对应的站点
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    /// origin（如 `http://localhost:9090`）到 token 的映射
+    #[serde(default)]
+    pub tokens: BTreeMap<String, String>,
+    #[serde(default)]
+    pub cookies: Vec<Cookie>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    pub domain: String,
+    /// 没有 Domain 属性时只发送给设置它的主机，不包括子域名
+    #[serde(default)]
+    pub host_only: bool,
+    pub path: String,
+    #[serde(default)]
+    pub secure: bool,
+    /// 由 Max-Age 或 Expires 得出，没有时一直保留
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<DateTime<Utc>>,
+}
+
+impl Session {
+    /// 文件不存在时返回空会话
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| anyhow::anyhow!("invalid session file {}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// 会话中有 token，只允许当前用户读写；先写临时文件再重命名，中途失败不会留下半个文件
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string_pretty(self)?;
+        utils::write_atomic(path, content.as_bytes(), Some(0o600))
+    }
+
+    /// 保存 token，之后只发送给同一 origin 的请求
+    pub fn set_token(&mut self, url: &Url, token: &str) {
+        self.tokens.insert(origin(url), token.to_string());
+    }
+
+    /// 请求中已有的 Authorization 和 Cookie 优先
+    pub fn apply(&self, url: &Url, headers: &mut HeaderMap) -> anyhow::Result<()> {
+        if let Some(token) = self.tokens.get(&origin(url))
+            && !headers.contains_key(header::AUTHORIZATION)
+        {
+            let value = HeaderValue::from_str(&format!("Bearer {}", token))?;
+            headers.insert(header::AUTHORIZATION, value);
+        }
+
+        let now = Utc::now();
+        let mut cookies = self
+            .cookies
+            .iter()
+            .filter(|cookie| !cookie.is_expired(now) && cookie.matches(url))
+            .collect::<Vec<_>>();
+        if !cookies.is_empty() && !headers.contains_key(header::COOKIE) {
+            // 路径更长的 cookie 排在前面
+            cookies.sort_by_key(|cookie| std::cmp::Reverse(cookie.path.len()));
+            let cookies = cookies
+                .iter()
+                .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+                .collect::<Vec<_>>()
+                .join("; ");
+            headers.insert(header::COOKIE, HeaderValue::from_str(&cookies)?);
+        }
+        Ok(())
+    }
+
+    /// 记录响应中的 Set-Cookie，`url` 为实际返回这个响应的地址；过期或值为空的 cookie 会被删除，
+    /// Domain 与请求的主机不匹配的 cookie 会被忽略
+    pub fn update(&mut self, url: &Url, headers: &HeaderMap) {
+        let now = Utc::now();
+        for value in headers.get_all(header::SET_COOKIE) {
+            let Ok(value) = value.to_str() else {
+                continue;
+            };
+            let Some(cookie) = Cookie::parse(url, value, now) else {
+                continue;
+            };
+
+            self.cookies.retain(|c| {
+                (&c.name, &c.domain, &c.path) != (&cookie.name, &cookie.domain, &cookie.path)
+            });
+            if !cookie.is_expired(now) && !cookie.value.is_empty() {
+                self.cookies.push(cookie);
+            }
+        }
+        self.cookies.retain(|c| !c.is_expired(now));
+    }
+}
+
+impl Cookie {
+    /// 解析 Set-Cookie，Max-Age 优先于 Expires
+    fn parse(url: &Url, value: &str, now: DateTime<Utc>) -> Option<Self> {
+        let host = url.host_str()?.to_ascii_lowercase();
+        let mut attrs = value.split(';');
+        let (name, value) = attrs.next()?.split_once('=')?;
+        let mut cookie = Cookie {
+            name: name.trim().to_string(),
+            value: value.trim().to_string(),
+            domain: host.clone(),
+            host_only: true,
+            path: default_path(url),
+            secure: false,
+            expires: None,
+        };
+        if cookie.name.is_empty() {
+            return None;
+        }
+
+        let (mut max_age, mut expires) = (None, None);
+        for attr in attrs {
+            let (key, value) = attr.split_once('=').unwrap_or((attr, ""));
+            let (key, value) = (key.trim().to_ascii_lowercase(), value.trim());
+            match key.as_str() {
+                "max-age" => {
+                    max_age = value
+                        .parse::<i64>()
+                        .ok()
+                        .map(|secs| now + TimeDelta::seconds(secs.clamp(0, i32::MAX.into())))
+                }
+                "expires" => {
+                    expires = DateTime::parse_from_rfc2822(value)
+                        .ok()
+                        .map(|t| t.with_timezone(&Utc))
+                }
+                "domain" if !value.is_empty() => {
+                    let domain = value.trim_start_matches('.').to_ascii_lowercase();
+                    if !domain_match(&host, &domain) {
+                        return None;
+                    }
+                    cookie.domain = domain;
+                    cookie.host_only = false;
+                }
+                "path" if value.starts_with('/') => cookie.path = value.to_string(),
+                "secure" => cookie.secure = true,
+                _ => {}
+            }
+        }
+        cookie.expires = max_age.or(expires);
+        Some(cookie)
+    }
+
+    fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires.is_some_and(|expires| expires <= now)
+    }
+
+    fn matches(&self, url: &Url) -> bool {
+        let Some(host) = url.host_str().map(|h| h.to_ascii_lowercase()) else {
+            return false;
+        };
+        let domain = if self.host_only {
+            host == self.domain
+        } else {
+            domain_match(&host, &self.domain)
+        };
+        domain && path_match(url.path(), &self.path) && (!self.secure || url.scheme() == "https")
+    }
+}
+
+fn origin(url: &Url) -> String {
+    url.origin().ascii_serialization()
+}
+
+fn domain_match(host: &str, domain: &str) -> bool {
+    host == domain
+        || host
+            .strip_suffix(domain)
+            .is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+fn path_match(path: &str, cookie_path: &str) -> bool {
+    match path.strip_prefix(cookie_path) {
+        Some(rest) => cookie_path.ends_with('/') || rest.is_empty() || rest.starts_with('/'),
+        None => false,
+    }
+}
+
+// 没有 Path 属性时，取请求路径最后一个 `/` 之前的部分
+fn default_path(url: &Url) -> String {
+    match url.path().rfind('/') {
+        Some(0) | None => "/".to_string(),
+        Some(i) => url.path()[..i].to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(url: &str) -> Url {
+        Url::parse(url).unwrap()
+    }
+
+    fn apply(session: &Session, to: &str) -> anyhow::Result<HeaderMap> {
+        let mut headers = HeaderMap::new();
+        session.apply(&url(to), &mut headers)?;
+        Ok(headers)
+    }
+
+    #[test]
+    fn test_session_cookies_and_token() -> anyhow::Result<()> {
+        let mut session = Session::default();
+        let api = url("http://localhost:9090/api/sign-in");
+        session.set_token(&api, "abc");
+
+        let mut res = HeaderMap::new();
+        res.append(
+            header::SET_COOKIE,
+            HeaderValue::from_static("id=1; Path=/; HttpOnly"),
+        );
+        res.append(header::SET_COOKIE, HeaderValue::from_static("theme=dark"));
+        session.update(&api, &res);
+
+        let headers = apply(&session, "http://localhost:9090/api/chats")?;
+        assert_eq!(headers[header::AUTHORIZATION], "Bearer abc");
+        assert_eq!(headers[header::COOKIE], "theme=dark; id=1");
+
+        // 显式指定的请求头不会被覆盖
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, HeaderValue::from_static("Basic x"));
+        session.apply(&api, &mut headers)?;
+        assert_eq!(headers[header::AUTHORIZATION], "Basic x");
+
+        let mut res = HeaderMap::new();
+        res.append(
+            header::SET_COOKIE,
+            HeaderValue::from_static("id=; Path=/; Max-Age=0"),
+        );
+        session.update(&api, &res);
+        let names = session.cookies.iter().map(|c| &c.name).collect::<Vec<_>>();
+        assert_eq!(names, ["theme"]);
+
+        // Expires 已经过去的 cookie 同样删除，未过期的到期后不再发送
+        let mut res = HeaderMap::new();
+        res.append(
+            header::SET_COOKIE,
+            HeaderValue::from_static("theme=dark; Expires=Thu, 01 Jan 1970 00:00:00 GMT"),
+        );
+        res.append(
+            header::SET_COOKIE,
+            HeaderValue::from_static("lang=en; Path=/; Expires=Fri, 01 Jan 2100 00:00:00 GMT"),
+        );
+        res.append(
+            header::SET_COOKIE,
+            HeaderValue::from_static(
+                "seen=1; Path=/; Max-Age=3600; Expires=Thu, 01 Jan 1970 00:00:00 GMT",
+            ),
+        );
+        session.update(&api, &res);
+        let names = session.cookies.iter().map(|c| &c.name).collect::<Vec<_>>();
+        assert_eq!(names, ["lang", "seen"]);
+        session.cookies[1].expires = Some(Utc::now() - TimeDelta::seconds(1));
+        assert_eq!(
+            apply(&session, "http://localhost:9090/")?[header::COOKIE],
+            "lang=en"
+        );
+        session.cookies.truncate(1);
+
+        let path = std::env::temp_dir().join(format!("cli-session-{}.json", std::process::id()));
+        session.save(&path)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path)?.permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        assert_eq!(Session::load(&path)?, session);
+        std::fs::remove_file(&path)?;
+        assert_eq!(Session::load(&path)?, Session::default());
+        Ok(())
+    }
+
+    #[test]
+    fn test_session_scoped_to_origin() -> anyhow::Result<()> {
+        let mut session = Session::default();
+        let login = url("http://app.acme.org/auth/login");
+        session.set_token(&login, "abc");
+
+        let mut res = HeaderMap::new();
+        for cookie in [
+            "sid=1; Path=/",
+            "shared=2; Domain=.acme.org; Path=/",
+            "admin=3; Path=/admin",
+            "pref=4",
+            "secret=5; Path=/; Secure",
+            "evil=6; Domain=example.com",
+        ] {
+            res.append(header::SET_COOKIE, HeaderValue::from_str(cookie)?);
+        }
+        session.update(&login, &res);
+        assert_eq!(session.cookies.len(), 5);
+
+        let headers = apply(&session, "http://app.acme.org/auth/me")?;
+        assert_eq!(headers[header::COOKIE], "pref=4; sid=1; shared=2");
+        let headers = apply(&session, "http://app.acme.org/admin/users")?;
+        assert_eq!(headers[header::COOKIE], "admin=3; sid=1; shared=2");
+        assert!(
+            !apply(&session, "http://app.acme.org/administrator")?[header::COOKIE]
+                .to_str()?
+                .contains("admin")
+        );
+        let headers = apply(&session, "https://app.acme.org/")?;
+        assert_eq!(headers[header::COOKIE], "sid=1; shared=2; secret=5");
+
+        // 子域名只能拿到设置了 Domain 的 cookie，token 不会发送给其他 origin
+        let headers = apply(&session, "http://api.acme.org/")?;
+        assert_eq!(headers[header::COOKIE], "shared=2");
+        assert!(!headers.contains_key(header::AUTHORIZATION));
+        let headers = apply(&session, "http://app.acme.org:8080/")?;
+        assert!(!headers.contains_key(header::AUTHORIZATION));
+        assert!(apply(&session, "http://example.com/")?.is_empty());
+        assert!(apply(&session, "http://notacme.org/")?.is_empty());
+        Ok(())
+    }
+}
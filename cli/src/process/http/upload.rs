@@ -0,0 +1,262 @@
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use axum::{
+    Json,
+    body::Bytes,
+    extract::{FromRequest, Multipart, Request},
+    http::{StatusCode, header},
+    response::{IntoResponse, Redirect, Response},
+};
+use futures::{Stream, StreamExt};
+use serde::Serialize;
+use tokio::{fs, io::AsyncWriteExt};
+use tracing::warn;
+
+use super::{HttpServeState, path_segments, resolve_segments};
+use crate::utils::TempFile;
+
+#[derive(Debug, Serialize)]
+struct UploadedFile {
+    name: String,
+    size: u64,
+}
+
+/// `PUT /dir/name` 把请求体写入 `name`，已存在的文件会被替换
+pub async fn put_file(state: Arc<HttpServeState>, req: Request) -> Response {
+    let target = match resolve_target(&state.dir, req.uri().path()) {
+        Ok(target) => target,
+        Err(status) => return status.into_response(),
+    };
+    if target.is_dir() {
+        return StatusCode::CONFLICT.into_response();
+    }
+
+    let existed = target.exists();
+    let body = req
+        .into_body()
+        .into_data_stream()
+        .map(|chunk| chunk.map_err(|_| StatusCode::BAD_REQUEST));
+    match write_atomic(&target, body, state.max_upload_size).await {
+        Ok(_) if existed => StatusCode::NO_CONTENT.into_response(),
+        Ok(_) => StatusCode::CREATED.into_response(),
+        Err(status) => status.into_response(),
+    }
+}
+
+/// `POST /dir/` 把 multipart 表单中的每个文件保存到该目录
+pub async fn post_multipart(state: Arc<HttpServeState>, req: Request) -> Response {
+    let dir = match path_segments(req.uri().path())
+        .and_then(|segments| resolve_segments(&state.dir, &segments))
+    {
+        Ok(dir) if dir.is_dir() => dir,
+        Ok(_) => return StatusCode::CONFLICT.into_response(),
+        Err(status) => return status.into_response(),
+    };
+
+    // 浏览器表单上传后跳转回目录列表，其他客户端返回 JSON
+    let from_browser = req
+        .headers()
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("text/html"));
+    let location = req.uri().path().to_string();
+
+    let mut multipart = match Multipart::from_request(req, &()).await {
+        Ok(multipart) => multipart,
+        Err(rejection) => return rejection.into_response(),
+    };
+
+    let mut uploaded = Vec::new();
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(e) => return e.into_response(),
+        };
+        // 忽略没有文件名的普通表单字段
+        let Some(file_name) = field.file_name() else {
+            continue;
+        };
+        let Some(name) = sanitize_file_name(file_name) else {
+            return StatusCode::BAD_REQUEST.into_response();
+        };
+
+        let target = dir.join(&name);
+        if target.is_dir() {
+            return StatusCode::CONFLICT.into_response();
+        }
+        let body = field.map(|chunk| chunk.map_err(|e| e.status()));
+        match write_atomic(&target, body, state.max_upload_size).await {
+            Ok(size) => uploaded.push(UploadedFile { name, size }),
+            Err(status) => return status.into_response(),
+        }
+    }
+
+    if from_browser {
+        Redirect::to(&location).into_response()
+    } else {
+        (StatusCode::CREATED, Json(uploaded)).into_response()
+    }
+}
+
+/// 删除文件、符号链接或空目录，不会跟随符号链接
+pub async fn delete_path(state: Arc<HttpServeState>, req: Request) -> Response {
+    let target = match resolve_target(&state.dir, req.uri().path()) {
+        Ok(target) => target,
+        Err(status) => return status.into_response(),
+    };
+
+    let result = match fs::symlink_metadata(&target).await {
+        Ok(meta) if meta.is_dir() => fs::remove_dir(&target).await,
+        Ok(_) => fs::remove_file(&target).await,
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) if e.kind() == ErrorKind::NotFound => StatusCode::NOT_FOUND.into_response(),
+        Err(e) if e.kind() == ErrorKind::DirectoryNotEmpty => StatusCode::CONFLICT.into_response(),
+        Err(e) => {
+            warn!("failed to delete {:?}: {}", target, e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// 浏览器会缓存 Basic 认证，其他站点的页面可以借此提交表单；写入和删除只接受同源的浏览器请求。
+/// 优先看 Sec-Fetch-Site，旧浏览器看 Origin，两者都没有时不是浏览器发起的跨站请求
+pub fn is_cross_site(req: &Request) -> bool {
+    let headers = req.headers();
+    if let Some(site) = headers.get("sec-fetch-site") {
+        return !matches!(site.as_bytes(), b"same-origin" | b"none");
+    }
+    let Some(origin) = headers.get(header::ORIGIN) else {
+        return false;
+    };
+    // HTTP/2 没有 Host 头，使用 :authority
+    let host = headers
+        .get(header::HOST)
+        .and_then(|h| h.to_str().ok())
+        .or_else(|| req.uri().authority().map(|a| a.as_str()));
+    let origin_host = origin
+        .to_str()
+        .ok()
+        .and_then(|origin| origin.split_once("://"))
+        .map(|(_, host)| host);
+    origin_host.is_none() || origin_host != host
+}
+
+/// 解析写入/删除的目标：父目录必须已存在且位于根目录下，最后一段不解析符号链接
+fn resolve_target(root: &Path, uri_path: &str) -> Result<PathBuf, StatusCode> {
+    let mut segments = path_segments(uri_path)?;
+    // 不能替换或删除根目录本身
+    let name = segments.pop().ok_or(StatusCode::FORBIDDEN)?;
+    if uri_path.ends_with('/') {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let parent = resolve_segments(root, &segments)?;
+    if !parent.is_dir() {
+        return Err(StatusCode::CONFLICT);
+    }
+    Ok(parent.join(name))
+}
+
+fn sanitize_file_name(name: &str) -> Option<String> {
+    // 部分浏览器会带上客户端的完整路径
+    let name = name.rsplit(['/', '\\']).next()?;
+    match name {
+        "" | "." | ".." => None,
+        // `:` 在 Windows 上不合法，也避免文件名在链接中被当作协议
+        name if name.contains(|c: char| c.is_control() || c == ':') => None,
+        name => Some(name.to_string()),
+    }
+}
+
+/// 先写入同目录下的临时文件，完成后再重命名，读者不会看到写了一半的文件；
+/// 替换已有文件时沿用它的权限
+async fn write_atomic(
+    target: &Path,
+    body: impl Stream<Item = Result<Bytes, StatusCode>>,
+    limit: u64,
+) -> Result<u64, StatusCode> {
+    let (temp, file) = TempFile::create(target, None).map_err(|e| io_error(target, e))?;
+    let size = write_stream(fs::File::from_std(file), target, body, limit).await?;
+    temp.persist().map_err(|e| io_error(target, e))?;
+    Ok(size)
+}
+
+async fn write_stream(
+    mut file: fs::File,
+    path: &Path,
+    body: impl Stream<Item = Result<Bytes, StatusCode>>,
+    limit: u64,
+) -> Result<u64, StatusCode> {
+    let mut body = std::pin::pin!(body);
+    let mut size = 0u64;
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk?;
+        size += chunk.len() as u64;
+        if size > limit {
+            return Err(StatusCode::PAYLOAD_TOO_LARGE);
+        }
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| io_error(path, e))?;
+    }
+
+    file.sync_all().await.map_err(|e| io_error(path, e))?;
+    Ok(size)
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> StatusCode {
+    warn!("failed to write {:?}: {}", path, e);
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_file_name() {
+        assert_eq!(sanitize_file_name("a.txt").as_deref(), Some("a.txt"));
+        assert_eq!(
+            sanitize_file_name("C:\\Users\\me\\a.txt").as_deref(),
+            Some("a.txt")
+        );
+        assert_eq!(sanitize_file_name("../../a.txt").as_deref(), Some("a.txt"));
+        assert_eq!(sanitize_file_name("dir/.."), None);
+        assert_eq!(sanitize_file_name(""), None);
+    }
+
+    #[tokio::test]
+    async fn test_write_atomic_respects_limit() -> anyhow::Result<()> {
+        let dir = std::env::temp_dir().join(format!("cli-upload-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let target = dir.join("a.txt");
+        std::fs::write(&target, "old")?;
+
+        let chunks = || {
+            futures::stream::iter(vec![
+                Ok(Bytes::from_static(b"hello ")),
+                Ok(Bytes::from_static(b"world")),
+            ])
+        };
+        assert_eq!(
+            write_atomic(&target, chunks(), 5).await,
+            Err(StatusCode::PAYLOAD_TOO_LARGE)
+        );
+        // 失败时原文件保持不变，临时文件被清理
+        assert_eq!(std::fs::read_to_string(&target)?, "old");
+        assert_eq!(std::fs::read_dir(&dir)?.count(), 1);
+
+        assert_eq!(write_atomic(&target, chunks(), 11).await, Ok(11));
+        assert_eq!(std::fs::read_to_string(&target)?, "hello world");
+
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
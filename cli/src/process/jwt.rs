@@ -0,0 +1,220 @@
+use std::io::Read;
+
+use base64::prelude::*;
+use jwt_simple::prelude::*;
+use serde_json::{Map, Value, json};
+
+use crate::{
+    opts::jwt::{JwtAlgorithm, JwtSignOpts, JwtVerifyOpts},
+    utils,
+};
+
+type CustomClaims = Map<String, Value>;
+
+enum SigningKey {
+    EdDSA(Ed25519KeyPair),
+    HS256(HS256Key),
+    ES256(ES256KeyPair),
+}
+
+enum VerifyingKey {
+    EdDSA(Ed25519PublicKey),
+    HS256(HS256Key),
+    ES256(ES256PublicKey),
+}
+
+pub async fn process_sign(opts: &JwtSignOpts) -> anyhow::Result<String> {
+    let key = SigningKey::load(&opts.key, opts.alg)?;
+
+    let custom = opts.claims.clone().unwrap_or_default();
+    let mut claims = Claims::with_custom_claims(custom, Duration::from_secs(opts.exp));
+    if let Some(sub) = &opts.sub {
+        claims = claims.with_subject(sub);
+    }
+    if let Some(iss) = &opts.iss {
+        claims = claims.with_issuer(iss);
+    }
+    match opts.aud.as_slice() {
+        [] => {}
+        [aud] => claims = claims.with_audience(aud),
+        auds => claims = claims.with_audiences(auds.iter().cloned().collect::<HashSet<_>>()),
+    }
+
+    key.sign(claims)
+}
+
+pub async fn process_verify(opts: &JwtVerifyOpts) -> anyhow::Result<JWTClaims<CustomClaims>> {
+    let key = VerifyingKey::load(&opts.key, opts.alg)?;
+    let token = read_token(&opts.token).await?;
+
+    let options = VerificationOptions {
+        allowed_issuers: (!opts.iss.is_empty()).then(|| opts.iss.iter().cloned().collect()),
+        allowed_audiences: (!opts.aud.is_empty()).then(|| opts.aud.iter().cloned().collect()),
+        ..Default::default()
+    };
+
+    key.verify(&token, options)
+}
+
+/// 只解码 header 和 claims，不校验签名
+pub async fn process_decode(token: &str) -> anyhow::Result<Value> {
+    let token = read_token(token).await?;
+    let parts = token.split('.').collect::<Vec<_>>();
+    let [header, claims, _signature] = parts.as_slice() else {
+        anyhow::bail!("invalid token: expected 3 parts, got {}", parts.len());
+    };
+
+    let decode = |part: &str| -> anyhow::Result<Value> {
+        let bytes = BASE64_URL_SAFE_NO_PAD.decode(part.trim_end_matches('='))?;
+        Ok(serde_json::from_slice(&bytes)?)
+    };
+
+    Ok(json!({
+        "header": decode(header)?,
+        "claims": decode(claims)?,
+    }))
+}
+
+async fn read_token(token: &str) -> anyhow::Result<String> {
+    if token != "-" {
+        return Ok(token.trim().to_string());
+    }
+
+    let mut reader = utils::get_reader(token).await?;
+    let mut buf = String::new();
+    reader.read_to_string(&mut buf)?;
+    Ok(buf.trim().to_string())
+}
+
+impl SigningKey {
+    fn load(path: &str, alg: JwtAlgorithm) -> anyhow::Result<Self> {
+        let key = match alg {
+            JwtAlgorithm::EdDSA => SigningKey::EdDSA(Ed25519KeyPair::from_pem(
+                std::fs::read_to_string(path)?.trim(),
+            )?),
+            JwtAlgorithm::HS256 => SigningKey::HS256(HS256Key::from_bytes(&std::fs::read(path)?)),
+            JwtAlgorithm::ES256 => SigningKey::ES256(ES256KeyPair::from_pem(
+                std::fs::read_to_string(path)?.trim(),
+            )?),
+        };
+        Ok(key)
+    }
+
+    fn sign(&self, claims: JWTClaims<CustomClaims>) -> anyhow::Result<String> {
+        let token = match self {
+            SigningKey::EdDSA(key) => key.sign(claims)?,
+            SigningKey::HS256(key) => key.authenticate(claims)?,
+            SigningKey::ES256(key) => key.sign(claims)?,
+        };
+        Ok(token)
+    }
+}
+
+impl VerifyingKey {
+    fn load(path: &str, alg: JwtAlgorithm) -> anyhow::Result<Self> {
+        let key = match alg {
+            JwtAlgorithm::EdDSA => VerifyingKey::EdDSA(Ed25519PublicKey::from_pem(
+                std::fs::read_to_string(path)?.trim(),
+            )?),
+            JwtAlgorithm::HS256 => VerifyingKey::HS256(HS256Key::from_bytes(&std::fs::read(path)?)),
+            JwtAlgorithm::ES256 => VerifyingKey::ES256(ES256PublicKey::from_pem(
+                std::fs::read_to_string(path)?.trim(),
+            )?),
+        };
+        Ok(key)
+    }
+
+    fn verify(
+        &self,
+        token: &str,
+        options: VerificationOptions,
+    ) -> anyhow::Result<JWTClaims<CustomClaims>> {
+        let claims = match self {
+            VerifyingKey::EdDSA(key) => key.verify_token(token, Some(options))?,
+            VerifyingKey::HS256(key) => key.verify_token(token, Some(options))?,
+            VerifyingKey::ES256(key) => key.verify_token(token, Some(options))?,
+        };
+        Ok(claims)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENC_PEM: &str = "../chat/chat_core/fixtures/enc.pem";
+    const DEC_PEM: &str = "../chat/chat_core/fixtures/dec.pem";
+
+    fn sign_opts(key: &str, alg: JwtAlgorithm) -> JwtSignOpts {
+        JwtSignOpts {
+            key: key.to_string(),
+            alg,
+            sub: Some("1".to_string()),
+            aud: vec!["chat_web".to_string()],
+            iss: Some("chat_server".to_string()),
+            exp: 3600,
+            claims: Some(
+                json!({"id": 1, "username": "Noah"})
+                    .as_object()
+                    .unwrap()
+                    .clone(),
+            ),
+        }
+    }
+
+    fn verify_opts(key: &str, alg: JwtAlgorithm, token: String, aud: &str) -> JwtVerifyOpts {
+        JwtVerifyOpts {
+            key: key.to_string(),
+            alg,
+            token,
+            aud: vec![aud.to_string()],
+            iss: vec!["chat_server".to_string()],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_eddsa_sign_verify() -> anyhow::Result<()> {
+        let token = process_sign(&sign_opts(ENC_PEM, JwtAlgorithm::EdDSA)).await?;
+
+        let opts = verify_opts(DEC_PEM, JwtAlgorithm::EdDSA, token.clone(), "chat_web");
+        let claims = process_verify(&opts).await?;
+        assert_eq!(claims.subject.as_deref(), Some("1"));
+        assert_eq!(claims.custom["username"], "Noah");
+
+        let opts = verify_opts(DEC_PEM, JwtAlgorithm::EdDSA, token, "other");
+        assert!(process_verify(&opts).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_hs256_sign_verify() -> anyhow::Result<()> {
+        let key = std::env::temp_dir()
+            .join(format!("cli-jwt-{}.key", std::process::id()))
+            .to_string_lossy()
+            .to_string();
+        std::fs::write(&key, b"0123456789abcdef0123456789abcdef")?;
+
+        let token = process_sign(&sign_opts(&key, JwtAlgorithm::HS256)).await?;
+        let opts = verify_opts(&key, JwtAlgorithm::HS256, token.clone(), "chat_web");
+        assert_eq!(process_verify(&opts).await?.custom["id"], 1);
+
+        // 算法不匹配
+        let opts = verify_opts(DEC_PEM, JwtAlgorithm::EdDSA, token, "chat_web");
+        assert!(process_verify(&opts).await.is_err());
+
+        std::fs::remove_file(key)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_decode() -> anyhow::Result<()> {
+        let token = process_sign(&sign_opts(ENC_PEM, JwtAlgorithm::EdDSA)).await?;
+        let decoded = process_decode(&token).await?;
+        assert_eq!(decoded["header"]["alg"], "EdDSA");
+        assert_eq!(decoded["claims"]["iss"], "chat_server");
+        assert_eq!(decoded["claims"]["id"], 1);
+
+        assert!(process_decode("not-a-token").await.is_err());
+        Ok(())
+    }
+}
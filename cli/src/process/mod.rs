@@ -1,5 +1,8 @@
-pub mod base64;
+pub mod codec;
 pub mod csv;
+pub mod data;
 pub mod genpass;
 pub mod http;
+pub mod jwt;
+pub mod otp;
 pub mod text;
@@ -0,0 +1,190 @@
+use hmac::{Hmac, Mac, digest::KeyInit};
+use percent_encoding::{NON_ALPHANUMERIC, utf8_percent_encode};
+use qrcode::{QrCode, render::unicode::Dense1x2};
+
+use crate::{
+    opts::{
+        codec::{Base64Format, Codec},
+        otp::{OtpAlgorithm, OtpOpts},
+    },
+    process::codec,
+    utils::constant_time_eq,
+};
+
+/// RFC 4226：HMAC 结果动态截断后取后 `digits` 位
+pub fn hotp(secret: &[u8], counter: u64, digits: u32, algorithm: OtpAlgorithm) -> String {
+    let counter = counter.to_be_bytes();
+    let hash = match algorithm {
+        OtpAlgorithm::Sha1 => hmac::<Hmac<sha1::Sha1>>(secret, &counter),
+        OtpAlgorithm::Sha256 => hmac::<Hmac<sha2::Sha256>>(secret, &counter),
+        OtpAlgorithm::Sha512 => hmac::<Hmac<sha2::Sha512>>(secret, &counter),
+    };
+
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let bytes = [
+        hash[offset],
+        hash[offset + 1],
+        hash[offset + 2],
+        hash[offset + 3],
+    ];
+    let code = (u32::from_be_bytes(bytes) & 0x7fff_ffff) as u64 % 10u64.pow(digits);
+    format!("{:0width$}", code, width = digits as usize)
+}
+
+/// RFC 6238：以 `time / period` 作为 HOTP 的计数器
+pub fn totp(secret: &[u8], time: u64, period: u64, digits: u32, algorithm: OtpAlgorithm) -> String {
+    hotp(secret, time / period, digits, algorithm)
+}
+
+/// 在 `counter..=counter + window` 中查找，返回匹配的计数器，调用方应把下次的计数器设为它加 1
+pub fn verify_hotp(opts: &OtpOpts, code: &str, counter: u64, window: u64) -> Option<u64> {
+    (counter..=counter.saturating_add(window)).find(|c| {
+        let expected = hotp(&opts.secret.0, *c, opts.digits, opts.algorithm);
+        constant_time_eq(expected.as_bytes(), code.trim().as_bytes())
+    })
+}
+
+/// 允许前后 `window` 个时间步的时钟偏差，返回匹配的偏移
+pub fn verify_totp(opts: &OtpOpts, code: &str, time: u64, window: u64) -> Option<i64> {
+    let step = (time / opts.period) as i64;
+    let window = window.min(i64::MAX as u64) as i64;
+    (-window..=window)
+        .filter(|offset| step + offset >= 0)
+        .find(|offset| {
+            let counter = (step + offset) as u64;
+            let expected = hotp(&opts.secret.0, counter, opts.digits, opts.algorithm);
+            constant_time_eq(expected.as_bytes(), code.trim().as_bytes())
+        })
+}
+
+/// Key Uri Format：`otpauth://totp/Issuer:account?secret=...&issuer=Issuer`
+pub fn otpauth_uri(opts: &OtpOpts, account: &str, issuer: Option<&str>) -> String {
+    let encode = |s: &str| utf8_percent_encode(s, NON_ALPHANUMERIC).to_string();
+    let secret = codec::encode(&opts.secret.0, Codec::Base32, Base64Format::Standard);
+
+    let (kind, label) = match (opts.counter, issuer) {
+        (Some(_), Some(issuer)) => ("hotp", format!("{}:{}", encode(issuer), encode(account))),
+        (None, Some(issuer)) => ("totp", format!("{}:{}", encode(issuer), encode(account))),
+        (Some(_), None) => ("hotp", encode(account)),
+        (None, None) => ("totp", encode(account)),
+    };
+    let mut params = vec![
+        format!("secret={}", secret.trim_end_matches('=')),
+        format!(
+            "algorithm={}",
+            format!("{:?}", opts.algorithm).to_uppercase()
+        ),
+        format!("digits={}", opts.digits),
+    ];
+    match opts.counter {
+        Some(counter) => params.push(format!("counter={}", counter)),
+        None => params.push(format!("period={}", opts.period)),
+    }
+    if let Some(issuer) = issuer {
+        params.push(format!("issuer={}", encode(issuer)));
+    }
+    format!("otpauth://{}/{}?{}", kind, label, params.join("&"))
+}
+
+/// 每个字符表示上下两个模块，使用浅色模块作为前景以适应深色背景的终端
+pub fn render_qr(data: &str) -> anyhow::Result<String> {
+    let code = QrCode::new(data.as_bytes())?;
+    Ok(code
+        .render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .build())
+}
+
+fn hmac<M: Mac + KeyInit>(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = <M as Mac>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::opts::otp::OtpSecret;
+
+    const SHA1_SEED: &[u8] = b"12345678901234567890";
+    const SHA256_SEED: &[u8] = b"12345678901234567890123456789012";
+    const SHA512_SEED: &[u8] = b"1234567890123456789012345678901234567890123456789012345678901234";
+
+    fn otp_opts(secret: &[u8], counter: Option<u64>) -> OtpOpts {
+        OtpOpts {
+            secret: OtpSecret(secret.to_vec()),
+            algorithm: OtpAlgorithm::Sha1,
+            digits: 6,
+            period: 30,
+            counter,
+            time: None,
+        }
+    }
+
+    #[test]
+    fn test_hotp_rfc4226() {
+        let expected = [
+            "755224", "287082", "359152", "969429", "338314", "254676", "287922", "162583",
+            "399871", "520489",
+        ];
+        for (counter, code) in expected.iter().enumerate() {
+            assert_eq!(
+                hotp(SHA1_SEED, counter as u64, 6, OtpAlgorithm::Sha1),
+                *code
+            );
+        }
+    }
+
+    #[test]
+    fn test_totp_rfc6238() {
+        let vectors = [
+            (59, "94287082", "46119246", "90693936"),
+            (1111111109, "07081804", "68084774", "25091201"),
+            (1111111111, "14050471", "67062674", "99943326"),
+            (1234567890, "89005924", "91819424", "93441116"),
+            (2000000000, "69279037", "90698825", "38618901"),
+            (20000000000, "65353130", "77737706", "47863826"),
+        ];
+        for (time, sha1, sha256, sha512) in vectors {
+            assert_eq!(totp(SHA1_SEED, time, 30, 8, OtpAlgorithm::Sha1), sha1);
+            assert_eq!(totp(SHA256_SEED, time, 30, 8, OtpAlgorithm::Sha256), sha256);
+            assert_eq!(totp(SHA512_SEED, time, 30, 8, OtpAlgorithm::Sha512), sha512);
+        }
+    }
+
+    #[test]
+    fn test_verify() {
+        let opts = otp_opts(SHA1_SEED, None);
+        // 59 秒时的代码在 60 秒后的下一个时间步仍然有效
+        assert_eq!(verify_totp(&opts, "287082", 59, 1), Some(0));
+        assert_eq!(verify_totp(&opts, "287082", 60, 1), Some(-1));
+        assert_eq!(verify_totp(&opts, "287082", 60, 0), None);
+        assert_eq!(verify_totp(&opts, "755224", 0, 1), Some(0));
+        assert_eq!(verify_totp(&opts, "000000", 59, 1), None);
+
+        assert_eq!(verify_hotp(&opts, "969429", 1, 2), Some(3));
+        assert_eq!(verify_hotp(&opts, "969429", 1, 1), None);
+        assert_eq!(verify_hotp(&opts, " 287082\n", 1, 0), Some(1));
+    }
+
+    #[test]
+    fn test_otpauth_uri() -> anyhow::Result<()> {
+        let opts = otp_opts(b"Hello!\xde\xad\xbe\xef", None);
+        assert_eq!(
+            otpauth_uri(&opts, "alice@example.com", Some("ACME Co")),
+            "otpauth://totp/ACME%20Co:alice%40example%2Ecom?secret=JBSWY3DPEHPK3PXP\
+             &algorithm=SHA1&digits=6&period=30&issuer=ACME%20Co"
+        );
+
+        let opts = otp_opts(b"abc", Some(5));
+        assert_eq!(
+            otpauth_uri(&opts, "bob", None),
+            "otpauth://hotp/bob?secret=MFRGG&algorithm=SHA1&digits=6&counter=5"
+        );
+
+        let qr = render_qr("otpauth://totp/bob?secret=MFRGG")?;
+        assert!(qr.lines().count() > 10);
+        Ok(())
+    }
+}
@@ -0,0 +1,528 @@
+use std::io::{Read, Write};
+
+use aes_gcm::Aes256Gcm;
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::{
+    ChaCha20Poly1305, KeyInit,
+    aead::{
+        AeadCore, AeadInPlace, Payload,
+        consts::U12,
+        stream::{DecryptorBE32, EncryptorBE32},
+    },
+};
+use rand::{RngCore as _, rngs::OsRng};
+
+use crate::{opts::text::TextCipher, utils};
+
+// 密文格式（多字节整数均为大端）：
+//   magic "RCLI" | version u8 | cipher u8 | kdf u8 | chunk_size u32
+//   | [kdf = argon2id: m_cost u32 | t_cost u32 | p_cost u32 | salt 16B]
+//   | nonce prefix 7B
+//   | chunk...（每块 chunk_size 字节明文 + 16 字节 tag，最后一块可以更短）
+// 整个头部作为每一块的关联数据，修改头部会导致解密失败
+const MAGIC: &[u8; 4] = b"RCLI";
+const VERSION: u8 = 1;
+const KEY_LENGTH: usize = 32;
+const SALT_LENGTH: usize = 16;
+const TAG_LENGTH: usize = 16;
+// STREAM 构造：12 字节 nonce 中 4 字节计数器 + 1 字节结束标志
+const NONCE_PREFIX_LENGTH: usize = 7;
+const MAX_CHUNK_SIZE: u32 = 16 * 1024 * 1024;
+// 解密时头部中的 Argon2 参数来自输入，超过上限直接拒绝，避免耗尽内存或 CPU；
+// m_cost 以 KiB 为单位，上限为 1 GiB
+const MAX_M_COST: u32 = 1024 * 1024;
+const MAX_T_COST: u32 = 16;
+const MAX_P_COST: u32 = 16;
+
+const KDF_NONE: u8 = 0;
+const KDF_ARGON2ID: u8 = 1;
+
+/// 加解密使用的密钥来源
+#[derive(Debug, Clone)]
+pub enum KeySource {
+    /// `text generate` 生成的 32 字节密钥文件
+    File(String),
+    /// 使用 Argon2id 从口令派生密钥
+    Passphrase(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Header {
+    cipher: TextCipher,
+    chunk_size: u32,
+    kdf: Option<Kdf>,
+    nonce_prefix: [u8; NONCE_PREFIX_LENGTH],
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Kdf {
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    salt: [u8; SALT_LENGTH],
+}
+
+pub fn process_generate_key() -> Vec<u8> {
+    let mut key = vec![0u8; KEY_LENGTH];
+    OsRng.fill_bytes(&mut key);
+    key
+}
+
+pub async fn process_encrypt(
+    input: &str,
+    output: &str,
+    key: &KeySource,
+    cipher: TextCipher,
+    chunk_size: u32,
+) -> anyhow::Result<()> {
+    let reader = utils::get_reader(input).await?;
+    let writer = utils::get_writer(output).await?;
+    let kdf = match key {
+        KeySource::File(_) => None,
+        KeySource::Passphrase(_) => Some(Kdf::new(Params::DEFAULT_M_COST, Params::DEFAULT_T_COST)),
+    };
+
+    encrypt(reader, writer, key, cipher, chunk_size, kdf)
+}
+
+pub async fn process_decrypt(input: &str, output: &str, key: &KeySource) -> anyhow::Result<()> {
+    let reader = utils::get_reader(input).await?;
+    let writer = utils::get_writer(output).await?;
+
+    let result = decrypt(reader, writer, key);
+    // 分块解密时前面的明文已经写出，失败时删除不完整的输出文件
+    if result.is_err() && output != "-" {
+        let _ = std::fs::remove_file(output);
+    }
+    result
+}
+
+fn encrypt(
+    reader: impl Read,
+    mut writer: impl Write,
+    key: &KeySource,
+    cipher: TextCipher,
+    chunk_size: u32,
+    kdf: Option<Kdf>,
+) -> anyhow::Result<()> {
+    if chunk_size == 0 || chunk_size > MAX_CHUNK_SIZE {
+        anyhow::bail!("chunk size must be between 1 and {}", MAX_CHUNK_SIZE);
+    }
+
+    let mut nonce_prefix = [0u8; NONCE_PREFIX_LENGTH];
+    OsRng.fill_bytes(&mut nonce_prefix);
+    let header = Header {
+        cipher,
+        chunk_size,
+        kdf,
+        nonce_prefix,
+    };
+    let key = header.derive_key(key)?;
+    let aad = header.to_bytes();
+    writer.write_all(&aad)?;
+
+    let mut chunks = Chunks::new(reader, chunk_size as usize);
+    match cipher {
+        TextCipher::ChaCha20Poly1305 => {
+            let aead = ChaCha20Poly1305::new_from_slice(&key)?;
+            encrypt_chunks(aead, &header, &aad, &mut chunks, &mut writer)?
+        }
+        TextCipher::Aes256Gcm => {
+            let aead = Aes256Gcm::new_from_slice(&key)?;
+            encrypt_chunks(aead, &header, &aad, &mut chunks, &mut writer)?
+        }
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+fn decrypt(mut reader: impl Read, mut writer: impl Write, key: &KeySource) -> anyhow::Result<()> {
+    let (header, aad) = Header::read(&mut reader)?;
+    let key = header.derive_key(key)?;
+
+    let mut chunks = Chunks::new(reader, header.chunk_size as usize + TAG_LENGTH);
+    match header.cipher {
+        TextCipher::ChaCha20Poly1305 => {
+            let aead = ChaCha20Poly1305::new_from_slice(&key)?;
+            decrypt_chunks(aead, &header, &aad, &mut chunks, &mut writer)?
+        }
+        TextCipher::Aes256Gcm => {
+            let aead = Aes256Gcm::new_from_slice(&key)?;
+            decrypt_chunks(aead, &header, &aad, &mut chunks, &mut writer)?
+        }
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+fn encrypt_chunks<A>(
+    aead: A,
+    header: &Header,
+    aad: &[u8],
+    chunks: &mut Chunks<impl Read>,
+    writer: &mut impl Write,
+) -> anyhow::Result<()>
+where
+    A: AeadInPlace + AeadCore<NonceSize = U12> + KeyInit,
+{
+    let mut encryptor = EncryptorBE32::from_aead(aead, (&header.nonce_prefix).into());
+    loop {
+        let (msg, last) = chunks.next_chunk()?;
+        let payload = Payload { msg: &msg, aad };
+        if last {
+            let chunk = encryptor
+                .encrypt_last(payload)
+                .map_err(|_| anyhow::anyhow!("encryption failed"))?;
+            writer.write_all(&chunk)?;
+            return Ok(());
+        }
+
+        let chunk = encryptor
+            .encrypt_next(payload)
+            .map_err(|_| anyhow::anyhow!("encryption failed"))?;
+        writer.write_all(&chunk)?;
+    }
+}
+
+fn decrypt_chunks<A>(
+    aead: A,
+    header: &Header,
+    aad: &[u8],
+    chunks: &mut Chunks<impl Read>,
+    writer: &mut impl Write,
+) -> anyhow::Result<()>
+where
+    A: AeadInPlace + AeadCore<NonceSize = U12> + KeyInit,
+{
+    let mut decryptor = DecryptorBE32::from_aead(aead, (&header.nonce_prefix).into());
+    let error = || anyhow::anyhow!("decryption failed: wrong key or corrupted data");
+    loop {
+        let (msg, last) = chunks.next_chunk()?;
+        let payload = Payload { msg: &msg, aad };
+        if last {
+            let chunk = decryptor.decrypt_last(payload).map_err(|_| error())?;
+            writer.write_all(&chunk)?;
+            return Ok(());
+        }
+
+        let chunk = decryptor.decrypt_next(payload).map_err(|_| error())?;
+        writer.write_all(&chunk)?;
+    }
+}
+
+impl Header {
+    fn derive_key(&self, key: &KeySource) -> anyhow::Result<Vec<u8>> {
+        match (key, &self.kdf) {
+            (KeySource::File(path), None) => {
+                let key = std::fs::read(path)?;
+                if key.len() != KEY_LENGTH {
+                    anyhow::bail!("key must be {} bytes, got {}", KEY_LENGTH, key.len());
+                }
+                Ok(key)
+            }
+            (KeySource::Passphrase(passphrase), Some(kdf)) => kdf.derive(passphrase),
+            (KeySource::File(_), Some(_)) => {
+                anyhow::bail!("data was encrypted with a passphrase, use --passphrase")
+            }
+            (KeySource::Passphrase(_), None) => {
+                anyhow::bail!("data was encrypted with a key file, use --key")
+            }
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(MAGIC);
+        buf.push(VERSION);
+        buf.push(self.cipher.id());
+        match &self.kdf {
+            None => buf.push(KDF_NONE),
+            Some(_) => buf.push(KDF_ARGON2ID),
+        }
+        buf.extend_from_slice(&self.chunk_size.to_be_bytes());
+        if let Some(kdf) = &self.kdf {
+            buf.extend_from_slice(&kdf.m_cost.to_be_bytes());
+            buf.extend_from_slice(&kdf.t_cost.to_be_bytes());
+            buf.extend_from_slice(&kdf.p_cost.to_be_bytes());
+            buf.extend_from_slice(&kdf.salt);
+        }
+        buf.extend_from_slice(&self.nonce_prefix);
+        buf
+    }
+
+    /// 读取并解析头部，同时返回头部的原始字节用作关联数据
+    fn read(reader: &mut impl Read) -> anyhow::Result<(Self, Vec<u8>)> {
+        let mut raw = vec![0u8; 11];
+        reader
+            .read_exact(&mut raw)
+            .map_err(|_| anyhow::anyhow!("input is not encrypted by rcli"))?;
+        if &raw[..4] != MAGIC {
+            anyhow::bail!("input is not encrypted by rcli");
+        }
+        if raw[4] != VERSION {
+            anyhow::bail!("unsupported ciphertext version: {}", raw[4]);
+        }
+        let cipher = TextCipher::from_id(raw[5])?;
+        let kdf_id = raw[6];
+        let chunk_size = u32::from_be_bytes(raw[7..11].try_into()?);
+        if chunk_size == 0 || chunk_size > MAX_CHUNK_SIZE {
+            anyhow::bail!("invalid chunk size: {}", chunk_size);
+        }
+
+        let kdf = match kdf_id {
+            KDF_NONE => None,
+            KDF_ARGON2ID => {
+                let mut buf = [0u8; 12 + SALT_LENGTH];
+                reader.read_exact(&mut buf)?;
+                raw.extend_from_slice(&buf);
+                let kdf = Kdf {
+                    m_cost: u32::from_be_bytes(buf[0..4].try_into()?),
+                    t_cost: u32::from_be_bytes(buf[4..8].try_into()?),
+                    p_cost: u32::from_be_bytes(buf[8..12].try_into()?),
+                    salt: buf[12..].try_into()?,
+                };
+                if kdf.m_cost > MAX_M_COST || kdf.t_cost > MAX_T_COST || kdf.p_cost > MAX_P_COST {
+                    anyhow::bail!(
+                        "argon2 parameters too large: m_cost={}, t_cost={}, p_cost={}",
+                        kdf.m_cost,
+                        kdf.t_cost,
+                        kdf.p_cost
+                    );
+                }
+                Some(kdf)
+            }
+            id => anyhow::bail!("unsupported key derivation: {}", id),
+        };
+
+        let mut nonce_prefix = [0u8; NONCE_PREFIX_LENGTH];
+        reader.read_exact(&mut nonce_prefix)?;
+        raw.extend_from_slice(&nonce_prefix);
+
+        let header = Header {
+            cipher,
+            chunk_size,
+            kdf,
+            nonce_prefix,
+        };
+        Ok((header, raw))
+    }
+}
+
+impl Kdf {
+    fn new(m_cost: u32, t_cost: u32) -> Self {
+        let mut salt = [0u8; SALT_LENGTH];
+        OsRng.fill_bytes(&mut salt);
+        Self {
+            m_cost,
+            t_cost,
+            p_cost: Params::DEFAULT_P_COST,
+            salt,
+        }
+    }
+
+    fn derive(&self, passphrase: &str) -> anyhow::Result<Vec<u8>> {
+        let params = Params::new(self.m_cost, self.t_cost, self.p_cost, Some(KEY_LENGTH))?;
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+        let mut key = vec![0u8; KEY_LENGTH];
+        argon2.hash_password_into(passphrase.as_bytes(), &self.salt, &mut key)?;
+        Ok(key)
+    }
+}
+
+impl TextCipher {
+    fn id(&self) -> u8 {
+        match self {
+            TextCipher::ChaCha20Poly1305 => 1,
+            TextCipher::Aes256Gcm => 2,
+        }
+    }
+
+    fn from_id(id: u8) -> anyhow::Result<Self> {
+        match id {
+            1 => Ok(TextCipher::ChaCha20Poly1305),
+            2 => Ok(TextCipher::Aes256Gcm),
+            _ => anyhow::bail!("unsupported cipher: {}", id),
+        }
+    }
+}
+
+/// 按固定大小分块读取，多读一块用来判断当前块是否为最后一块
+struct Chunks<R> {
+    reader: R,
+    size: usize,
+    next: Option<Vec<u8>>,
+}
+
+impl<R: Read> Chunks<R> {
+    fn new(reader: R, size: usize) -> Self {
+        Self {
+            reader,
+            size,
+            next: None,
+        }
+    }
+
+    fn read_chunk(&mut self) -> std::io::Result<Vec<u8>> {
+        let mut buf = Vec::with_capacity(self.size);
+        self.reader
+            .by_ref()
+            .take(self.size as u64)
+            .read_to_end(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn next_chunk(&mut self) -> std::io::Result<(Vec<u8>, bool)> {
+        let current = match self.next.take() {
+            Some(chunk) => chunk,
+            None => self.read_chunk()?,
+        };
+        if current.len() < self.size {
+            return Ok((current, true));
+        }
+
+        let next = self.read_chunk()?;
+        let last = next.is_empty();
+        self.next = Some(next);
+        Ok((current, last))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_key() -> anyhow::Result<String> {
+        let path = std::env::temp_dir()
+            .join(format!(
+                "cli-cipher-{}-{:x}.key",
+                std::process::id(),
+                OsRng.next_u64()
+            ))
+            .to_string_lossy()
+            .to_string();
+        std::fs::write(&path, process_generate_key())?;
+        Ok(path)
+    }
+
+    fn roundtrip(
+        plain: &[u8],
+        key: &KeySource,
+        cipher: TextCipher,
+        chunk_size: u32,
+        kdf: Option<Kdf>,
+    ) -> anyhow::Result<Vec<u8>> {
+        let mut encrypted = Vec::new();
+        encrypt(plain, &mut encrypted, key, cipher, chunk_size, kdf)?;
+
+        let mut decrypted = Vec::new();
+        decrypt(encrypted.as_slice(), &mut decrypted, key)?;
+        Ok(decrypted)
+    }
+
+    #[test]
+    fn test_encrypt_decrypt_with_key() -> anyhow::Result<()> {
+        let path = temp_key()?;
+        let key = KeySource::File(path.clone());
+
+        for cipher in [TextCipher::ChaCha20Poly1305, TextCipher::Aes256Gcm] {
+            // 空输入、恰好整块、跨多块
+            for plain in [&b""[..], &[7u8; 64][..], &[42u8; 1000][..]] {
+                assert_eq!(roundtrip(plain, &key, cipher, 64, None)?, plain);
+            }
+        }
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_encrypt_decrypt_with_passphrase() -> anyhow::Result<()> {
+        let key = KeySource::Passphrase("correct horse battery staple".to_string());
+        let kdf = Kdf::new(64, 1);
+        let plain = b"hello world";
+        assert_eq!(
+            roundtrip(plain, &key, TextCipher::Aes256Gcm, 4, Some(kdf.clone()))?,
+            plain
+        );
+
+        let mut encrypted = Vec::new();
+        encrypt(
+            &plain[..],
+            &mut encrypted,
+            &key,
+            TextCipher::Aes256Gcm,
+            4,
+            Some(kdf),
+        )?;
+        let wrong = KeySource::Passphrase("wrong".to_string());
+        assert!(decrypt(encrypted.as_slice(), &mut Vec::new(), &wrong).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_decrypt_detects_tampering() -> anyhow::Result<()> {
+        let path = temp_key()?;
+        let key = KeySource::File(path.clone());
+        let plain = [1u8; 100];
+        let mut encrypted = Vec::new();
+        encrypt(
+            &plain[..],
+            &mut encrypted,
+            &key,
+            TextCipher::ChaCha20Poly1305,
+            32,
+            None,
+        )?;
+
+        // 修改头部中的 chunk_size
+        let mut tampered = encrypted.clone();
+        tampered[10] ^= 1;
+        assert!(decrypt(tampered.as_slice(), &mut Vec::new(), &key).is_err());
+
+        // 修改密文
+        let mut tampered = encrypted.clone();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert!(decrypt(tampered.as_slice(), &mut Vec::new(), &key).is_err());
+
+        // 截断最后一块
+        let truncated = &encrypted[..encrypted.len() - 4 - TAG_LENGTH];
+        assert!(decrypt(truncated, &mut Vec::new(), &key).is_err());
+
+        // 使用口令解密密钥加密的数据
+        let passphrase = KeySource::Passphrase("x".to_string());
+        assert!(decrypt(encrypted.as_slice(), &mut Vec::new(), &passphrase).is_err());
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_header_roundtrip() -> anyhow::Result<()> {
+        let header = Header {
+            cipher: TextCipher::Aes256Gcm,
+            chunk_size: 1024,
+            kdf: Some(Kdf::new(64, 2)),
+            nonce_prefix: [9; NONCE_PREFIX_LENGTH],
+        };
+        let bytes = header.to_bytes();
+        let (parsed, raw) = Header::read(&mut bytes.as_slice())?;
+        assert_eq!(parsed, header);
+        assert_eq!(raw, bytes);
+
+        assert!(Header::read(&mut &b"hello world, not encrypted"[..]).is_err());
+
+        // 伪造的头部不能让解密方分配过多内存或计算过久
+        for (m_cost, t_cost) in [(u32::MAX, 2), (MAX_M_COST + 1, 2), (64, u32::MAX)] {
+            let header = Header {
+                kdf: Some(Kdf::new(m_cost, t_cost)),
+                ..header.clone()
+            };
+            let err = Header::read(&mut header.to_bytes().as_slice()).unwrap_err();
+            assert!(err.to_string().contains("too large"), "{}", err);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,260 @@
+use std::{
+    fs::File,
+    io::{BufReader, Read, Write},
+    path::Path,
+};
+
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+use walkdir::WalkDir;
+
+use crate::{opts::text::HashAlgorithm, utils};
+
+// 流式读取时的缓冲区大小
+const BUFFER_SIZE: usize = 1024 * 1024;
+
+/// 目录中单个文件的哈希
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManifestEntry {
+    pub path: String,
+    pub digest: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HashOutput {
+    pub root: Vec<u8>,
+    /// 输入为目录时按路径排序的文件列表，否则为空
+    pub entries: Vec<ManifestEntry>,
+}
+
+pub async fn process_hash(input: &str, algo: HashAlgorithm) -> anyhow::Result<HashOutput> {
+    if input == "-" {
+        let reader = utils::get_reader(input).await?;
+        let root = hash_reader(reader, algo)?;
+        return Ok(HashOutput {
+            root,
+            entries: Vec::new(),
+        });
+    }
+
+    let path = Path::new(input);
+    if path.is_dir() {
+        hash_dir(path, algo)
+    } else {
+        Ok(HashOutput {
+            root: hash_file(path, algo)?,
+            entries: Vec::new(),
+        })
+    }
+}
+
+/// 每行一个文件，格式与 sha256sum/b3sum 相同，最后一行为根哈希
+pub fn write_manifest(output: &HashOutput, mut writer: impl Write) -> anyhow::Result<()> {
+    for entry in &output.entries {
+        writeln!(writer, "{}  {}", hex::encode(&entry.digest), entry.path)?;
+    }
+    writeln!(writer, "# root {}", hex::encode(&output.root))?;
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn hash_reader(reader: impl Read, algo: HashAlgorithm) -> anyhow::Result<Vec<u8>> {
+    let digest = match algo {
+        HashAlgorithm::Blake3 => {
+            let mut hasher = blake3::Hasher::new();
+            hasher.update_reader(reader)?;
+            hasher.finalize().as_bytes().to_vec()
+        }
+        HashAlgorithm::Sha256 => {
+            let mut hasher = Sha256::new();
+            read_chunks(reader, |chunk| hasher.update(chunk))?;
+            hasher.finalize().to_vec()
+        }
+    };
+    Ok(digest)
+}
+
+/// 按块读取，避免把整个输入读入内存
+pub fn read_chunks(mut reader: impl Read, mut f: impl FnMut(&[u8])) -> std::io::Result<()> {
+    let mut buf = vec![0u8; BUFFER_SIZE];
+    loop {
+        match reader.read(&mut buf) {
+            Ok(0) => return Ok(()),
+            Ok(n) => f(&buf[..n]),
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+fn hash_file(path: &Path, algo: HashAlgorithm) -> anyhow::Result<Vec<u8>> {
+    match algo {
+        // Blake3 可以 mmap 后用 rayon 并行计算，小文件会自动退回普通读取
+        HashAlgorithm::Blake3 => {
+            let mut hasher = blake3::Hasher::new();
+            hasher.update_mmap_rayon(path)?;
+            Ok(hasher.finalize().as_bytes().to_vec())
+        }
+        HashAlgorithm::Sha256 => {
+            let file = BufReader::with_capacity(BUFFER_SIZE, File::open(path)?);
+            hash_reader(file, algo)
+        }
+    }
+}
+
+// 类似 Merkle 树：
+//   叶子 = H(0x00 | path | 0x00 | H(file))，节点 = H(0x01 | left | right)
+//   奇数个节点时最后一个直接进入上一层，空目录的根为 H(空)
+fn hash_dir(dir: &Path, algo: HashAlgorithm) -> anyhow::Result<HashOutput> {
+    let mut paths = Vec::new();
+    for entry in WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            let relative = entry
+                .path()
+                .strip_prefix(dir)?
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            paths.push((relative, entry.into_path()));
+        }
+    }
+    // WalkDir 按每一层排序，这里按完整路径重新排序，保证清单稳定
+    paths.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let entries = paths
+        .into_par_iter()
+        .map(|(path, full)| {
+            let digest = hash_file(&full, algo)?;
+            Ok(ManifestEntry { path, digest })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let mut level = entries
+        .iter()
+        .map(|e| digest_parts(algo, &[&[0], e.path.as_bytes(), &[0], &e.digest]))
+        .collect::<Vec<_>>();
+    if level.is_empty() {
+        level.push(digest_parts(algo, &[]));
+    }
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => digest_parts(algo, &[&[1], left, right]),
+                [single] => single.clone(),
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+
+    Ok(HashOutput {
+        root: level.remove(0),
+        entries,
+    })
+}
+
+fn digest_parts(algo: HashAlgorithm, parts: &[&[u8]]) -> Vec<u8> {
+    match algo {
+        HashAlgorithm::Blake3 => {
+            let mut hasher = blake3::Hasher::new();
+            for part in parts {
+                hasher.update(part);
+            }
+            hasher.finalize().as_bytes().to_vec()
+        }
+        HashAlgorithm::Sha256 => {
+            let mut hasher = Sha256::new();
+            for part in parts {
+                hasher.update(part);
+            }
+            hasher.finalize().to_vec()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("cli-hash-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_hash_file() -> anyhow::Result<()> {
+        let dir = temp_dir("file");
+        let file = dir.join("hello.txt");
+        std::fs::write(&file, "hello world")?;
+        let input = file.to_string_lossy();
+
+        let output = process_hash(&input, HashAlgorithm::Sha256).await?;
+        assert_eq!(
+            hex::encode(output.root),
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+        );
+        assert!(output.entries.is_empty());
+
+        let output = process_hash(&input, HashAlgorithm::Blake3).await?;
+        assert_eq!(output.root, blake3::hash(b"hello world").as_bytes());
+
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_hash_dir() -> anyhow::Result<()> {
+        let dir = temp_dir("dir");
+        std::fs::create_dir_all(dir.join("b"))?;
+        std::fs::write(dir.join("a.txt"), "a")?;
+        std::fs::write(dir.join("b/c.txt"), "c")?;
+        std::fs::write(dir.join("b.txt"), "b")?;
+        let input = dir.to_string_lossy();
+
+        let output = process_hash(&input, HashAlgorithm::Blake3).await?;
+        let paths = output
+            .entries
+            .iter()
+            .map(|e| e.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, ["a.txt", "b.txt", "b/c.txt"]);
+        assert_eq!(output.entries[0].digest, blake3::hash(b"a").as_bytes());
+
+        // 内容或路径变化都会改变根哈希
+        std::fs::write(dir.join("b/c.txt"), "changed")?;
+        let changed = process_hash(&input, HashAlgorithm::Blake3).await?;
+        assert_ne!(changed.root, output.root);
+
+        std::fs::write(dir.join("b/c.txt"), "c")?;
+        assert_eq!(process_hash(&input, HashAlgorithm::Blake3).await?, output);
+        std::fs::rename(dir.join("a.txt"), dir.join("d.txt"))?;
+        assert_ne!(
+            process_hash(&input, HashAlgorithm::Blake3).await?.root,
+            output.root
+        );
+
+        let mut manifest = Vec::new();
+        write_manifest(&output, &mut manifest)?;
+        let manifest = String::from_utf8(manifest)?;
+        assert!(manifest.starts_with(&format!(
+            "{}  a.txt\n",
+            hex::encode(blake3::hash(b"a").as_bytes())
+        )));
+        assert!(manifest.ends_with(&format!("# root {}\n", hex::encode(&output.root))));
+
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_hash_reader_in_chunks() -> anyhow::Result<()> {
+        let data = vec![7u8; BUFFER_SIZE * 2 + 3];
+        let digest = hash_reader(data.as_slice(), HashAlgorithm::Sha256)?;
+        assert_eq!(digest, Sha256::digest(&data).to_vec());
+        Ok(())
+    }
+}
@@ -0,0 +1,469 @@
+pub mod cipher;
+pub mod hash;
+
+use std::io::Read;
+
+use base64::prelude::*;
+use ed25519_dalek::{
+    PUBLIC_KEY_LENGTH, SECRET_KEY_LENGTH, SIGNATURE_LENGTH, Signature, Signer, SigningKey,
+    VerifyingKey,
+    pkcs8::{DecodePrivateKey, DecodePublicKey},
+};
+use rand::rngs::OsRng;
+use sha2::{Digest, Sha512};
+
+use crate::utils;
+use crate::{opts::text::TextSignFormat, process::genpass};
+
+// Ed25519ph 的 context，用于区分其他用途的签名；只在 `--prehash` 时使用
+const ED25519_CONTEXT: &[u8] = b"rcli text sign";
+
+pub async fn process_generate(format: &TextSignFormat) -> anyhow::Result<Vec<Vec<u8>>> {
+    match format {
+        TextSignFormat::Blake3 => Blake3::generate().await,
+        TextSignFormat::Ed25519 => Ed25519Signer::generate().await,
+    }
+}
+
+pub async fn process_sign(
+    input: &str,
+    key: &str,
+    format: &TextSignFormat,
+    prehash: bool,
+) -> anyhow::Result<String> {
+    check_prehash(format, prehash)?;
+    let mut reader = utils::get_reader(input).await?;
+
+    let signed = match format {
+        TextSignFormat::Blake3 => {
+            let signer = Blake3::load(key)?;
+            signer.sign(&mut reader)?
+        }
+        TextSignFormat::Ed25519 => {
+            let signer = Ed25519Signer::load(key)?.with_prehash(prehash);
+            signer.sign(&mut reader)?
+        }
+    };
+
+    let signed = BASE64_URL_SAFE_NO_PAD.encode(&signed);
+
+    Ok(signed)
+}
+
+pub async fn process_verify(
+    input: &str,
+    key: &str,
+    sig: &str,
+    format: &TextSignFormat,
+    prehash: bool,
+) -> anyhow::Result<bool> {
+    check_prehash(format, prehash)?;
+    let mut reader = utils::get_reader(input).await?;
+
+    let sig = BASE64_URL_SAFE_NO_PAD.decode(sig.trim())?;
+
+    let is_valid = match format {
+        TextSignFormat::Blake3 => {
+            let verifier = Blake3::load(key)?;
+            verifier.verify(&mut reader, &sig)?
+        }
+        TextSignFormat::Ed25519 => {
+            let verifier = Ed25519Verifier::load(key)?.with_prehash(prehash);
+            verifier.verify(&mut reader, &sig)?
+        }
+    };
+
+    Ok(is_valid)
+}
+
+fn check_prehash(format: &TextSignFormat, prehash: bool) -> anyhow::Result<()> {
+    if prehash && !matches!(format, TextSignFormat::Ed25519) {
+        anyhow::bail!("--prehash only applies to ed25519");
+    }
+    Ok(())
+}
+
+/// 写出分离的签名文件，内容为 base64 编码的签名
+pub fn write_sig_file(path: &str, sig: &str) -> anyhow::Result<()> {
+    std::fs::write(path, format!("{}\n", sig))?;
+    Ok(())
+}
+
+/// 读取分离的签名文件，支持 base64 文本和原始字节
+pub fn read_sig_file(path: &str) -> anyhow::Result<String> {
+    let content = std::fs::read(path)?;
+    let text = std::str::from_utf8(&content).map(str::trim);
+    match text {
+        Ok(text) if BASE64_URL_SAFE_NO_PAD.decode(text).is_ok() => Ok(text.to_string()),
+        _ => Ok(BASE64_URL_SAFE_NO_PAD.encode(&content)),
+    }
+}
+
+/// 读取密钥文件：长度正好为 `len` 时视为原始字节，否则按 base64 文本解析
+fn load_key_bytes(path: &str, len: usize) -> anyhow::Result<Vec<u8>> {
+    let content = std::fs::read(path)?;
+    decode_key_bytes(content, len)
+}
+
+fn decode_key_bytes(content: Vec<u8>, len: usize) -> anyhow::Result<Vec<u8>> {
+    if content.len() == len {
+        return Ok(content);
+    }
+
+    let text = std::str::from_utf8(&content)
+        .map_err(|_| anyhow::anyhow!("key must be {} raw bytes, PEM or base64", len))?
+        .trim();
+    let key = if text.starts_with("-----BEGIN") {
+        let (_, der) = pem_rfc7468::decode_vec(text.as_bytes())
+            .map_err(|e| anyhow::anyhow!("invalid PEM key: {}", e))?;
+        der
+    } else {
+        [
+            &BASE64_STANDARD,
+            &BASE64_URL_SAFE,
+            &BASE64_STANDARD_NO_PAD,
+            &BASE64_URL_SAFE_NO_PAD,
+        ]
+        .iter()
+        .find_map(|engine| engine.decode(text).ok())
+        .ok_or_else(|| anyhow::anyhow!("key must be {} raw bytes, PEM or base64", len))?
+    };
+
+    if key.len() != len {
+        anyhow::bail!("key must be {} bytes, got {}", len, key.len());
+    }
+    Ok(key)
+}
+
+fn is_pem(path: &str) -> anyhow::Result<Option<String>> {
+    let content = std::fs::read(path)?;
+    match String::from_utf8(content) {
+        Ok(text) if text.trim_start().starts_with("-----BEGIN") => Ok(Some(text)),
+        _ => Ok(None),
+    }
+}
+
+trait GenerateKey {
+    async fn generate() -> anyhow::Result<Vec<Vec<u8>>>;
+}
+
+trait TextSign {
+    fn sign<T: Read>(&self, reader: &mut T) -> anyhow::Result<Vec<u8>>;
+}
+
+trait TextVerify {
+    fn verify<T: Read>(&self, reader: &mut T, sig: &[u8]) -> anyhow::Result<bool>;
+}
+
+struct Blake3 {
+    key: [u8; 32],
+}
+
+impl GenerateKey for Blake3 {
+    async fn generate() -> anyhow::Result<Vec<Vec<u8>>> {
+        let key = genpass::process_genpass(32, true, true, true, true).await?;
+        Ok(vec![key.into_bytes()])
+    }
+}
+
+impl TextSign for Blake3 {
+    fn sign<T: Read>(&self, reader: &mut T) -> anyhow::Result<Vec<u8>> {
+        let mut hasher = blake3::Hasher::new_keyed(&self.key);
+        hasher.update_reader(reader)?;
+
+        Ok(hasher.finalize().as_bytes().to_vec())
+    }
+}
+
+impl TextVerify for Blake3 {
+    fn verify<T: Read>(&self, reader: &mut T, sig: &[u8]) -> anyhow::Result<bool> {
+        let mut hasher = blake3::Hasher::new_keyed(&self.key);
+        hasher.update_reader(reader)?;
+
+        // blake3::Hash 的比较是常数时间的
+        let sig: [u8; blake3::OUT_LEN] = match sig.try_into() {
+            Ok(sig) => sig,
+            Err(_) => return Ok(false),
+        };
+        Ok(hasher.finalize() == blake3::Hash::from_bytes(sig))
+    }
+}
+
+impl Blake3 {
+    fn load(key: &str) -> anyhow::Result<Self> {
+        let key = load_key_bytes(key, blake3::KEY_LEN)?;
+        Self::try_from(key)
+    }
+
+    fn try_from(key: impl AsRef<[u8]>) -> anyhow::Result<Self> {
+        let key = key.as_ref();
+        let signer = Blake3 {
+            key: key.try_into()?,
+        };
+
+        Ok(signer)
+    }
+}
+
+// Ed25519ph（先 SHA-512 预哈希）可以流式处理任意大小的输入，
+// 但签名与标准 Ed25519 不兼容，所以需要显式开启
+fn prehash<T: Read>(reader: &mut T) -> anyhow::Result<Sha512> {
+    let mut hasher = Sha512::new();
+    hash::read_chunks(reader, |chunk| hasher.update(chunk))?;
+    Ok(hasher)
+}
+
+struct Ed25519Signer {
+    key: SigningKey,
+    prehash: bool,
+}
+
+impl GenerateKey for Ed25519Signer {
+    async fn generate() -> anyhow::Result<Vec<Vec<u8>>> {
+        let mut csprng = OsRng;
+        let signing_key: SigningKey = SigningKey::generate(&mut csprng);
+        let verifying_key: VerifyingKey = signing_key.verifying_key();
+
+        Ok(vec![
+            signing_key.to_bytes().to_vec(),
+            verifying_key.to_bytes().to_vec(),
+        ])
+    }
+}
+
+impl TextSign for Ed25519Signer {
+    fn sign<T: Read>(&self, reader: &mut T) -> anyhow::Result<Vec<u8>> {
+        let sig = if self.prehash {
+            self.key
+                .sign_prehashed(prehash(reader)?, Some(ED25519_CONTEXT))?
+        } else {
+            self.key.sign(&read_all(reader)?)
+        };
+
+        Ok(sig.to_bytes().to_vec())
+    }
+}
+
+impl Ed25519Signer {
+    fn load(key: &str) -> anyhow::Result<Self> {
+        if let Some(pem) = is_pem(key)? {
+            let key = SigningKey::from_pkcs8_pem(&pem)
+                .map_err(|e| anyhow::anyhow!("invalid Ed25519 private key: {}", e))?;
+            return Ok(Ed25519Signer {
+                key,
+                prehash: false,
+            });
+        }
+
+        let key = load_key_bytes(key, SECRET_KEY_LENGTH)?;
+        Self::try_from(key)
+    }
+
+    fn try_from(key: impl AsRef<[u8]>) -> anyhow::Result<Self> {
+        let key = key.as_ref().try_into()?;
+        let signer = Ed25519Signer {
+            key: SigningKey::from_bytes(key),
+            prehash: false,
+        };
+
+        Ok(signer)
+    }
+
+    fn with_prehash(mut self, prehash: bool) -> Self {
+        self.prehash = prehash;
+        self
+    }
+}
+
+struct Ed25519Verifier {
+    key: VerifyingKey,
+    prehash: bool,
+}
+
+impl TextVerify for Ed25519Verifier {
+    fn verify<T: Read>(&self, reader: &mut T, sig: &[u8]) -> anyhow::Result<bool> {
+        let sig: [u8; SIGNATURE_LENGTH] = match sig.try_into() {
+            Ok(sig) => sig,
+            Err(_) => return Ok(false),
+        };
+        let sig = Signature::from_bytes(&sig);
+        let result = if self.prehash {
+            self.key
+                .verify_prehashed(prehash(reader)?, Some(ED25519_CONTEXT), &sig)
+        } else {
+            self.key.verify_strict(&read_all(reader)?, &sig)
+        };
+
+        Ok(result.is_ok())
+    }
+}
+
+impl Ed25519Verifier {
+    fn load(key: &str) -> anyhow::Result<Self> {
+        if let Some(pem) = is_pem(key)? {
+            let key = VerifyingKey::from_public_key_pem(&pem)
+                .map_err(|e| anyhow::anyhow!("invalid Ed25519 public key: {}", e))?;
+            return Ok(Ed25519Verifier {
+                key,
+                prehash: false,
+            });
+        }
+
+        let key = load_key_bytes(key, PUBLIC_KEY_LENGTH)?;
+        Self::try_from(key)
+    }
+
+    fn try_from(key: impl AsRef<[u8]>) -> anyhow::Result<Self> {
+        let key = key.as_ref().try_into()?;
+        let verifier = Ed25519Verifier {
+            key: VerifyingKey::from_bytes(key)?,
+            prehash: false,
+        };
+
+        Ok(verifier)
+    }
+
+    fn with_prehash(mut self, prehash: bool) -> Self {
+        self.prehash = prehash;
+        self
+    }
+}
+
+// 标准 Ed25519 需要完整的消息
+fn read_all<T: Read>(reader: &mut T) -> anyhow::Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_blake3_sign_verify() -> anyhow::Result<()> {
+        let key = Blake3::generate().await?;
+        let signer = Blake3::try_from(&key[0])?;
+        let verifier = Blake3::try_from(&key[0])?;
+
+        let input = "hello world".as_bytes();
+        let mut reader = input;
+        let sig = signer.sign(&mut reader)?;
+
+        let mut reader = input;
+        let is_valid = verifier.verify(&mut reader, &sig)?;
+
+        assert!(is_valid);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ed25519_sign_verify() -> anyhow::Result<()> {
+        let key = Ed25519Signer::generate().await?;
+        let signer = Ed25519Signer::try_from(&key[0])?;
+        let verifier = Ed25519Verifier::try_from(&key[1])?;
+
+        let input = "hello world".as_bytes();
+        let mut reader = input;
+        let sig = signer.sign(&mut reader)?;
+
+        let mut reader = input;
+        let is_valid = verifier.verify(&mut reader, &sig)?;
+
+        assert!(is_valid);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ed25519_compatible_with_plain_signature() -> anyhow::Result<()> {
+        let key = Ed25519Signer::generate().await?;
+        let signing_key = SigningKey::from_bytes(key[0].as_slice().try_into()?);
+        let verifier = Ed25519Verifier::try_from(&key[1])?;
+
+        // 其他工具用标准 Ed25519 生成的签名
+        let sig = signing_key.sign(b"hello world").to_bytes();
+        let mut reader = "hello world".as_bytes();
+        assert!(verifier.verify(&mut reader, &sig)?);
+
+        let signer = Ed25519Signer::try_from(&key[0])?;
+        let mut reader = "hello world".as_bytes();
+        assert_eq!(signer.sign(&mut reader)?, sig.to_vec());
+
+        // 预哈希的签名只能用 --prehash 验证
+        let signer = signer.with_prehash(true);
+        let mut reader = "hello world".as_bytes();
+        let sig = signer.sign(&mut reader)?;
+        let mut reader = "hello world".as_bytes();
+        assert!(!verifier.verify(&mut reader, &sig)?);
+        let verifier = verifier.with_prehash(true);
+        let mut reader = "hello world".as_bytes();
+        assert!(verifier.verify(&mut reader, &sig)?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_blake3_rejects_wrong_signature() -> anyhow::Result<()> {
+        let key = Blake3::generate().await?;
+        let verifier = Blake3::try_from(&key[0])?;
+
+        let mut reader = "hello world".as_bytes();
+        assert!(!verifier.verify(&mut reader, &[0u8; 32])?);
+        let mut reader = "hello world".as_bytes();
+        assert!(!verifier.verify(&mut reader, b"short")?);
+        assert!(Blake3::try_from(b"short").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_key_bytes() -> anyhow::Result<()> {
+        let raw = vec![7u8; 32];
+        assert_eq!(decode_key_bytes(raw.clone(), 32)?, raw);
+
+        let base64 = BASE64_STANDARD.encode(&raw).into_bytes();
+        assert_eq!(decode_key_bytes(base64, 32)?, raw);
+
+        let base64 = format!("{}\n", BASE64_URL_SAFE_NO_PAD.encode(&raw)).into_bytes();
+        assert_eq!(decode_key_bytes(base64, 32)?, raw);
+
+        let pem = pem_rfc7468::encode_string("KEY", pem_rfc7468::LineEnding::LF, &raw)?;
+        assert_eq!(decode_key_bytes(pem.into_bytes(), 32)?, raw);
+
+        assert!(decode_key_bytes(b"too short".to_vec(), 32).is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ed25519_pem_keys() -> anyhow::Result<()> {
+        // chat_core 的 JWT 密钥也是 PKCS#8 PEM 格式的 Ed25519 密钥
+        let signer = Ed25519Signer::load("../chat/chat_core/fixtures/enc.pem")?;
+        let verifier = Ed25519Verifier::load("../chat/chat_core/fixtures/dec.pem")?;
+
+        let mut reader = "hello world".as_bytes();
+        let sig = signer.sign(&mut reader)?;
+        let mut reader = "hello world".as_bytes();
+        assert!(verifier.verify(&mut reader, &sig)?);
+        let mut reader = "hello world!".as_bytes();
+        assert!(!verifier.verify(&mut reader, &sig)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sig_file() -> anyhow::Result<()> {
+        let path = std::env::temp_dir()
+            .join(format!("cli-text-{}.sig", std::process::id()))
+            .to_string_lossy()
+            .to_string();
+        let sig = BASE64_URL_SAFE_NO_PAD.encode([1u8; 64]);
+
+        write_sig_file(&path, &sig)?;
+        assert_eq!(read_sig_file(&path)?, sig);
+
+        std::fs::write(&path, [1u8; 64])?;
+        assert_eq!(read_sig_file(&path)?, sig);
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+}
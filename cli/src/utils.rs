@@ -1,3 +1,8 @@
+use std::{
+    io::Write as _,
+    path::{Path, PathBuf},
+};
+
 pub async fn get_reader(input: &str) -> anyhow::Result<Box<dyn std::io::Read>> {
     if input == "-" {
         let stdin = std::io::stdin();
@@ -7,3 +12,140 @@ pub async fn get_reader(input: &str) -> anyhow::Result<Box<dyn std::io::Read>> {
         Ok(Box::new(file))
     }
 }
+
+pub async fn get_writer(output: &str) -> anyhow::Result<Box<dyn std::io::Write + Send>> {
+    if output == "-" {
+        Ok(Box::new(std::io::stdout()))
+    } else {
+        let file = std::fs::File::create(output)?;
+        Ok(Box::new(std::io::BufWriter::new(file)))
+    }
+}
+
+/// 同目录下的临时文件，`persist` 时重命名为目标文件，之前 drop 则删除，
+/// 读者不会看到写了一半的文件
+#[derive(Debug)]
+pub struct TempFile {
+    path: PathBuf,
+    target: PathBuf,
+    persisted: bool,
+}
+
+impl TempFile {
+    /// `mode` 为 `None` 时沿用目标文件的权限，目标不存在时按 umask 创建；
+    /// 权限在创建时就设置好，写入期间内容不会被其他用户读到
+    pub fn create(target: &Path, mode: Option<u32>) -> std::io::Result<(Self, std::fs::File)> {
+        let name = target.file_name().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("invalid file: {}", target.display()),
+            )
+        })?;
+        let path = target.with_file_name(format!(
+            ".{}.{:016x}.tmp",
+            name.to_string_lossy(),
+            rand::random::<u64>()
+        ));
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        let mode = {
+            use std::os::unix::fs::{OpenOptionsExt as _, PermissionsExt as _};
+            let mode = mode.or_else(|| {
+                let meta = std::fs::metadata(target).ok()?;
+                Some(meta.permissions().mode() & 0o7777)
+            });
+            options.mode(mode.unwrap_or(0o666));
+            mode
+        };
+        #[cfg(not(unix))]
+        let _ = mode;
+        let file = options.open(&path)?;
+        // 创建时的权限会被 umask 去掉一部分，沿用或指定的权限需要显式设置
+        #[cfg(unix)]
+        if let Some(mode) = mode {
+            use std::os::unix::fs::PermissionsExt as _;
+            file.set_permissions(std::fs::Permissions::from_mode(mode))?;
+        }
+
+        let temp = Self {
+            path,
+            target: target.to_path_buf(),
+            persisted: false,
+        };
+        Ok((temp, file))
+    }
+
+    pub fn persist(mut self) -> std::io::Result<()> {
+        std::fs::rename(&self.path, &self.target)?;
+        self.persisted = true;
+        Ok(())
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        if !self.persisted {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// 原子地替换文件内容，权限规则见 [`TempFile::create`]
+pub fn write_atomic(path: &Path, contents: &[u8], mode: Option<u32>) -> anyhow::Result<()> {
+    let (temp, mut file) = TempFile::create(path, mode)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    drop(file);
+    temp.persist()?;
+    Ok(())
+}
+
+// 比较耗时与第一个不同字节的位置无关，避免通过响应时间猜测密码
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_atomic() -> anyhow::Result<()> {
+        let dir = std::env::temp_dir().join(format!("cli-utils-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join("a.toml");
+        write_atomic(&path, b"a = 1", None)?;
+        assert_eq!(std::fs::read(&path)?, b"a = 1");
+
+        // 中途放弃时不留下临时文件
+        let (temp, _) = TempFile::create(&path, None)?;
+        drop(temp);
+        assert_eq!(std::fs::read_dir(&dir)?.count(), 1);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = |path: &Path| -> anyhow::Result<u32> {
+                Ok(std::fs::metadata(path)?.permissions().mode() & 0o777)
+            };
+
+            // 替换后沿用原文件的权限
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+            write_atomic(&path, b"a = 2", None)?;
+            assert_eq!(mode(&path)?, 0o600);
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o640))?;
+            write_atomic(&path, b"a = 3", None)?;
+            assert_eq!(mode(&path)?, 0o640);
+
+            // 指定权限时覆盖原有的权限
+            write_atomic(&path, b"key", Some(0o600))?;
+            assert_eq!(mode(&path)?, 0o600);
+            assert_eq!(std::fs::read(&path)?, b"key");
+        }
+
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+// cargo bench --package conc --bench channel
+use std::{
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use conc::sync;
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+
+const CAP: usize = 1024;
+
+/// (生产者数, 消费者数)
+const SHAPES: [(usize, usize); 4] = [(1, 1), (4, 1), (1, 4), (4, 4)];
+
+/// `producers` 个线程共发送 `iters` 条消息，每个接收端一个线程，接收直到通道断开
+fn run<S, R>(
+    producers: usize,
+    iters: u64,
+    tx: S,
+    rxs: Vec<R>,
+    send: impl Fn(&S, u64) + Sync,
+    recv: impl Fn(&R) -> bool + Sync,
+) -> Duration
+where
+    S: Clone + Send,
+    R: Send,
+{
+    let per_producer = iters.div_ceil(producers as u64);
+    let start = Instant::now();
+    thread::scope(|s| {
+        for _ in 0..producers {
+            let tx = tx.clone();
+            let send = &send;
+            s.spawn(move || {
+                for i in 0..per_producer {
+                    send(&tx, i);
+                }
+            });
+        }
+        drop(tx);
+        for rx in rxs {
+            let recv = &recv;
+            s.spawn(move || while recv(&rx) {});
+        }
+    });
+    start.elapsed()
+}
+
+fn clones<R: Clone>(rx: R, n: usize) -> Vec<R> {
+    vec![rx; n]
+}
+
+fn bench_channel(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bounded_channel");
+    group
+        .sample_size(20)
+        .warm_up_time(Duration::from_millis(500))
+        .measurement_time(Duration::from_secs(2))
+        .throughput(Throughput::Elements(1));
+
+    for shape in SHAPES {
+        let id = format!("{}p{}c", shape.0, shape.1);
+
+        group.bench_function(BenchmarkId::new("conc", &id), |b| {
+            b.iter_custom(|iters| {
+                let (tx, rx) = sync::bounded(CAP);
+                run(
+                    shape.0,
+                    iters,
+                    tx,
+                    clones(rx, shape.1),
+                    |tx, i| tx.send(i).unwrap(),
+                    |rx| rx.recv().is_ok(),
+                )
+            });
+        });
+
+        group.bench_function(BenchmarkId::new("crossbeam", &id), |b| {
+            b.iter_custom(|iters| {
+                let (tx, rx) = crossbeam_channel::bounded(CAP);
+                run(
+                    shape.0,
+                    iters,
+                    tx,
+                    clones(rx, shape.1),
+                    |tx, i| tx.send(i).unwrap(),
+                    |rx| rx.recv().is_ok(),
+                )
+            });
+        });
+
+        // 标准库的接收端不能克隆，只比较单消费者
+        if shape.1 == 1 {
+            group.bench_function(BenchmarkId::new("std_mpsc", &id), |b| {
+                b.iter_custom(|iters| {
+                    let (tx, rx) = mpsc::sync_channel(CAP);
+                    run(
+                        shape.0,
+                        iters,
+                        tx,
+                        vec![rx],
+                        |tx, i| tx.send(i).unwrap(),
+                        |rx| rx.recv().is_ok(),
+                    )
+                });
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_channel);
+criterion_main!(benches);
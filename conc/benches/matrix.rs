@@ -0,0 +1,38 @@
+// cargo bench --package conc --bench matrix
+use std::{hint::black_box, time::Duration};
+
+use conc::{matrix::Matrix, pool::ThreadPool};
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+
+fn matrix(n: usize, seed: usize) -> Matrix<f64> {
+    Matrix::from_fn(n, n, |i, j| ((i * 31 + j * 17 + seed) % 97) as f64 / 97.0)
+}
+
+fn bench_multiply(c: &mut Criterion) {
+    let mut group = c.benchmark_group("matrix_multiply");
+    group
+        .sample_size(10)
+        .warm_up_time(Duration::from_millis(500))
+        .measurement_time(Duration::from_secs(3));
+
+    for n in [64, 256, 512] {
+        let (a, b) = (matrix(n, 1), matrix(n, 2));
+        // 每次乘法的浮点运算次数
+        group.throughput(Throughput::Elements(2 * (n * n * n) as u64));
+
+        group.bench_with_input(BenchmarkId::new("naive", n), &n, |bench, _| {
+            bench.iter(|| black_box(a.multiply_naive(&b).unwrap()));
+        });
+        group.bench_with_input(BenchmarkId::new("blocked", n), &n, |bench, _| {
+            bench.iter(|| black_box(a.multiply_blocked(&b).unwrap()));
+        });
+        group.bench_with_input(BenchmarkId::new("pool", n), &n, |bench, _| {
+            let pool = ThreadPool::global();
+            bench.iter(|| black_box(a.multiply_parallel(&b, pool).unwrap()));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_multiply);
+criterion_main!(benches);
@@ -0,0 +1,76 @@
+// cargo bench --package conc --bench metrics
+use std::{
+    hint::black_box,
+    thread,
+    time::{Duration, Instant},
+};
+
+use conc::metrics::{Registry, ShardedCounter, amap::AmapMetrics, cmap::CmapMetrics};
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+
+const THREADS: [usize; 7] = [1, 2, 4, 8, 16, 32, 64];
+
+/// `iters` 次操作平均分给 `threads` 个线程，计时包含创建线程的开销，操作次数足够多时可以忽略
+fn run_threads<F>(threads: usize, iters: u64, op: F) -> Duration
+where
+    F: Fn() + Sync,
+{
+    let per_thread = iters.div_ceil(threads as u64);
+    let start = Instant::now();
+    thread::scope(|s| {
+        for _ in 0..threads {
+            s.spawn(|| {
+                for _ in 0..per_thread {
+                    op();
+                }
+            });
+        }
+    });
+    start.elapsed()
+}
+
+fn bench_counter_inc(c: &mut Criterion) {
+    let mut group = c.benchmark_group("counter_inc");
+    group
+        .sample_size(20)
+        .warm_up_time(Duration::from_millis(500))
+        .measurement_time(Duration::from_secs(2))
+        .throughput(Throughput::Elements(1));
+
+    for threads in THREADS {
+        group.bench_with_input(BenchmarkId::new("amap", threads), &threads, |b, &n| {
+            let metrics = AmapMetrics::new();
+            b.iter_custom(|iters| run_threads(n, iters, || metrics.inc(black_box("hits"), 1)));
+        });
+
+        group.bench_with_input(BenchmarkId::new("cmap", threads), &threads, |b, &n| {
+            let metrics = CmapMetrics::<i64>::new();
+            b.iter_custom(|iters| run_threads(n, iters, || metrics.inc(black_box("hits"), 1)));
+        });
+
+        group.bench_with_input(BenchmarkId::new("handle", threads), &threads, |b, &n| {
+            let hits = AmapMetrics::new().counter("hits");
+            b.iter_custom(|iters| run_threads(n, iters, || hits.inc()));
+        });
+
+        group.bench_with_input(
+            BenchmarkId::new("registry_counter", threads),
+            &threads,
+            |b, &n| {
+                let hits = Registry::new()
+                    .counter("hits", "")
+                    .expect("register counter");
+                b.iter_custom(|iters| run_threads(n, iters, || hits.inc()));
+            },
+        );
+
+        group.bench_with_input(BenchmarkId::new("sharded", threads), &threads, |b, &n| {
+            let hits = ShardedCounter::new();
+            b.iter_custom(|iters| run_threads(n, iters, || hits.inc()));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_counter_inc);
+criterion_main!(benches);
@@ -0,0 +1,38 @@
+// cargo run --package conc --example pool
+use std::{thread, time::Duration};
+
+use anyhow::Result;
+use conc::{matrix::Matrix, pool::ThreadPool};
+
+fn main() -> Result<()> {
+    let pool = ThreadPool::new(4)?;
+
+    let handles: Vec<_> = (0..8u64)
+        .map(|i| {
+            pool.spawn(move || {
+                thread::sleep(Duration::from_millis(100));
+                println!("job {} on {:?}", i, thread::current().name());
+                i * i
+            })
+        })
+        .collect();
+    let results = handles
+        .into_iter()
+        .map(|handle| handle.join())
+        .collect::<Result<Vec<_>>>()?;
+    println!("results: {:?}", results);
+
+    // 任务 panic 不会影响工作线程
+    if let Err(e) = pool.spawn(|| -> u64 { panic!("oops") }).join() {
+        println!("error: {}", e);
+    }
+
+    // 每个 64x64 的结果块是一个任务
+    let a = Matrix::from_fn(300, 200, |i, j| (i + j) as f64);
+    let b = Matrix::identity(200, 1.0);
+    assert_eq!(a.multiply_parallel(&b, &pool)?, a);
+
+    // 等待队列中的任务执行完再退出
+    pool.shutdown();
+    Ok(())
+}
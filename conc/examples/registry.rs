@@ -0,0 +1,59 @@
+use std::{thread, time::Duration};
+
+use anyhow::Result;
+use conc::metrics::{Buckets, MetricValue, Registry};
+use rand::Rng;
+
+fn main() -> Result<()> {
+    let registry = Registry::new();
+    let requests = registry.counter_vec("http_requests", "HTTP requests", &["method", "status"])?;
+    let latency = registry.histogram_vec(
+        "http_latency_seconds",
+        "HTTP request latency",
+        &["method"],
+        Buckets::exponential(0.001, 2.0, 10),
+    )?;
+    let in_flight = registry.gauge("http_in_flight", "requests in flight")?;
+
+    let mut handles = vec![];
+    for method in ["GET", "POST"] {
+        let requests = requests.clone();
+        let latency = latency.with_label_values(&[method])?;
+        let in_flight = in_flight.clone();
+        handles.push(thread::spawn(move || {
+            let mut rng = rand::thread_rng();
+            for _ in 0..50 {
+                in_flight.inc();
+                let elapsed = Duration::from_micros(rng.gen_range(500..20_000));
+                thread::sleep(elapsed / 10);
+                latency.observe_duration(elapsed);
+
+                let status = if rng.gen_bool(0.9) { "200" } else { "500" };
+                requests.with_label_values(&[method, status]).unwrap().inc();
+                in_flight.dec();
+            }
+        }));
+    }
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    for family in registry.snapshot() {
+        for sample in family.samples {
+            match sample.value {
+                MetricValue::Histogram(h) => println!(
+                    "{}{}: count={} p50={:?} p99={:?}",
+                    family.name,
+                    sample.labels,
+                    h.count,
+                    h.quantile(0.5),
+                    h.quantile(0.99)
+                ),
+                value => println!("{}{}: {:?}", family.name, sample.labels, value),
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,37 @@
+// 先启动聚合服务：cargo run --package conc --features aggregator --bin statsd-aggregator
+// 再启动多个进程：cargo run --package conc --example statsd_push -- chat udp
+//                cargo run --package conc --example statsd_push -- notify tcp
+// 查看合计：curl http://127.0.0.1:9102/metrics
+use std::{env, time::Duration};
+
+use anyhow::Result;
+use conc::metrics::{StatsdPusher, Transport, amap::AmapMetrics};
+use rand::Rng;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let mut args = env::args().skip(1);
+    let name = args.next().unwrap_or_else(|| "chat".to_string());
+    let transport = match args.next().as_deref() {
+        Some("tcp") => Transport::Tcp,
+        _ => Transport::Udp,
+    };
+
+    let metrics = AmapMetrics::new();
+    let pusher = StatsdPusher::new(metrics.clone(), "127.0.0.1:8125".parse()?, transport);
+    let handle = pusher.spawn(Duration::from_secs(1));
+
+    for _ in 0..20 {
+        let n = rand::thread_rng().gen_range(1..10);
+        metrics.inc("requests", n);
+        metrics.inc(&format!("{}.requests", name), n);
+        tokio::time::sleep(Duration::from_millis(250)).await;
+    }
+    // 等待最后一次推送
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    handle.abort();
+
+    println!("{} pushed:", name);
+    metrics.read();
+    Ok(())
+}
@@ -0,0 +1,42 @@
+// cargo run --package conc --example sync
+use std::{thread, time::Duration};
+
+use anyhow::Result;
+use conc::sync::{OnceCell, Semaphore, bounded};
+
+fn main() -> Result<()> {
+    let (tx, rx) = bounded(4);
+    let config = OnceCell::new();
+    // 最多两个消费者同时处理
+    let sem = Semaphore::new(2);
+
+    thread::scope(|s| {
+        for p in 0..3 {
+            let tx = tx.clone();
+            s.spawn(move || {
+                for i in 0..4 {
+                    tx.send(format!("producer {} msg {}", p, i)).unwrap();
+                }
+            });
+        }
+        drop(tx);
+
+        for c in 0..4 {
+            let (rx, config, sem) = (rx.clone(), &config, &sem);
+            s.spawn(move || {
+                for msg in rx {
+                    let prefix = config.get_or_init(|| {
+                        println!("consumer {} loads config", c);
+                        "[log]"
+                    });
+                    let _permit = sem.acquire();
+                    println!("{} consumer {} got {}", prefix, c, msg);
+                    thread::sleep(Duration::from_millis(10));
+                }
+            });
+        }
+    });
+
+    assert_eq!(rx.try_recv(), Err(conc::sync::TryRecvError::Disconnected));
+    Ok(())
+}
@@ -0,0 +1,46 @@
+// cargo run --package conc --features aggregator --bin statsd-aggregator -- --statsd 127.0.0.1:8125 --http 127.0.0.1:9102
+use std::net::SocketAddr;
+
+use anyhow::Result;
+use clap::Parser;
+use conc::metrics::Aggregator;
+use tokio::net::{TcpListener, UdpSocket};
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::{
+    Layer as _, fmt, layer::SubscriberExt as _, util::SubscriberInitExt as _,
+};
+
+/// 汇总多个进程推送的 StatsD 指标，通过 HTTP 输出合计值
+#[derive(Debug, Parser)]
+#[command(name = "statsd-aggregator", version, about)]
+struct Opts {
+    /// 同时监听该地址的 UDP 和 TCP
+    #[arg(long, default_value = "127.0.0.1:8125")]
+    statsd: SocketAddr,
+    /// 提供 `GET /metrics`
+    #[arg(long, default_value = "127.0.0.1:9102")]
+    http: SocketAddr,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let console = fmt::Layer::new().with_filter(LevelFilter::INFO);
+    tracing_subscriber::registry().with(console).init();
+
+    let opts = Opts::parse();
+    let aggregator = Aggregator::new();
+
+    let socket = UdpSocket::bind(opts.statsd).await?;
+    let listener = TcpListener::bind(opts.statsd).await?;
+    let http = TcpListener::bind(opts.http).await?;
+    tracing::info!("statsd listening on {} (udp/tcp)", opts.statsd);
+    tracing::info!("metrics available at http://{}/metrics", opts.http);
+
+    let app = aggregator.router();
+    tokio::try_join!(
+        aggregator.clone().serve_udp(socket),
+        aggregator.clone().serve_tcp(listener),
+        async { Ok(axum::serve(http, app).await?) },
+    )?;
+    Ok(())
+}
@@ -1 +1,6 @@
+pub mod matrix;
+// 以 --cfg loom 编译时 tokio 不提供 net 模块，模型测试只需要 sync
+#[cfg(not(loom))]
 pub mod metrics;
+pub mod pool;
+pub mod sync;
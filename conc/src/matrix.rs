@@ -0,0 +1,417 @@
+use std::{
+    fmt,
+    ops::{Add, AddAssign, Index, IndexMut, Mul, Range},
+    sync::Arc,
+};
+
+use anyhow::{Result, bail};
+
+use crate::pool::ThreadPool;
+
+/// 分块乘法的块大小，三个 64x64 的 f64 块约 96KB，可以放进 L2 缓存
+pub const BLOCK: usize = 64;
+
+/// `rows * inner * cols` 超过该值时使用线程池
+pub const PARALLEL_THRESHOLD: usize = 128 * 128 * 128;
+
+/// 行优先存储的矩阵
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matrix<T> {
+    data: Vec<T>,
+    rows: usize,
+    cols: usize,
+}
+
+/// 矩阵元素需要满足的约束
+pub trait Element:
+    Copy + Default + Add<Output = Self> + Mul<Output = Self> + AddAssign + Send + Sync + 'static
+{
+}
+
+impl<T> Element for T where
+    T: Copy + Default + Add<Output = T> + Mul<Output = T> + AddAssign + Send + Sync + 'static
+{
+}
+
+impl<T> Matrix<T> {
+    pub fn new(rows: usize, cols: usize, data: impl Into<Vec<T>>) -> Result<Self> {
+        let data = data.into();
+        if data.len() != rows * cols {
+            bail!(
+                "a {}x{} matrix needs {} elements, got {}",
+                rows,
+                cols,
+                rows * cols,
+                data.len()
+            );
+        }
+        Ok(Self { data, rows, cols })
+    }
+
+    pub fn from_fn(rows: usize, cols: usize, mut f: impl FnMut(usize, usize) -> T) -> Self {
+        let data = (0..rows * cols).map(|i| f(i / cols, i % cols)).collect();
+        Self { data, rows, cols }
+    }
+
+    /// 每行长度必须相同
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Result<Self> {
+        let cols = rows.first().map_or(0, Vec::len);
+        if let Some(i) = rows.iter().position(|row| row.len() != cols) {
+            bail!("row {} has {} columns, expected {}", i, rows[i].len(), cols);
+        }
+        let n = rows.len();
+        Ok(Self {
+            data: rows.into_iter().flatten().collect(),
+            rows: n,
+            cols,
+        })
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// (行数, 列数)
+    pub fn shape(&self) -> (usize, usize) {
+        (self.rows, self.cols)
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Option<&T> {
+        (row < self.rows && col < self.cols).then(|| &self.data[row * self.cols + col])
+    }
+
+    pub fn row(&self, row: usize) -> &[T] {
+        &self.data[row * self.cols..(row + 1) * self.cols]
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+        self.data
+    }
+}
+
+impl<T: Element> Matrix<T> {
+    pub fn zeros(rows: usize, cols: usize) -> Self {
+        Self {
+            data: vec![T::default(); rows * cols],
+            rows,
+            cols,
+        }
+    }
+
+    /// 对角线为 `one` 的方阵
+    pub fn identity(n: usize, one: T) -> Self {
+        Self::from_fn(n, n, |i, j| if i == j { one } else { T::default() })
+    }
+
+    pub fn transpose(&self) -> Self {
+        Self::from_fn(self.cols, self.rows, |i, j| self[(j, i)])
+    }
+
+    /// 三重循环的朴素实现，用于对比和验证
+    pub fn multiply_naive(&self, other: &Self) -> Result<Self> {
+        self.check_mul(other)?;
+        let mut c = Self::zeros(self.rows, other.cols);
+        for i in 0..self.rows {
+            for j in 0..other.cols {
+                let mut sum = T::default();
+                for k in 0..self.cols {
+                    sum += self[(i, k)] * other[(k, j)];
+                }
+                c[(i, j)] = sum;
+            }
+        }
+        Ok(c)
+    }
+
+    /// 分块乘法，规模较大时提交到全局线程池并行计算。
+    /// 在线程池的任务中调用时改为单线程计算，避免工作线程互相等待而死锁
+    pub fn multiply(&self, other: &Self) -> Result<Self> {
+        let large = self.rows * self.cols * other.cols >= PARALLEL_THRESHOLD;
+        if large && !ThreadPool::is_worker() {
+            self.multiply_parallel(other, ThreadPool::global())
+        } else {
+            self.multiply_blocked(other)
+        }
+    }
+
+    /// 单线程分块乘法，每次计算 `BLOCK` 行
+    pub fn multiply_blocked(&self, other: &Self) -> Result<Self> {
+        self.check_mul(other)?;
+        let mut c = Self::zeros(self.rows, other.cols);
+        let p = other.cols;
+        if c.data.is_empty() {
+            return Ok(c);
+        }
+        for (band, out) in c.data.chunks_mut(BLOCK * p).enumerate() {
+            let start = band * BLOCK;
+            self.multiply_tile(other, start..start + out.len() / p, 0..p, out);
+        }
+        Ok(c)
+    }
+
+    /// 结果中每个 `BLOCK` x `BLOCK` 的块作为一个任务提交到 `pool`。
+    /// 任务需要拥有数据，因此会先复制两个输入矩阵；不要在同一个线程池的任务中调用，
+    /// 否则工作线程全部等待结果时会死锁
+    pub fn multiply_parallel(&self, other: &Self, pool: &ThreadPool) -> Result<Self> {
+        self.check_mul(other)?;
+        let (a, b) = (Arc::new(self.clone()), Arc::new(other.clone()));
+        let (m, p) = (self.rows, other.cols);
+
+        let mut tiles = Vec::new();
+        for i in (0..m).step_by(BLOCK) {
+            for j in (0..p).step_by(BLOCK) {
+                let (rows, cols) = (i..(i + BLOCK).min(m), j..(j + BLOCK).min(p));
+                let (a, b) = (a.clone(), b.clone());
+                let task = (rows.clone(), cols.clone());
+                let handle = pool.spawn(move || {
+                    let (rows, cols) = task;
+                    let mut out = vec![T::default(); rows.len() * cols.len()];
+                    a.multiply_tile(&b, rows, cols, &mut out);
+                    out
+                });
+                tiles.push((rows, cols, handle));
+            }
+        }
+
+        let mut c = Self::zeros(m, p);
+        for (rows, cols, handle) in tiles {
+            let out = handle.join()?;
+            for (i, tile_row) in rows.zip(out.chunks(cols.len())) {
+                c.data[i * p + cols.start..i * p + cols.end].copy_from_slice(tile_row);
+            }
+        }
+        Ok(c)
+    }
+
+    /// 计算结果中 `rows` x `cols` 的块，写入按行存储的 `out`。
+    /// 按 i-k-j 顺序访问使内层循环连续
+    fn multiply_tile(&self, other: &Self, rows: Range<usize>, cols: Range<usize>, out: &mut [T]) {
+        let (n, width) = (self.cols, cols.len());
+        for kk in (0..n).step_by(BLOCK) {
+            let k_end = (kk + BLOCK).min(n);
+            for jj in cols.clone().step_by(BLOCK) {
+                let j_end = (jj + BLOCK).min(cols.end);
+                for (i, out_row) in rows.clone().zip(out.chunks_mut(width)) {
+                    let c_row = &mut out_row[jj - cols.start..j_end - cols.start];
+                    for (k, &a) in self.row(i).iter().enumerate().take(k_end).skip(kk) {
+                        let b_row = &other.row(k)[jj..j_end];
+                        for (c, &b) in c_row.iter_mut().zip(b_row) {
+                            *c += a * b;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_mul(&self, other: &Self) -> Result<()> {
+        if self.cols != other.rows {
+            bail!(
+                "cannot multiply a {}x{} matrix by a {}x{} matrix",
+                self.rows,
+                self.cols,
+                other.rows,
+                other.cols
+            );
+        }
+        Ok(())
+    }
+}
+
+impl<T> Index<(usize, usize)> for Matrix<T> {
+    type Output = T;
+
+    fn index(&self, (row, col): (usize, usize)) -> &T {
+        assert!(
+            row < self.rows && col < self.cols,
+            "index ({row}, {col}) out of bounds for a {}x{} matrix",
+            self.rows,
+            self.cols
+        );
+        &self.data[row * self.cols + col]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Matrix<T> {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut T {
+        assert!(
+            row < self.rows && col < self.cols,
+            "index ({row}, {col}) out of bounds for a {}x{} matrix",
+            self.rows,
+            self.cols
+        );
+        &mut self.data[row * self.cols + col]
+    }
+}
+
+/// 形状不匹配时返回错误
+impl<T: Element> Mul for &Matrix<T> {
+    type Output = Result<Matrix<T>>;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        self.multiply(rhs)
+    }
+}
+
+impl<T: Element> Mul for Matrix<T> {
+    type Output = Result<Matrix<T>>;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        self.multiply(&rhs)
+    }
+}
+
+/// 形状不同时返回错误
+impl<T: Element> Add for &Matrix<T> {
+    type Output = Result<Matrix<T>>;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        if self.shape() != rhs.shape() {
+            bail!(
+                "cannot add a {}x{} matrix to a {}x{} matrix",
+                rhs.rows,
+                rhs.cols,
+                self.rows,
+                self.cols
+            );
+        }
+        let data = self
+            .data
+            .iter()
+            .zip(&rhs.data)
+            .map(|(&a, &b)| a + b)
+            .collect();
+        Ok(Matrix {
+            data,
+            rows: self.rows,
+            cols: self.cols,
+        })
+    }
+}
+
+impl<T: Element> Add for Matrix<T> {
+    type Output = Result<Matrix<T>>;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        &self + &rhs
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Matrix<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in 0..self.rows {
+            let cells = &self.data[row * self.cols..(row + 1) * self.cols];
+            for (i, cell) in cells.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(" ")?;
+                }
+                write!(f, "{}", cell)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constructors() -> Result<()> {
+        let m = Matrix::new(2, 3, [1, 2, 3, 4, 5, 6])?;
+        assert_eq!(m.shape(), (2, 3));
+        assert_eq!(m[(1, 2)], 6);
+        assert_eq!(m.get(2, 0), None);
+        assert_eq!(m.row(1), &[4, 5, 6]);
+        assert_eq!(m, Matrix::from_rows(vec![vec![1, 2, 3], vec![4, 5, 6]])?);
+        assert_eq!(m, Matrix::from_fn(2, 3, |i, j| i * 3 + j + 1));
+        assert_eq!(m.to_string(), "1 2 3\n4 5 6\n");
+
+        assert!(Matrix::new(2, 2, [1, 2, 3]).is_err());
+        assert!(Matrix::from_rows(vec![vec![1, 2], vec![3]]).is_err());
+        assert_eq!(Matrix::identity(2, 1).into_vec(), [1, 0, 0, 1]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_transpose_and_add() -> Result<()> {
+        let mut m = Matrix::new(2, 3, [1, 2, 3, 4, 5, 6])?;
+        let t = m.transpose();
+        assert_eq!(t, Matrix::new(3, 2, [1, 4, 2, 5, 3, 6])?);
+        assert!((&m + &t).is_err());
+
+        m[(0, 0)] = 10;
+        let sum = (&m + &m)?;
+        assert_eq!(sum.row(0), &[20, 4, 6]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_multiply() -> Result<()> {
+        let a = Matrix::new(2, 3, [1, 2, 3, 4, 5, 6])?;
+        let b = Matrix::new(3, 2, [10, 11, 20, 21, 30, 31])?;
+        let c = (&a * &b)?;
+        assert_eq!(c, Matrix::new(2, 2, [140, 146, 320, 335])?);
+        assert_eq!(c, a.multiply_naive(&b)?);
+
+        let err = (&a * &a).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot multiply a 2x3 matrix by a 2x3 matrix"
+        );
+
+        let empty = Matrix::<i32>::zeros(0, 3);
+        assert_eq!((&empty * &b)?.shape(), (0, 2));
+        Ok(())
+    }
+
+    #[test]
+    fn test_blocked_matches_naive() -> Result<()> {
+        // 不是块大小的整数倍，覆盖边界上的不完整块
+        let a = Matrix::from_fn(150, 70, |i, j| ((i * 7 + j * 3) % 11) as i64 - 5);
+        let b = Matrix::from_fn(70, 130, |i, j| ((i * 5 + j) % 13) as i64 - 6);
+        let expected = a.multiply_naive(&b)?;
+        assert_eq!(a.multiply_blocked(&b)?, expected);
+        let pool = ThreadPool::new(3)?;
+        assert_eq!(a.multiply_parallel(&b, &pool)?, expected);
+
+        let a = Matrix::from_fn(200, 200, |i, j| (i + j) as f64 / 100.0);
+        let identity = Matrix::identity(200, 1.0);
+        assert_eq!((&a * &identity)?, a);
+        Ok(())
+    }
+
+    #[test]
+    fn test_multiply_inside_global_pool() -> Result<()> {
+        let pool = ThreadPool::global();
+        let a = Arc::new(Matrix::from_fn(200, 200, |i, j| (i + j) as f64 / 100.0));
+
+        // 占满全局线程池的每个工作线程，乘法不能再等待同一个线程池
+        let (tx, rx) = std::sync::mpsc::channel();
+        let handles = (0..pool.size())
+            .map(|_| {
+                let a = a.clone();
+                pool.spawn(move || &*a * &Matrix::identity(200, 1.0))
+            })
+            .collect::<Vec<_>>();
+        std::thread::spawn(move || {
+            let results = handles.into_iter().map(|h| h.join()).collect::<Vec<_>>();
+            let _ = tx.send(results);
+        });
+
+        let results = rx.recv_timeout(std::time::Duration::from_secs(30))?;
+        for result in results {
+            assert_eq!(result??, *a);
+        }
+        Ok(())
+    }
+}
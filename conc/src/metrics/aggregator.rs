@@ -0,0 +1,412 @@
+use std::{
+    collections::{BTreeMap, btree_map::Entry},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use anyhow::Result;
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    net::{TcpListener, UdpSocket},
+};
+
+use super::{
+    Buckets, Histogram, HistogramSnapshot, Labels,
+    exposition::{self, Format, sanitize_metric_name},
+    registry::{FamilySnapshot, MetricKind, MetricValue, Sample},
+    statsd::{StatsdKind, StatsdLine},
+};
+
+/// 接收多个进程推送的 StatsD 数据并合并：计数器累加，仪表盘取最新值，耗时记入直方图
+#[derive(Debug, Clone)]
+pub struct Aggregator {
+    state: Arc<Mutex<State>>,
+    buckets: Buckets,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    counters: BTreeMap<String, f64>,
+    gauges: BTreeMap<String, f64>,
+    timers: BTreeMap<String, Histogram>,
+    received: u64,
+    invalid: u64,
+}
+
+impl Aggregator {
+    pub fn new() -> Self {
+        Self::with_buckets(Buckets::default())
+    }
+
+    /// 耗时直方图使用的桶，单位为秒
+    pub fn with_buckets(buckets: Buckets) -> Self {
+        Self {
+            state: Arc::default(),
+            buckets,
+        }
+    }
+
+    /// 处理一个 UDP 包或一段 TCP 数据，每行一条，返回有效的行数；无效的行只计数
+    pub fn ingest(&self, payload: &str) -> usize {
+        let mut state = self.lock();
+        let mut accepted = 0;
+        for line in payload.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            state.received += 1;
+            match line.parse::<StatsdLine>() {
+                Ok(line) if self.apply(&mut state, &line) => accepted += 1,
+                _ => state.invalid += 1,
+            }
+        }
+        accepted
+    }
+
+    pub fn counter(&self, name: &str) -> f64 {
+        self.lock().counters.get(name).copied().unwrap_or_default()
+    }
+
+    pub fn gauge(&self, name: &str) -> Option<f64> {
+        self.lock().gauges.get(name).copied()
+    }
+
+    pub fn timer(&self, name: &str) -> Option<HistogramSnapshot> {
+        self.lock().timers.get(name).map(Histogram::snapshot)
+    }
+
+    /// 计数器可能因为进程调用 `dec` 而减小，因此和仪表盘一样输出为 gauge，名称加上 `_total` 后缀；
+    /// 耗时的名称加上 `_seconds` 后缀。
+    ///
+    /// 不合法的字符替换为 `_` 后，多个指标可能落到同一个族：类型相同时合并，用 `statsd_name` 标签区分；
+    /// 类型不同时保留先出现的族（内置指标、计数器、仪表盘、耗时依次处理），其余的丢弃并记录警告
+    pub fn families(&self) -> Vec<FamilySnapshot> {
+        let state = self.lock();
+        let mut families = BTreeMap::new();
+        for (name, help, value) in [
+            (
+                "statsd_lines_received_total",
+                "Lines received",
+                state.received,
+            ),
+            (
+                "statsd_lines_invalid_total",
+                "Lines rejected",
+                state.invalid,
+            ),
+        ] {
+            families.insert(
+                name.to_string(),
+                FamilySnapshot {
+                    name: name.to_string(),
+                    help: help.to_string(),
+                    kind: MetricKind::Counter,
+                    samples: vec![Sample {
+                        labels: Labels::default(),
+                        value: MetricValue::Counter(value),
+                    }],
+                },
+            );
+        }
+
+        let counters = state.counters.iter().map(|(name, value)| {
+            let family = sanitize_metric_name(name);
+            let family = match family.ends_with("_total") {
+                true => family,
+                false => format!("{}_total", family),
+            };
+            (family, name, MetricKind::Gauge, MetricValue::Gauge(*value))
+        });
+        let gauges = state.gauges.iter().map(|(name, value)| {
+            let family = sanitize_metric_name(name);
+            (family, name, MetricKind::Gauge, MetricValue::Gauge(*value))
+        });
+        let timers = state.timers.iter().map(|(name, histogram)| {
+            let family = format!("{}_seconds", sanitize_metric_name(name));
+            let value = MetricValue::Histogram(histogram.snapshot());
+            (family, name, MetricKind::Histogram, value)
+        });
+        for (family, name, kind, value) in counters.chain(gauges).chain(timers) {
+            let sample = Sample {
+                labels: Labels::new([("statsd_name", name.as_str())]),
+                value,
+            };
+            match families.entry(family) {
+                Entry::Vacant(entry) => {
+                    let name = entry.key().clone();
+                    entry.insert(FamilySnapshot {
+                        name,
+                        help: String::new(),
+                        kind,
+                        samples: vec![sample],
+                    });
+                }
+                Entry::Occupied(entry) if entry.get().kind == kind => {
+                    entry.into_mut().samples.push(sample)
+                }
+                Entry::Occupied(entry) => tracing::warn!(
+                    "statsd metric {} dropped: family {} is a {:?}",
+                    name,
+                    entry.key(),
+                    entry.get().kind
+                ),
+            }
+        }
+
+        families
+            .into_values()
+            .map(|mut family| {
+                // 没有冲突时不加标签
+                if let [sample] = family.samples.as_mut_slice() {
+                    sample.labels = Labels::default();
+                }
+                family.samples.sort_by(|a, b| a.labels.cmp(&b.labels));
+                family
+            })
+            .collect()
+    }
+
+    pub fn render(&self, format: Format) -> String {
+        exposition::render(&self.families(), format)
+    }
+
+    /// 每个 UDP 包独立处理
+    pub async fn serve_udp(self, socket: UdpSocket) -> Result<()> {
+        let mut buf = vec![0; 65536];
+        loop {
+            let (n, _) = socket.recv_from(&mut buf).await?;
+            self.ingest(&String::from_utf8_lossy(&buf[..n]));
+        }
+    }
+
+    /// 每个连接一个任务，按行读取
+    pub async fn serve_tcp(self, listener: TcpListener) -> Result<()> {
+        loop {
+            let (stream, addr) = listener.accept().await?;
+            let aggregator = self.clone();
+            tokio::spawn(async move {
+                let mut lines = BufReader::new(stream).lines();
+                loop {
+                    match lines.next_line().await {
+                        Ok(Some(line)) => {
+                            aggregator.ingest(&line);
+                        }
+                        Ok(None) => break,
+                        Err(e) => {
+                            tracing::warn!("read from {} failed: {}", addr, e);
+                            break;
+                        }
+                    }
+                }
+            });
+        }
+    }
+
+    /// 提供 `GET /metrics`
+    #[cfg(feature = "http")]
+    pub fn router<S>(&self) -> axum::Router<S>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        let aggregator = self.clone();
+        axum::Router::new().route(
+            "/metrics",
+            axum::routing::get(move |headers: axum::http::HeaderMap| async move {
+                super::http::render_families(&aggregator.families(), &headers)
+            }),
+        )
+    }
+
+    fn apply(&self, state: &mut State, line: &StatsdLine) -> bool {
+        match line.kind {
+            StatsdKind::Counter => {
+                *state.counters.entry(line.name.clone()).or_default() +=
+                    line.value / line.sample_rate;
+            }
+            StatsdKind::Gauge => {
+                let gauge = state.gauges.entry(line.name.clone()).or_default();
+                if line.delta {
+                    *gauge += line.value;
+                } else {
+                    *gauge = line.value;
+                }
+            }
+            StatsdKind::Timer => {
+                if !state.timers.contains_key(&line.name) {
+                    match Histogram::new(&self.buckets) {
+                        Ok(histogram) => state.timers.insert(line.name.clone(), histogram),
+                        Err(_) => return false,
+                    };
+                }
+                state.timers[&line.name].observe(line.value / 1000.0);
+            }
+        }
+        true
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Default for Aggregator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::metrics::{
+        amap::AmapMetrics,
+        statsd::{StatsdPusher, Transport},
+    };
+
+    #[test]
+    fn test_ingest() {
+        let aggregator = Aggregator::new();
+        let accepted =
+            aggregator.ingest("hits:2|c\nhits:1|c|@0.5\nbad line\n\nqueue:5|g\nqueue:-2|g\n");
+        assert_eq!(accepted, 4);
+        assert_eq!(aggregator.counter("hits"), 4.0);
+        assert_eq!(aggregator.gauge("queue"), Some(3.0));
+
+        aggregator.ingest("db.query:20|ms\ndb.query:40|ms");
+        let timer = aggregator.timer("db.query").unwrap();
+        assert_eq!(timer.count, 2);
+        assert!((timer.sum - 0.06).abs() < 1e-9);
+
+        let text = aggregator.render(Format::Prometheus);
+        assert!(text.contains("hits_total 4\n"), "{}", text);
+        assert!(text.contains("queue 3\n"), "{}", text);
+        assert!(text.contains("db_query_seconds_count 2\n"), "{}", text);
+        assert!(text.contains("statsd_lines_received_total 7\n"), "{}", text);
+        assert!(text.contains("statsd_lines_invalid_total 1\n"), "{}", text);
+    }
+
+    #[test]
+    fn test_families_do_not_collide() {
+        let aggregator = Aggregator::new();
+        aggregator.ingest(concat!(
+            "jobs:3|c\njobs:7|g\n",
+            "db.query:2|c\ndb_query:5|c\n",
+            "db.query:20|ms\ndb_query_seconds:1|g\n",
+            "statsd.lines.received:1|c\n",
+        ));
+        let families = aggregator.families();
+        let names = families.iter().map(|f| f.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "db_query_seconds",
+                "db_query_total",
+                "jobs",
+                "jobs_total",
+                "statsd_lines_invalid_total",
+                "statsd_lines_received_total",
+            ]
+        );
+
+        // 同名的计数器和仪表盘分别输出
+        let text = aggregator.render(Format::Prometheus);
+        assert!(text.contains("jobs 7\n"), "{}", text);
+        assert!(text.contains("jobs_total 3\n"), "{}", text);
+        // 名称替换后相同的计数器合并为一个族
+        assert!(
+            text.contains("db_query_total{statsd_name=\"db.query\"} 2\n"),
+            "{}",
+            text
+        );
+        assert!(
+            text.contains("db_query_total{statsd_name=\"db_query\"} 5\n"),
+            "{}",
+            text
+        );
+        // 类型冲突时保留先出现的族
+        assert_eq!(text.matches("# TYPE db_query_seconds ").count(), 1);
+        assert!(text.contains("db_query_seconds 1\n"), "{}", text);
+        assert!(!text.contains("db_query_seconds_count"), "{}", text);
+        assert!(text.contains("statsd_lines_received_total 7\n"), "{}", text);
+    }
+
+    /// 等待聚合端收到数据，UDP 包到达的时间不确定
+    async fn wait_for(aggregator: &Aggregator, name: &str, expected: f64) {
+        for _ in 0..100 {
+            if aggregator.counter(name) == expected {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!(
+            "{} = {}, expected {}",
+            name,
+            aggregator.counter(name),
+            expected
+        );
+    }
+
+    #[tokio::test]
+    async fn test_push_over_localhost() -> Result<()> {
+        let aggregator = Aggregator::new();
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let socket = UdpSocket::bind(addr).await?;
+        tokio::spawn(aggregator.clone().serve_tcp(listener));
+        tokio::spawn(aggregator.clone().serve_udp(socket));
+
+        // 两个“进程”分别通过 UDP 和 TCP 推送同名指标
+        let (chat, notify) = (AmapMetrics::new(), AmapMetrics::new());
+        let mut udp = StatsdPusher::new(chat.clone(), addr, Transport::Udp);
+        let mut tcp = StatsdPusher::new(notify.clone(), addr, Transport::Tcp);
+
+        chat.inc("requests", 5);
+        notify.inc("requests", 3);
+        notify.inc("connections", 2);
+        assert_eq!(udp.flush().await?, 1);
+        assert_eq!(tcp.flush().await?, 2);
+        wait_for(&aggregator, "requests", 8.0).await;
+        wait_for(&aggregator, "connections", 2.0).await;
+
+        // 只推送增量
+        chat.inc("requests", 1);
+        notify.dec("connections", 1);
+        assert_eq!(udp.flush().await?, 1);
+        assert_eq!(tcp.flush().await?, 1);
+        assert_eq!(tcp.flush().await?, 0);
+        wait_for(&aggregator, "requests", 9.0).await;
+        wait_for(&aggregator, "connections", 1.0).await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_tcp_retry_keeps_deltas() -> Result<()> {
+        // 先占一个端口再释放，确保连接失败
+        let addr = TcpListener::bind("127.0.0.1:0").await?.local_addr()?;
+        let metrics = AmapMetrics::new();
+        let mut pusher = StatsdPusher::new(metrics.clone(), addr, Transport::Tcp);
+        metrics.inc("jobs", 4);
+        assert!(pusher.flush().await.is_err());
+
+        let aggregator = Aggregator::new();
+        let listener = TcpListener::bind(addr).await?;
+        tokio::spawn(aggregator.clone().serve_tcp(listener));
+        assert_eq!(pusher.flush().await?, 1);
+        wait_for(&aggregator, "jobs", 4.0).await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_spawn_pushes_periodically() -> Result<()> {
+        let aggregator = Aggregator::new();
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(aggregator.clone().serve_tcp(listener));
+
+        let metrics = AmapMetrics::new();
+        let pusher = StatsdPusher::new(metrics.clone(), addr, Transport::Tcp).with_prefix("crm");
+        let handle = pusher.spawn(Duration::from_millis(50));
+        metrics.inc("users", 7);
+        wait_for(&aggregator, "crm.users", 7.0).await;
+        handle.abort();
+        Ok(())
+    }
+}
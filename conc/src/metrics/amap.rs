@@ -1,6 +1,9 @@
-use std::sync::{
-    Arc,
-    atomic::{AtomicI64, Ordering},
+use std::{
+    collections::BTreeMap,
+    sync::{
+        Arc,
+        atomic::{AtomicI64, Ordering},
+    },
 };
 
 use dashmap::DashMap;
@@ -10,6 +13,10 @@ pub struct AmapMetrics {
     pub data: Arc<DashMap<String, Arc<AtomicI64>>>,
 }
 
+/// 预先注册的计数器句柄，只操作原子变量，不再查找 key
+#[derive(Debug, Clone)]
+pub struct AmapCounter(Arc<AtomicI64>);
+
 impl AmapMetrics {
     pub fn new() -> Self {
         Self {
@@ -17,33 +24,98 @@ impl AmapMetrics {
         }
     }
 
+    /// 返回的句柄与 `inc`/`dec` 操作的是同一个计数器
+    pub fn counter(&self, key: &str) -> AmapCounter {
+        if let Some(counter) = self.data.get(key) {
+            return AmapCounter(counter.clone());
+        }
+        AmapCounter(self.data.entry(key.to_string()).or_default().clone())
+    }
+
     pub fn inc(&self, key: &str, value: i64) {
-        let counter = self
-            .data
-            .entry(key.to_string())
-            .or_insert_with(|| Arc::new(AtomicI64::new(0)));
-        counter.fetch_add(value, Ordering::Relaxed);
+        // key 已存在时只需要读锁，也不用分配 String
+        match self.data.get(key) {
+            Some(counter) => counter.fetch_add(value, Ordering::Relaxed),
+            None => self.counter(key).0.fetch_add(value, Ordering::Relaxed),
+        };
     }
 
     pub fn dec(&self, key: &str, value: i64) {
-        let counter = self
-            .data
-            .entry(key.to_string())
-            .or_insert_with(|| Arc::new(AtomicI64::new(0)));
-        counter.fetch_sub(value, Ordering::Relaxed);
+        self.inc(key, value.wrapping_neg());
+    }
+
+    /// 按 key 排序的当前值
+    pub fn snapshot(&self) -> BTreeMap<String, i64> {
+        self.data
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().load(Ordering::Relaxed)))
+            .collect()
     }
 
     pub fn read(&self) {
-        for entry in self.data.iter() {
-            let key = entry.key().clone();
-            let counter = entry.value().clone();
-            println!("{key}: {:?}", counter.load(Ordering::Relaxed));
+        for (key, value) in self.snapshot() {
+            println!("{key}: {:?}", value);
         }
     }
 }
 
+impl AmapCounter {
+    pub fn inc(&self) {
+        self.add(1);
+    }
+
+    pub fn dec(&self) {
+        self.add(-1);
+    }
+
+    pub fn add(&self, value: i64) {
+        self.0.fetch_add(value, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> i64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
 impl Default for AmapMetrics {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_amap_snapshot() {
+        let metrics = AmapMetrics::new();
+        metrics.inc("b", 3);
+        metrics.inc("a", 1);
+        metrics.dec("b", 1);
+        assert_eq!(
+            metrics.snapshot(),
+            BTreeMap::from([("a".to_string(), 1), ("b".to_string(), 2)])
+        );
+    }
+
+    #[test]
+    fn test_amap_counter_handle() {
+        let metrics = AmapMetrics::new();
+        metrics.inc("hits", 2);
+        let hits = metrics.counter("hits");
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                let hits = hits.clone();
+                s.spawn(move || {
+                    for _ in 0..1000 {
+                        hits.inc();
+                    }
+                });
+            }
+        });
+        hits.dec();
+        assert_eq!(hits.get(), 4001);
+        assert_eq!(metrics.snapshot()["hits"], 4001);
+    }
+}
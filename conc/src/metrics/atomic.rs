@@ -0,0 +1,32 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// 以 `AtomicU64` 存储 f64 的位模式，`add` 使用 CAS 循环
+#[derive(Debug, Default)]
+pub struct AtomicF64(AtomicU64);
+
+impl AtomicF64 {
+    pub fn new(value: f64) -> Self {
+        Self(AtomicU64::new(value.to_bits()))
+    }
+
+    pub fn load(&self) -> f64 {
+        f64::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    pub fn store(&self, value: f64) {
+        self.0.store(value.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn add(&self, value: f64) {
+        let _ = self
+            .0
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+                Some((f64::from_bits(bits) + value).to_bits())
+            });
+    }
+
+    /// 返回旧值
+    pub fn swap(&self, value: f64) -> f64 {
+        f64::from_bits(self.0.swap(value.to_bits(), Ordering::Relaxed))
+    }
+}
@@ -0,0 +1,99 @@
+use std::{
+    fmt::Debug,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use tokio::{task::JoinHandle, time};
+
+/// 时间窗口指标使用的时钟，返回从某个固定起点开始经过的时间
+pub trait Clock: Debug + Send + Sync + 'static {
+    fn now(&self) -> Duration;
+}
+
+/// 基于 `Instant` 的单调时钟
+#[derive(Debug, Clone, Copy)]
+pub struct SystemClock(Instant);
+
+/// 只有调用 `advance` 时才前进，用于测试，或由 tokio interval 驱动
+#[derive(Debug, Clone, Default)]
+pub struct ManualClock(Arc<AtomicU64>);
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self(Instant::now())
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.0.elapsed()
+    }
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        let nanos = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+        self.0.fetch_add(nanos, Ordering::Relaxed);
+    }
+
+    /// 每隔 `period` 前进 `period`，窗口只在 tick 时轮转；需要在 tokio 运行时中调用
+    pub fn spawn_ticker(&self, period: Duration) -> JoinHandle<()> {
+        let clock = self.clone();
+        tokio::spawn(async move {
+            let mut interval = time::interval(period);
+            // 第一次 tick 立即完成
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                clock.advance(period);
+            }
+        })
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.0.load(Ordering::Relaxed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manual_clock() {
+        let clock = ManualClock::new();
+        let shared = clock.clone();
+        assert_eq!(clock.now(), Duration::ZERO);
+        shared.advance(Duration::from_millis(1500));
+        assert_eq!(clock.now(), Duration::from_millis(1500));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_spawn_ticker() {
+        let clock = ManualClock::new();
+        let ticker = clock.spawn_ticker(Duration::from_secs(1));
+
+        time::sleep(Duration::from_millis(3500)).await;
+        assert_eq!(clock.now(), Duration::from_secs(3));
+
+        ticker.abort();
+        time::sleep(Duration::from_secs(2)).await;
+        assert_eq!(clock.now(), Duration::from_secs(3));
+    }
+}
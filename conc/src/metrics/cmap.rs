@@ -1,4 +1,5 @@
 use std::{
+    collections::BTreeMap,
     fmt::Debug,
     ops::{Add, AddAssign, Sub, SubAssign},
     sync::Arc,
@@ -22,18 +23,31 @@ where
     }
 
     pub fn inc(&self, key: &str, value: T) {
-        let mut counter = self.data.entry(key.to_string()).or_default();
-        *counter += value;
+        // key 已存在时不用分配 String
+        match self.data.get_mut(key) {
+            Some(mut counter) => *counter += value,
+            None => *self.data.entry(key.to_string()).or_default() += value,
+        }
     }
 
     pub fn dec(&self, key: &str, value: T) {
-        let mut counter = self.data.entry(key.to_string()).or_default();
-        *counter -= value;
+        match self.data.get_mut(key) {
+            Some(mut counter) => *counter -= value,
+            None => *self.data.entry(key.to_string()).or_default() -= value,
+        }
+    }
+
+    /// 按 key 排序的当前值
+    pub fn snapshot(&self) -> BTreeMap<String, T> {
+        self.data
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect()
     }
 
     pub fn read(&self) {
-        for entry in self.data.iter() {
-            println!("{}: {:?}", entry.key(), entry.value());
+        for (key, value) in self.snapshot() {
+            println!("{}: {:?}", key, value);
         }
     }
 }
@@ -46,3 +60,19 @@ where
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cmap_snapshot() {
+        let metrics = CmapMetrics::<isize>::new();
+        metrics.inc("b", 3);
+        metrics.dec("a", 1);
+        assert_eq!(
+            metrics.snapshot(),
+            BTreeMap::from([("a".to_string(), -1), ("b".to_string(), 3)])
+        );
+    }
+}
@@ -0,0 +1,288 @@
+use std::fmt::Write;
+
+use super::{
+    HistogramSnapshot, Labels,
+    amap::AmapMetrics,
+    cmap::CmapMetrics,
+    registry::{FamilySnapshot, MetricKind, MetricValue, Sample},
+};
+
+/// 指标的文本格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    /// Prometheus text format 0.0.4
+    #[default]
+    Prometheus,
+    /// OpenMetrics 1.0：计数器样本带 `_total` 后缀，以 `# EOF` 结尾
+    OpenMetrics,
+}
+
+impl Format {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Format::Prometheus => "text/plain; version=0.0.4; charset=utf-8",
+            Format::OpenMetrics => "application/openmetrics-text; version=1.0.0; charset=utf-8",
+        }
+    }
+
+    /// 根据请求的 Accept 头选择格式，Prometheus 抓取时会优先请求 OpenMetrics
+    pub fn from_accept(accept: &str) -> Self {
+        if accept.contains("application/openmetrics-text") {
+            Format::OpenMetrics
+        } else {
+            Format::Prometheus
+        }
+    }
+}
+
+/// 可以作为仪表盘值输出的数值类型
+pub trait MetricNumber {
+    fn to_f64(&self) -> f64;
+}
+
+macro_rules! impl_metric_number {
+    ($($t:ty),*) => {
+        $(impl MetricNumber for $t {
+            fn to_f64(&self) -> f64 {
+                *self as f64
+            }
+        })*
+    };
+}
+
+impl_metric_number!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, f32, f64);
+
+pub fn render(families: &[FamilySnapshot], format: Format) -> String {
+    let mut out = String::new();
+    for family in families {
+        render_family(&mut out, family, format);
+    }
+    if format == Format::OpenMetrics {
+        out.push_str("# EOF\n");
+    }
+    out
+}
+
+/// 把 `AmapMetrics`/`CmapMetrics` 这类扁平的 key-value 转成没有标签的仪表盘，key 中不合法的字符替换为 `_`
+pub fn gauges<K, V>(values: impl IntoIterator<Item = (K, V)>) -> Vec<FamilySnapshot>
+where
+    K: AsRef<str>,
+    V: MetricNumber,
+{
+    let mut families: Vec<_> = values
+        .into_iter()
+        .map(|(key, value)| FamilySnapshot {
+            name: sanitize_metric_name(key.as_ref()),
+            help: String::new(),
+            kind: MetricKind::Gauge,
+            samples: vec![Sample {
+                labels: Labels::default(),
+                value: MetricValue::Gauge(value.to_f64()),
+            }],
+        })
+        .collect();
+    families.sort_by(|a, b| a.name.cmp(&b.name));
+    families
+}
+
+pub fn sanitize_metric_name(name: &str) -> String {
+    let mut sanitized: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == ':' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if !sanitized.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == ':') {
+        sanitized.insert(0, '_');
+    }
+    sanitized
+}
+
+impl AmapMetrics {
+    pub fn families(&self) -> Vec<FamilySnapshot> {
+        gauges(self.snapshot())
+    }
+
+    pub fn render(&self, format: Format) -> String {
+        render(&self.families(), format)
+    }
+}
+
+impl<T> CmapMetrics<T>
+where
+    T: MetricNumber,
+{
+    pub fn families(&self) -> Vec<FamilySnapshot> {
+        gauges(
+            self.data
+                .iter()
+                .map(|entry| (entry.key().clone(), entry.value().to_f64()))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    pub fn render(&self, format: Format) -> String {
+        render(&self.families(), format)
+    }
+}
+
+fn render_family(out: &mut String, family: &FamilySnapshot, format: Format) {
+    let name = family.name.as_str();
+    // OpenMetrics 的计数器族名不带 `_total`，样本名带
+    let (family_name, counter_name) = match (format, family.kind) {
+        (Format::OpenMetrics, MetricKind::Counter) => {
+            let base = name.strip_suffix("_total").unwrap_or(name);
+            (base.to_string(), format!("{}_total", base))
+        }
+        _ => (name.to_string(), name.to_string()),
+    };
+    let is_summary = family.samples.iter().any(|s| match &s.value {
+        MetricValue::Histogram(h) => h.buckets.is_empty(),
+        _ => false,
+    });
+    let kind = match family.kind {
+        MetricKind::Counter => "counter",
+        MetricKind::Gauge => "gauge",
+        // HDR 直方图只有分位数，按 summary 输出
+        MetricKind::Histogram if is_summary => "summary",
+        MetricKind::Histogram => "histogram",
+    };
+
+    if !family.help.is_empty() {
+        let help = family.help.replace('\\', "\\\\").replace('\n', "\\n");
+        let _ = writeln!(out, "# HELP {} {}", family_name, help);
+    }
+    let _ = writeln!(out, "# TYPE {} {}", family_name, kind);
+
+    for sample in &family.samples {
+        let labels = &sample.labels;
+        match &sample.value {
+            MetricValue::Counter(v) => {
+                let _ = writeln!(out, "{}{} {}", counter_name, labels, v);
+            }
+            MetricValue::Gauge(v) => {
+                let _ = writeln!(out, "{}{} {}", name, labels, format_float(*v));
+            }
+            MetricValue::Histogram(h) => render_histogram(out, name, labels, h),
+        }
+    }
+}
+
+fn render_histogram(out: &mut String, name: &str, labels: &Labels, h: &HistogramSnapshot) {
+    for (le, count) in &h.buckets {
+        let labels = labels.with("le", format_float(*le));
+        let _ = writeln!(out, "{}_bucket{} {}", name, labels, count);
+    }
+    for (q, value) in &h.quantiles {
+        let labels = labels.with("quantile", format_float(*q));
+        let _ = writeln!(out, "{}{} {}", name, labels, format_float(*value));
+    }
+    let _ = writeln!(out, "{}_sum{} {}", name, labels, format_float(h.sum));
+    let _ = writeln!(out, "{}_count{} {}", name, labels, h.count);
+}
+
+fn format_float(v: f64) -> String {
+    if v.is_nan() {
+        "NaN".to_string()
+    } else if v == f64::INFINITY {
+        "+Inf".to_string()
+    } else if v == f64::NEG_INFINITY {
+        "-Inf".to_string()
+    } else {
+        v.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::{Buckets, Registry};
+
+    fn registry() -> anyhow::Result<Registry> {
+        let registry = Registry::new();
+        let requests = registry.counter_vec("http_requests_total", "HTTP requests", &["method"])?;
+        requests.with_label_values(&["GET"])?.inc_by(3);
+        registry
+            .gauge("temperature", "multi\nline \\ help")?
+            .set(-1.5);
+        let latency = registry.histogram("latency_seconds", "", Buckets::Fixed(vec![0.1, 1.0]))?;
+        latency.observe(0.05);
+        latency.observe(0.5);
+        Ok(registry)
+    }
+
+    #[test]
+    fn test_render_prometheus() -> anyhow::Result<()> {
+        let text = render(&registry()?.snapshot(), Format::Prometheus);
+        assert_eq!(
+            text,
+            "# HELP http_requests_total HTTP requests\n\
+             # TYPE http_requests_total counter\n\
+             http_requests_total{method=\"GET\"} 3\n\
+             # TYPE latency_seconds histogram\n\
+             latency_seconds_bucket{le=\"0.1\"} 1\n\
+             latency_seconds_bucket{le=\"1\"} 2\n\
+             latency_seconds_bucket{le=\"+Inf\"} 2\n\
+             latency_seconds_sum 0.55\n\
+             latency_seconds_count 2\n\
+             # HELP temperature multi\\nline \\\\ help\n\
+             # TYPE temperature gauge\n\
+             temperature -1.5\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_openmetrics() -> anyhow::Result<()> {
+        let registry = registry()?;
+        let latency =
+            registry.histogram_vec("rpc_seconds", "", &["service"], Buckets::hdr(10.0, 1e-3, 3))?;
+        latency.with_label_values(&["crm"])?.observe(0.25);
+
+        let text = render(&registry.snapshot(), Format::OpenMetrics);
+        assert!(text.starts_with(
+            "# HELP http_requests HTTP requests\n\
+             # TYPE http_requests counter\n\
+             http_requests_total{method=\"GET\"} 3\n"
+        ));
+        assert!(text.contains(
+            "# TYPE rpc_seconds summary\n\
+             rpc_seconds{service=\"crm\",quantile=\"0.5\"} 0.25\n"
+        ));
+        assert!(text.contains("rpc_seconds_count{service=\"crm\"} 1\n"));
+        assert!(text.ends_with("# EOF\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_maps() {
+        let amap = AmapMetrics::new();
+        amap.inc("call.thread.worker.0", 2);
+        amap.dec("1st", 1);
+        assert_eq!(
+            amap.render(Format::Prometheus),
+            "# TYPE _1st gauge\n_1st -1\n\
+             # TYPE call_thread_worker_0 gauge\ncall_thread_worker_0 2\n"
+        );
+
+        let cmap = CmapMetrics::<f64>::new();
+        cmap.inc("ratio", 0.25);
+        assert_eq!(
+            cmap.render(Format::OpenMetrics),
+            "# TYPE ratio gauge\nratio 0.25\n# EOF\n"
+        );
+    }
+
+    #[test]
+    fn test_format_from_accept() {
+        assert_eq!(
+            Format::from_accept("application/openmetrics-text;version=1.0.0,text/plain;q=0.5"),
+            Format::OpenMetrics
+        );
+        assert_eq!(Format::from_accept("*/*"), Format::Prometheus);
+    }
+}
@@ -0,0 +1,459 @@
+use std::{
+    sync::{
+        Arc, Mutex, PoisonError,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use anyhow::{Result, anyhow, bail};
+
+use super::atomic::AtomicF64;
+
+/// 与 Prometheus 客户端相同的默认桶，适合以秒为单位的请求延迟
+pub const DEFAULT_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// HDR 直方图快照中导出的分位数
+pub const DEFAULT_QUANTILES: [f64; 4] = [0.5, 0.9, 0.99, 0.999];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Buckets {
+    /// 各个桶的上界（不含 +Inf），必须严格递增
+    Fixed(Vec<f64>),
+    /// 值按 `resolution` 换算成整数后记录，`max` 为可记录的最大值，`sigfig` 为有效位数（0..=5）
+    Hdr {
+        max: f64,
+        resolution: f64,
+        sigfig: u8,
+    },
+}
+
+impl Buckets {
+    /// `start, start + width, ...` 共 `count` 个桶
+    pub fn linear(start: f64, width: f64, count: usize) -> Self {
+        Self::Fixed((0..count).map(|i| start + width * i as f64).collect())
+    }
+
+    /// `start, start * factor, ...` 共 `count` 个桶
+    pub fn exponential(start: f64, factor: f64, count: usize) -> Self {
+        Self::Fixed((0..count).map(|i| start * factor.powi(i as i32)).collect())
+    }
+
+    pub fn hdr(max: f64, resolution: f64, sigfig: u8) -> Self {
+        Self::Hdr {
+            max,
+            resolution,
+            sigfig,
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            Buckets::Fixed(bounds) => {
+                if bounds.is_empty() {
+                    bail!("histogram needs at least one bucket");
+                }
+                if bounds.iter().any(|b| !b.is_finite()) {
+                    bail!("bucket bounds must be finite, +Inf is added automatically");
+                }
+                if bounds.windows(2).any(|w| w[0] >= w[1]) {
+                    bail!("bucket bounds must be strictly increasing: {:?}", bounds);
+                }
+                Ok(())
+            }
+            Buckets::Hdr { .. } => self.new_hdr().map(|_| ()),
+        }
+    }
+
+    fn new_hdr(&self) -> Result<hdrhistogram::Histogram<u64>> {
+        let Buckets::Hdr {
+            max,
+            resolution,
+            sigfig,
+        } = *self
+        else {
+            bail!("not an HDR histogram");
+        };
+        if !(resolution > 0.0 && max > resolution) {
+            bail!("HDR histogram needs 0 < resolution < max, got {resolution} and {max}");
+        }
+        let high = (max / resolution).ceil() as u64;
+        hdrhistogram::Histogram::new_with_max(high.max(2), sigfig)
+            .map_err(|e| anyhow!("invalid HDR histogram: {:?}", e))
+    }
+}
+
+impl Default for Buckets {
+    fn default() -> Self {
+        Self::Fixed(DEFAULT_BUCKETS.to_vec())
+    }
+}
+
+/// 直方图，克隆后共享同一份数据
+#[derive(Debug, Clone)]
+pub struct Histogram(Arc<Inner>);
+
+#[derive(Debug)]
+enum Inner {
+    Fixed {
+        bounds: Vec<f64>,
+        // 最后一个为 +Inf 桶
+        counts: Vec<AtomicU64>,
+        sum: AtomicF64,
+    },
+    Hdr {
+        hist: Mutex<hdrhistogram::Histogram<u64>>,
+        resolution: f64,
+        sum: AtomicF64,
+    },
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HistogramSnapshot {
+    pub count: u64,
+    pub sum: f64,
+    /// 固定桶：(上界, 累计数量)，最后一个上界为 +Inf
+    pub buckets: Vec<(f64, u64)>,
+    /// HDR：(分位数, 值)
+    pub quantiles: Vec<(f64, f64)>,
+}
+
+impl Histogram {
+    pub fn new(buckets: &Buckets) -> Result<Self> {
+        buckets.validate()?;
+        let inner = match buckets {
+            Buckets::Fixed(bounds) => Inner::Fixed {
+                bounds: bounds.clone(),
+                counts: (0..=bounds.len()).map(|_| AtomicU64::new(0)).collect(),
+                sum: AtomicF64::default(),
+            },
+            Buckets::Hdr { resolution, .. } => Inner::Hdr {
+                hist: Mutex::new(buckets.new_hdr()?),
+                resolution: *resolution,
+                sum: AtomicF64::default(),
+            },
+        };
+        Ok(Self(Arc::new(inner)))
+    }
+
+    /// NaN 会被忽略；HDR 直方图中负值记为 0，超过上限的值记为上限
+    pub fn observe(&self, value: f64) {
+        if value.is_nan() {
+            return;
+        }
+        match &*self.0 {
+            Inner::Fixed {
+                bounds,
+                counts,
+                sum,
+            } => {
+                let idx = bounds.partition_point(|b| *b < value);
+                counts[idx].fetch_add(1, Ordering::Relaxed);
+                sum.add(value);
+            }
+            Inner::Hdr {
+                hist,
+                resolution,
+                sum,
+            } => {
+                let v = (value / resolution).round().max(0.0) as u64;
+                hist.lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .saturating_record(v);
+                sum.add(value);
+            }
+        }
+    }
+
+    /// 以秒为单位记录
+    pub fn observe_duration(&self, duration: Duration) {
+        self.observe(duration.as_secs_f64());
+    }
+
+    /// 固定桶在桶内线性插值，HDR 直方图按其精度返回
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        match &*self.0 {
+            Inner::Fixed { .. } => self.snapshot().quantile(q),
+            Inner::Hdr {
+                hist, resolution, ..
+            } => {
+                let hist = hist.lock().unwrap_or_else(PoisonError::into_inner);
+                if hist.is_empty() {
+                    return None;
+                }
+                Some(hist.value_at_quantile(q.clamp(0.0, 1.0)) as f64 * resolution)
+            }
+        }
+    }
+
+    /// 各个计数器分别读取，并发写入时快照中的 `sum` 与 `count` 可能相差一次观测
+    pub fn snapshot(&self) -> HistogramSnapshot {
+        Self::merge([self])
+    }
+
+    pub fn reset(&self) {
+        match &*self.0 {
+            Inner::Fixed { counts, sum, .. } => {
+                counts.iter().for_each(|c| c.store(0, Ordering::Relaxed));
+                sum.store(0.0);
+            }
+            Inner::Hdr { hist, sum, .. } => {
+                hist.lock().unwrap_or_else(PoisonError::into_inner).reset();
+                sum.store(0.0);
+            }
+        }
+    }
+
+    /// 合并多个直方图的快照，用于时间窗口；与第一个直方图配置不同的会被忽略
+    pub fn merge<'a>(histograms: impl IntoIterator<Item = &'a Histogram>) -> HistogramSnapshot {
+        let mut histograms = histograms.into_iter().peekable();
+        let Some(first) = histograms.peek() else {
+            return HistogramSnapshot::default();
+        };
+
+        match &*first.0 {
+            Inner::Fixed { bounds, .. } => {
+                let bounds = bounds.clone();
+                let mut counts = vec![0; bounds.len() + 1];
+                let mut total = 0.0;
+                for h in histograms {
+                    if let Inner::Fixed {
+                        bounds: b,
+                        counts: c,
+                        sum,
+                    } = &*h.0
+                        && *b == bounds
+                    {
+                        for (acc, count) in counts.iter_mut().zip(c) {
+                            *acc += count.load(Ordering::Relaxed);
+                        }
+                        total += sum.load();
+                    }
+                }
+
+                let mut cumulative = 0;
+                let buckets = bounds
+                    .iter()
+                    .copied()
+                    .chain([f64::INFINITY])
+                    .zip(counts)
+                    .map(|(bound, count)| {
+                        cumulative += count;
+                        (bound, cumulative)
+                    })
+                    .collect();
+                HistogramSnapshot {
+                    count: cumulative,
+                    sum: total,
+                    buckets,
+                    quantiles: vec![],
+                }
+            }
+            Inner::Hdr {
+                hist, resolution, ..
+            } => {
+                let resolution = *resolution;
+                let mut merged = hist.lock().unwrap_or_else(PoisonError::into_inner).clone();
+                merged.reset();
+                let mut total = 0.0;
+                for h in histograms {
+                    if let Inner::Hdr {
+                        hist,
+                        resolution: r,
+                        sum,
+                    } = &*h.0
+                        && *r == resolution
+                    {
+                        let hist = hist.lock().unwrap_or_else(PoisonError::into_inner);
+                        if merged.add(&*hist).is_ok() {
+                            total += sum.load();
+                        }
+                    }
+                }
+
+                let quantiles = if merged.is_empty() {
+                    vec![]
+                } else {
+                    DEFAULT_QUANTILES
+                        .iter()
+                        .map(|q| (*q, merged.value_at_quantile(*q) as f64 * resolution))
+                        .collect()
+                };
+                HistogramSnapshot {
+                    count: merged.len(),
+                    sum: total,
+                    buckets: vec![],
+                    quantiles,
+                }
+            }
+        }
+    }
+}
+
+impl HistogramSnapshot {
+    /// 与 PromQL 的 `histogram_quantile` 相同：在目标桶内线性插值，落在 +Inf 桶时返回最大的有限上界。
+    /// HDR 快照只能查询导出的分位数
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+        if self.buckets.is_empty() {
+            return self
+                .quantiles
+                .iter()
+                .find(|(quantile, _)| *quantile == q)
+                .map(|(_, value)| *value);
+        }
+
+        let rank = q.clamp(0.0, 1.0) * self.count as f64;
+        let idx = self
+            .buckets
+            .iter()
+            .position(|(_, count)| *count as f64 >= rank)?;
+        let (upper, count) = self.buckets[idx];
+        if upper.is_infinite() {
+            return Some(self.buckets[idx.saturating_sub(1)].0);
+        }
+        let (lower, prev) = match idx {
+            0 if upper > 0.0 => (0.0, 0),
+            0 => return Some(upper),
+            _ => self.buckets[idx - 1],
+        };
+        if count == prev {
+            return Some(upper);
+        }
+        Some(lower + (upper - lower) * (rank - prev as f64) / (count - prev) as f64)
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum / self.count as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buckets() {
+        assert_eq!(
+            Buckets::linear(1.0, 2.0, 3),
+            Buckets::Fixed(vec![1.0, 3.0, 5.0])
+        );
+        assert_eq!(
+            Buckets::exponential(0.001, 10.0, 3),
+            Buckets::Fixed(vec![0.001, 0.01, 0.1])
+        );
+        assert!(Buckets::default().validate().is_ok());
+        assert!(Buckets::Fixed(vec![]).validate().is_err());
+        assert!(Buckets::Fixed(vec![1.0, 1.0]).validate().is_err());
+        assert!(Buckets::Fixed(vec![1.0, f64::INFINITY]).validate().is_err());
+        assert!(Buckets::hdr(60.0, 1e-6, 3).validate().is_ok());
+        assert!(Buckets::hdr(60.0, 1e-6, 6).validate().is_err());
+        assert!(Buckets::hdr(1.0, 0.0, 3).validate().is_err());
+    }
+
+    #[test]
+    fn test_fixed_histogram() -> Result<()> {
+        let h = Histogram::new(&Buckets::Fixed(vec![1.0, 2.0, 4.0]))?;
+        assert_eq!(h.quantile(0.5), None);
+
+        for v in [0.5, 1.0, 1.5, 3.0, 3.5, 100.0, f64::NAN] {
+            h.observe(v);
+        }
+        let snapshot = h.snapshot();
+        assert_eq!(snapshot.count, 6);
+        assert_eq!(snapshot.sum, 109.5);
+        assert_eq!(
+            snapshot.buckets,
+            vec![(1.0, 2), (2.0, 3), (4.0, 5), (f64::INFINITY, 6)]
+        );
+        assert_eq!(snapshot.mean(), Some(18.25));
+
+        // rank 3 落在 (1, 2] 桶的末尾
+        assert_eq!(snapshot.quantile(0.5), Some(2.0));
+        // rank 1.5 落在 [0, 1] 桶的 3/4 处
+        assert_eq!(snapshot.quantile(0.25), Some(0.75));
+        assert_eq!(snapshot.quantile(0.99), Some(4.0));
+        assert_eq!(h.quantile(0.5), Some(2.0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_hdr_histogram() -> Result<()> {
+        let h = Histogram::new(&Buckets::hdr(10.0, 1e-3, 3))?;
+        for ms in 1..=1000 {
+            h.observe_duration(Duration::from_millis(ms));
+        }
+        h.observe(-1.0);
+        h.observe(1000.0);
+
+        let snapshot = h.snapshot();
+        assert_eq!(snapshot.count, 1002);
+        assert!(snapshot.buckets.is_empty());
+        assert_eq!(snapshot.quantiles.len(), DEFAULT_QUANTILES.len());
+
+        let p50 = snapshot.quantile(0.5).unwrap();
+        assert!((p50 - 0.5).abs() < 0.001, "p50 = {p50}");
+        let p99 = h.quantile(0.99).unwrap();
+        assert!((p99 - 0.99).abs() < 0.002, "p99 = {p99}");
+        assert!(h.quantile(1.0).unwrap() >= 10.0);
+        assert_eq!(snapshot.quantile(0.75), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_and_reset() -> Result<()> {
+        let buckets = Buckets::Fixed(vec![1.0, 2.0]);
+        let a = Histogram::new(&buckets)?;
+        let b = Histogram::new(&buckets)?;
+        let other = Histogram::new(&Buckets::default())?;
+        a.observe(0.5);
+        b.observe(1.5);
+        b.observe(3.0);
+        other.observe(0.5);
+
+        let merged = Histogram::merge([&a, &b, &other]);
+        assert_eq!(merged.count, 3);
+        assert_eq!(merged.sum, 5.0);
+        assert_eq!(merged.buckets, vec![(1.0, 1), (2.0, 2), (f64::INFINITY, 3)]);
+        assert_eq!(Histogram::merge([]), HistogramSnapshot::default());
+
+        b.reset();
+        assert_eq!(b.snapshot().count, 0);
+        assert_eq!(b.snapshot().sum, 0.0);
+
+        let buckets = Buckets::hdr(10.0, 1e-3, 3);
+        let a = Histogram::new(&buckets)?;
+        let b = Histogram::new(&buckets)?;
+        a.observe(0.1);
+        b.observe(0.3);
+        let merged = Histogram::merge([&a, &b]);
+        assert_eq!(merged.count, 2);
+        assert!((merged.quantile(0.99).unwrap() - 0.3).abs() < 0.001);
+
+        a.reset();
+        assert_eq!(a.quantile(0.5), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_concurrent_observe() -> Result<()> {
+        let h = Histogram::new(&Buckets::default())?;
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for _ in 0..1000 {
+                        h.observe(0.25);
+                    }
+                });
+            }
+        });
+        let snapshot = h.snapshot();
+        assert_eq!(snapshot.count, 4000);
+        assert_eq!(snapshot.sum, 1000.0);
+        Ok(())
+    }
+}
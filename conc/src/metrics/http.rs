@@ -0,0 +1,268 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use axum::{
+    Router,
+    extract::MatchedPath,
+    http::{HeaderMap, Request, Response, header},
+    response::IntoResponse,
+    routing,
+};
+use tower::{Layer, Service};
+
+use super::{
+    Buckets, Counter, Family, FamilySnapshot, Gauge, Histogram, Registry,
+    exposition::{self, Format},
+};
+
+/// 没有匹配到路由（fallback）的请求使用的 route 标签，避免按原始路径产生无限多的标签
+pub const FALLBACK_ROUTE: &str = "fallback";
+
+/// 按路由统计请求数、延迟和正在处理的请求数
+#[derive(Debug, Clone)]
+pub struct HttpMetrics {
+    registry: Registry,
+    requests: Family<Counter>,
+    latency: Family<Histogram>,
+    in_flight: Gauge,
+}
+
+#[derive(Debug, Clone)]
+pub struct MetricsLayer {
+    metrics: HttpMetrics,
+}
+
+#[derive(Debug, Clone)]
+pub struct MetricsService<S> {
+    inner: S,
+    metrics: HttpMetrics,
+}
+
+/// future 被丢弃（客户端断开）时也要减少正在处理的请求数
+struct InFlightGuard(Gauge);
+
+impl HttpMetrics {
+    pub fn new(registry: Registry) -> Result<Self> {
+        let requests = registry.counter_vec(
+            "http_requests_total",
+            "Total number of HTTP requests",
+            &["method", "route", "status"],
+        )?;
+        let latency = registry.histogram_vec(
+            "http_request_duration_seconds",
+            "HTTP request latency until the response headers are sent",
+            &["method", "route"],
+            Buckets::default(),
+        )?;
+        let in_flight = registry.gauge(
+            "http_requests_in_flight",
+            "Number of HTTP requests being served",
+        )?;
+        Ok(Self {
+            registry,
+            requests,
+            latency,
+            in_flight,
+        })
+    }
+
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    pub fn record(&self, method: &str, route: &str, status: &str, elapsed: Duration) {
+        if let Ok(counter) = self.requests.with_label_values(&[method, route, status]) {
+            counter.inc();
+        }
+        if let Ok(histogram) = self.latency.with_label_values(&[method, route]) {
+            histogram.observe_duration(elapsed);
+        }
+    }
+
+    /// 需要通过 `Router::layer` 添加，这样才能拿到 `MatchedPath`
+    pub fn layer(&self) -> MetricsLayer {
+        MetricsLayer {
+            metrics: self.clone(),
+        }
+    }
+
+    /// `GET /metrics`，根据 Accept 头返回 Prometheus 或 OpenMetrics 格式
+    pub fn router<S>(&self) -> Router<S>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        let registry = self.registry.clone();
+        Router::new().route(
+            "/metrics",
+            routing::get(move |headers: HeaderMap| async move { render(&registry, &headers) }),
+        )
+    }
+}
+
+pub fn render(registry: &Registry, headers: &HeaderMap) -> impl IntoResponse + use<> {
+    render_families(&registry.snapshot(), headers)
+}
+
+/// 按请求的 Accept 头选择格式输出
+pub fn render_families(
+    families: &[FamilySnapshot],
+    headers: &HeaderMap,
+) -> impl IntoResponse + use<> {
+    let format = headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .map(Format::from_accept)
+        .unwrap_or_default();
+    (
+        [(header::CONTENT_TYPE, format.content_type())],
+        exposition::render(families, format),
+    )
+}
+
+impl<S> Layer<S> for MetricsLayer {
+    type Service = MetricsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MetricsService {
+            inner,
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for MetricsService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<ReqBody>) -> Self::Future {
+        let method = req.method().to_string();
+        let route = req
+            .extensions()
+            .get::<MatchedPath>()
+            .map(|path| path.as_str().to_string())
+            .unwrap_or_else(|| FALLBACK_ROUTE.to_string());
+        let metrics = self.metrics.clone();
+        let guard = InFlightGuard::new(metrics.in_flight.clone());
+        let start = Instant::now();
+        let future = self.inner.call(req);
+
+        Box::pin(async move {
+            let result = future.await;
+            drop(guard);
+            let status = match &result {
+                Ok(res) => res.status().as_u16().to_string(),
+                Err(_) => "error".to_string(),
+            };
+            metrics.record(&method, &route, &status, start.elapsed());
+            result
+        })
+    }
+}
+
+impl InFlightGuard {
+    fn new(gauge: Gauge) -> Self {
+        gauge.inc();
+        Self(gauge)
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.0.dec();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::Body, http::StatusCode};
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::metrics::MetricValue;
+
+    async fn get(app: &Router, uri: &str, accept: &str) -> Result<(StatusCode, String, String)> {
+        let req = Request::get(uri)
+            .header(header::ACCEPT, accept)
+            .body(Body::empty())?;
+        let res = app.clone().oneshot(req).await?;
+        let status = res.status();
+        let content_type = res
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .map(|v| v.to_str().unwrap_or_default().to_string())
+            .unwrap_or_default();
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX).await?;
+        Ok((status, content_type, String::from_utf8(body.to_vec())?))
+    }
+
+    #[tokio::test]
+    async fn test_metrics_layer() -> Result<()> {
+        let metrics = HttpMetrics::new(Registry::new())?;
+        let app = Router::new()
+            .route("/users/{id}", routing::get(|| async { "user" }))
+            .fallback(|| async { StatusCode::NOT_FOUND })
+            .merge(metrics.router())
+            .layer(metrics.layer());
+
+        get(&app, "/users/1", "*/*").await?;
+        get(&app, "/users/2", "*/*").await?;
+        get(&app, "/missing", "*/*").await?;
+
+        let requests = metrics.registry().snapshot();
+        let requests = requests
+            .iter()
+            .find(|f| f.name == "http_requests_total")
+            .expect("requests family");
+        let samples: Vec<_> = requests
+            .samples
+            .iter()
+            .map(|s| (s.labels.to_string(), s.value.clone()))
+            .collect();
+        assert_eq!(
+            samples,
+            vec![
+                (
+                    r#"{method="GET",route="/users/{id}",status="200"}"#.to_string(),
+                    MetricValue::Counter(2)
+                ),
+                (
+                    r#"{method="GET",route="fallback",status="404"}"#.to_string(),
+                    MetricValue::Counter(1)
+                ),
+            ]
+        );
+
+        let (status, content_type, body) = get(&app, "/metrics", "text/plain").await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content_type, Format::Prometheus.content_type());
+        assert!(body.contains(
+            "http_request_duration_seconds_count{method=\"GET\",route=\"/users/{id}\"} 2\n"
+        ));
+        assert!(body.contains("http_requests_in_flight 1\n"));
+
+        let (_, content_type, body) = get(
+            &app,
+            "/metrics",
+            "application/openmetrics-text; version=1.0.0",
+        )
+        .await?;
+        assert_eq!(content_type, Format::OpenMetrics.content_type());
+        assert!(body.contains("# TYPE http_requests counter\n"));
+        assert!(body.ends_with("# EOF\n"));
+        Ok(())
+    }
+}
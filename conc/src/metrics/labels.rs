@@ -0,0 +1,114 @@
+use std::fmt;
+
+/// 一组有序的标签，顺序与注册指标族时声明的标签名一致
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Labels(Vec<(String, String)>);
+
+impl Labels {
+    pub fn new<K, V>(pairs: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        Self(
+            pairs
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        )
+    }
+
+    /// 追加一个标签，用于输出直方图的 `le` 和摘要的 `quantile`
+    pub fn with(&self, name: &str, value: impl Into<String>) -> Self {
+        let mut labels = self.clone();
+        labels.0.push((name.to_string(), value.into()));
+        labels
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// 按 Prometheus 的格式输出：`{method="GET",status="200"}`，没有标签时为空
+impl fmt::Display for Labels {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+        f.write_str("{")?;
+        for (i, (k, v)) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{}=\"", k)?;
+            for c in v.chars() {
+                match c {
+                    '\\' => f.write_str("\\\\")?,
+                    '"' => f.write_str("\\\"")?,
+                    '\n' => f.write_str("\\n")?,
+                    c => write!(f, "{}", c)?,
+                }
+            }
+            f.write_str("\"")?;
+        }
+        f.write_str("}")
+    }
+}
+
+/// 指标名：`[a-zA-Z_:][a-zA-Z0-9_:]*`
+pub fn is_valid_metric_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_' || c == ':')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+}
+
+/// 标签名：`[a-zA-Z_][a-zA-Z0-9_]*`，`__` 开头的为保留名
+pub fn is_valid_label_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !name.starts_with("__")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_labels_display() {
+        let labels = Labels::new([("method", "GET"), ("path", "/a\"b\\c\n")]);
+        assert_eq!(labels.to_string(), r#"{method="GET",path="/a\"b\\c\n"}"#);
+        assert_eq!(labels.get("method"), Some("GET"));
+        assert_eq!(labels.get("status"), None);
+        assert_eq!(Labels::default().to_string(), "");
+    }
+
+    #[test]
+    fn test_valid_names() {
+        assert!(is_valid_metric_name("http_requests_total"));
+        assert!(is_valid_metric_name("ns:requests"));
+        assert!(!is_valid_metric_name("1xx"));
+        assert!(!is_valid_metric_name("http-requests"));
+        assert!(!is_valid_metric_name(""));
+
+        assert!(is_valid_label_name("status"));
+        assert!(!is_valid_label_name("__name__"));
+        assert!(!is_valid_label_name("a:b"));
+    }
+}
@@ -1,2 +1,27 @@
+pub mod aggregator;
 pub mod amap;
+pub mod atomic;
+pub mod clock;
 pub mod cmap;
+pub mod exposition;
+pub mod histogram;
+#[cfg(feature = "http")]
+pub mod http;
+pub mod labels;
+pub mod registry;
+pub mod sharded;
+pub mod statsd;
+pub mod window;
+
+pub use aggregator::Aggregator;
+pub use amap::AmapCounter;
+pub use clock::{Clock, ManualClock, SystemClock};
+pub use exposition::Format;
+pub use histogram::{Buckets, Histogram, HistogramSnapshot};
+pub use labels::Labels;
+pub use registry::{
+    Counter, Family, FamilySnapshot, Gauge, MetricKind, MetricValue, Registry, Sample,
+};
+pub use sharded::ShardedCounter;
+pub use statsd::{StatsdKind, StatsdLine, StatsdPusher, Transport};
+pub use window::{RollingCounter, RollingHistogram};
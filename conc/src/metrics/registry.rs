@@ -0,0 +1,510 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicU64, Ordering},
+};
+
+use anyhow::{Result, bail};
+use dashmap::DashMap;
+
+use super::{
+    atomic::AtomicF64,
+    histogram::{Buckets, Histogram, HistogramSnapshot},
+    labels::{Labels, is_valid_label_name, is_valid_metric_name},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricKind {
+    Counter,
+    Gauge,
+    Histogram,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetricValue {
+    Counter(u64),
+    Gauge(f64),
+    Histogram(HistogramSnapshot),
+}
+
+/// 单调递增的计数器
+#[derive(Debug, Clone, Default)]
+pub struct Counter(Arc<AtomicU64>);
+
+/// 可增可减的瞬时值
+#[derive(Debug, Clone, Default)]
+pub struct Gauge(Arc<AtomicF64>);
+
+impl Counter {
+    pub fn inc(&self) {
+        self.inc_by(1);
+    }
+
+    pub fn inc_by(&self, value: u64) {
+        self.0.fetch_add(value, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl Gauge {
+    pub fn set(&self, value: f64) {
+        self.0.store(value);
+    }
+
+    pub fn inc(&self) {
+        self.add(1.0);
+    }
+
+    pub fn dec(&self) {
+        self.add(-1.0);
+    }
+
+    pub fn add(&self, value: f64) {
+        self.0.add(value);
+    }
+
+    pub fn sub(&self, value: f64) {
+        self.0.add(-value);
+    }
+
+    pub fn get(&self) -> f64 {
+        self.0.load()
+    }
+}
+
+/// 可以放入指标族的指标类型
+pub trait Metric: Clone + Send + Sync + 'static {
+    const KIND: MetricKind;
+
+    fn build(buckets: &Buckets) -> Self;
+
+    fn value(&self) -> MetricValue;
+
+    fn wrap(family: Family<Self>) -> AnyFamily;
+
+    fn unwrap(family: &AnyFamily) -> Option<&Family<Self>>;
+}
+
+impl Metric for Counter {
+    const KIND: MetricKind = MetricKind::Counter;
+
+    fn build(_: &Buckets) -> Self {
+        Self::default()
+    }
+
+    fn value(&self) -> MetricValue {
+        MetricValue::Counter(self.get())
+    }
+
+    fn wrap(family: Family<Self>) -> AnyFamily {
+        AnyFamily::Counter(family)
+    }
+
+    fn unwrap(family: &AnyFamily) -> Option<&Family<Self>> {
+        match family {
+            AnyFamily::Counter(family) => Some(family),
+            _ => None,
+        }
+    }
+}
+
+impl Metric for Gauge {
+    const KIND: MetricKind = MetricKind::Gauge;
+
+    fn build(_: &Buckets) -> Self {
+        Self::default()
+    }
+
+    fn value(&self) -> MetricValue {
+        MetricValue::Gauge(self.get())
+    }
+
+    fn wrap(family: Family<Self>) -> AnyFamily {
+        AnyFamily::Gauge(family)
+    }
+
+    fn unwrap(family: &AnyFamily) -> Option<&Family<Self>> {
+        match family {
+            AnyFamily::Gauge(family) => Some(family),
+            _ => None,
+        }
+    }
+}
+
+impl Metric for Histogram {
+    const KIND: MetricKind = MetricKind::Histogram;
+
+    // 注册时已经校验过桶的配置
+    fn build(buckets: &Buckets) -> Self {
+        Histogram::new(buckets).expect("buckets are validated on registration")
+    }
+
+    fn value(&self) -> MetricValue {
+        MetricValue::Histogram(self.snapshot())
+    }
+
+    fn wrap(family: Family<Self>) -> AnyFamily {
+        AnyFamily::Histogram(family)
+    }
+
+    fn unwrap(family: &AnyFamily) -> Option<&Family<Self>> {
+        match family {
+            AnyFamily::Histogram(family) => Some(family),
+            _ => None,
+        }
+    }
+}
+
+/// 同名、同类型、同一组标签名的指标集合，例如 `http_requests{method, status}`
+#[derive(Debug, Clone)]
+pub struct Family<M> {
+    inner: Arc<FamilyInner<M>>,
+}
+
+#[derive(Debug)]
+struct FamilyInner<M> {
+    name: String,
+    help: String,
+    label_names: Vec<String>,
+    buckets: Buckets,
+    metrics: DashMap<Labels, M>,
+}
+
+#[derive(Debug, Clone)]
+pub enum AnyFamily {
+    Counter(Family<Counter>),
+    Gauge(Family<Gauge>),
+    Histogram(Family<Histogram>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    pub labels: Labels,
+    pub value: MetricValue,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FamilySnapshot {
+    pub name: String,
+    pub help: String,
+    pub kind: MetricKind,
+    /// 按标签排序
+    pub samples: Vec<Sample>,
+}
+
+impl<M: Metric> Family<M> {
+    fn new(name: &str, help: &str, label_names: &[&str], buckets: Buckets) -> Self {
+        Self {
+            inner: Arc::new(FamilyInner {
+                name: name.to_string(),
+                help: help.to_string(),
+                label_names: label_names.iter().map(|s| s.to_string()).collect(),
+                buckets,
+                metrics: DashMap::new(),
+            }),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.inner.name
+    }
+
+    pub fn label_names(&self) -> &[String] {
+        &self.inner.label_names
+    }
+
+    /// 按声明顺序给出标签值，返回的句柄可以保存下来重复使用
+    pub fn with_label_values(&self, values: &[&str]) -> Result<M> {
+        let labels = self.labels(values)?;
+        if let Some(metric) = self.inner.metrics.get(&labels) {
+            return Ok(metric.clone());
+        }
+        Ok(self
+            .inner
+            .metrics
+            .entry(labels)
+            .or_insert_with(|| M::build(&self.inner.buckets))
+            .clone())
+    }
+
+    pub fn remove(&self, values: &[&str]) -> Result<bool> {
+        let labels = self.labels(values)?;
+        Ok(self.inner.metrics.remove(&labels).is_some())
+    }
+
+    pub fn snapshot(&self) -> FamilySnapshot {
+        let mut samples: Vec<_> = self
+            .inner
+            .metrics
+            .iter()
+            .map(|entry| Sample {
+                labels: entry.key().clone(),
+                value: entry.value().value(),
+            })
+            .collect();
+        samples.sort_by(|a, b| a.labels.cmp(&b.labels));
+        FamilySnapshot {
+            name: self.inner.name.clone(),
+            help: self.inner.help.clone(),
+            kind: M::KIND,
+            samples,
+        }
+    }
+
+    fn labels(&self, values: &[&str]) -> Result<Labels> {
+        let names = &self.inner.label_names;
+        if values.len() != names.len() {
+            bail!(
+                "metric {} expects {} label values {:?}, got {:?}",
+                self.inner.name,
+                names.len(),
+                names,
+                values
+            );
+        }
+        Ok(Labels::new(
+            names.iter().map(String::as_str).zip(values.iter().copied()),
+        ))
+    }
+}
+
+impl AnyFamily {
+    pub fn snapshot(&self) -> FamilySnapshot {
+        match self {
+            AnyFamily::Counter(family) => family.snapshot(),
+            AnyFamily::Gauge(family) => family.snapshot(),
+            AnyFamily::Histogram(family) => family.snapshot(),
+        }
+    }
+}
+
+/// 指标注册表，克隆后共享同一份数据
+#[derive(Debug, Clone, Default)]
+pub struct Registry {
+    families: Arc<DashMap<String, AnyFamily>>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn counter(&self, name: &str, help: &str) -> Result<Counter> {
+        self.counter_vec(name, help, &[])?.with_label_values(&[])
+    }
+
+    pub fn counter_vec(&self, name: &str, help: &str, labels: &[&str]) -> Result<Family<Counter>> {
+        self.register(name, help, labels, Buckets::default())
+    }
+
+    pub fn gauge(&self, name: &str, help: &str) -> Result<Gauge> {
+        self.gauge_vec(name, help, &[])?.with_label_values(&[])
+    }
+
+    pub fn gauge_vec(&self, name: &str, help: &str, labels: &[&str]) -> Result<Family<Gauge>> {
+        self.register(name, help, labels, Buckets::default())
+    }
+
+    pub fn histogram(&self, name: &str, help: &str, buckets: Buckets) -> Result<Histogram> {
+        self.histogram_vec(name, help, &[], buckets)?
+            .with_label_values(&[])
+    }
+
+    pub fn histogram_vec(
+        &self,
+        name: &str,
+        help: &str,
+        labels: &[&str],
+        buckets: Buckets,
+    ) -> Result<Family<Histogram>> {
+        buckets.validate()?;
+        if labels.contains(&"le") {
+            bail!("label name `le` is reserved for histogram buckets");
+        }
+        self.register(name, help, labels, buckets)
+    }
+
+    pub fn unregister(&self, name: &str) -> bool {
+        self.families.remove(name).is_some()
+    }
+
+    /// 按指标名排序
+    pub fn snapshot(&self) -> Vec<FamilySnapshot> {
+        let mut families: Vec<_> = self
+            .families
+            .iter()
+            .map(|entry| entry.value().snapshot())
+            .collect();
+        families.sort_by(|a, b| a.name.cmp(&b.name));
+        families
+    }
+
+    /// 同名指标重复注册时返回已有的指标族，类型、标签名或桶不一致时报错
+    fn register<M: Metric>(
+        &self,
+        name: &str,
+        help: &str,
+        labels: &[&str],
+        buckets: Buckets,
+    ) -> Result<Family<M>> {
+        if !is_valid_metric_name(name) {
+            bail!("invalid metric name: {:?}", name);
+        }
+        if let Some(label) = labels.iter().find(|l| !is_valid_label_name(l)) {
+            bail!("invalid label name for {}: {:?}", name, label);
+        }
+        if (1..labels.len()).any(|i| labels[..i].contains(&labels[i])) {
+            bail!("duplicate label names for {}: {:?}", name, labels);
+        }
+
+        let entry = self
+            .families
+            .entry(name.to_string())
+            .or_insert_with(|| M::wrap(Family::new(name, help, labels, buckets.clone())));
+        let Some(family) = M::unwrap(&entry) else {
+            bail!(
+                "metric {} is already registered as {:?}",
+                name,
+                entry.snapshot().kind
+            );
+        };
+        if family.inner.label_names != labels || family.inner.buckets != buckets {
+            bail!(
+                "metric {} is already registered with labels {:?}",
+                name,
+                family.inner.label_names
+            );
+        }
+        Ok(family.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_registry_snapshot() -> Result<()> {
+        let registry = Registry::new();
+        let requests =
+            registry.counter_vec("http_requests", "HTTP requests", &["method", "status"])?;
+        requests.with_label_values(&["GET", "200"])?.inc();
+        requests.with_label_values(&["GET", "200"])?.inc_by(2);
+        requests.with_label_values(&["POST", "500"])?.inc();
+
+        let conns = registry.gauge("connections", "open connections")?;
+        conns.inc();
+        conns.inc();
+        conns.dec();
+
+        let latency = registry.histogram_vec(
+            "http_latency_seconds",
+            "request latency",
+            &["method"],
+            Buckets::Fixed(vec![0.1, 1.0]),
+        )?;
+        latency
+            .with_label_values(&["GET"])?
+            .observe_duration(Duration::from_millis(50));
+
+        let snapshot = registry.snapshot();
+        let names: Vec<_> = snapshot.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["connections", "http_latency_seconds", "http_requests"]
+        );
+
+        assert_eq!(snapshot[0].kind, MetricKind::Gauge);
+        assert_eq!(snapshot[0].samples[0].labels, Labels::default());
+        assert_eq!(snapshot[0].samples[0].value, MetricValue::Gauge(1.0));
+
+        let MetricValue::Histogram(h) = &snapshot[1].samples[0].value else {
+            panic!("expected histogram");
+        };
+        assert_eq!(h.buckets, vec![(0.1, 1), (1.0, 1), (f64::INFINITY, 1)]);
+
+        let requests = &snapshot[2];
+        assert_eq!(requests.help, "HTTP requests");
+        assert_eq!(
+            requests.samples,
+            vec![
+                Sample {
+                    labels: Labels::new([("method", "GET"), ("status", "200")]),
+                    value: MetricValue::Counter(3),
+                },
+                Sample {
+                    labels: Labels::new([("method", "POST"), ("status", "500")]),
+                    value: MetricValue::Counter(1),
+                },
+            ]
+        );
+        assert_eq!(
+            requests.samples[0].labels.to_string(),
+            r#"{method="GET",status="200"}"#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_register_conflicts() -> Result<()> {
+        let registry = Registry::new();
+        let hits = registry.counter("hits", "")?;
+        hits.inc();
+        // 重复注册返回同一个计数器
+        assert_eq!(registry.counter("hits", "")?.get(), 1);
+
+        assert!(registry.gauge("hits", "").is_err());
+        assert!(registry.counter_vec("hits", "", &["a"]).is_err());
+        assert!(registry.counter("bad-name", "").is_err());
+        assert!(registry.counter_vec("c", "", &["__x"]).is_err());
+        assert!(registry.counter_vec("c", "", &["a", "a"]).is_err());
+        assert!(
+            registry
+                .histogram_vec("h", "", &["le"], Buckets::default())
+                .is_err()
+        );
+        assert!(registry.histogram("h", "", Buckets::Fixed(vec![])).is_err());
+
+        let family = registry.counter_vec("c", "", &["a", "b"])?;
+        assert!(family.with_label_values(&["x"]).is_err());
+        family.with_label_values(&["x", "y"])?;
+        assert!(family.remove(&["x", "y"])?);
+        assert!(!family.remove(&["x", "y"])?);
+
+        assert!(registry.unregister("hits"));
+        assert_eq!(registry.counter("hits", "")?.get(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_concurrent_handles() -> Result<()> {
+        let registry = Registry::new();
+        let family = registry.counter_vec("jobs", "", &["worker"])?;
+        std::thread::scope(|s| {
+            for i in 0..4 {
+                let family = family.clone();
+                s.spawn(move || {
+                    let worker = (i % 2).to_string();
+                    for _ in 0..1000 {
+                        family.with_label_values(&[&worker]).unwrap().inc();
+                    }
+                });
+            }
+        });
+        let values: Vec<_> = family
+            .snapshot()
+            .samples
+            .into_iter()
+            .map(|s| s.value)
+            .collect();
+        assert_eq!(
+            values,
+            vec![MetricValue::Counter(2000), MetricValue::Counter(2000)]
+        );
+        Ok(())
+    }
+}
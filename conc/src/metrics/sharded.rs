@@ -0,0 +1,99 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicI64, AtomicUsize, Ordering},
+    },
+    thread,
+};
+
+/// 每个分片独占一条缓存行，避免不同线程写相邻分片时的伪共享
+#[derive(Debug, Default)]
+#[repr(align(128))]
+struct Shard(AtomicI64);
+
+/// 分片计数器：每个线程固定写一个分片，读取时求和。
+/// 适合写多读少的热点路径，读取的开销随分片数线性增长
+#[derive(Debug, Clone)]
+pub struct ShardedCounter {
+    shards: Arc<[Shard]>,
+}
+
+static NEXT_SHARD: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    // 线程按创建顺序轮流分配分片
+    static SHARD_INDEX: usize = NEXT_SHARD.fetch_add(1, Ordering::Relaxed);
+}
+
+impl ShardedCounter {
+    /// 分片数与 CPU 核数相同
+    pub fn new() -> Self {
+        let cpus = thread::available_parallelism().map_or(1, |n| n.get());
+        Self::with_shards(cpus)
+    }
+
+    /// 分片数向上取整为 2 的幂
+    pub fn with_shards(shards: usize) -> Self {
+        let shards = shards.max(1).next_power_of_two();
+        Self {
+            shards: (0..shards).map(|_| Shard::default()).collect(),
+        }
+    }
+
+    pub fn shards(&self) -> usize {
+        self.shards.len()
+    }
+
+    pub fn inc(&self) {
+        self.add(1);
+    }
+
+    pub fn dec(&self) {
+        self.add(-1);
+    }
+
+    pub fn add(&self, value: i64) {
+        let idx = SHARD_INDEX.with(|idx| *idx) & (self.shards.len() - 1);
+        self.shards[idx].0.fetch_add(value, Ordering::Relaxed);
+    }
+
+    /// 各分片分别读取，并发写入时结果不是某一时刻的精确值
+    pub fn get(&self) -> i64 {
+        self.shards
+            .iter()
+            .map(|shard| shard.0.load(Ordering::Relaxed))
+            .sum()
+    }
+}
+
+impl Default for ShardedCounter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sharded_counter() {
+        assert_eq!(ShardedCounter::with_shards(0).shards(), 1);
+        assert_eq!(ShardedCounter::with_shards(5).shards(), 8);
+        assert_eq!(std::mem::align_of::<Shard>(), 128);
+
+        let counter = ShardedCounter::with_shards(4);
+        thread::scope(|s| {
+            for _ in 0..8 {
+                let counter = counter.clone();
+                s.spawn(move || {
+                    for _ in 0..1000 {
+                        counter.inc();
+                    }
+                    counter.dec();
+                });
+            }
+        });
+        assert_eq!(counter.get(), 8 * 999);
+    }
+}
@@ -0,0 +1,383 @@
+use std::{collections::BTreeMap, fmt, net::SocketAddr, str::FromStr, time::Duration};
+
+use anyhow::{Context, Result, anyhow, bail};
+use tokio::{
+    io::AsyncWriteExt,
+    net::{TcpStream, UdpSocket},
+    task::JoinHandle,
+    time::{self, MissedTickBehavior},
+};
+
+use super::amap::AmapMetrics;
+
+/// 单个 UDP 包的最大长度，避免在以太网 MTU 下分片
+pub const MAX_DATAGRAM: usize = 1432;
+
+/// StatsD 的指标类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsdKind {
+    /// `c`：聚合端累加
+    Counter,
+    /// `g`：聚合端保留最新值，带 `+`/`-` 前缀时为增量
+    Gauge,
+    /// `ms`：耗时，聚合端记入直方图
+    Timer,
+}
+
+/// 一行 StatsD 数据：`name:value|type[|@sample_rate][|#tags]`，标签会被忽略
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatsdLine {
+    pub name: String,
+    pub value: f64,
+    pub kind: StatsdKind,
+    /// 采样率，计数器聚合时按 `value / sample_rate` 累加
+    pub sample_rate: f64,
+    /// 仪表盘的值是否为增量
+    pub delta: bool,
+}
+
+/// 传输方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transport {
+    /// 不保证送达，进程和聚合端互不阻塞
+    Udp,
+    /// 断线后下次推送时重连，失败的增量会保留到下次
+    Tcp,
+}
+
+/// 定期把 `AmapMetrics` 的增量以 StatsD 计数器推送给聚合端，多个进程的同名指标在聚合端相加
+#[derive(Debug)]
+pub struct StatsdPusher {
+    metrics: AmapMetrics,
+    addr: SocketAddr,
+    transport: Transport,
+    prefix: String,
+    // 上次成功推送时的值
+    sent: BTreeMap<String, i64>,
+    conn: Option<Conn>,
+}
+
+#[derive(Debug)]
+enum Conn {
+    Udp(UdpSocket),
+    Tcp(TcpStream),
+}
+
+impl StatsdKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StatsdKind::Counter => "c",
+            StatsdKind::Gauge => "g",
+            StatsdKind::Timer => "ms",
+        }
+    }
+}
+
+impl FromStr for StatsdKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "c" => Ok(StatsdKind::Counter),
+            "g" => Ok(StatsdKind::Gauge),
+            "ms" => Ok(StatsdKind::Timer),
+            _ => bail!("unsupported metric type: {}", s),
+        }
+    }
+}
+
+impl StatsdLine {
+    pub fn counter(name: impl Into<String>, value: f64) -> Self {
+        Self::new(name, value, StatsdKind::Counter)
+    }
+
+    pub fn gauge(name: impl Into<String>, value: f64) -> Self {
+        Self::new(name, value, StatsdKind::Gauge)
+    }
+
+    pub fn timer(name: impl Into<String>, duration: Duration) -> Self {
+        Self::new(name, duration.as_secs_f64() * 1000.0, StatsdKind::Timer)
+    }
+
+    fn new(name: impl Into<String>, value: f64, kind: StatsdKind) -> Self {
+        Self {
+            name: name.into(),
+            value,
+            kind,
+            sample_rate: 1.0,
+            delta: false,
+        }
+    }
+}
+
+impl FromStr for StatsdLine {
+    type Err = anyhow::Error;
+
+    fn from_str(line: &str) -> Result<Self> {
+        let (name, rest) = line
+            .split_once(':')
+            .ok_or_else(|| anyhow!("missing ':' in {:?}", line))?;
+        if name.is_empty() {
+            bail!("empty metric name in {:?}", line);
+        }
+        let mut parts = rest.split('|');
+        let raw = parts.next().unwrap_or_default();
+        let kind: StatsdKind = parts
+            .next()
+            .ok_or_else(|| anyhow!("missing metric type in {:?}", line))?
+            .parse()?;
+        let value: f64 = raw
+            .parse()
+            .with_context(|| format!("invalid value in {:?}", line))?;
+        if !value.is_finite() {
+            bail!("invalid value in {:?}", line);
+        }
+
+        let mut sample_rate = 1.0;
+        for part in parts {
+            if let Some(rate) = part.strip_prefix('@') {
+                sample_rate = rate
+                    .parse()
+                    .ok()
+                    .filter(|r: &f64| *r > 0.0 && *r <= 1.0)
+                    .ok_or_else(|| anyhow!("invalid sample rate in {:?}", line))?;
+            } else if !part.starts_with('#') {
+                bail!("unexpected field {:?} in {:?}", part, line);
+            }
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            value,
+            kind,
+            sample_rate,
+            delta: kind == StatsdKind::Gauge && raw.starts_with(['+', '-']),
+        })
+    }
+}
+
+impl fmt::Display for StatsdLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:", self.name)?;
+        if self.delta && self.value >= 0.0 {
+            f.write_str("+")?;
+        }
+        write!(f, "{}|{}", self.value, self.kind.as_str())?;
+        if self.sample_rate < 1.0 {
+            write!(f, "|@{}", self.sample_rate)?;
+        }
+        Ok(())
+    }
+}
+
+/// 把 `:`、`|`、`@`、`#` 和空白替换为 `_`，这些字符在 StatsD 格式中有特殊含义
+pub fn sanitize_statsd_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if matches!(c, ':' | '|' | '@' | '#') || c.is_whitespace() {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+/// 按行拼接，每段不超过 `max` 字节；单行超过 `max` 时单独成段
+pub fn encode_batches(lines: &[StatsdLine], max: usize) -> Vec<String> {
+    let mut batches = Vec::new();
+    let mut current = String::new();
+    for line in lines {
+        let line = line.to_string();
+        if !current.is_empty() && current.len() + 1 + line.len() > max {
+            batches.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push('\n');
+        }
+        current.push_str(&line);
+    }
+    if !current.is_empty() {
+        batches.push(current);
+    }
+    batches
+}
+
+impl StatsdPusher {
+    pub fn new(metrics: AmapMetrics, addr: SocketAddr, transport: Transport) -> Self {
+        Self {
+            metrics,
+            addr,
+            transport,
+            prefix: String::new(),
+            sent: BTreeMap::new(),
+            conn: None,
+        }
+    }
+
+    /// 指标名前加上 `prefix.`
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// 与上次成功推送相比有变化的指标
+    pub fn pending(&self) -> Vec<StatsdLine> {
+        self.deltas(&self.metrics.snapshot())
+    }
+
+    /// 推送增量，返回发送的行数；失败时不更新基准值，增量留到下次推送
+    pub async fn flush(&mut self) -> Result<usize> {
+        let snapshot = self.metrics.snapshot();
+        let lines = self.deltas(&snapshot);
+        if lines.is_empty() {
+            return Ok(0);
+        }
+        if let Err(e) = self.send(&lines).await {
+            // TCP 连接可能已断开，下次重新建立
+            self.conn = None;
+            return Err(e);
+        }
+        self.sent = snapshot;
+        Ok(lines.len())
+    }
+
+    /// 每隔 `period` 推送一次；需要在 tokio 运行时中调用
+    pub fn spawn(mut self, period: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = time::interval(period);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.flush().await {
+                    tracing::warn!("push metrics to {} failed: {:#}", self.addr, e);
+                }
+            }
+        })
+    }
+
+    fn deltas(&self, snapshot: &BTreeMap<String, i64>) -> Vec<StatsdLine> {
+        snapshot
+            .iter()
+            .filter_map(|(key, value)| {
+                let delta = value.wrapping_sub(self.sent.get(key).copied().unwrap_or(0));
+                (delta != 0).then(|| StatsdLine::counter(self.metric_name(key), delta as f64))
+            })
+            .collect()
+    }
+
+    fn metric_name(&self, key: &str) -> String {
+        let name = sanitize_statsd_name(key);
+        if self.prefix.is_empty() {
+            name
+        } else {
+            format!("{}.{}", self.prefix, name)
+        }
+    }
+
+    async fn send(&mut self, lines: &[StatsdLine]) -> Result<()> {
+        let conn = match &mut self.conn {
+            Some(conn) => conn,
+            None => self.conn.insert(connect(self.addr, self.transport).await?),
+        };
+        match conn {
+            Conn::Udp(socket) => {
+                for batch in encode_batches(lines, MAX_DATAGRAM) {
+                    socket.send(batch.as_bytes()).await?;
+                }
+            }
+            Conn::Tcp(stream) => {
+                let mut payload = encode_batches(lines, usize::MAX).concat();
+                payload.push('\n');
+                stream.write_all(payload.as_bytes()).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+async fn connect(addr: SocketAddr, transport: Transport) -> Result<Conn> {
+    let conn = match transport {
+        Transport::Udp => {
+            let local: SocketAddr = if addr.is_ipv4() {
+                ([0, 0, 0, 0], 0).into()
+            } else {
+                ([0u16; 8], 0).into()
+            };
+            let socket = UdpSocket::bind(local).await?;
+            socket.connect(addr).await?;
+            Conn::Udp(socket)
+        }
+        Transport::Tcp => Conn::Tcp(
+            TcpStream::connect(addr)
+                .await
+                .with_context(|| format!("connect to {} failed", addr))?,
+        ),
+    };
+    Ok(conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line() -> Result<()> {
+        let line: StatsdLine = "chat.messages:3|c|@0.5|#host:a".parse()?;
+        assert_eq!(line.name, "chat.messages");
+        assert_eq!((line.value, line.sample_rate), (3.0, 0.5));
+        assert_eq!(line.kind, StatsdKind::Counter);
+        assert_eq!(line.to_string(), "chat.messages:3|c|@0.5");
+
+        let line: StatsdLine = "queue:-2|g".parse()?;
+        assert!(line.delta);
+        assert_eq!(line.to_string(), "queue:-2|g");
+        let line: StatsdLine = "latency:12.5|ms".parse()?;
+        assert_eq!(line.kind, StatsdKind::Timer);
+
+        for bad in [
+            "", "a", ":1|c", "a:1", "a:x|c", "a:1|s", "a:1|c|@2", "a:1|c|x",
+        ] {
+            assert!(bad.parse::<StatsdLine>().is_err(), "{:?}", bad);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_batches() {
+        let lines: Vec<_> = (0..5)
+            .map(|i| StatsdLine::counter(format!("m{}", i), 1.0))
+            .collect();
+        // 每行 6 字节，加换行后每段最多两行
+        assert_eq!(
+            encode_batches(&lines, 13),
+            ["m0:1|c\nm1:1|c", "m2:1|c\nm3:1|c", "m4:1|c"]
+        );
+        assert_eq!(encode_batches(&lines[..1], 3), ["m0:1|c"]);
+        assert_eq!(sanitize_statsd_name("a:b|c d"), "a_b_c_d");
+    }
+
+    #[test]
+    fn test_pending_deltas() {
+        let metrics = AmapMetrics::new();
+        let addr = ([127, 0, 0, 1], 8125).into();
+        let mut pusher =
+            StatsdPusher::new(metrics.clone(), addr, Transport::Udp).with_prefix("chat");
+        metrics.inc("sign in", 3);
+        metrics.inc("errors", 1);
+        assert_eq!(
+            pusher.pending(),
+            [
+                StatsdLine::counter("chat.errors", 1.0),
+                StatsdLine::counter("chat.sign_in", 3.0)
+            ]
+        );
+
+        pusher.sent = metrics.snapshot();
+        metrics.dec("sign in", 1);
+        assert_eq!(
+            pusher.pending(),
+            [StatsdLine::counter("chat.sign_in", -1.0)]
+        );
+    }
+}
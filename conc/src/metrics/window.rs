@@ -0,0 +1,293 @@
+use std::{
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use anyhow::{Result, bail};
+
+use super::{
+    Buckets, Histogram, HistogramSnapshot,
+    clock::{Clock, SystemClock},
+};
+
+/// 窗口最多包含的 tick 数
+pub const MAX_SLOTS: u64 = 3600;
+
+/// 时间窗口内的计数，如最近一分钟的请求数。
+/// 窗口由 `window / tick` 个槽组成，每经过一个 tick 丢弃最旧的槽，
+/// 因此实际覆盖的时间在 `window - tick` 到 `window` 之间
+#[derive(Debug, Clone)]
+pub struct RollingCounter {
+    ring: Arc<Mutex<Ring<u64>>>,
+}
+
+/// 时间窗口内的直方图，如最近五分钟的 p99 延迟
+#[derive(Debug, Clone)]
+pub struct RollingHistogram {
+    ring: Arc<Mutex<Ring<Histogram>>>,
+}
+
+#[derive(Debug)]
+struct Ring<T> {
+    clock: Arc<dyn Clock>,
+    tick: Duration,
+    // (所属的 tick 序号, 数据)
+    slots: Vec<(Option<u64>, T)>,
+}
+
+impl RollingCounter {
+    pub fn new(window: Duration, tick: Duration) -> Result<Self> {
+        Self::with_clock(window, tick, SystemClock::new())
+    }
+
+    pub fn with_clock(window: Duration, tick: Duration, clock: impl Clock) -> Result<Self> {
+        let ring = Ring::new(window, tick, Arc::new(clock), || Ok(0))?;
+        Ok(Self {
+            ring: Arc::new(Mutex::new(ring)),
+        })
+    }
+
+    pub fn inc(&self) {
+        self.add(1);
+    }
+
+    pub fn add(&self, value: u64) {
+        *self.lock().current(|count| *count = 0) += value;
+    }
+
+    pub fn sum(&self) -> u64 {
+        self.lock().live().sum()
+    }
+
+    /// 窗口内每秒的平均值
+    pub fn rate(&self) -> f64 {
+        let ring = self.lock();
+        ring.live().sum::<u64>() as f64 / ring.window().as_secs_f64()
+    }
+
+    pub fn window(&self) -> Duration {
+        self.lock().window()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Ring<u64>> {
+        self.ring.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl RollingHistogram {
+    pub fn new(window: Duration, tick: Duration, buckets: &Buckets) -> Result<Self> {
+        Self::with_clock(window, tick, buckets, SystemClock::new())
+    }
+
+    pub fn with_clock(
+        window: Duration,
+        tick: Duration,
+        buckets: &Buckets,
+        clock: impl Clock,
+    ) -> Result<Self> {
+        let ring = Ring::new(window, tick, Arc::new(clock), || Histogram::new(buckets))?;
+        Ok(Self {
+            ring: Arc::new(Mutex::new(ring)),
+        })
+    }
+
+    pub fn observe(&self, value: f64) {
+        self.lock().current(|h| h.reset()).observe(value);
+    }
+
+    pub fn observe_duration(&self, duration: Duration) {
+        self.observe(duration.as_secs_f64());
+    }
+
+    /// 窗口内所有观测值合并后的快照
+    pub fn snapshot(&self) -> HistogramSnapshot {
+        Histogram::merge(self.lock().live())
+    }
+
+    /// HDR 直方图只支持 `DEFAULT_QUANTILES` 中的分位数
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        self.snapshot().quantile(q)
+    }
+
+    /// 窗口内每秒的观测次数
+    pub fn rate(&self) -> f64 {
+        let ring = self.lock();
+        Histogram::merge(ring.live()).count as f64 / ring.window().as_secs_f64()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Ring<Histogram>> {
+        self.ring.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<T> Ring<T> {
+    fn new(
+        window: Duration,
+        tick: Duration,
+        clock: Arc<dyn Clock>,
+        init: impl Fn() -> Result<T>,
+    ) -> Result<Self> {
+        if tick.is_zero() || window < tick {
+            bail!("window ({window:?}) must be at least one non-zero tick ({tick:?})");
+        }
+        let slots = window.as_nanos().div_ceil(tick.as_nanos());
+        if slots > MAX_SLOTS as u128 {
+            bail!("window ({window:?}) has more than {MAX_SLOTS} ticks of {tick:?}");
+        }
+        let slots = (0..slots as usize)
+            .map(|_| Ok((None, init()?)))
+            .collect::<Result<_>>()?;
+        Ok(Self { clock, tick, slots })
+    }
+
+    fn window(&self) -> Duration {
+        self.tick * self.slots.len() as u32
+    }
+
+    fn now(&self) -> u64 {
+        (self.clock.now().as_nanos() / self.tick.as_nanos()) as u64
+    }
+
+    /// 当前 tick 的槽，槽中是旧数据时先清空
+    fn current(&mut self, reset: impl FnOnce(&mut T)) -> &mut T {
+        let now = self.now();
+        let len = self.slots.len() as u64;
+        let (epoch, data) = &mut self.slots[(now % len) as usize];
+        if *epoch != Some(now) {
+            reset(data);
+            *epoch = Some(now);
+        }
+        data
+    }
+
+    /// 窗口内的槽
+    fn live(&self) -> impl Iterator<Item = &T> {
+        let now = self.now();
+        let len = self.slots.len() as u64;
+        self.slots
+            .iter()
+            .filter(move |(epoch, _)| matches!(epoch, Some(t) if now.saturating_sub(*t) < len))
+            .map(|(_, data)| data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::clock::ManualClock;
+
+    const SEC: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn test_window_config() {
+        assert!(RollingCounter::new(SEC, Duration::ZERO).is_err());
+        assert!(RollingCounter::new(SEC, 2 * SEC).is_err());
+        assert!(RollingCounter::new(2 * 3600 * SEC, SEC).is_err());
+        let counter = RollingCounter::new(Duration::from_millis(2500), SEC).unwrap();
+        assert_eq!(counter.window(), 3 * SEC);
+    }
+
+    #[test]
+    fn test_rolling_counter() -> Result<()> {
+        let clock = ManualClock::new();
+        let counter = RollingCounter::with_clock(60 * SEC, 10 * SEC, clock.clone())?;
+
+        counter.add(30);
+        clock.advance(5 * SEC);
+        counter.add(30);
+        assert_eq!(counter.sum(), 60);
+        assert_eq!(counter.rate(), 1.0);
+
+        // 第 55 秒：第一个槽仍在窗口内
+        clock.advance(50 * SEC);
+        counter.inc();
+        assert_eq!(counter.sum(), 61);
+
+        // 第 60 秒：第一个槽滑出窗口，同时被复用
+        clock.advance(5 * SEC);
+        assert_eq!(counter.sum(), 1);
+        counter.add(2);
+        assert_eq!(counter.sum(), 3);
+
+        // 长时间没有写入，所有槽都过期
+        clock.advance(3600 * SEC);
+        assert_eq!(counter.sum(), 0);
+        assert_eq!(counter.rate(), 0.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rolling_histogram() -> Result<()> {
+        let clock = ManualClock::new();
+        let latency = RollingHistogram::with_clock(
+            300 * SEC,
+            60 * SEC,
+            &Buckets::hdr(60.0, 1e-3, 3),
+            clock.clone(),
+        )?;
+        assert_eq!(latency.quantile(0.99), None);
+
+        // 第一分钟很慢，之后恢复正常
+        for _ in 0..100 {
+            latency.observe(2.0);
+        }
+        for minute in 1..5 {
+            clock.advance(60 * SEC);
+            for ms in 1..=100 {
+                latency.observe_duration(Duration::from_millis(ms + minute));
+            }
+        }
+        assert_eq!(latency.snapshot().count, 500);
+        let p99 = latency.quantile(0.99).unwrap();
+        assert!((p99 - 2.0).abs() < 0.001, "p99 = {p99}");
+
+        clock.advance(60 * SEC);
+        let snapshot = latency.snapshot();
+        assert_eq!(snapshot.count, 400);
+        let p99 = snapshot.quantile(0.99).unwrap();
+        assert!((p99 - 0.102).abs() < 0.001, "p99 = {p99}");
+        assert_eq!(latency.rate(), 400.0 / 300.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fixed_rolling_histogram() -> Result<()> {
+        let clock = ManualClock::new();
+        let h = RollingHistogram::with_clock(
+            2 * SEC,
+            SEC,
+            &Buckets::Fixed(vec![1.0, 2.0]),
+            clock.clone(),
+        )?;
+        h.observe(0.5);
+        clock.advance(SEC);
+        h.observe(1.5);
+        assert_eq!(
+            h.snapshot().buckets,
+            vec![(1.0, 1), (2.0, 2), (f64::INFINITY, 2)]
+        );
+        clock.advance(SEC);
+        assert_eq!(
+            h.snapshot().buckets,
+            vec![(1.0, 0), (2.0, 1), (f64::INFINITY, 1)]
+        );
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_driven_by_interval() -> Result<()> {
+        let clock = ManualClock::new();
+        let ticker = clock.spawn_ticker(SEC);
+        let counter = RollingCounter::with_clock(3 * SEC, SEC, clock)?;
+
+        counter.add(5);
+        tokio::time::sleep(Duration::from_millis(2500)).await;
+        counter.add(1);
+        assert_eq!(counter.sum(), 6);
+
+        tokio::time::sleep(SEC).await;
+        assert_eq!(counter.sum(), 1);
+        ticker.abort();
+        Ok(())
+    }
+}
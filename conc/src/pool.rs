@@ -0,0 +1,314 @@
+use std::{
+    any::Any,
+    cell::Cell,
+    future::Future,
+    mem,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::{
+        Arc, Condvar, Mutex, MutexGuard, OnceLock, PoisonError,
+        mpsc::{self, Receiver, Sender},
+    },
+    task::{Context, Poll, Waker},
+    thread::{self, JoinHandle},
+};
+
+use anyhow::{Result, anyhow, bail};
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// 固定大小的线程池，用于 CPU 密集型任务。
+/// 任务通过 channel 排队，空闲的工作线程依次领取执行
+#[derive(Debug)]
+pub struct ThreadPool {
+    sender: Option<Sender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+/// 任务结果的句柄，可以阻塞等待 `join`，也可以在异步代码中 `.await`
+#[derive(Debug)]
+pub struct JobHandle<R> {
+    shared: Arc<Shared<R>>,
+}
+
+#[derive(Debug)]
+struct Shared<R> {
+    state: Mutex<State<R>>,
+    ready: Condvar,
+}
+
+#[derive(Debug)]
+struct State<R> {
+    slot: Slot<R>,
+    waker: Option<Waker>,
+}
+
+#[derive(Debug)]
+enum Slot<R> {
+    Pending,
+    Ready(Result<R>),
+    Taken,
+}
+
+/// 由任务持有，任务没有执行就被丢弃时也会通知等待方
+struct Completer<R> {
+    shared: Arc<Shared<R>>,
+}
+
+static GLOBAL: OnceLock<ThreadPool> = OnceLock::new();
+
+thread_local! {
+    // 当前线程是否为线程池的工作线程
+    static IS_WORKER: Cell<bool> = const { Cell::new(false) };
+}
+
+impl ThreadPool {
+    pub fn new(size: usize) -> Result<Self> {
+        if size == 0 {
+            bail!("thread pool size must be greater than 0");
+        }
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let workers = (0..size)
+            .map(|id| {
+                let receiver = receiver.clone();
+                thread::Builder::new()
+                    .name(format!("pool-worker-{id}"))
+                    .spawn(move || work(&receiver))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            sender: Some(sender),
+            workers,
+        })
+    }
+
+    /// 进程内共享的线程池，线程数与 CPU 核数相同
+    pub fn global() -> &'static Self {
+        GLOBAL.get_or_init(|| {
+            let cpus = thread::available_parallelism().map_or(1, |n| n.get());
+            Self::new(cpus).expect("failed to start global thread pool")
+        })
+    }
+
+    pub fn size(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// 当前线程是否为某个线程池的工作线程。
+    /// 在任务中提交子任务并阻塞等待可能死锁，可以据此改为在当前线程执行
+    pub fn is_worker() -> bool {
+        IS_WORKER.with(Cell::get)
+    }
+
+    /// 提交任务，任务 panic 时句柄返回错误，工作线程不受影响
+    pub fn spawn<F, R>(&self, f: F) -> JobHandle<R>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                slot: Slot::Pending,
+                waker: None,
+            }),
+            ready: Condvar::new(),
+        });
+        let completer = Completer {
+            shared: shared.clone(),
+        };
+        let job = Box::new(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(f))
+                .map_err(|e| anyhow!("job panicked: {}", panic_message(&*e)));
+            completer.complete(result);
+        });
+        // 发送失败时任务被丢弃，由 Completer 通知句柄
+        if let Some(sender) = &self.sender {
+            let _ = sender.send(job);
+        }
+        JobHandle { shared }
+    }
+
+    /// 不再接收新任务，等待队列中已有的任务全部执行完
+    pub fn shutdown(mut self) {
+        self.join_workers();
+    }
+
+    fn join_workers(&mut self) {
+        // 关闭 channel，工作线程取完剩余任务后退出
+        drop(self.sender.take());
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Drop for ThreadPool {
+    fn drop(&mut self) {
+        self.join_workers();
+    }
+}
+
+fn work(receiver: &Mutex<Receiver<Job>>) {
+    IS_WORKER.with(|w| w.set(true));
+    loop {
+        // 取到任务后立即释放锁，其他线程可以同时领取
+        let job = receiver
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .recv();
+        match job {
+            Ok(job) => job(),
+            Err(_) => break,
+        }
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s
+    } else {
+        "unknown panic"
+    }
+}
+
+impl<R> Shared<R> {
+    fn lock(&self) -> MutexGuard<'_, State<R>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<R> Completer<R> {
+    fn complete(&self, result: Result<R>) {
+        let mut state = self.shared.lock();
+        if matches!(state.slot, Slot::Pending) {
+            state.slot = Slot::Ready(result);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+            self.shared.ready.notify_all();
+        }
+    }
+}
+
+impl<R> Drop for Completer<R> {
+    fn drop(&mut self) {
+        self.complete(Err(anyhow!("job was dropped before it finished")));
+    }
+}
+
+impl<R> JobHandle<R> {
+    pub fn is_finished(&self) -> bool {
+        !matches!(self.shared.lock().slot, Slot::Pending)
+    }
+
+    /// 阻塞等待任务完成
+    pub fn join(self) -> Result<R> {
+        let mut state = self.shared.lock();
+        while matches!(state.slot, Slot::Pending) {
+            state = self
+                .shared
+                .ready
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        take(&mut state)
+    }
+}
+
+impl<R> Future for JobHandle<R> {
+    type Output = Result<R>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.shared.lock();
+        if matches!(state.slot, Slot::Pending) {
+            state.waker = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+        Poll::Ready(take(&mut state))
+    }
+}
+
+fn take<R>(state: &mut State<R>) -> Result<R> {
+    match mem::replace(&mut state.slot, Slot::Taken) {
+        Slot::Ready(result) => result,
+        _ => bail!("job result was already taken"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_spawn_and_join() -> Result<()> {
+        assert!(ThreadPool::new(0).is_err());
+
+        let pool = ThreadPool::new(4)?;
+        assert_eq!(pool.size(), 4);
+        let handles: Vec<_> = (0..32u64).map(|i| pool.spawn(move || i * i)).collect();
+        let sum = handles
+            .into_iter()
+            .map(JobHandle::join)
+            .sum::<Result<u64>>()?;
+        assert_eq!(sum, (0..32).map(|i| i * i).sum::<u64>());
+        Ok(())
+    }
+
+    #[test]
+    fn test_panic_is_isolated() -> Result<()> {
+        let pool = ThreadPool::new(1)?;
+        let err = pool.spawn(|| panic!("boom")).join().unwrap_err();
+        assert_eq!(err.to_string(), "job panicked: boom");
+
+        // 唯一的工作线程仍然可用
+        assert_eq!(pool.spawn(|| 42).join()?, 42);
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_worker() -> Result<()> {
+        let pool = ThreadPool::new(1)?;
+        assert!(!ThreadPool::is_worker());
+        assert!(pool.spawn(ThreadPool::is_worker).join()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_shutdown_drains_queue() -> Result<()> {
+        let pool = ThreadPool::new(2)?;
+        let done = Arc::new(AtomicUsize::new(0));
+        let handles: Vec<_> = (0..10)
+            .map(|_| {
+                let done = done.clone();
+                pool.spawn(move || {
+                    thread::sleep(Duration::from_millis(10));
+                    done.fetch_add(1, Ordering::SeqCst);
+                })
+            })
+            .collect();
+
+        pool.shutdown();
+        assert_eq!(done.load(Ordering::SeqCst), 10);
+        assert!(handles.iter().all(JobHandle::is_finished));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_await_handle() -> Result<()> {
+        let pool = ThreadPool::new(2)?;
+        let handle = pool.spawn(|| {
+            thread::sleep(Duration::from_millis(20));
+            "done"
+        });
+        assert_eq!(handle.await?, "done");
+        Ok(())
+    }
+}
@@ -0,0 +1,381 @@
+use std::fmt;
+
+use thiserror::Error;
+
+use super::{
+    queue::ArrayQueue,
+    shim::{Arc, AtomicBool, AtomicUsize, Ordering},
+    waiters::Waiters,
+};
+
+/// 发送端，可以克隆出多个生产者
+pub struct Sender<T> {
+    chan: Arc<Chan<T>>,
+}
+
+/// 接收端，可以克隆出多个消费者，每条消息只会被其中一个收到
+pub struct Receiver<T> {
+    chan: Arc<Chan<T>>,
+}
+
+/// `Receiver::iter` 返回的阻塞迭代器
+#[derive(Debug)]
+pub struct Iter<'a, T> {
+    rx: &'a Receiver<T>,
+}
+
+/// 获得所有权的阻塞迭代器
+#[derive(Debug)]
+pub struct IntoIter<T> {
+    rx: Receiver<T>,
+}
+
+struct Chan<T> {
+    queue: ArrayQueue<T>,
+    senders: AtomicUsize,
+    receivers: AtomicUsize,
+    // 任意一端全部被丢弃
+    disconnected: AtomicBool,
+    // 等待队列有空位的发送方
+    send_waiters: Waiters,
+    // 等待队列有消息的接收方
+    recv_waiters: Waiters,
+}
+
+/// 所有接收端都已丢弃，消息原样返回
+#[derive(Error, Clone, Copy, PartialEq, Eq)]
+#[error("sending on a disconnected channel")]
+pub struct SendError<T>(pub T);
+
+#[derive(Error, Clone, Copy, PartialEq, Eq)]
+pub enum TrySendError<T> {
+    #[error("sending on a full channel")]
+    Full(T),
+    #[error("sending on a disconnected channel")]
+    Disconnected(T),
+}
+
+/// 所有发送端都已丢弃且通道为空
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+#[error("receiving on an empty and disconnected channel")]
+pub struct RecvError;
+
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    #[error("receiving on an empty channel")]
+    Empty,
+    #[error("receiving on an empty and disconnected channel")]
+    Disconnected,
+}
+
+/// 容量为 `cap` 的有界通道，队列满时发送方挂起，空时接收方挂起。
+/// `cap` 为 0 时 panic
+pub fn bounded<T>(cap: usize) -> (Sender<T>, Receiver<T>) {
+    let chan = Arc::new(Chan {
+        queue: ArrayQueue::new(cap),
+        senders: AtomicUsize::new(1),
+        receivers: AtomicUsize::new(1),
+        disconnected: AtomicBool::new(false),
+        send_waiters: Waiters::new(),
+        recv_waiters: Waiters::new(),
+    });
+    (Sender { chan: chan.clone() }, Receiver { chan })
+}
+
+impl<T> Sender<T> {
+    /// 队列满时阻塞，直到有空位或所有接收端被丢弃
+    pub fn send(&self, mut value: T) -> Result<(), SendError<T>> {
+        loop {
+            match self.try_send(value) {
+                Ok(()) => return Ok(()),
+                Err(TrySendError::Disconnected(v)) => return Err(SendError(v)),
+                Err(TrySendError::Full(v)) => value = v,
+            }
+            let chan = &self.chan;
+            chan.send_waiters
+                .wait_until(|| !chan.queue.is_full() || chan.is_disconnected());
+        }
+    }
+
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        if self.chan.is_disconnected() {
+            return Err(TrySendError::Disconnected(value));
+        }
+        match self.chan.queue.push(value) {
+            Ok(()) => {
+                self.chan.recv_waiters.notify_one();
+                Ok(())
+            }
+            Err(v) => Err(TrySendError::Full(v)),
+        }
+    }
+}
+
+impl<T> Receiver<T> {
+    /// 队列空时阻塞，直到收到消息或所有发送端被丢弃
+    pub fn recv(&self) -> Result<T, RecvError> {
+        loop {
+            match self.try_recv() {
+                Ok(value) => return Ok(value),
+                Err(TryRecvError::Disconnected) => return Err(RecvError),
+                Err(TryRecvError::Empty) => {}
+            }
+            let chan = &self.chan;
+            chan.recv_waiters
+                .wait_until(|| !chan.queue.is_empty() || chan.is_disconnected());
+        }
+    }
+
+    /// 断开后仍然可以取出队列中剩余的消息
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        // 先读断开标志：断开前发送的消息此时都已在队列中
+        let disconnected = self.chan.is_disconnected();
+        match self.chan.queue.pop() {
+            Some(value) => {
+                self.chan.send_waiters.notify_one();
+                Ok(value)
+            }
+            None if disconnected => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    /// 阻塞迭代，所有发送端被丢弃且消息取完后结束
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { rx: self }
+    }
+}
+
+impl<T> Chan<T> {
+    fn is_disconnected(&self) -> bool {
+        self.disconnected.load(Ordering::SeqCst)
+    }
+
+    fn disconnect(&self) {
+        self.disconnected.store(true, Ordering::SeqCst);
+        self.send_waiters.notify_all();
+        self.recv_waiters.notify_all();
+    }
+}
+
+macro_rules! endpoint {
+    ($name:ident, $count:ident) => {
+        impl<T> $name<T> {
+            pub fn len(&self) -> usize {
+                self.chan.queue.len()
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.chan.queue.is_empty()
+            }
+
+            pub fn capacity(&self) -> usize {
+                self.chan.queue.capacity()
+            }
+        }
+
+        impl<T> Clone for $name<T> {
+            fn clone(&self) -> Self {
+                self.chan.$count.fetch_add(1, Ordering::Relaxed);
+                Self {
+                    chan: self.chan.clone(),
+                }
+            }
+        }
+
+        impl<T> Drop for $name<T> {
+            fn drop(&mut self) {
+                if self.chan.$count.fetch_sub(1, Ordering::AcqRel) == 1 {
+                    self.chan.disconnect();
+                }
+            }
+        }
+
+        impl<T> fmt::Debug for $name<T> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_struct(stringify!($name))
+                    .field("len", &self.len())
+                    .field("capacity", &self.capacity())
+                    .finish()
+            }
+        }
+    };
+}
+
+endpoint!(Sender, senders);
+endpoint!(Receiver, receivers);
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.rx.recv().ok()
+    }
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.rx.recv().ok()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Receiver<T> {
+    type Item = T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T> IntoIterator for Receiver<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter { rx: self }
+    }
+}
+
+// 与标准库一样，错误的 Debug 不要求消息实现 Debug
+impl<T> fmt::Debug for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SendError { .. }")
+    }
+}
+
+impl<T> fmt::Debug for TrySendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Full(_) => f.write_str("Full(..)"),
+            Self::Disconnected(_) => f.write_str("Disconnected(..)"),
+        }
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use std::{thread, time::Duration};
+
+    use super::*;
+
+    #[test]
+    fn test_try_operations() {
+        let (tx, rx) = bounded(2);
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+        tx.try_send(1).unwrap();
+        tx.try_send(2).unwrap();
+        assert_eq!(tx.try_send(3), Err(TrySendError::Full(3)));
+        assert_eq!((tx.len(), tx.capacity()), (2, 2));
+
+        assert_eq!(rx.try_recv(), Ok(1));
+        drop(tx);
+        // 断开后剩余消息仍然可以取出
+        assert_eq!(rx.try_recv(), Ok(2));
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+        assert_eq!(rx.recv(), Err(RecvError));
+    }
+
+    #[test]
+    fn test_disconnect_receivers() {
+        let (tx, rx) = bounded(1);
+        let rx2 = rx.clone();
+        drop(rx);
+        tx.send(1).unwrap();
+        drop(rx2);
+        assert_eq!(tx.send(2), Err(SendError(2)));
+        assert_eq!(tx.try_send(3), Err(TrySendError::Disconnected(3)));
+    }
+
+    #[test]
+    fn test_blocking_send_wakes_on_disconnect() {
+        let (tx, rx) = bounded(1);
+        tx.send(1).unwrap();
+        let handle = thread::spawn(move || tx.send(2));
+        thread::sleep(Duration::from_millis(50));
+        drop(rx);
+        assert_eq!(handle.join().unwrap(), Err(SendError(2)));
+    }
+
+    #[test]
+    fn test_mpmc() {
+        const PRODUCERS: usize = 4;
+        const PER_PRODUCER: usize = 1000;
+
+        let (tx, rx) = bounded(8);
+        let total: usize = thread::scope(|s| {
+            for p in 0..PRODUCERS {
+                let tx = tx.clone();
+                s.spawn(move || {
+                    for i in 0..PER_PRODUCER {
+                        tx.send(p * PER_PRODUCER + i).unwrap();
+                    }
+                });
+            }
+            drop(tx);
+
+            let consumers: Vec<_> = (0..3)
+                .map(|_| {
+                    let rx = rx.clone();
+                    s.spawn(move || rx.iter().sum::<usize>())
+                })
+                .collect();
+            consumers.into_iter().map(|h| h.join().unwrap()).sum()
+        });
+        let n = PRODUCERS * PER_PRODUCER;
+        assert_eq!(total, n * (n - 1) / 2);
+    }
+}
+
+#[cfg(all(test, loom))]
+mod loom_tests {
+    use loom::thread;
+
+    use super::*;
+
+    #[test]
+    fn loom_send_recv() {
+        // 发送方和接收方都会多次挂起，不限制抢占次数时状态空间过大
+        let mut builder = loom::model::Builder::new();
+        builder.preemption_bound = Some(3);
+        builder.check(|| {
+            let (tx, rx) = bounded(1);
+            let producer = thread::spawn(move || {
+                tx.send(1).unwrap();
+                tx.send(2).unwrap();
+            });
+            assert_eq!(rx.recv(), Ok(1));
+            assert_eq!(rx.recv(), Ok(2));
+            assert_eq!(rx.recv(), Err(RecvError));
+            producer.join().unwrap();
+        });
+    }
+
+    #[test]
+    fn loom_two_consumers() {
+        loom::model(|| {
+            let (tx, rx) = bounded(1);
+            let rx2 = rx.clone();
+            let consumer = thread::spawn(move || rx2.recv().ok());
+            tx.send(1).unwrap();
+            tx.send(2).unwrap();
+            drop(tx);
+            let mine = rx.iter().sum::<i32>();
+            let theirs = consumer.join().unwrap().unwrap_or(0);
+            assert_eq!(mine + theirs, 3);
+        });
+    }
+
+    #[test]
+    fn loom_disconnect_wakes_sender() {
+        loom::model(|| {
+            let (tx, rx) = bounded(1);
+            tx.send(1).unwrap();
+            let sender = thread::spawn(move || tx.send(2));
+            drop(rx);
+            assert!(sender.join().unwrap().is_err());
+        });
+    }
+}
@@ -0,0 +1,12 @@
+mod channel;
+mod once;
+mod queue;
+mod semaphore;
+mod shim;
+mod waiters;
+
+pub use channel::{
+    IntoIter, Iter, Receiver, RecvError, SendError, Sender, TryRecvError, TrySendError, bounded,
+};
+pub use once::OnceCell;
+pub use semaphore::{Semaphore, SemaphorePermit};
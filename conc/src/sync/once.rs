@@ -0,0 +1,211 @@
+use std::{fmt, mem::MaybeUninit};
+
+use super::{
+    shim::{AtomicU8, Ordering, UnsafeCell},
+    waiters::Waiters,
+};
+
+const EMPTY: u8 = 0;
+const RUNNING: u8 = 1;
+const READY: u8 = 2;
+
+/// 只初始化一次的单元格，多个线程同时初始化时只有一个执行，其他线程挂起等待
+pub struct OnceCell<T> {
+    state: AtomicU8,
+    value: UnsafeCell<MaybeUninit<T>>,
+    waiters: Waiters,
+}
+
+/// 初始化函数 panic 时恢复为未初始化，唤醒等待的线程重新竞争
+struct Guard<'a, T> {
+    cell: &'a OnceCell<T>,
+    state: u8,
+}
+
+unsafe impl<T: Send> Send for OnceCell<T> {}
+unsafe impl<T: Send + Sync> Sync for OnceCell<T> {}
+
+impl<T> OnceCell<T> {
+    pub fn new() -> Self {
+        Self {
+            state: AtomicU8::new(EMPTY),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+            waiters: Waiters::new(),
+        }
+    }
+
+    pub fn get(&self) -> Option<&T> {
+        if self.state.load(Ordering::Acquire) == READY {
+            Some(self.value.with(|p| unsafe { (*p).assume_init_ref() }))
+        } else {
+            None
+        }
+    }
+
+    /// 已经初始化时把值还给调用方
+    pub fn set(&self, value: T) -> Result<(), T> {
+        let mut value = Some(value);
+        self.get_or_init(|| value.take().expect("value is taken once"));
+        match value {
+            None => Ok(()),
+            Some(value) => Err(value),
+        }
+    }
+
+    pub fn get_or_init(&self, f: impl FnOnce() -> T) -> &T {
+        if let Some(value) = self.get() {
+            return value;
+        }
+        let mut f = Some(f);
+        loop {
+            match self
+                .state
+                .compare_exchange(EMPTY, RUNNING, Ordering::Acquire, Ordering::Acquire)
+            {
+                Ok(_) => {
+                    let mut guard = Guard {
+                        cell: self,
+                        state: EMPTY,
+                    };
+                    let init = f.take().expect("initializer runs once");
+                    let value = init();
+                    self.value.with_mut(|p| unsafe { (*p).write(value) });
+                    guard.state = READY;
+                    drop(guard);
+                }
+                Err(RUNNING) => self
+                    .waiters
+                    .wait_until(|| self.state.load(Ordering::SeqCst) != RUNNING),
+                Err(_) => {}
+            }
+            if let Some(value) = self.get() {
+                return value;
+            }
+        }
+    }
+
+    pub fn into_inner(mut self) -> Option<T> {
+        self.take()
+    }
+
+    pub fn take(&mut self) -> Option<T> {
+        if self.state.load(Ordering::Acquire) != READY {
+            return None;
+        }
+        self.state.store(EMPTY, Ordering::Relaxed);
+        Some(self.value.with(|p| unsafe { (*p).assume_init_read() }))
+    }
+}
+
+impl<T> Default for OnceCell<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for OnceCell<T> {
+    fn drop(&mut self) {
+        self.take();
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for OnceCell<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("OnceCell").field(&self.get()).finish()
+    }
+}
+
+impl<T> Drop for Guard<'_, T> {
+    fn drop(&mut self) {
+        self.cell.state.store(self.state, Ordering::SeqCst);
+        self.cell.waiters.notify_all();
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use std::{
+        panic::{self, AssertUnwindSafe},
+        sync::atomic::AtomicUsize,
+        thread,
+        time::Duration,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_get_or_init() {
+        let cell = OnceCell::new();
+        assert_eq!(cell.get(), None);
+        assert_eq!(cell.get_or_init(|| 1), &1);
+        assert_eq!(cell.get_or_init(|| 2), &1);
+        assert_eq!(cell.set(3), Err(3));
+        assert_eq!(cell.into_inner(), Some(1));
+
+        let cell = OnceCell::default();
+        assert_eq!(cell.set("a"), Ok(()));
+        assert_eq!(format!("{:?}", cell), r#"OnceCell(Some("a"))"#);
+    }
+
+    #[test]
+    fn test_init_once_across_threads() {
+        let cell = OnceCell::new();
+        let calls = AtomicUsize::new(0);
+        thread::scope(|s| {
+            for _ in 0..8 {
+                s.spawn(|| {
+                    let value = cell.get_or_init(|| {
+                        calls.fetch_add(1, Ordering::SeqCst);
+                        thread::sleep(Duration::from_millis(20));
+                        String::from("config")
+                    });
+                    assert_eq!(value, "config");
+                });
+            }
+        });
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_panic_in_init() {
+        let cell = OnceCell::new();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            cell.get_or_init(|| panic!("init failed"));
+        }));
+        assert!(result.is_err());
+        assert_eq!(cell.get(), None);
+        assert_eq!(cell.get_or_init(|| 7), &7);
+    }
+}
+
+#[cfg(all(test, loom))]
+mod loom_tests {
+    use loom::{
+        sync::{Arc, atomic::AtomicUsize},
+        thread,
+    };
+
+    use super::*;
+
+    #[test]
+    fn loom_init_once() {
+        loom::model(|| {
+            let cell = Arc::new(OnceCell::new());
+            let calls = Arc::new(AtomicUsize::new(0));
+            let handles: Vec<_> = (0..2)
+                .map(|i| {
+                    let (cell, calls) = (cell.clone(), calls.clone());
+                    thread::spawn(move || {
+                        *cell.get_or_init(|| {
+                            calls.fetch_add(1, Ordering::SeqCst);
+                            i
+                        })
+                    })
+                })
+                .collect();
+            let values: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+            assert_eq!(values[0], values[1]);
+            assert_eq!(calls.load(Ordering::SeqCst), 1);
+        });
+    }
+}
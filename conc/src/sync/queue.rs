@@ -0,0 +1,216 @@
+use std::mem::MaybeUninit;
+
+use super::shim::{AtomicUsize, Ordering, UnsafeCell, fence, spin};
+
+/// 有界无锁 MPMC 队列（Vyukov 算法）。
+/// `head`/`tail` 的低位是槽的下标，高位是圈数；每个槽的 `stamp` 表示它当前可以被哪个位置写入或读取
+#[derive(Debug)]
+pub struct ArrayQueue<T> {
+    head: Padded<AtomicUsize>,
+    tail: Padded<AtomicUsize>,
+    buffer: Box<[Slot<T>]>,
+    cap: usize,
+    // 大于 cap 的最小 2 的幂，加到位置上表示进入下一圈
+    one_lap: usize,
+}
+
+#[derive(Debug)]
+struct Slot<T> {
+    stamp: AtomicUsize,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+/// 生产者和消费者分别修改 head 和 tail，放在不同的缓存行
+#[derive(Debug)]
+#[repr(align(128))]
+struct Padded<T>(T);
+
+unsafe impl<T: Send> Send for ArrayQueue<T> {}
+unsafe impl<T: Send> Sync for ArrayQueue<T> {}
+
+impl<T> ArrayQueue<T> {
+    /// `cap` 为 0 时 panic
+    pub fn new(cap: usize) -> Self {
+        assert!(cap > 0, "capacity must be greater than 0");
+        let buffer = (0..cap)
+            .map(|i| Slot {
+                stamp: AtomicUsize::new(i),
+                value: UnsafeCell::new(MaybeUninit::uninit()),
+            })
+            .collect();
+        Self {
+            head: Padded(AtomicUsize::new(0)),
+            tail: Padded(AtomicUsize::new(0)),
+            buffer,
+            cap,
+            one_lap: (cap + 1).next_power_of_two(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    /// 队列已满时把值还给调用方
+    pub fn push(&self, value: T) -> Result<(), T> {
+        let mut tail = self.tail.0.load(Ordering::Relaxed);
+        loop {
+            let slot = &self.buffer[tail & (self.one_lap - 1)];
+            let stamp = slot.stamp.load(Ordering::Acquire);
+
+            if stamp == tail {
+                // 槽为空，抢占这个位置
+                let next = self.advance(tail);
+                match self.tail.0.compare_exchange_weak(
+                    tail,
+                    next,
+                    Ordering::SeqCst,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        slot.value.with_mut(|p| unsafe { (*p).write(value) });
+                        slot.stamp.store(tail + 1, Ordering::Release);
+                        return Ok(());
+                    }
+                    Err(t) => tail = t,
+                }
+            } else if stamp.wrapping_add(self.one_lap) == tail + 1 {
+                // 槽里还是上一圈的值，确认队列是否已满
+                fence(Ordering::SeqCst);
+                let head = self.head.0.load(Ordering::Relaxed);
+                if head.wrapping_add(self.one_lap) == tail {
+                    return Err(value);
+                }
+                spin();
+                tail = self.tail.0.load(Ordering::Relaxed);
+            } else {
+                // 其他线程正在写这个槽
+                spin();
+                tail = self.tail.0.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let mut head = self.head.0.load(Ordering::Relaxed);
+        loop {
+            let slot = &self.buffer[head & (self.one_lap - 1)];
+            let stamp = slot.stamp.load(Ordering::Acquire);
+
+            if stamp == head + 1 {
+                // 槽已写入，抢占这个位置
+                let next = self.advance(head);
+                match self.head.0.compare_exchange_weak(
+                    head,
+                    next,
+                    Ordering::SeqCst,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        let value = slot.value.with(|p| unsafe { (*p).assume_init_read() });
+                        slot.stamp
+                            .store(head.wrapping_add(self.one_lap), Ordering::Release);
+                        return Some(value);
+                    }
+                    Err(h) => head = h,
+                }
+            } else if stamp == head {
+                // 槽还没有写入，确认队列是否为空
+                fence(Ordering::SeqCst);
+                let tail = self.tail.0.load(Ordering::Relaxed);
+                if tail == head {
+                    return None;
+                }
+                spin();
+                head = self.head.0.load(Ordering::Relaxed);
+            } else {
+                spin();
+                head = self.head.0.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        loop {
+            let tail = self.tail.0.load(Ordering::SeqCst);
+            let head = self.head.0.load(Ordering::SeqCst);
+            // 两次读取之间 tail 没有变化，得到的是某一时刻的值
+            if self.tail.0.load(Ordering::SeqCst) == tail {
+                let hix = head & (self.one_lap - 1);
+                let tix = tail & (self.one_lap - 1);
+                return if hix < tix {
+                    tix - hix
+                } else if hix > tix {
+                    self.cap - hix + tix
+                } else if tail == head {
+                    0
+                } else {
+                    self.cap
+                };
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        let head = self.head.0.load(Ordering::SeqCst);
+        let tail = self.tail.0.load(Ordering::SeqCst);
+        tail == head
+    }
+
+    pub fn is_full(&self) -> bool {
+        let tail = self.tail.0.load(Ordering::SeqCst);
+        let head = self.head.0.load(Ordering::SeqCst);
+        head.wrapping_add(self.one_lap) == tail
+    }
+
+    /// 下一个位置，到达末尾时回到下标 0 并进入下一圈
+    fn advance(&self, pos: usize) -> usize {
+        let index = pos & (self.one_lap - 1);
+        let lap = pos & !(self.one_lap - 1);
+        if index + 1 < self.cap {
+            pos + 1
+        } else {
+            lap.wrapping_add(self.one_lap)
+        }
+    }
+}
+
+impl<T> Drop for ArrayQueue<T> {
+    fn drop(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_pop() {
+        let q = ArrayQueue::new(3);
+        assert_eq!(q.capacity(), 3);
+        assert!(q.is_empty());
+        for round in 0..5 {
+            for i in 0..3 {
+                assert_eq!(q.push(round * 10 + i), Ok(()));
+            }
+            assert!(q.is_full());
+            assert_eq!(q.len(), 3);
+            assert_eq!(q.push(99), Err(99));
+            for i in 0..3 {
+                assert_eq!(q.pop(), Some(round * 10 + i));
+            }
+            assert_eq!(q.pop(), None);
+        }
+    }
+
+    #[test]
+    fn test_drop_remaining() {
+        let value = std::sync::Arc::new(());
+        let q = ArrayQueue::new(4);
+        q.push(value.clone()).unwrap();
+        q.push(value.clone()).unwrap();
+        drop(q);
+        assert_eq!(std::sync::Arc::strong_count(&value), 1);
+    }
+}
@@ -0,0 +1,151 @@
+use super::{
+    shim::{AtomicUsize, Ordering},
+    waiters::Waiters,
+};
+
+/// 计数信号量，限制同时访问某个资源的线程数
+#[derive(Debug)]
+pub struct Semaphore {
+    permits: AtomicUsize,
+    waiters: Waiters,
+}
+
+/// 丢弃时归还许可
+#[derive(Debug)]
+#[must_use = "the permit is released immediately if unused"]
+pub struct SemaphorePermit<'a> {
+    sem: &'a Semaphore,
+}
+
+impl Semaphore {
+    pub fn new(permits: usize) -> Self {
+        Self {
+            permits: AtomicUsize::new(permits),
+            waiters: Waiters::new(),
+        }
+    }
+
+    pub fn available_permits(&self) -> usize {
+        self.permits.load(Ordering::SeqCst)
+    }
+
+    /// 没有可用许可时阻塞
+    pub fn acquire(&self) -> SemaphorePermit<'_> {
+        loop {
+            if let Some(permit) = self.try_acquire() {
+                return permit;
+            }
+            self.waiters.wait_until(|| self.available_permits() > 0);
+        }
+    }
+
+    pub fn try_acquire(&self) -> Option<SemaphorePermit<'_>> {
+        let mut permits = self.permits.load(Ordering::Relaxed);
+        while permits > 0 {
+            match self.permits.compare_exchange_weak(
+                permits,
+                permits - 1,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return Some(SemaphorePermit { sem: self }),
+                Err(p) => permits = p,
+            }
+        }
+        None
+    }
+
+    /// 增加许可，每个许可唤醒一个等待的线程
+    pub fn add_permits(&self, n: usize) {
+        self.permits.fetch_add(n, Ordering::SeqCst);
+        for _ in 0..n {
+            self.waiters.notify_one();
+        }
+    }
+}
+
+impl SemaphorePermit<'_> {
+    /// 不归还许可，信号量的许可数永久减少一个
+    pub fn forget(self) {
+        std::mem::forget(self);
+    }
+}
+
+impl Drop for SemaphorePermit<'_> {
+    fn drop(&mut self) {
+        self.sem.add_permits(1);
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use std::{sync::atomic::AtomicUsize, thread, time::Duration};
+
+    use super::*;
+
+    #[test]
+    fn test_try_acquire() {
+        let sem = Semaphore::new(2);
+        let a = sem.try_acquire().unwrap();
+        let b = sem.try_acquire().unwrap();
+        assert!(sem.try_acquire().is_none());
+        drop(a);
+        assert_eq!(sem.available_permits(), 1);
+        b.forget();
+        assert_eq!(sem.available_permits(), 1);
+        sem.add_permits(2);
+        assert_eq!(sem.available_permits(), 3);
+    }
+
+    #[test]
+    fn test_limits_concurrency() {
+        let sem = Semaphore::new(3);
+        let running = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        thread::scope(|s| {
+            for _ in 0..12 {
+                s.spawn(|| {
+                    let _permit = sem.acquire();
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    thread::sleep(Duration::from_millis(5));
+                    running.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+        assert!(peak.load(Ordering::SeqCst) <= 3);
+        assert_eq!(sem.available_permits(), 3);
+    }
+}
+
+#[cfg(all(test, loom))]
+mod loom_tests {
+    use loom::{
+        sync::{Arc, atomic::AtomicUsize},
+        thread,
+    };
+
+    use super::*;
+
+    #[test]
+    fn loom_mutual_exclusion() {
+        loom::model(|| {
+            let sem = Arc::new(Semaphore::new(1));
+            let inside = Arc::new(AtomicUsize::new(0));
+            let handles: Vec<_> = (0..2)
+                .map(|_| {
+                    let (sem, inside) = (sem.clone(), inside.clone());
+                    thread::spawn(move || {
+                        let _permit = sem.acquire();
+                        assert_eq!(inside.fetch_add(1, Ordering::SeqCst), 0);
+                        inside.fetch_sub(1, Ordering::SeqCst);
+                    })
+                })
+                .collect();
+            for handle in handles {
+                handle.join().unwrap();
+            }
+            assert_eq!(sem.available_permits(), 1);
+        });
+    }
+}
@@ -0,0 +1,47 @@
+//! 以 `--cfg loom` 编译时换成 loom 的实现，由 loom 枚举所有线程交错
+
+#[cfg(loom)]
+pub use loom::{
+    cell::UnsafeCell,
+    sync::{
+        Arc, Mutex, MutexGuard,
+        atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering, fence},
+    },
+    thread::{self, Thread},
+};
+#[cfg(not(loom))]
+pub use std::{
+    sync::{
+        Arc, Mutex, MutexGuard,
+        atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering, fence},
+    },
+    thread::{self, Thread},
+};
+
+/// 与 `loom::cell::UnsafeCell` 接口相同，访问都通过闭包进行
+#[cfg(not(loom))]
+#[derive(Debug)]
+pub struct UnsafeCell<T>(std::cell::UnsafeCell<T>);
+
+#[cfg(not(loom))]
+impl<T> UnsafeCell<T> {
+    pub fn new(value: T) -> Self {
+        Self(std::cell::UnsafeCell::new(value))
+    }
+
+    pub fn with<R>(&self, f: impl FnOnce(*const T) -> R) -> R {
+        f(self.0.get())
+    }
+
+    pub fn with_mut<R>(&self, f: impl FnOnce(*mut T) -> R) -> R {
+        f(self.0.get())
+    }
+}
+
+/// 等待其他线程完成写入时让出 CPU，loom 需要借此切换线程
+pub fn spin() {
+    #[cfg(loom)]
+    thread::yield_now();
+    #[cfg(not(loom))]
+    std::hint::spin_loop();
+}
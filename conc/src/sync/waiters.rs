@@ -0,0 +1,88 @@
+use std::sync::PoisonError;
+
+use super::shim::{AtomicUsize, Mutex, MutexGuard, Ordering, Thread, fence, thread};
+
+/// 挂起的线程列表。
+/// 等待方先登记再检查条件，通知方先修改状态再检查列表，
+/// 两边之间的 SeqCst fence 保证至少一方能看到对方，不会丢失唤醒
+#[derive(Debug, Default)]
+pub struct Waiters {
+    // 登记的线程数，通知方没有等待者时不需要加锁
+    len: AtomicUsize,
+    list: Mutex<List>,
+}
+
+#[derive(Debug, Default)]
+struct List {
+    next_token: usize,
+    threads: Vec<(usize, Thread)>,
+}
+
+impl Waiters {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 登记当前线程，之后需要再次检查条件，不满足时才挂起
+    pub fn register(&self) -> usize {
+        let mut list = self.lock();
+        let token = list.next_token;
+        list.next_token += 1;
+        list.threads.push((token, thread::current()));
+        self.len.store(list.threads.len(), Ordering::Relaxed);
+        drop(list);
+        fence(Ordering::SeqCst);
+        token
+    }
+
+    /// `ready` 不满足时挂起当前线程，返回后需要重新尝试，可能是虚假唤醒
+    pub fn wait_until(&self, ready: impl FnOnce() -> bool) {
+        let token = self.register();
+        if !ready() {
+            thread::park();
+        }
+        self.unregister(token);
+    }
+
+    /// 被唤醒或不再需要等待时移除登记，已被通知方移除时什么也不做
+    pub fn unregister(&self, token: usize) {
+        let mut list = self.lock();
+        if let Some(i) = list.threads.iter().position(|(t, _)| *t == token) {
+            list.threads.remove(i);
+            self.len.store(list.threads.len(), Ordering::Relaxed);
+        }
+    }
+
+    /// 唤醒最早登记的线程
+    pub fn notify_one(&self) {
+        if self.is_empty() {
+            return;
+        }
+        let mut list = self.lock();
+        if !list.threads.is_empty() {
+            let (_, thread) = list.threads.remove(0);
+            self.len.store(list.threads.len(), Ordering::Relaxed);
+            thread.unpark();
+        }
+    }
+
+    pub fn notify_all(&self) {
+        if self.is_empty() {
+            return;
+        }
+        let mut list = self.lock();
+        self.len.store(0, Ordering::Relaxed);
+        for (_, thread) in list.threads.drain(..) {
+            thread.unpark();
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        fence(Ordering::SeqCst);
+        self.len.load(Ordering::Relaxed) == 0
+    }
+
+    fn lock(&self) -> MutexGuard<'_, List> {
+        self.list.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
@@ -0,0 +1,68 @@
+use std::time::{Duration, Instant};
+
+use redis::{backend::Backend, network};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+const REQUESTS: usize = 100_000;
+const PIPELINE: usize = 100;
+
+const SET_CMD: &[u8] = b"*3\r\n$3\r\nset\r\n$3\r\nkey\r\n$5\r\nvalue\r\n";
+const SET_REPLY: &[u8] = b"+OK\r\n";
+
+// 运行: cargo run --release --package redis --example pipeline
+// 对比逐条发送和 pipeline 发送 SET 命令的吞吐量
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    let backend = Backend::new();
+
+    tokio::spawn(async move {
+        loop {
+            let Ok((socket, _)) = listener.accept().await else {
+                break;
+            };
+            let _ = socket.set_nodelay(true);
+            let backend = backend.clone();
+            tokio::spawn(async move { network::handle_stream(socket, &backend).await });
+        }
+    });
+
+    let mut stream = TcpStream::connect(addr).await?;
+    stream.set_nodelay(true)?;
+
+    let elapsed = run(&mut stream, 1).await?;
+    report("one by one", elapsed);
+
+    let elapsed = run(&mut stream, PIPELINE).await?;
+    report(&format!("pipeline {}", PIPELINE), elapsed);
+
+    Ok(())
+}
+
+async fn run(stream: &mut TcpStream, pipeline: usize) -> anyhow::Result<Duration> {
+    let request = SET_CMD.repeat(pipeline);
+    let mut reply = vec![0u8; SET_REPLY.len() * pipeline];
+
+    let start = Instant::now();
+    for _ in 0..REQUESTS / pipeline {
+        stream.write_all(&request).await?;
+        stream.read_exact(&mut reply).await?;
+    }
+
+    Ok(start.elapsed())
+}
+
+fn report(name: &str, elapsed: Duration) {
+    println!(
+        "{:<12} {} requests in {:?}, {:.0} req/s",
+        name,
+        REQUESTS,
+        elapsed,
+        REQUESTS as f64 / elapsed.as_secs_f64()
+    );
+}
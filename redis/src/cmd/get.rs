@@ -1,10 +1,12 @@
 use crate::{
     backend::Backend,
-    cmd::{Cmd, CmdError, CmdExecutor},
+    cmd::{self, Cmd, CmdError, CmdExecutor},
     resp::{array::RespArray, frame::RespFrame, null::RespNull},
 };
 use anyhow::Result;
+use serde::Deserialize;
 
+#[derive(Deserialize)]
 pub struct Get {
     key: String,
 }
@@ -22,17 +24,7 @@ impl TryFrom<RespArray> for Get {
     type Error = CmdError;
 
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
-        match &value[1] {
-            RespFrame::BulkString(key) => {
-                let key = String::from_utf8(key.to_vec())
-                    .map_err(|e| CmdError::InvalidArguments(e.to_string()))?;
-
-                Ok(Self { key })
-            }
-            _ => Err(CmdError::InvalidArguments(
-                "Invalid GET command arguments".to_string(),
-            )),
-        }
+        cmd::parse_args(value, "GET")
     }
 }
 
@@ -62,4 +54,22 @@ mod tests {
 
         Ok(())
     }
+    #[test]
+    fn test_get_cmd_invalid_arguments() {
+        let array = RespArray(vec![RespFrame::BulkString(BulkString(b"GET".to_vec()))]);
+        assert!(matches!(
+            Get::try_from(array),
+            Err(CmdError::InvalidArguments(_))
+        ));
+
+        let array = RespArray(vec![
+            RespFrame::BulkString(BulkString(b"GET".to_vec())),
+            RespFrame::BulkString(BulkString(b"key".to_vec())),
+            RespFrame::BulkString(BulkString(b"extra".to_vec())),
+        ]);
+        assert!(matches!(
+            Get::try_from(array),
+            Err(CmdError::InvalidArguments(_))
+        ));
+    }
 }
@@ -1,10 +1,12 @@
 use crate::backend::Backend;
-use crate::cmd::{Cmd, CmdError, CmdExecutor};
+use crate::cmd::{self, Cmd, CmdError, CmdExecutor};
 use crate::resp::array::RespArray;
 use crate::resp::frame::RespFrame;
 use crate::resp::null::RespNull;
 use anyhow::Result;
+use serde::Deserialize;
 
+#[derive(Deserialize)]
 pub struct HGet {
     key: String,
     field: String,
@@ -23,20 +25,7 @@ impl TryFrom<RespArray> for HGet {
     type Error = CmdError;
 
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
-        match (&value[1], &value[2]) {
-            (RespFrame::BulkString(key), RespFrame::BulkString(field)) => {
-                let key = String::from_utf8(key.to_vec())
-                    .map_err(|e| CmdError::InvalidArguments(e.to_string()))?;
-
-                let field = String::from_utf8(field.to_vec())
-                    .map_err(|e| CmdError::InvalidArguments(e.to_string()))?;
-
-                Ok(HGet { key, field })
-            }
-            _ => Err(CmdError::InvalidArguments(
-                "Invalid HGET command arguments".to_string(),
-            )),
-        }
+        cmd::parse_args(value, "HGET")
     }
 }
 
@@ -1,12 +1,14 @@
 use crate::backend::Backend;
-use crate::cmd::{Cmd, CmdError, CmdExecutor};
+use crate::cmd::{self, Cmd, CmdError, CmdExecutor};
 use crate::resp::array::RespArray;
 use crate::resp::frame::RespFrame;
 use crate::resp::map::RespMap;
 use crate::resp::null::RespNull;
 use crate::resp::simple_string::SimpleString;
 use anyhow::Result;
+use serde::Deserialize;
 
+#[derive(Deserialize)]
 pub struct HGetAll {
     key: String,
 }
@@ -33,17 +35,7 @@ impl TryFrom<RespArray> for HGetAll {
     type Error = CmdError;
 
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
-        match &value[1] {
-            RespFrame::BulkString(key) => {
-                let key = String::from_utf8(key.to_vec())
-                    .map_err(|e| CmdError::InvalidArguments(e.to_string()))?;
-
-                Ok(HGetAll { key })
-            }
-            _ => Err(CmdError::InvalidArguments(
-                "Invalid HGETALL command arguments".to_string(),
-            )),
-        }
+        cmd::parse_args(value, "HGETALL")
     }
 }
 
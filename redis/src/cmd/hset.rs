@@ -1,19 +1,22 @@
 use crate::{
     backend::Backend,
-    cmd::{Cmd, CmdError, CmdExecutor},
-    resp::{array::RespArray, frame::RespFrame},
+    cmd::{self, Cmd, CmdError, CmdExecutor},
+    resp::{array::RespArray, bulk_string::BulkString, frame::RespFrame},
 };
 use anyhow::Result;
+use serde::Deserialize;
 
+#[derive(Deserialize)]
 pub struct HSet {
     key: String,
     field: String,
-    value: RespFrame,
+    value: BulkString,
 }
 
 impl CmdExecutor for HSet {
     fn execute(&self, backend: &Backend) -> Result<RespFrame> {
-        backend.hset(self.key.clone(), self.field.clone(), self.value.clone())?;
+        let value = RespFrame::BulkString(self.value.clone());
+        backend.hset(self.key.clone(), self.field.clone(), value)?;
         Ok(RespFrame::Integer(1))
     }
 }
@@ -22,26 +25,7 @@ impl TryFrom<RespArray> for HSet {
     type Error = CmdError;
 
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
-        match (&value[1], &value[2], &value[3]) {
-            (
-                RespFrame::BulkString(key),
-                RespFrame::BulkString(field),
-                RespFrame::BulkString(value),
-            ) => {
-                let key = String::from_utf8(key.to_vec())
-                    .map_err(|e| CmdError::InvalidArguments(e.to_string()))?;
-
-                let field = String::from_utf8(field.to_vec())
-                    .map_err(|e| CmdError::InvalidArguments(e.to_string()))?;
-
-                let value = RespFrame::BulkString(value.clone());
-
-                Ok(HSet { key, field, value })
-            }
-            _ => Err(CmdError::InvalidArguments(
-                "Invalid HSET command arguments".to_string(),
-            )),
-        }
+        cmd::parse_args(value, "HSET")
     }
 }
 
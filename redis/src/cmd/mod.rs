@@ -1,9 +1,10 @@
 use crate::{
     backend::Backend,
     cmd::{get::Get, hget::HGet, hgetall::HGetAll, hset::HSet, set::Set, unknown::Unknown},
-    resp::{RespError, array::RespArray, frame::RespFrame},
+    resp::{RespError, array::RespArray, frame::RespFrame, serde::from_frame},
 };
 use anyhow::Result;
+use serde::de::DeserializeOwned;
 use thiserror::Error;
 
 pub mod get;
@@ -50,6 +51,14 @@ impl CmdExecutor for Cmd {
     }
 }
 
+// 去掉命令名，将剩余参数按顺序反序列化为命令结构体的字段
+fn parse_args<T: DeserializeOwned>(value: RespArray, name: &str) -> Result<T, CmdError> {
+    let args = value.0.into_iter().skip(1).collect::<Vec<_>>();
+    from_frame(RespFrame::Array(RespArray::new(args))).map_err(|e| {
+        CmdError::InvalidArguments(format!("Invalid {} command arguments: {}", name, e))
+    })
+}
+
 impl TryFrom<RespFrame> for Cmd {
     type Error = CmdError;
 
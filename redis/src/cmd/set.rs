@@ -1,18 +1,22 @@
 use crate::{
     backend::Backend,
-    cmd::{Cmd, CmdError, CmdExecutor},
-    resp::{array::RespArray, frame::RespFrame, simple_string::SimpleString},
+    cmd::{self, Cmd, CmdError, CmdExecutor},
+    resp::{
+        array::RespArray, bulk_string::BulkString, frame::RespFrame, simple_string::SimpleString,
+    },
 };
 use anyhow::Result;
+use serde::Deserialize;
 
+#[derive(Deserialize)]
 pub struct Set {
     key: String,
-    value: RespFrame,
+    value: BulkString,
 }
 
 impl CmdExecutor for Set {
     fn execute(&self, backend: &Backend) -> Result<RespFrame> {
-        backend.set(self.key.clone(), self.value.clone())?;
+        backend.set(self.key.clone(), RespFrame::BulkString(self.value.clone()))?;
         Ok(RespFrame::SimpleString(SimpleString::new("OK")))
     }
 }
@@ -21,19 +25,7 @@ impl TryFrom<RespArray> for Set {
     type Error = CmdError;
 
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
-        match (&value[1], &value[2]) {
-            (RespFrame::BulkString(key), RespFrame::BulkString(value)) => {
-                let key = String::from_utf8(key.to_vec())
-                    .map_err(|e| CmdError::InvalidArguments(e.to_string()))?;
-
-                let value = RespFrame::BulkString(value.clone());
-
-                Ok(Set { key, value })
-            }
-            _ => Err(CmdError::InvalidArguments(
-                "Invalid SET command arguments".to_string(),
-            )),
-        }
+        cmd::parse_args(value, "SET")
     }
 }
 
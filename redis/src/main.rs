@@ -16,6 +16,8 @@ async fn main() -> anyhow::Result<()> {
     loop {
         let backend = backend.clone();
         let (socket, addr) = listener.accept().await?;
+        // 回复按批次 flush，关闭 Nagle 避免小包等待 ACK
+        socket.set_nodelay(true)?;
         println!("server redis accepts connection from {}", addr);
 
         tokio::spawn(async move {
@@ -1,15 +1,26 @@
+use std::time::Duration;
+
 use bytes::BytesMut;
 use futures::SinkExt as _;
-use tokio::net::TcpStream;
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio_stream::StreamExt;
 use tokio_util::codec::{Decoder, Encoder, Framed};
 
 use crate::backend::Backend;
 
 use crate::cmd::{Cmd, CmdExecutor as _};
-use crate::resp::frame::RespFrame;
+use crate::resp::frame::{self, RespFrame};
 use crate::resp::{RespDecode as _, RespEncode as _, RespError};
 
+// 单个批次最多执行的命令数，避免一个连接长时间不 flush
+const MAX_BATCH_SIZE: usize = 1024;
+
+// 每个连接待发送数据的上限，超过后断开连接（慢消费者）
+const OUTPUT_BUFFER_LIMIT: usize = 32 * 1024 * 1024;
+
+// 客户端一直不读取时 flush 会永远等待，超时后断开连接
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(30);
+
 struct Request {
     frame: RespFrame,
     backend: Backend,
@@ -36,6 +47,13 @@ impl Decoder for RespFrameCodec {
     type Error = anyhow::Error;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        // 先确认缓冲区中有完整的帧，否则数组的头部会被提前消耗掉
+        match frame::expect_length(src) {
+            Ok(_) => {}
+            Err(RespError::Incomplete) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+
         match RespFrame::decode(src) {
             Ok(frame) => Ok(Some(frame)),
             Err(RespError::Incomplete) => Ok(None),
@@ -44,9 +62,25 @@ impl Decoder for RespFrameCodec {
     }
 }
 
-pub async fn handle_stream(socket: TcpStream, backend: &Backend) -> anyhow::Result<()> {
+pub async fn handle_stream<S>(socket: S, backend: &Backend) -> anyhow::Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    handle_stream_with_timeout(socket, backend, FLUSH_TIMEOUT).await
+}
+
+async fn handle_stream_with_timeout<S>(
+    socket: S,
+    backend: &Backend,
+    flush_timeout: Duration,
+) -> anyhow::Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
     let codec = RespFrameCodec;
     let mut framed = Framed::new(socket, codec);
+    // feed 时不自动 flush，每个批次结束后统一 flush 一次
+    framed.set_backpressure_boundary(usize::MAX);
 
     // 举例
     // *3\r\n
@@ -64,21 +98,45 @@ pub async fn handle_stream(socket: TcpStream, backend: &Backend) -> anyhow::Resu
     //
     // framed.next() 消耗的是整个 RESP 帧（整个数组）
     while let Some(result) = framed.next().await {
-        match result {
-            Ok(frame) => {
-                eprintln!("Received frame: {:?}", frame);
-
-                let request = Request {
-                    frame,
-                    backend: backend.clone(),
-                };
-                let resp = handle_request(request).await?;
-                framed.send(resp.frame).await?;
-            }
+        let frame = match result {
+            Ok(frame) => frame,
             Err(e) => {
                 eprintln!("Decode error: {:?}", e);
                 return Err(e);
             }
+        };
+
+        // pipeline：读缓冲区中已经完整到达的帧一次性取出，不再读 socket
+        let mut batch = vec![frame];
+        while batch.len() < MAX_BATCH_SIZE
+            && let Some(frame) = RespFrameCodec.decode(framed.read_buffer_mut())?
+        {
+            batch.push(frame);
+        }
+
+        // 按顺序执行，回复先写入发送缓冲区
+        for frame in batch {
+            let request = Request {
+                frame,
+                backend: backend.clone(),
+            };
+            let resp = handle_request(request).await?;
+            framed.feed(resp.frame).await?;
+
+            if framed.write_buffer().len() > OUTPUT_BUFFER_LIMIT {
+                anyhow::bail!(
+                    "Output buffer limit exceeded: {} bytes pending",
+                    framed.write_buffer().len()
+                );
+            }
+        }
+
+        match tokio::time::timeout(flush_timeout, framed.flush()).await {
+            Ok(result) => result?,
+            Err(_) => anyhow::bail!(
+                "Flush timed out: {} bytes pending",
+                framed.write_buffer().len()
+            ),
         }
     }
 
@@ -91,3 +149,77 @@ async fn handle_request(request: Request) -> anyhow::Result<Response> {
     let resp = cmd.execute(&backend)?;
     Ok(Response { frame: resp })
 }
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+
+    use super::*;
+
+    #[test]
+    fn test_codec_keeps_incomplete_array() -> anyhow::Result<()> {
+        let mut buf = BytesMut::from("*3\r\n$3\r\nset\r\n$3\r\nkey\r\n$5\r\nval");
+        let frame = RespFrameCodec.decode(&mut buf)?;
+
+        assert!(frame.is_none());
+        assert!(buf.starts_with(b"*3\r\n"));
+
+        buf.extend_from_slice(b"ue\r\n");
+        let frame = RespFrameCodec.decode(&mut buf)?;
+
+        assert!(matches!(frame, Some(RespFrame::Array(_))));
+        assert!(buf.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_handle_stream_pipeline() -> anyhow::Result<()> {
+        let (mut client, server) = tokio::io::duplex(4096);
+        let backend = Backend::new();
+        let handle = tokio::spawn(async move { handle_stream(server, &backend).await });
+
+        client
+            .write_all(
+                b"*3\r\n$3\r\nset\r\n$1\r\na\r\n$1\r\n1\r\n\
+                  *3\r\n$3\r\nset\r\n$1\r\nb\r\n$1\r\n2\r\n\
+                  *2\r\n$3\r\nget\r\n$1\r\na\r\n",
+            )
+            .await?;
+
+        let expected = b"+OK\r\n+OK\r\n$1\r\n1\r\n";
+        let mut buf = vec![0; expected.len()];
+        client.read_exact(&mut buf).await?;
+        assert_eq!(buf, expected);
+
+        drop(client);
+        handle.await??;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_handle_stream_drops_client_that_never_reads() -> anyhow::Result<()> {
+        let (mut client, server) = tokio::io::duplex(64);
+        let backend = Backend::new();
+        let handle = tokio::spawn(async move {
+            handle_stream_with_timeout(server, &backend, Duration::from_millis(100)).await
+        });
+
+        // 回复远大于管道容量，客户端只发不收
+        let value = "x".repeat(1024);
+        let set = format!(
+            "*3\r\n$3\r\nset\r\n$1\r\na\r\n${}\r\n{}\r\n",
+            value.len(),
+            value
+        );
+        client.write_all(set.as_bytes()).await?;
+        client.write_all(b"*2\r\n$3\r\nget\r\n$1\r\na\r\n").await?;
+
+        let result = tokio::time::timeout(Duration::from_secs(5), handle).await??;
+        let err = result.expect_err("connection should be dropped");
+        assert!(err.to_string().contains("Flush timed out"));
+
+        Ok(())
+    }
+}
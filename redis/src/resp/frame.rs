@@ -21,6 +21,16 @@ use crate::resp::{
     - map:                  %<number-of-entries>\r\n<key-1><value-1>...<key-n><value-n>
     - set:                  ~<number-of-elements>\r\n<element-1>...<element-n>
 */
+
+// bulk string 的最大长度，与 redis 的 proto-max-bulk-len 默认值一致
+pub const MAX_BULK_LEN: usize = 512 * 1024 * 1024;
+
+// 数组、集合、map 的最大元素个数
+pub const MAX_AGGREGATE_LEN: usize = 1024 * 1024;
+
+// 聚合类型的最大嵌套层数，避免深层嵌套的帧在递归解析时耗尽栈空间
+pub const MAX_NESTING_DEPTH: usize = 128;
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum RespFrame {
     SimpleString(SimpleString),
@@ -91,6 +101,70 @@ pub fn extract_frame(buf: &[u8], prefix: &str) -> Result<usize, RespError> {
     Ok(end)
 }
 
+// 计算 buf 开头第一个完整帧的字节数，不消耗 buf
+// 数组等嵌套类型在 decode 时会先消耗头部，数据不完整时需要先用它判断
+pub fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
+    expect_length_at(buf, 0)
+}
+
+fn expect_length_at(buf: &[u8], depth: usize) -> Result<usize, RespError> {
+    match buf.first() {
+        Some(b'+' | b'-' | b':' | b'_' | b'#' | b',') => {
+            let end = find_crlf(buf, 1).ok_or(RespError::Incomplete)?;
+            Ok(end + 2)
+        }
+        Some(b'$') => {
+            let (len, end) = parse_len(buf, "$")?;
+            let total = checked_sum(&[end, 2, len, 2])?;
+            if buf.len() < total {
+                return Err(RespError::Incomplete);
+            }
+            Ok(total)
+        }
+        Some(b'*') => expect_aggregate_length(buf, "*", 1, depth),
+        Some(b'~') => expect_aggregate_length(buf, "~", 1, depth),
+        Some(b'%') => expect_aggregate_length(buf, "%", 2, depth),
+        _ => Err(RespError::Incomplete),
+    }
+}
+
+// 聚合类型：头部之后跟着 len * per_entry 个子帧
+fn expect_aggregate_length(
+    buf: &[u8],
+    prefix: &str,
+    per_entry: usize,
+    depth: usize,
+) -> Result<usize, RespError> {
+    if depth >= MAX_NESTING_DEPTH {
+        return Err(RespError::InvalidFrame(format!(
+            "Frame nested deeper than {}",
+            MAX_NESTING_DEPTH
+        )));
+    }
+    let (len, end) = parse_len(buf, prefix)?;
+    let count = len
+        .checked_mul(per_entry)
+        .ok_or_else(|| overflow(prefix, len))?;
+    let mut total = end + 2;
+
+    for _ in 0..count {
+        total = checked_sum(&[total, expect_length_at(&buf[total..], depth + 1)?])?;
+    }
+
+    Ok(total)
+}
+
+fn checked_sum(values: &[usize]) -> Result<usize, RespError> {
+    values
+        .iter()
+        .try_fold(0usize, |acc, v| acc.checked_add(*v))
+        .ok_or_else(|| RespError::InvalidFrame("Frame length overflow".to_string()))
+}
+
+fn overflow(prefix: &str, len: usize) -> RespError {
+    RespError::InvalidFrame(format!("Invalid {} length: {}", prefix, len))
+}
+
 // 查找 buf 中第 nth 个 CRLF
 fn find_crlf(buf: &[u8], nth: usize) -> Option<usize> {
     let mut count = 0;
@@ -117,6 +191,15 @@ pub fn parse_len(buf: &[u8], prefix: &str) -> Result<(usize, usize), RespError>
     let len_str = buf[prefix.len()..end].to_vec();
     let len_val = String::from_utf8(len_str)?.parse::<usize>()?;
 
+    let max = if prefix == "$" {
+        MAX_BULK_LEN
+    } else {
+        MAX_AGGREGATE_LEN
+    };
+    if len_val > max {
+        return Err(overflow(prefix, len_val));
+    }
+
     Ok((len_val, end))
 }
 
@@ -139,3 +222,76 @@ pub fn extract_fixed_frame(buf: &mut BytesMut, prefix: &str, name: &str) -> Resu
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expect_length() -> anyhow::Result<()> {
+        let buf = b"*2\r\n$3\r\nget\r\n$5\r\nhello\r\n+OK\r\n";
+        assert_eq!(expect_length(buf)?, 24);
+        assert_eq!(expect_length(&buf[24..])?, 5);
+
+        let buf = b"%1\r\n+a\r\n:+1\r\n";
+        assert_eq!(expect_length(buf)?, buf.len());
+
+        assert!(matches!(
+            expect_length(b"*2\r\n$3\r\nget\r\n$5\r\nhel"),
+            Err(RespError::Incomplete)
+        ));
+        assert!(matches!(expect_length(b""), Err(RespError::Incomplete)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_expect_length_rejects_deep_nesting() {
+        let nested = |depth: usize| {
+            let mut buf = b"*1\r\n".repeat(depth);
+            buf.extend_from_slice(b":1\r\n");
+            buf
+        };
+        let buf = nested(MAX_NESTING_DEPTH);
+        assert_eq!(expect_length(&buf).ok(), Some(buf.len()));
+
+        // 远超上限的嵌套不会耗尽栈空间，数据不完整时同样拒绝
+        for buf in [nested(MAX_NESTING_DEPTH + 1), b"*1\r\n".repeat(100_000)] {
+            assert!(matches!(
+                expect_length(&buf),
+                Err(RespError::InvalidFrame(_))
+            ));
+        }
+        assert!(matches!(
+            expect_length(&b"%1\r\n+k\r\n".repeat(MAX_NESTING_DEPTH + 1)),
+            Err(RespError::InvalidFrame(_))
+        ));
+    }
+
+    #[test]
+    fn test_expect_length_rejects_huge_length() {
+        for buf in [
+            &b"%18446744073709551615\r\n"[..],
+            b"*18446744073709551615\r\n",
+            b"$18446744073709551615\r\n",
+            b"$536870913\r\n",
+            b"~1048577\r\n",
+        ] {
+            assert!(
+                matches!(expect_length(buf), Err(RespError::InvalidFrame(_))),
+                "{:?}",
+                String::from_utf8_lossy(buf)
+            );
+        }
+
+        // 上限以内的长度只是数据还没收完
+        assert!(matches!(
+            expect_length(b"$536870912\r\n"),
+            Err(RespError::Incomplete)
+        ));
+        assert!(matches!(
+            expect_length(b"%1048576\r\n"),
+            Err(RespError::Incomplete)
+        ));
+    }
+}
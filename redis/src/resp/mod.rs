@@ -6,6 +6,7 @@ pub mod frame;
 pub mod integer;
 pub mod map;
 pub mod null;
+pub mod serde;
 pub mod set;
 pub mod simple_error;
 pub mod simple_string;
@@ -39,4 +40,7 @@ pub enum RespError {
     ParseFloatError(#[from] std::num::ParseFloatError),
     #[error("Parse utf8 error: {0}")]
     Utf8Error(#[from] std::string::FromUtf8Error),
+
+    #[error("Serde error: {0}")]
+    Serde(String),
 }
@@ -0,0 +1,291 @@
+use std::{collections::hash_map, vec};
+
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer as _, MapAccess,
+    SeqAccess, VariantAccess, Visitor, value::StringDeserializer,
+};
+
+use crate::resp::{RespError, frame::RespFrame, simple_string::SimpleString};
+
+/// 将 RespFrame 反序列化为 Rust 值
+///
+/// 除了和 `to_frame` 对应的映射外，BulkString / SimpleString 也可以解析为
+/// 数字和 bool，这样命令参数（全部是 BulkString）可以直接反序列化为结构体
+pub fn from_frame<T: DeserializeOwned>(frame: RespFrame) -> Result<T, RespError> {
+    T::deserialize(Deserializer::new(frame))
+}
+
+pub struct Deserializer {
+    frame: RespFrame,
+}
+
+impl Deserializer {
+    pub fn new(frame: RespFrame) -> Self {
+        Self { frame }
+    }
+
+    fn into_string(self) -> Result<String, RespError> {
+        match self.frame {
+            RespFrame::SimpleString(s) => Ok(s.0),
+            RespFrame::BulkString(b) => Ok(String::from_utf8(b.0)?),
+            frame => Err(RespError::Serde(format!("expect string, got {:?}", frame))),
+        }
+    }
+}
+
+// BulkString / SimpleString 先解析字符串，其他类型按原类型处理
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident($ty:ty)),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RespError> {
+                match self.frame {
+                    RespFrame::SimpleString(_) | RespFrame::BulkString(_) => {
+                        let s = self.into_string()?;
+                        let v = s.parse::<$ty>().map_err(|e| {
+                            RespError::Serde(format!("invalid {} {:?}: {}", stringify!($ty), s, e))
+                        })?;
+                        visitor.$visit(v)
+                    }
+                    _ => self.deserialize_any(visitor),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Deserializer {
+    type Error = RespError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RespError> {
+        match self.frame {
+            RespFrame::SimpleString(s) => visitor.visit_string(s.0),
+            RespFrame::Error(e) => Err(RespError::Serde(format!("error frame: {}", e.as_str()))),
+            RespFrame::Integer(i) => visitor.visit_i64(i),
+            RespFrame::BulkString(b) => match String::from_utf8(b.0) {
+                Ok(s) => visitor.visit_string(s),
+                Err(e) => visitor.visit_byte_buf(e.into_bytes()),
+            },
+            RespFrame::Array(a) => visit_seq(a.0, visitor),
+            RespFrame::Null(_) => visitor.visit_unit(),
+            RespFrame::Boolean(b) => visitor.visit_bool(b),
+            RespFrame::Double(d) => visitor.visit_f64(d),
+            RespFrame::Map(mut m) => visit_map(std::mem::take(&mut *m), visitor),
+            RespFrame::Set(mut s) => visit_seq(std::mem::take(&mut *s), visitor),
+        }
+    }
+
+    deserialize_parsed! {
+        deserialize_bool => visit_bool(bool),
+        deserialize_i8 => visit_i8(i8),
+        deserialize_i16 => visit_i16(i16),
+        deserialize_i32 => visit_i32(i32),
+        deserialize_i64 => visit_i64(i64),
+        deserialize_u8 => visit_u8(u8),
+        deserialize_u16 => visit_u16(u16),
+        deserialize_u32 => visit_u32(u32),
+        deserialize_u64 => visit_u64(u64),
+        deserialize_f32 => visit_f32(f32),
+        deserialize_f64 => visit_f64(f64),
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RespError> {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RespError> {
+        match self.frame {
+            RespFrame::BulkString(b) => visitor.visit_byte_buf(b.0),
+            RespFrame::SimpleString(s) => visitor.visit_byte_buf(s.0.into_bytes()),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RespError> {
+        match self.frame {
+            RespFrame::Null(_) => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, RespError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, RespError> {
+        match self.frame {
+            RespFrame::SimpleString(_) | RespFrame::BulkString(_) => {
+                visitor.visit_enum(self.into_string()?.into_deserializer())
+            }
+            RespFrame::Map(mut m) if m.len() == 1 => {
+                let (variant, value) = std::mem::take(&mut *m)
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| RespError::Serde("empty enum map".to_string()))?;
+                visitor.visit_enum(EnumDeserializer {
+                    variant: variant.0,
+                    value,
+                })
+            }
+            frame => Err(RespError::Serde(format!(
+                "expect string or single entry map for enum, got {:?}",
+                frame
+            ))),
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RespError> {
+        visitor.visit_unit()
+    }
+
+    serde::forward_to_deserialize_any! {
+        char str string unit unit_struct seq tuple tuple_struct map struct identifier
+    }
+}
+
+fn visit_seq<'de, V: Visitor<'de>>(
+    frames: Vec<RespFrame>,
+    visitor: V,
+) -> Result<V::Value, RespError> {
+    let len = frames.len();
+    let mut seq = SeqDeserializer {
+        iter: frames.into_iter(),
+    };
+    let value = visitor.visit_seq(&mut seq)?;
+
+    match seq.iter.len() {
+        0 => Ok(value),
+        _ => Err(de::Error::invalid_length(len, &"fewer elements in array")),
+    }
+}
+
+fn visit_map<'de, V: Visitor<'de>>(
+    entries: std::collections::HashMap<SimpleString, RespFrame>,
+    visitor: V,
+) -> Result<V::Value, RespError> {
+    let mut map = MapDeserializer {
+        iter: entries.into_iter(),
+        value: None,
+    };
+    visitor.visit_map(&mut map)
+}
+
+struct SeqDeserializer {
+    iter: vec::IntoIter<RespFrame>,
+}
+
+impl<'de> SeqAccess<'de> for SeqDeserializer {
+    type Error = RespError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, RespError> {
+        match self.iter.next() {
+            Some(frame) => seed.deserialize(Deserializer::new(frame)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct MapDeserializer {
+    iter: hash_map::IntoIter<SimpleString, RespFrame>,
+    value: Option<RespFrame>,
+}
+
+impl<'de> MapAccess<'de> for MapDeserializer {
+    type Error = RespError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, RespError> {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                let key = RespFrame::SimpleString(key);
+                seed.deserialize(Deserializer::new(key)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, RespError> {
+        match self.value.take() {
+            Some(value) => seed.deserialize(Deserializer::new(value)),
+            None => Err(RespError::Serde("value is missing".to_string())),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct EnumDeserializer {
+    variant: String,
+    value: RespFrame,
+}
+
+impl<'de> EnumAccess<'de> for EnumDeserializer {
+    type Error = RespError;
+    type Variant = VariantDeserializer;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, VariantDeserializer), RespError> {
+        let variant = seed.deserialize(StringDeserializer::<RespError>::new(self.variant))?;
+        Ok((variant, VariantDeserializer { value: self.value }))
+    }
+}
+
+struct VariantDeserializer {
+    value: RespFrame,
+}
+
+impl<'de> VariantAccess<'de> for VariantDeserializer {
+    type Error = RespError;
+
+    fn unit_variant(self) -> Result<(), RespError> {
+        match self.value {
+            RespFrame::Null(_) => Ok(()),
+            frame => Err(RespError::Serde(format!(
+                "expect null for unit variant, got {:?}",
+                frame
+            ))),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, RespError> {
+        seed.deserialize(Deserializer::new(self.value))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, RespError> {
+        de::Deserializer::deserialize_seq(Deserializer::new(self.value), visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, RespError> {
+        de::Deserializer::deserialize_map(Deserializer::new(self.value), visitor)
+    }
+}
@@ -0,0 +1,186 @@
+mod de;
+mod ser;
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::resp::{RespError, bulk_string::BulkString};
+
+pub use de::{Deserializer, from_frame};
+pub use ser::{Serializer, to_frame};
+
+impl serde::ser::Error for RespError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        RespError::Serde(msg.to_string())
+    }
+}
+
+impl serde::de::Error for RespError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        RespError::Serde(msg.to_string())
+    }
+}
+
+// BulkString 按字节序列化，非 UTF-8 数据也能保留
+impl Serialize for BulkString {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for BulkString {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct BulkStringVisitor;
+
+        impl serde::de::Visitor<'_> for BulkStringVisitor {
+            type Value = BulkString;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a bulk string")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<BulkString, E> {
+                Ok(BulkString::new(v))
+            }
+
+            fn visit_bytes<E: serde::de::Error>(self, v: &[u8]) -> Result<BulkString, E> {
+                Ok(BulkString::new(v))
+            }
+
+            fn visit_byte_buf<E: serde::de::Error>(self, v: Vec<u8>) -> Result<BulkString, E> {
+                Ok(BulkString::new(v))
+            }
+        }
+
+        deserializer.deserialize_byte_buf(BulkStringVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::resp::{
+        array::RespArray, frame::RespFrame, map::RespMap, null::RespNull,
+        simple_string::SimpleString,
+    };
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct User {
+        name: String,
+        age: u32,
+        score: f64,
+        email: Option<String>,
+        tags: Vec<String>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Event {
+        Ping,
+        Login(String),
+        Move { x: i64, y: i64 },
+    }
+
+    #[test]
+    fn test_struct_to_map() -> anyhow::Result<()> {
+        let user = User {
+            name: "alice".to_string(),
+            age: 30,
+            score: 9.5,
+            email: None,
+            tags: vec!["a".to_string(), "b".to_string()],
+        };
+        let frame = to_frame(&user)?;
+
+        let RespFrame::Map(map) = &frame else {
+            panic!("Expected Map, got {:?}", frame);
+        };
+        assert_eq!(
+            map.get(&SimpleString::new("name")),
+            Some(&RespFrame::BulkString(BulkString::new("alice")))
+        );
+        assert_eq!(
+            map.get(&SimpleString::new("age")),
+            Some(&RespFrame::Integer(30))
+        );
+        assert_eq!(
+            map.get(&SimpleString::new("score")),
+            Some(&RespFrame::Double(9.5))
+        );
+        assert_eq!(
+            map.get(&SimpleString::new("email")),
+            Some(&RespFrame::Null(RespNull))
+        );
+        assert_eq!(
+            map.get(&SimpleString::new("tags")),
+            Some(&RespFrame::Array(RespArray::new(vec![
+                RespFrame::BulkString(BulkString::new("a")),
+                RespFrame::BulkString(BulkString::new("b")),
+            ])))
+        );
+
+        let decoded: User = from_frame(frame)?;
+        assert_eq!(decoded, user);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_enum_roundtrip() -> anyhow::Result<()> {
+        for event in [
+            Event::Ping,
+            Event::Login("bob".to_string()),
+            Event::Move { x: 1, y: -2 },
+        ] {
+            let frame = to_frame(&event)?;
+            let decoded: Event = from_frame(frame)?;
+            assert_eq!(decoded, event);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_args_from_bulk_strings() -> anyhow::Result<()> {
+        let frame = RespFrame::Array(RespArray::new(vec![
+            RespFrame::BulkString(BulkString::new("key")),
+            RespFrame::BulkString(BulkString::new("42")),
+            RespFrame::BulkString(BulkString::new(vec![0xff, 0x00])),
+        ]));
+        let (key, n, raw): (String, i64, BulkString) = from_frame(frame)?;
+
+        assert_eq!(key, "key");
+        assert_eq!(n, 42);
+        assert_eq!(raw.as_slice(), &[0xff, 0x00]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_array_length_mismatch() {
+        let frame = RespFrame::Array(RespArray::new(vec![
+            RespFrame::Integer(1),
+            RespFrame::Integer(2),
+            RespFrame::Integer(3),
+        ]));
+        assert!(from_frame::<(i64, i64)>(frame.clone()).is_err());
+        assert!(from_frame::<(i64, i64, i64, i64)>(frame).is_err());
+    }
+
+    #[test]
+    fn test_map_to_hashmap() -> anyhow::Result<()> {
+        let mut map = RespMap::new();
+        map.insert(SimpleString::new("a"), RespFrame::Integer(1));
+        map.insert(SimpleString::new("b"), RespFrame::Integer(2));
+
+        let decoded: HashMap<String, i64> = from_frame(RespFrame::Map(map.clone()))?;
+        assert_eq!(decoded.len(), 2);
+        assert_eq!(decoded["b"], 2);
+
+        assert_eq!(to_frame(&decoded)?, RespFrame::Map(map));
+
+        Ok(())
+    }
+}
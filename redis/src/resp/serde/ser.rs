@@ -0,0 +1,500 @@
+use serde::{
+    Serialize,
+    ser::{self, Impossible},
+};
+
+use crate::resp::{
+    RespError, array::RespArray, bulk_string::BulkString, frame::RespFrame, map::RespMap,
+    null::RespNull, simple_string::SimpleString,
+};
+
+/// 将 Rust 值序列化为 RespFrame
+///
+/// - bool -> Boolean, 整数 -> Integer, 浮点数 -> Double
+/// - 字符串、字节 -> BulkString
+/// - Option::None、() -> Null
+/// - 序列、元组 -> Array
+/// - 结构体、map -> Map，非 unit 的枚举成员 -> 只有一项的 Map
+pub fn to_frame<T: Serialize + ?Sized>(value: &T) -> Result<RespFrame, RespError> {
+    value.serialize(Serializer)
+}
+
+pub struct Serializer;
+
+pub struct SerializeVec {
+    frames: Vec<RespFrame>,
+}
+
+pub struct SerializeTupleVariant {
+    name: &'static str,
+    frames: Vec<RespFrame>,
+}
+
+pub struct SerializeMap {
+    map: RespMap,
+    next_key: Option<SimpleString>,
+}
+
+pub struct SerializeStructVariant {
+    name: &'static str,
+    map: RespMap,
+}
+
+impl ser::Serializer for Serializer {
+    type Ok = RespFrame;
+    type Error = RespError;
+
+    type SerializeSeq = SerializeVec;
+    type SerializeTuple = SerializeVec;
+    type SerializeTupleStruct = SerializeVec;
+    type SerializeTupleVariant = SerializeTupleVariant;
+    type SerializeMap = SerializeMap;
+    type SerializeStruct = SerializeMap;
+    type SerializeStructVariant = SerializeStructVariant;
+
+    fn serialize_bool(self, v: bool) -> Result<RespFrame, RespError> {
+        Ok(RespFrame::Boolean(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<RespFrame, RespError> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<RespFrame, RespError> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<RespFrame, RespError> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<RespFrame, RespError> {
+        Ok(RespFrame::Integer(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<RespFrame, RespError> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<RespFrame, RespError> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<RespFrame, RespError> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<RespFrame, RespError> {
+        let v = i64::try_from(v)
+            .map_err(|_| RespError::Serde(format!("integer {} is out of range", v)))?;
+        self.serialize_i64(v)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<RespFrame, RespError> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<RespFrame, RespError> {
+        Ok(RespFrame::Double(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<RespFrame, RespError> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<RespFrame, RespError> {
+        Ok(RespFrame::BulkString(BulkString::new(v)))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<RespFrame, RespError> {
+        Ok(RespFrame::BulkString(BulkString::new(v)))
+    }
+
+    fn serialize_none(self) -> Result<RespFrame, RespError> {
+        Ok(RespFrame::Null(RespNull))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<RespFrame, RespError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<RespFrame, RespError> {
+        Ok(RespFrame::Null(RespNull))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<RespFrame, RespError> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<RespFrame, RespError> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<RespFrame, RespError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<RespFrame, RespError> {
+        let mut map = RespMap::new();
+        map.insert(SimpleString::new(variant), to_frame(value)?);
+        Ok(RespFrame::Map(map))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeVec, RespError> {
+        Ok(SerializeVec {
+            frames: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeVec, RespError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeVec, RespError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeTupleVariant, RespError> {
+        Ok(SerializeTupleVariant {
+            name: variant,
+            frames: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<SerializeMap, RespError> {
+        Ok(SerializeMap {
+            map: RespMap::new(),
+            next_key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<SerializeMap, RespError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<SerializeStructVariant, RespError> {
+        Ok(SerializeStructVariant {
+            name: variant,
+            map: RespMap::new(),
+        })
+    }
+}
+
+impl ser::SerializeSeq for SerializeVec {
+    type Ok = RespFrame;
+    type Error = RespError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RespError> {
+        self.frames.push(to_frame(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<RespFrame, RespError> {
+        Ok(RespFrame::Array(RespArray::new(self.frames)))
+    }
+}
+
+impl ser::SerializeTuple for SerializeVec {
+    type Ok = RespFrame;
+    type Error = RespError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RespError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<RespFrame, RespError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeVec {
+    type Ok = RespFrame;
+    type Error = RespError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RespError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<RespFrame, RespError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeTupleVariant {
+    type Ok = RespFrame;
+    type Error = RespError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RespError> {
+        self.frames.push(to_frame(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<RespFrame, RespError> {
+        let mut map = RespMap::new();
+        map.insert(
+            SimpleString::new(self.name),
+            RespFrame::Array(RespArray::new(self.frames)),
+        );
+        Ok(RespFrame::Map(map))
+    }
+}
+
+impl ser::SerializeMap for SerializeMap {
+    type Ok = RespFrame;
+    type Error = RespError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), RespError> {
+        self.next_key = Some(key.serialize(MapKeySerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RespError> {
+        let key = self
+            .next_key
+            .take()
+            .ok_or_else(|| RespError::Serde("serialize_value called before key".to_string()))?;
+        self.map.insert(key, to_frame(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<RespFrame, RespError> {
+        Ok(RespFrame::Map(self.map))
+    }
+}
+
+impl ser::SerializeStruct for SerializeMap {
+    type Ok = RespFrame;
+    type Error = RespError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), RespError> {
+        self.map.insert(SimpleString::new(key), to_frame(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<RespFrame, RespError> {
+        Ok(RespFrame::Map(self.map))
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeStructVariant {
+    type Ok = RespFrame;
+    type Error = RespError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), RespError> {
+        self.map.insert(SimpleString::new(key), to_frame(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<RespFrame, RespError> {
+        let mut map = RespMap::new();
+        map.insert(SimpleString::new(self.name), RespFrame::Map(self.map));
+        Ok(RespFrame::Map(map))
+    }
+}
+
+// RespMap 的 key 是 SimpleString，只接受字符串、字符和整数
+struct MapKeySerializer;
+
+fn key_must_be_a_string() -> RespError {
+    RespError::Serde("map key must be a string".to_string())
+}
+
+impl ser::Serializer for MapKeySerializer {
+    type Ok = SimpleString;
+    type Error = RespError;
+
+    type SerializeSeq = Impossible<SimpleString, RespError>;
+    type SerializeTuple = Impossible<SimpleString, RespError>;
+    type SerializeTupleStruct = Impossible<SimpleString, RespError>;
+    type SerializeTupleVariant = Impossible<SimpleString, RespError>;
+    type SerializeMap = Impossible<SimpleString, RespError>;
+    type SerializeStruct = Impossible<SimpleString, RespError>;
+    type SerializeStructVariant = Impossible<SimpleString, RespError>;
+
+    fn serialize_str(self, v: &str) -> Result<SimpleString, RespError> {
+        Ok(SimpleString::new(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<SimpleString, RespError> {
+        Ok(SimpleString::new(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<SimpleString, RespError> {
+        Ok(SimpleString::new(v.to_string()))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<SimpleString, RespError> {
+        Ok(SimpleString::new(v.to_string()))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<SimpleString, RespError> {
+        Ok(SimpleString::new(v.to_string()))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<SimpleString, RespError> {
+        Ok(SimpleString::new(v.to_string()))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<SimpleString, RespError> {
+        Ok(SimpleString::new(v.to_string()))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<SimpleString, RespError> {
+        Ok(SimpleString::new(v.to_string()))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<SimpleString, RespError> {
+        Ok(SimpleString::new(v.to_string()))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<SimpleString, RespError> {
+        Ok(SimpleString::new(v.to_string()))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<SimpleString, RespError> {
+        Ok(SimpleString::new(variant))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<SimpleString, RespError> {
+        value.serialize(self)
+    }
+
+    fn serialize_bool(self, _v: bool) -> Result<SimpleString, RespError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<SimpleString, RespError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<SimpleString, RespError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<SimpleString, RespError> {
+        let s = String::from_utf8(v.to_vec())?;
+        Ok(SimpleString::new(s))
+    }
+
+    fn serialize_none(self) -> Result<SimpleString, RespError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<SimpleString, RespError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<SimpleString, RespError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<SimpleString, RespError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<SimpleString, RespError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, RespError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, RespError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, RespError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, RespError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, RespError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, RespError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, RespError> {
+        Err(key_must_be_a_string())
+    }
+}